@@ -0,0 +1,243 @@
+//! indb command line client.
+//!
+//! Without a command the client starts an interactive prompt. Each line is split into
+//! arguments, sent to the server as an array of bulk strings and the reply is printed. When a
+//! command is given on the command line, it is executed once and the client exits.
+
+use indb::client::{self, Client, Event};
+use indb::{Frame, DEFAULT_PORT};
+
+use bytes::Bytes;
+use rustyline::error::ReadlineError;
+use rustyline::Editor;
+use std::io;
+use std::path::PathBuf;
+use structopt::clap::AppSettings;
+use structopt::StructOpt;
+use tokio_stream::StreamExt;
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "indb-cli", setting = AppSettings::TrailingVarArg, version = env!("CARGO_PKG_VERSION"), author = env!("CARGO_PKG_AUTHORS"), about = "Issue Redis commands")]
+struct Cli {
+    /// Server hostname.
+    #[structopt(name = "hostname", long = "--host", default_value = "127.0.0.1")]
+    host: String,
+
+    /// Server port.
+    #[structopt(name = "port", long = "--port", default_value = DEFAULT_PORT)]
+    port: u16,
+
+    /// Command to run once instead of starting the interactive prompt, e.g. `get foo`.
+    #[structopt(name = "command", allow_hyphen_values = true)]
+    command: Vec<String>,
+}
+
+/// Name of the history file, stored in the user's home directory.
+const HISTORY_FILE: &str = ".indb_cli_history";
+
+/// Entry point for the CLI tool.
+///
+/// `flavor = "current_thread"` is used as the prompt blocks the thread while waiting for input
+/// and there is no other work to schedule.
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> indb::Result<()> {
+    // Enable logging.
+    tracing_subscriber::fmt::try_init()?;
+
+    let cli = Cli::from_args();
+
+    // The host and port are resolved as a pair, as IPv6 addresses cannot be joined with a colon.
+    let mut client = client::connect((&cli.host[..], cli.port)).await?;
+
+    if !cli.command.is_empty() {
+        return run_command(client, cli.command).await;
+    }
+
+    let mut editor = Editor::<()>::new();
+    let history = history_path();
+    if let Some(path) = &history {
+        // A missing history file simply means this is the first session.
+        let _ = editor.load_history(path);
+    }
+
+    let prompt = format!("{}:{}> ", cli.host, cli.port);
+
+    loop {
+        let line = match editor.readline(&prompt) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => break,
+            Err(err) => return Err(err.into()),
+        };
+
+        let args = match split_args(&line) {
+            Ok(args) => args,
+            Err(msg) => {
+                println!("(error) {}", msg);
+                continue;
+            }
+        };
+
+        if args.is_empty() {
+            continue;
+        }
+
+        editor.add_history_entry(line.as_str());
+
+        match args[0].to_lowercase().as_str() {
+            "quit" | "exit" => break,
+            // Subscribing turns the connection into a pub/sub connection for good, so the
+            // prompt is left and messages are streamed until the connection closes.
+            "subscribe" | "psubscribe" if args.len() == 1 => {
+                println!("(error) channel(s) must be provided");
+            }
+            "subscribe" | "psubscribe" => {
+                save_history(&mut editor, &history);
+                return subscribe(client, args).await;
+            }
+            _ => match client.execute(&make_command(args)).await {
+                Ok(reply) => println!("{}", reply),
+                Err(err) => {
+                    println!("(error) {}", err);
+
+                    // The connection cannot be used anymore once reading or writing it failed,
+                    // e.g. as the server closes it on malformed commands. The session goes on
+                    // over a new connection, and ends only if the server cannot be reached.
+                    if err.is::<io::Error>() {
+                        client = match client::connect((&cli.host[..], cli.port)).await {
+                            Ok(client) => client,
+                            Err(err) => {
+                                save_history(&mut editor, &history);
+                                return Err(err);
+                            }
+                        };
+                    }
+                }
+            },
+        }
+    }
+
+    save_history(&mut editor, &history);
+
+    Ok(())
+}
+
+/// Runs a single command given on the command line and prints the reply.
+async fn run_command(mut client: Client, args: Vec<String>) -> indb::Result<()> {
+    if matches!(&args[0].to_lowercase()[..], "subscribe" | "psubscribe") {
+        return subscribe(client, args).await;
+    }
+
+    println!("{}", client.execute(&make_command(args)).await?);
+
+    Ok(())
+}
+
+/// Subscribes to the channels, or to the patterns if the command is `psubscribe`, given in
+/// `args` and prints every received message until the connection closes.
+async fn subscribe(client: Client, mut args: Vec<String>) -> indb::Result<()> {
+    let names = args.split_off(1);
+
+    if names.is_empty() {
+        return Err("channel(s) must be provided".into());
+    }
+
+    let subscriber = if args[0].to_lowercase() == "psubscribe" {
+        client.psubscribe(names).await?
+    } else {
+        client.subscribe(names).await?
+    };
+
+    for channel in subscriber.get_subscribed() {
+        println!("subscribed to {}", channel);
+    }
+    for pattern in subscriber.get_subscribed_patterns() {
+        println!("subscribed to pattern {}", pattern);
+    }
+
+    let messages = subscriber.into_stream();
+    tokio::pin!(messages);
+
+    while let Some(event) = messages.next().await {
+        match event? {
+            Event::Message(msg) => println!("{}: {}", msg.channel, Frame::Bulk(msg.content)),
+            Event::Lagged {
+                subscription,
+                skipped,
+            } => println!("{}: missed {} messages", subscription, skipped),
+        }
+    }
+
+    Ok(())
+}
+
+/// Encodes the arguments as an array of bulk strings.
+fn make_command(args: Vec<String>) -> Frame {
+    Frame::Array(
+        args.into_iter()
+            .map(|arg| Frame::Bulk(Bytes::from(arg)))
+            .collect(),
+    )
+}
+
+/// Splits a line into arguments.
+///
+/// Arguments are separated by whitespace. Double or single quotes group words into a single
+/// argument, and within double quotes `\"`, `\\`, `\n`, `\r` and `\t` are unescaped.
+fn split_args(line: &str) -> Result<Vec<String>, &'static str> {
+    let mut args = vec![];
+    let mut chars = line.chars().peekable();
+
+    loop {
+        // Skip leading whitespace.
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+
+        let quote = match chars.peek() {
+            None => return Ok(args),
+            Some(&c) if c == '"' || c == '\'' => {
+                chars.next();
+                Some(c)
+            }
+            Some(_) => None,
+        };
+
+        let mut arg = String::new();
+
+        loop {
+            match (chars.next(), quote) {
+                (None, Some(_)) => return Err("unbalanced quotes in request"),
+                (None, None) => break,
+                (Some(c), Some(q)) if c == q => {
+                    // A closing quote must be followed by a space or the end of the line.
+                    if chars.peek().is_some_and(|c| !c.is_whitespace()) {
+                        return Err("closing quote must be followed by a space");
+                    }
+                    break;
+                }
+                (Some('\\'), Some('"')) => match chars.next() {
+                    Some('n') => arg.push('\n'),
+                    Some('r') => arg.push('\r'),
+                    Some('t') => arg.push('\t'),
+                    Some(c) => arg.push(c),
+                    None => return Err("unbalanced quotes in request"),
+                },
+                (Some(c), None) if c.is_whitespace() => break,
+                (Some(c), _) => arg.push(c),
+            }
+        }
+
+        args.push(arg);
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE))
+}
+
+fn save_history(editor: &mut Editor<()>, path: &Option<PathBuf>) {
+    if let Some(path) = path {
+        // Failing to persist the history is not worth aborting the session for.
+        let _ = editor.save_history(path);
+    }
+}
@@ -0,0 +1,136 @@
+//! indb server.
+//!
+//! This file is the entry point for the server implemented in the library. It performs command
+//! line parsing, sets up logging and passes the arguments on to `indb::server`.
+
+use indb::server::{self, ServerConfig};
+use indb::{KeyspaceEvents, LagPolicy, DEFAULT_PORT};
+
+use std::time::Duration;
+use structopt::StructOpt;
+use tokio::net::TcpListener;
+use tracing::Level;
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "indb-server", version = env!("CARGO_PKG_VERSION"), author = env!("CARGO_PKG_AUTHORS"), about = "An in-memory Redis server")]
+struct Cli {
+    /// Address to bind the listener to.
+    #[structopt(name = "hostname", long = "--bind", default_value = "127.0.0.1")]
+    bind: String,
+
+    /// Port to listen on.
+    #[structopt(name = "port", long = "--port", default_value = DEFAULT_PORT)]
+    port: u16,
+
+    /// Maximum number of concurrent connections [default: 250].
+    #[structopt(name = "clients", long = "--maxclients")]
+    max_connections: Option<usize>,
+
+    /// Initial size of the read buffer of each connection, in bytes [default: 4096].
+    #[structopt(name = "size", long = "--read-buffer-size")]
+    read_buffer_size: Option<usize>,
+
+    /// Maximum size of a frame sent by a client, in bytes [default: unlimited].
+    #[structopt(name = "frame-bytes", long = "--max-frame-size")]
+    max_frame_size: Option<usize>,
+
+    /// Maximum length of a bulk string sent by a client, in bytes [default: unlimited].
+    #[structopt(name = "bulk-bytes", long = "--max-bulk-size")]
+    max_bulk_size: Option<usize>,
+
+    /// Seconds after which idle clients are disconnected [default: never].
+    #[structopt(name = "seconds", long = "--timeout")]
+    idle_timeout: Option<u64>,
+
+    /// Seconds after which TCP keepalive probes are sent over idle connections [default: none].
+    #[structopt(name = "keepalive-seconds", long = "--tcp-keepalive")]
+    tcp_keepalive: Option<u64>,
+
+    /// Disable Nagle's algorithm on the connections.
+    #[structopt(long = "--tcp-nodelay")]
+    tcp_nodelay: bool,
+
+    /// Number of messages buffered for each pub/sub channel and pattern [default: 1024].
+    #[structopt(name = "messages", long = "--pubsub-capacity")]
+    pubsub_capacity: Option<usize>,
+
+    /// What to do with subscribers missing messages (disconnect, notify or drop).
+    #[structopt(name = "policy", long = "--pubsub-lag-policy", default_value = "drop")]
+    lag_policy: LagPolicy,
+
+    /// Classes of keyspace notifications to publish, as the Redis `notify-keyspace-events`
+    /// setting (K, E, g, $, l, s, h, z, t, x or A) [default: none].
+    #[structopt(name = "classes", long = "--notify-keyspace-events")]
+    keyspace_events: Option<KeyspaceEvents>,
+
+    /// Milliseconds after which a running Lua script is aborted [default: 5000].
+    #[structopt(name = "milliseconds", long = "--lua-time-limit")]
+    lua_time_limit: Option<u64>,
+
+    /// Maximum level of the emitted log messages (error, warn, info, debug or trace).
+    #[structopt(name = "level", long = "--log-level", default_value = "info")]
+    log_level: Level,
+}
+
+#[tokio::main]
+pub async fn main() -> indb::Result<()> {
+    let cli = Cli::from_args();
+
+    // Enable logging. `try_init` fails only if a global subscriber has already been installed.
+    tracing_subscriber::fmt()
+        .with_max_level(cli.log_level)
+        .try_init()?;
+
+    // Bind a TCP listener.
+    //
+    // The address and port are resolved as a pair, as IPv6 addresses cannot be joined with a
+    // colon.
+    let listener = TcpListener::bind((&cli.bind[..], cli.port)).await?;
+
+    let mut config = ServerConfig::new()
+        .max_frame_size(cli.max_frame_size)
+        .max_bulk_size(cli.max_bulk_size)
+        .idle_timeout(cli.idle_timeout.map(Duration::from_secs))
+        .tcp_keepalive(cli.tcp_keepalive.map(Duration::from_secs))
+        .tcp_nodelay(cli.tcp_nodelay)
+        .lag_policy(cli.lag_policy)
+        .keyspace_events(cli.keyspace_events.unwrap_or_default());
+
+    if let Some(max) = cli.max_connections {
+        config = config.max_connections(max);
+    }
+
+    if let Some(size) = cli.read_buffer_size {
+        config = config.read_buffer_size(size);
+    }
+
+    if let Some(capacity) = cli.pubsub_capacity {
+        config = config.pubsub_capacity(capacity);
+    }
+
+    if let Some(limit) = cli.lua_time_limit {
+        config = config.script_time_limit(Duration::from_millis(limit));
+    }
+
+    server::run(listener, config, shutdown_signal()).await
+}
+
+/// Completes when the process receives either Ctrl-C or SIGTERM.
+#[cfg(unix)]
+async fn shutdown_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    // Registering a signal handler only fails if the signal is forbidden, which SIGTERM is not.
+    let mut sigterm = signal(SignalKind::terminate()).expect("failed to install SIGTERM handler");
+
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = sigterm.recv() => {}
+    }
+}
+
+/// Completes when the process receives Ctrl-C.
+#[cfg(not(unix))]
+async fn shutdown_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
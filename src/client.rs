@@ -1,11 +1,25 @@
 //! Redis client implementation.
 
-use crate::cmd::{Get, Publish, Set, Subscribe, Unsubscribe};
-use crate::{Connection, Frame};
+use crate::cmd::{
+    Append, BLMove, BPop, Copy, Del, Discard, Eval, Exec, Exists, Expire, Get, GetDel, GetEx,
+    GetRange, HDel, HExists, HGet, HGetAll, HIncrBy, HKeys, HLen, HMGet, HSet, HVals, Incr,
+    IncrByFloat, LIndex, LLen, LRange, LRem, LSet, LTrim, MGet, MSet, Multi, PSubscribe,
+    PUnsubscribe, Persist, Pop, PubSub, Publish, Push, Rename, SAdd, SCard, SCombine, SIsMember,
+    SMembers, SRem, Script, Set, SetRange, Strlen, Subscribe, Ttl, Type, Unsubscribe, Unwatch,
+    Watch, XAck, XAdd, XAutoClaim, XClaim, XGroup, XLen, XPending, XRange, XRead, XReadGroup,
+    XTrim, ZAdd, ZCount, ZIncrBy, ZPop, ZRange, ZRank, ZRem, ZScore,
+};
+use crate::{
+    Connection, Frame, GroupEntry, PendingEntry, PendingSummary, ScoreBound, SetOp, StreamEntry,
+    StreamId, XAddId, ZAddOptions, ZRangeBy,
+};
 
 use async_stream::try_stream;
 use bytes::Bytes;
+use std::collections::{HashMap, HashSet};
 use std::io::{Error, ErrorKind};
+use std::ops::Bound;
+use std::time::SystemTime;
 use tokio::net::{TcpStream, ToSocketAddrs};
 use tokio::time::Duration;
 use tokio_stream::Stream;
@@ -23,6 +37,7 @@ pub struct Client {
 pub struct Subscriber {
     client: Client,
     subscribed_channels: Vec<String>,
+    subscribed_patterns: Vec<String>,
 }
 
 /// A message received on a subscribed channel.
@@ -30,62 +45,1319 @@ pub struct Subscriber {
 pub struct Message {
     pub channel: String,
     pub content: Bytes,
+    /// The subscribed pattern matching the channel, if the message was received through a
+    /// pattern subscription.
+    pub pattern: Option<String>,
+}
+
+/// An event received by a `Subscriber`.
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// A message published on a subscribed channel.
+    Message(Message),
+    /// The subscriber lagged behind and the server dropped messages instead of delivering them.
+    ///
+    /// Only received when the server is configured to notify lagging subscribers.
+    Lagged {
+        /// The subscribed channel or pattern the messages were published through.
+        subscription: String,
+        /// Number of messages missed.
+        skipped: u64,
+    },
+}
+
+/// A transaction being built, created by `Client::transaction`.
+///
+/// Commands are buffered until `exec` sends them, along with `MULTI` and `EXEC`. The server
+/// applies all of them atomically.
+pub struct Transaction<'a> {
+    client: &'a mut Client,
+    commands: Vec<Frame>,
 }
 
 /// Establish a connection with the Redis server located at `addr`.
 pub async fn connect<T: ToSocketAddrs>(addr: T) -> crate::Result<Client> {
     let socket = TcpStream::connect(addr).await?;
 
-    let connection = Connection::new(socket);
+    let connection = Connection::new(socket);
+
+    Ok(Client { connection })
+}
+
+impl Client {
+    /// Get the value of the given `key`.
+    #[instrument(skip(self))]
+    pub async fn get(&mut self, key: &str) -> crate::Result<Option<Bytes>> {
+        let frame = Get::new(key).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(value) => Ok(Some(value.into())),
+            Frame::Bulk(value) => Ok(Some(value)),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Set `key` to hold the given `value`.
+    #[instrument(skip(self))]
+    pub async fn set(&mut self, key: &str, value: Bytes) -> crate::Result<()> {
+        self.set_cmd(Set::new(key, value, None)).await
+    }
+
+    /// Set `key` to hold the given `value`. The `value` expires after `expiration`.
+    #[instrument(skip(self))]
+    pub async fn set_expires(
+        &mut self,
+        key: &str,
+        value: Bytes,
+        expiration: Duration,
+    ) -> crate::Result<()> {
+        self.set_cmd(Set::new(key, value, Some(expiration))).await
+    }
+
+    /// Set `key` to hold the given `value` only if `key` does not exist yet. Returns `true` if
+    /// the value was set.
+    #[instrument(skip(self))]
+    pub async fn set_nx(&mut self, key: &str, value: Bytes) -> crate::Result<bool> {
+        let frame = Set::new(key, value, None).nx().into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(true),
+            Frame::Null => Ok(false),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Set `key` to hold the given `value` and return the value previously stored at `key`.
+    #[instrument(skip(self))]
+    pub async fn set_get(&mut self, key: &str, value: Bytes) -> crate::Result<Option<Bytes>> {
+        let frame = Set::new(key, value, None).get().into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Bulk(value) => Ok(Some(value)),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    async fn set_cmd(&mut self, cmd: Set) -> crate::Result<()> {
+        let frame = cmd.into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        // On success, the server responds simply with `Ok`. Any other response indicates an error.
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Removes the given `keys`. Returns the number of keys that were removed.
+    #[instrument(skip(self))]
+    pub async fn del(&mut self, keys: &[String]) -> crate::Result<u64> {
+        Ok(self.integer_cmd(Del::new(keys).into_frame()).await? as u64)
+    }
+
+    /// Returns how many of the given `keys` exist.
+    #[instrument(skip(self))]
+    pub async fn exists(&mut self, keys: &[String]) -> crate::Result<u64> {
+        Ok(self.integer_cmd(Exists::new(keys).into_frame()).await? as u64)
+    }
+
+    /// Returns the type of the value stored at `key`, or `"none"` if the key does not exist.
+    #[instrument(skip(self))]
+    pub async fn key_type(&mut self, key: &str) -> crate::Result<String> {
+        let frame = Type::new(key).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) => Ok(response),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Renames `key` to `newkey`, overwriting `newkey` if it exists.
+    #[instrument(skip(self))]
+    pub async fn rename(&mut self, key: &str, newkey: &str) -> crate::Result<()> {
+        let frame = Rename::new(key, newkey, false).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Renames `key` to `newkey` only if `newkey` does not exist yet. Returns `true` if the key
+    /// was renamed.
+    #[instrument(skip(self))]
+    pub async fn renamenx(&mut self, key: &str, newkey: &str) -> crate::Result<bool> {
+        let frame = Rename::new(key, newkey, true).into_frame();
+        Ok(self.integer_cmd(frame).await? == 1)
+    }
+
+    /// Copies the value stored at `source` to `destination`. Returns `true` if the value was
+    /// copied.
+    #[instrument(skip(self))]
+    pub async fn copy(
+        &mut self,
+        source: &str,
+        destination: &str,
+        replace: bool,
+    ) -> crate::Result<bool> {
+        let frame = Copy::new(source, destination, replace).into_frame();
+        Ok(self.integer_cmd(frame).await? == 1)
+    }
+
+    /// Sets a timeout on `key`, after which the key is deleted. Returns `false` if the key does
+    /// not exist.
+    #[instrument(skip(self))]
+    pub async fn expire(&mut self, key: &str, timeout: Duration) -> crate::Result<bool> {
+        let frame = Expire::new(key, timeout).into_frame();
+        Ok(self.integer_cmd(frame).await? == 1)
+    }
+
+    /// Sets the point in time at which `key` is deleted. Returns `false` if the key does not
+    /// exist.
+    #[instrument(skip(self))]
+    pub async fn expire_at(&mut self, key: &str, when: SystemTime) -> crate::Result<bool> {
+        let frame = Expire::at(key, when).into_frame();
+        Ok(self.integer_cmd(frame).await? == 1)
+    }
+
+    /// Returns the remaining time to live of `key` in seconds.
+    ///
+    /// `-2` is returned if the key does not exist and `-1` if it has no timeout.
+    #[instrument(skip(self))]
+    pub async fn ttl(&mut self, key: &str) -> crate::Result<i64> {
+        self.integer_cmd(Ttl::new(key, false).into_frame()).await
+    }
+
+    /// Returns the remaining time to live of `key` in milliseconds.
+    ///
+    /// `-2` is returned if the key does not exist and `-1` if it has no timeout.
+    #[instrument(skip(self))]
+    pub async fn pttl(&mut self, key: &str) -> crate::Result<i64> {
+        self.integer_cmd(Ttl::new(key, true).into_frame()).await
+    }
+
+    /// Removes the timeout of `key`. Returns `false` if the key does not exist or has no
+    /// timeout.
+    #[instrument(skip(self))]
+    pub async fn persist(&mut self, key: &str) -> crate::Result<bool> {
+        let frame = Persist::new(key).into_frame();
+        Ok(self.integer_cmd(frame).await? == 1)
+    }
+
+    /// Increments the integer stored at `key` by one and returns the new value.
+    #[instrument(skip(self))]
+    pub async fn incr(&mut self, key: &str) -> crate::Result<i64> {
+        self.incr_by(key, 1).await
+    }
+
+    /// Decrements the integer stored at `key` by one and returns the new value.
+    #[instrument(skip(self))]
+    pub async fn decr(&mut self, key: &str) -> crate::Result<i64> {
+        self.incr_by(key, -1).await
+    }
+
+    /// Adds `increment` to the integer stored at `key` and returns the new value.
+    #[instrument(skip(self))]
+    pub async fn incr_by(&mut self, key: &str, increment: i64) -> crate::Result<i64> {
+        self.integer_cmd(Incr::new(key, increment).into_frame())
+            .await
+    }
+
+    /// Subtracts `decrement` from the integer stored at `key` and returns the new value.
+    #[instrument(skip(self))]
+    pub async fn decr_by(&mut self, key: &str, decrement: i64) -> crate::Result<i64> {
+        let increment = decrement.checked_neg().ok_or("decrement is out of range")?;
+        self.incr_by(key, increment).await
+    }
+
+    /// Adds `increment` to the floating point number stored at `key` and returns the new value.
+    #[instrument(skip(self))]
+    pub async fn incr_by_float(&mut self, key: &str, increment: f64) -> crate::Result<f64> {
+        let frame = IncrByFloat::new(key, increment).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Bulk(ref value) => std::str::from_utf8(value)
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .ok_or_else(|| "invalid float response".into()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Appends `value` to the string stored at `key` and returns the new length of the string.
+    #[instrument(skip(self))]
+    pub async fn append(&mut self, key: &str, value: Bytes) -> crate::Result<u64> {
+        Ok(self
+            .integer_cmd(Append::new(key, value).into_frame())
+            .await? as u64)
+    }
+
+    /// Returns the length of the string stored at `key`.
+    #[instrument(skip(self))]
+    pub async fn strlen(&mut self, key: &str) -> crate::Result<u64> {
+        Ok(self.integer_cmd(Strlen::new(key).into_frame()).await? as u64)
+    }
+
+    /// Returns the part of the string stored at `key` between the offsets `start` and `end`
+    /// (both inclusive). Negative offsets count from the end of the string.
+    #[instrument(skip(self))]
+    pub async fn get_range(&mut self, key: &str, start: i64, end: i64) -> crate::Result<Bytes> {
+        let frame = GetRange::new(key, start, end).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Bulk(value) => Ok(value),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Overwrites the string stored at `key` with `value`, starting at `offset`. Returns the
+    /// new length of the string.
+    #[instrument(skip(self))]
+    pub async fn set_range(&mut self, key: &str, offset: u64, value: Bytes) -> crate::Result<u64> {
+        let frame = SetRange::new(key, offset, value).into_frame();
+        Ok(self.integer_cmd(frame).await? as u64)
+    }
+
+    /// Removes `key` and returns the value it held.
+    #[instrument(skip(self))]
+    pub async fn get_del(&mut self, key: &str) -> crate::Result<Option<Bytes>> {
+        self.value_cmd(GetDel::new(key).into_frame()).await
+    }
+
+    /// Returns the value of `key` and sets it to expire after `timeout`.
+    #[instrument(skip(self))]
+    pub async fn get_ex(&mut self, key: &str, timeout: Duration) -> crate::Result<Option<Bytes>> {
+        self.value_cmd(GetEx::new(key, timeout).into_frame()).await
+    }
+
+    /// Returns the value of `key` and removes its time to live.
+    #[instrument(skip(self))]
+    pub async fn get_persist(&mut self, key: &str) -> crate::Result<Option<Bytes>> {
+        self.value_cmd(GetEx::persist(key).into_frame()).await
+    }
+
+    /// Returns the values of all the given `keys`.
+    #[instrument(skip(self))]
+    pub async fn mget(&mut self, keys: &[String]) -> crate::Result<Vec<Option<Bytes>>> {
+        self.optional_values_cmd(MGet::new(keys).into_frame()).await
+    }
+
+    /// Sets all the given keys to their respective values at once.
+    #[instrument(skip(self))]
+    pub async fn mset(&mut self, pairs: &[(String, Bytes)]) -> crate::Result<()> {
+        let frame = MSet::new(pairs, false).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Sets all the given keys to their respective values, unless any of the keys already
+    /// exists. Returns `true` if the values were set.
+    #[instrument(skip(self))]
+    pub async fn mset_nx(&mut self, pairs: &[(String, Bytes)]) -> crate::Result<bool> {
+        let frame = MSet::new(pairs, true).into_frame();
+        Ok(self.integer_cmd(frame).await? == 1)
+    }
+
+    /// Inserts `values` at the head of the list stored at `key`. Returns the length of the list
+    /// after the push.
+    #[instrument(skip(self))]
+    pub async fn lpush(&mut self, key: &str, values: Vec<Bytes>) -> crate::Result<u64> {
+        Ok(self
+            .integer_cmd(Push::new(key, values, true).into_frame())
+            .await? as u64)
+    }
+
+    /// Inserts `values` at the tail of the list stored at `key`. Returns the length of the list
+    /// after the push.
+    #[instrument(skip(self))]
+    pub async fn rpush(&mut self, key: &str, values: Vec<Bytes>) -> crate::Result<u64> {
+        Ok(self
+            .integer_cmd(Push::new(key, values, false).into_frame())
+            .await? as u64)
+    }
+
+    /// Removes and returns the first element of the list stored at `key`.
+    #[instrument(skip(self))]
+    pub async fn lpop(&mut self, key: &str) -> crate::Result<Option<Bytes>> {
+        self.value_cmd(Pop::new(key, None, true).into_frame()).await
+    }
+
+    /// Removes and returns the last element of the list stored at `key`.
+    #[instrument(skip(self))]
+    pub async fn rpop(&mut self, key: &str) -> crate::Result<Option<Bytes>> {
+        self.value_cmd(Pop::new(key, None, false).into_frame())
+            .await
+    }
+
+    /// Removes and returns up to `count` elements from the head of the list stored at `key`.
+    /// Returns `None` if the key does not exist.
+    #[instrument(skip(self))]
+    pub async fn lpop_count(&mut self, key: &str, count: u64) -> crate::Result<Option<Vec<Bytes>>> {
+        self.values_cmd(Pop::new(key, Some(count), true).into_frame())
+            .await
+    }
+
+    /// Removes and returns up to `count` elements from the tail of the list stored at `key`.
+    /// Returns `None` if the key does not exist.
+    #[instrument(skip(self))]
+    pub async fn rpop_count(&mut self, key: &str, count: u64) -> crate::Result<Option<Vec<Bytes>>> {
+        self.values_cmd(Pop::new(key, Some(count), false).into_frame())
+            .await
+    }
+
+    /// Removes and returns the first element of the first non-empty list among `keys`, along
+    /// with the key it was popped from. If all the lists are empty, blocks until an element is
+    /// pushed or `timeout` elapses, in which case `None` is returned. A `timeout` of `None`
+    /// blocks indefinitely.
+    #[instrument(skip(self))]
+    pub async fn blpop(
+        &mut self,
+        keys: &[String],
+        timeout: Option<Duration>,
+    ) -> crate::Result<Option<(String, Bytes)>> {
+        self.bpop_cmd(BPop::new(keys, timeout, true)).await
+    }
+
+    /// Removes and returns the last element of the first non-empty list among `keys`, along
+    /// with the key it was popped from. Blocks the same way as `blpop`.
+    #[instrument(skip(self))]
+    pub async fn brpop(
+        &mut self,
+        keys: &[String],
+        timeout: Option<Duration>,
+    ) -> crate::Result<Option<(String, Bytes)>> {
+        self.bpop_cmd(BPop::new(keys, timeout, false)).await
+    }
+
+    async fn bpop_cmd(&mut self, cmd: BPop) -> crate::Result<Option<(String, Bytes)>> {
+        let frame = cmd.into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        let response = self.read_response().await?;
+
+        match response {
+            Frame::Array(ref frame) => match frame.as_slice() {
+                [Frame::Bulk(key), Frame::Bulk(value)] => {
+                    let key = std::str::from_utf8(key)?.to_string();
+                    Ok(Some((key, value.clone())))
+                }
+                _ => Err(response.to_error()),
+            },
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Moves an element from the list stored at `source` to the list stored at `destination`,
+    /// blocking until `source` holds elements or `timeout` elapses. The element is taken from
+    /// the head of `source` if `from_front` is set and pushed to the head of `destination` if
+    /// `to_front` is set.
+    ///
+    /// Returns the moved element, or `None` on timeout. A `timeout` of `None` blocks
+    /// indefinitely.
+    #[instrument(skip(self))]
+    pub async fn blmove(
+        &mut self,
+        source: &str,
+        destination: &str,
+        from_front: bool,
+        to_front: bool,
+        timeout: Option<Duration>,
+    ) -> crate::Result<Option<Bytes>> {
+        let cmd = BLMove::new(source, destination, from_front, to_front, timeout);
+        self.value_cmd(cmd.into_frame()).await
+    }
+
+    /// Returns the elements of the list stored at `key` between `start` and `stop`, both
+    /// inclusive.
+    #[instrument(skip(self))]
+    pub async fn lrange(&mut self, key: &str, start: i64, stop: i64) -> crate::Result<Vec<Bytes>> {
+        let values = self
+            .values_cmd(LRange::new(key, start, stop).into_frame())
+            .await?;
+        Ok(values.unwrap_or_default())
+    }
+
+    /// Returns the length of the list stored at `key`.
+    #[instrument(skip(self))]
+    pub async fn llen(&mut self, key: &str) -> crate::Result<u64> {
+        Ok(self.integer_cmd(LLen::new(key).into_frame()).await? as u64)
+    }
+
+    /// Returns the element at `index` in the list stored at `key`.
+    #[instrument(skip(self))]
+    pub async fn lindex(&mut self, key: &str, index: i64) -> crate::Result<Option<Bytes>> {
+        self.value_cmd(LIndex::new(key, index).into_frame()).await
+    }
+
+    /// Replaces the element at `index` in the list stored at `key` with `value`.
+    #[instrument(skip(self))]
+    pub async fn lset(&mut self, key: &str, index: i64, value: Bytes) -> crate::Result<()> {
+        self.ok_cmd(LSet::new(key, index, value).into_frame()).await
+    }
+
+    /// Removes up to `count` occurrences of `value` from the list stored at `key`. Returns the
+    /// number of removed elements.
+    #[instrument(skip(self))]
+    pub async fn lrem(&mut self, key: &str, count: i64, value: Bytes) -> crate::Result<u64> {
+        Ok(self
+            .integer_cmd(LRem::new(key, count, value).into_frame())
+            .await? as u64)
+    }
+
+    /// Trims the list stored at `key` to the elements between `start` and `stop`, both
+    /// inclusive.
+    #[instrument(skip(self))]
+    pub async fn ltrim(&mut self, key: &str, start: i64, stop: i64) -> crate::Result<()> {
+        self.ok_cmd(LTrim::new(key, start, stop).into_frame()).await
+    }
+
+    /// Sets the given fields to their respective values in the hash stored at `key`. Returns the
+    /// number of fields that were added.
+    #[instrument(skip(self))]
+    pub async fn hset(&mut self, key: &str, pairs: &[(String, Bytes)]) -> crate::Result<u64> {
+        Ok(self.integer_cmd(HSet::new(key, pairs).into_frame()).await? as u64)
+    }
+
+    /// Returns the value of `field` in the hash stored at `key`.
+    #[instrument(skip(self))]
+    pub async fn hget(&mut self, key: &str, field: &str) -> crate::Result<Option<Bytes>> {
+        self.value_cmd(HGet::new(key, field).into_frame()).await
+    }
+
+    /// Returns the values of all the given `fields` in the hash stored at `key`.
+    #[instrument(skip(self))]
+    pub async fn hmget(
+        &mut self,
+        key: &str,
+        fields: &[String],
+    ) -> crate::Result<Vec<Option<Bytes>>> {
+        self.optional_values_cmd(HMGet::new(key, fields).into_frame())
+            .await
+    }
+
+    /// Removes the given `fields` from the hash stored at `key`. Returns the number of fields
+    /// that were removed.
+    #[instrument(skip(self))]
+    pub async fn hdel(&mut self, key: &str, fields: &[String]) -> crate::Result<u64> {
+        Ok(self
+            .integer_cmd(HDel::new(key, fields).into_frame())
+            .await? as u64)
+    }
+
+    /// Returns all the fields and values of the hash stored at `key`.
+    #[instrument(skip(self))]
+    pub async fn hgetall(&mut self, key: &str) -> crate::Result<HashMap<String, Bytes>> {
+        let values = self.values_cmd(HGetAll::new(key).into_frame()).await?;
+
+        let mut hash = HashMap::new();
+        let mut values = values.unwrap_or_default().into_iter();
+        while let (Some(field), Some(value)) = (values.next(), values.next()) {
+            hash.insert(std::str::from_utf8(&field)?.to_string(), value);
+        }
+
+        Ok(hash)
+    }
+
+    /// Adds `increment` to the integer stored in `field` of the hash stored at `key` and returns
+    /// the new value.
+    #[instrument(skip(self))]
+    pub async fn hincr_by(&mut self, key: &str, field: &str, increment: i64) -> crate::Result<i64> {
+        self.integer_cmd(HIncrBy::new(key, field, increment).into_frame())
+            .await
+    }
+
+    /// Returns `true` if `field` exists in the hash stored at `key`.
+    #[instrument(skip(self))]
+    pub async fn hexists(&mut self, key: &str, field: &str) -> crate::Result<bool> {
+        Ok(self
+            .integer_cmd(HExists::new(key, field).into_frame())
+            .await?
+            == 1)
+    }
+
+    /// Returns all the field names of the hash stored at `key`.
+    #[instrument(skip(self))]
+    pub async fn hkeys(&mut self, key: &str) -> crate::Result<Vec<String>> {
+        let fields = self.values_cmd(HKeys::new(key).into_frame()).await?;
+
+        fields
+            .unwrap_or_default()
+            .iter()
+            .map(|field| Ok(std::str::from_utf8(field)?.to_string()))
+            .collect()
+    }
+
+    /// Returns all the values of the hash stored at `key`.
+    #[instrument(skip(self))]
+    pub async fn hvals(&mut self, key: &str) -> crate::Result<Vec<Bytes>> {
+        let values = self.values_cmd(HVals::new(key).into_frame()).await?;
+        Ok(values.unwrap_or_default())
+    }
+
+    /// Returns the number of fields of the hash stored at `key`.
+    #[instrument(skip(self))]
+    pub async fn hlen(&mut self, key: &str) -> crate::Result<u64> {
+        Ok(self.integer_cmd(HLen::new(key).into_frame()).await? as u64)
+    }
+
+    /// Adds `members` to the set stored at `key`. Returns the number of members that were added.
+    #[instrument(skip(self))]
+    pub async fn sadd(&mut self, key: &str, members: Vec<Bytes>) -> crate::Result<u64> {
+        Ok(self
+            .integer_cmd(SAdd::new(key, members).into_frame())
+            .await? as u64)
+    }
+
+    /// Removes `members` from the set stored at `key`. Returns the number of members that were
+    /// removed.
+    #[instrument(skip(self))]
+    pub async fn srem(&mut self, key: &str, members: Vec<Bytes>) -> crate::Result<u64> {
+        Ok(self
+            .integer_cmd(SRem::new(key, members).into_frame())
+            .await? as u64)
+    }
+
+    /// Returns all the members of the set stored at `key`.
+    #[instrument(skip(self))]
+    pub async fn smembers(&mut self, key: &str) -> crate::Result<HashSet<Bytes>> {
+        self.members_cmd(SMembers::new(key).into_frame()).await
+    }
+
+    /// Returns `true` if `member` belongs to the set stored at `key`.
+    #[instrument(skip(self))]
+    pub async fn sismember(&mut self, key: &str, member: Bytes) -> crate::Result<bool> {
+        Ok(self
+            .integer_cmd(SIsMember::new(key, member).into_frame())
+            .await?
+            == 1)
+    }
+
+    /// Returns the number of members of the set stored at `key`.
+    #[instrument(skip(self))]
+    pub async fn scard(&mut self, key: &str) -> crate::Result<u64> {
+        Ok(self.integer_cmd(SCard::new(key).into_frame()).await? as u64)
+    }
+
+    /// Returns the members present in all the sets stored at `keys`.
+    #[instrument(skip(self))]
+    pub async fn sinter(&mut self, keys: &[String]) -> crate::Result<HashSet<Bytes>> {
+        let frame = SCombine::new(SetOp::Inter, keys, None).into_frame();
+        self.members_cmd(frame).await
+    }
+
+    /// Returns the members present in any of the sets stored at `keys`.
+    #[instrument(skip(self))]
+    pub async fn sunion(&mut self, keys: &[String]) -> crate::Result<HashSet<Bytes>> {
+        let frame = SCombine::new(SetOp::Union, keys, None).into_frame();
+        self.members_cmd(frame).await
+    }
+
+    /// Returns the members of the set stored at the first key that are not present in the sets
+    /// stored at the following `keys`.
+    #[instrument(skip(self))]
+    pub async fn sdiff(&mut self, keys: &[String]) -> crate::Result<HashSet<Bytes>> {
+        let frame = SCombine::new(SetOp::Diff, keys, None).into_frame();
+        self.members_cmd(frame).await
+    }
+
+    /// Stores the intersection of the sets stored at `keys` at `destination`. Returns the
+    /// number of members of the resulting set.
+    #[instrument(skip(self))]
+    pub async fn sinter_store(&mut self, destination: &str, keys: &[String]) -> crate::Result<u64> {
+        self.combine_store_cmd(SetOp::Inter, destination, keys)
+            .await
+    }
+
+    /// Stores the union of the sets stored at `keys` at `destination`. Returns the number of
+    /// members of the resulting set.
+    #[instrument(skip(self))]
+    pub async fn sunion_store(&mut self, destination: &str, keys: &[String]) -> crate::Result<u64> {
+        self.combine_store_cmd(SetOp::Union, destination, keys)
+            .await
+    }
+
+    /// Stores the difference of the sets stored at `keys` at `destination`. Returns the number
+    /// of members of the resulting set.
+    #[instrument(skip(self))]
+    pub async fn sdiff_store(&mut self, destination: &str, keys: &[String]) -> crate::Result<u64> {
+        self.combine_store_cmd(SetOp::Diff, destination, keys).await
+    }
+
+    async fn combine_store_cmd(
+        &mut self,
+        op: SetOp,
+        destination: &str,
+        keys: &[String],
+    ) -> crate::Result<u64> {
+        let frame = SCombine::new(op, keys, Some(destination.to_string())).into_frame();
+        Ok(self.integer_cmd(frame).await? as u64)
+    }
+
+    /// Adds `members` with their scores to the sorted set stored at `key`, subject to `options`.
+    /// Returns the number of added members, plus the number of updated members if `options.ch`
+    /// is set.
+    #[instrument(skip(self))]
+    pub async fn zadd(
+        &mut self,
+        key: &str,
+        members: Vec<(f64, Bytes)>,
+        options: ZAddOptions,
+    ) -> crate::Result<u64> {
+        Ok(self
+            .integer_cmd(ZAdd::new(key, members, options).into_frame())
+            .await? as u64)
+    }
+
+    /// Increments the score of `member` in the sorted set stored at `key` by `increment`, subject
+    /// to `options`. Returns the new score, or `None` if `options` prevented the update.
+    #[instrument(skip(self))]
+    pub async fn zadd_incr(
+        &mut self,
+        key: &str,
+        increment: f64,
+        member: Bytes,
+        options: ZAddOptions,
+    ) -> crate::Result<Option<f64>> {
+        let frame = ZAdd::new_incr(key, increment, member, options).into_frame();
+        self.score_cmd(frame).await
+    }
+
+    /// Returns the members of the sorted set stored at `key` selected by `by`, from the lowest
+    /// to the highest score or the other way around if `rev` is set.
+    ///
+    /// `limit` skips the given number of selected members and returns at most the given count of
+    /// the remaining ones. It is only supported for ranges by score or lexicographical order.
+    #[instrument(skip(self))]
+    pub async fn zrange(
+        &mut self,
+        key: &str,
+        by: ZRangeBy,
+        rev: bool,
+        limit: Option<(u64, i64)>,
+    ) -> crate::Result<Vec<Bytes>> {
+        let frame = zrange_cmd(key, by, rev, limit).into_frame();
+        Ok(self.values_cmd(frame).await?.unwrap_or_default())
+    }
+
+    /// Same as `zrange`, returning the score of each member along with it.
+    #[instrument(skip(self))]
+    pub async fn zrange_with_scores(
+        &mut self,
+        key: &str,
+        by: ZRangeBy,
+        rev: bool,
+        limit: Option<(u64, i64)>,
+    ) -> crate::Result<Vec<(Bytes, f64)>> {
+        let frame = zrange_cmd(key, by, rev, limit).with_scores().into_frame();
+        self.scored_members_cmd(frame).await
+    }
+
+    /// Returns the rank of `member` in the sorted set stored at `key`, starting at 0 for the
+    /// lowest score.
+    #[instrument(skip(self))]
+    pub async fn zrank(&mut self, key: &str, member: Bytes) -> crate::Result<Option<u64>> {
+        let frame = ZRank::new(key, member).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(rank) => Ok(Some(rank as u64)),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Returns the score of `member` in the sorted set stored at `key`.
+    #[instrument(skip(self))]
+    pub async fn zscore(&mut self, key: &str, member: Bytes) -> crate::Result<Option<f64>> {
+        self.score_cmd(ZScore::new(key, member).into_frame()).await
+    }
+
+    /// Removes `members` from the sorted set stored at `key`. Returns the number of members that
+    /// were removed.
+    #[instrument(skip(self))]
+    pub async fn zrem(&mut self, key: &str, members: Vec<Bytes>) -> crate::Result<u64> {
+        Ok(self
+            .integer_cmd(ZRem::new(key, members).into_frame())
+            .await? as u64)
+    }
+
+    /// Returns the number of members of the sorted set stored at `key` with a score between
+    /// `min` and `max`.
+    #[instrument(skip(self))]
+    pub async fn zcount(
+        &mut self,
+        key: &str,
+        min: ScoreBound,
+        max: ScoreBound,
+    ) -> crate::Result<u64> {
+        Ok(self
+            .integer_cmd(ZCount::new(key, min, max).into_frame())
+            .await? as u64)
+    }
+
+    /// Increments the score of `member` in the sorted set stored at `key` by `increment`.
+    /// Returns the new score.
+    #[instrument(skip(self))]
+    pub async fn zincr_by(
+        &mut self,
+        key: &str,
+        increment: f64,
+        member: Bytes,
+    ) -> crate::Result<f64> {
+        let frame = ZIncrBy::new(key, increment, member).into_frame();
+        self.score_cmd(frame)
+            .await?
+            .ok_or_else(|| "unexpected null score".into())
+    }
+
+    /// Removes and returns up to `count` members with the lowest scores from the sorted set
+    /// stored at `key`, along with their scores.
+    #[instrument(skip(self))]
+    pub async fn zpop_min(&mut self, key: &str, count: u64) -> crate::Result<Vec<(Bytes, f64)>> {
+        let frame = ZPop::new(key, Some(count), false).into_frame();
+        self.scored_members_cmd(frame).await
+    }
+
+    /// Removes and returns up to `count` members with the highest scores from the sorted set
+    /// stored at `key`, along with their scores.
+    #[instrument(skip(self))]
+    pub async fn zpop_max(&mut self, key: &str, count: u64) -> crate::Result<Vec<(Bytes, f64)>> {
+        let frame = ZPop::new(key, Some(count), true).into_frame();
+        self.scored_members_cmd(frame).await
+    }
+
+    /// Appends an entry made of `fields` to the stream stored at `key`, then trims the stream to
+    /// `max_len` entries if given. Returns the ID of the new entry.
+    #[instrument(skip(self))]
+    pub async fn xadd(
+        &mut self,
+        key: &str,
+        id: XAddId,
+        fields: Vec<(Bytes, Bytes)>,
+        max_len: Option<u64>,
+    ) -> crate::Result<StreamId> {
+        let frame = XAdd::new(key, id, fields, max_len).into_frame();
+
+        match self.value_cmd(frame).await? {
+            Some(id) => parse_stream_id(&id),
+            None => Err("unexpected null stream ID".into()),
+        }
+    }
+
+    /// Returns the entries of the stream stored at `key` with an ID between `start` and `end`, at
+    /// most `count` if given.
+    #[instrument(skip(self))]
+    pub async fn xrange(
+        &mut self,
+        key: &str,
+        start: Bound<StreamId>,
+        end: Bound<StreamId>,
+        count: Option<u64>,
+    ) -> crate::Result<Vec<StreamEntry>> {
+        let frame = XRange::new(key, start, end, count, false).into_frame();
+        self.entries_cmd(frame).await
+    }
+
+    /// Same as `xrange`, returning the entries from the greatest ID to the smallest.
+    #[instrument(skip(self))]
+    pub async fn xrevrange(
+        &mut self,
+        key: &str,
+        start: Bound<StreamId>,
+        end: Bound<StreamId>,
+        count: Option<u64>,
+    ) -> crate::Result<Vec<StreamEntry>> {
+        let frame = XRange::new(key, start, end, count, true).into_frame();
+        self.entries_cmd(frame).await
+    }
+
+    /// Returns the number of entries of the stream stored at `key`.
+    #[instrument(skip(self))]
+    pub async fn xlen(&mut self, key: &str) -> crate::Result<u64> {
+        Ok(self.integer_cmd(XLen::new(key).into_frame()).await? as u64)
+    }
+
+    /// Trims the stream stored at `key` to `max_len` entries. Returns the number of entries that
+    /// were removed.
+    #[instrument(skip(self))]
+    pub async fn xtrim(&mut self, key: &str, max_len: u64) -> crate::Result<u64> {
+        Ok(self
+            .integer_cmd(XTrim::new(key, max_len).into_frame())
+            .await? as u64)
+    }
+
+    /// Returns the entries with an ID greater than the given one from each of `streams`, at most
+    /// `count` per stream if given. A `None` ID reads only the entries added after the command is
+    /// received.
+    ///
+    /// If `block` is given and no entries are available, waits for at most that duration, or
+    /// indefinitely if it is zero. Streams without entries are omitted from the result.
+    #[instrument(skip(self))]
+    pub async fn xread(
+        &mut self,
+        streams: &[(String, Option<StreamId>)],
+        count: Option<u64>,
+        block: Option<Duration>,
+    ) -> crate::Result<Vec<(String, Vec<StreamEntry>)>> {
+        let frame = XRead::new(streams, count, block).into_frame();
+        self.read_cmd(frame, parse_entries).await
+    }
+
+    /// Creates the consumer group `group` for the stream stored at `key`, delivering the entries
+    /// following `id`, or only the entries added from now on if `id` is `None`. The stream is
+    /// created if it does not exist and `mk_stream` is set.
+    #[instrument(skip(self))]
+    pub async fn xgroup_create(
+        &mut self,
+        key: &str,
+        group: &str,
+        id: Option<StreamId>,
+        mk_stream: bool,
+    ) -> crate::Result<()> {
+        let frame = XGroup::create(key, group, id, mk_stream).into_frame();
+        self.ok_cmd(frame).await
+    }
+
+    /// Destroys the consumer group `group` of the stream stored at `key`. Returns `true` if the
+    /// group existed.
+    #[instrument(skip(self))]
+    pub async fn xgroup_destroy(&mut self, key: &str, group: &str) -> crate::Result<bool> {
+        let frame = XGroup::destroy(key, group).into_frame();
+        Ok(self.integer_cmd(frame).await? == 1)
+    }
+
+    /// Creates the consumer `consumer` in the consumer group `group` of the stream stored at
+    /// `key`. Returns `true` if the consumer did not exist.
+    #[instrument(skip(self))]
+    pub async fn xgroup_create_consumer(
+        &mut self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+    ) -> crate::Result<bool> {
+        let frame = XGroup::create_consumer(key, group, consumer).into_frame();
+        Ok(self.integer_cmd(frame).await? == 1)
+    }
+
+    /// Deletes the consumer `consumer` from the consumer group `group` of the stream stored at
+    /// `key`, dropping its pending entries. Returns the number of pending entries it owned.
+    #[instrument(skip(self))]
+    pub async fn xgroup_del_consumer(
+        &mut self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+    ) -> crate::Result<u64> {
+        let frame = XGroup::del_consumer(key, group, consumer).into_frame();
+        Ok(self.integer_cmd(frame).await? as u64)
+    }
+
+    /// Sets the last delivered ID of the consumer group `group` of the stream stored at `key` to
+    /// `id`, or to the last ID of the stream if `id` is `None`.
+    #[instrument(skip(self))]
+    pub async fn xgroup_set_id(
+        &mut self,
+        key: &str,
+        group: &str,
+        id: Option<StreamId>,
+    ) -> crate::Result<()> {
+        let frame = XGroup::set_id(key, group, id).into_frame();
+        self.ok_cmd(frame).await
+    }
+
+    /// Reads entries from each of `streams` on behalf of `consumer` of the consumer group
+    /// `group`, at most `count` per stream if given. A `None` ID reads the entries never
+    /// delivered to the group, which become pending for the consumer unless `no_ack` is set.
+    /// Other IDs read the pending entries of the consumer following the ID, without fields for
+    /// the entries removed from the stream since they were delivered.
+    ///
+    /// If `block` is given and no entries are available, waits for at most that duration, or
+    /// indefinitely if it is zero.
+    #[instrument(skip(self))]
+    pub async fn xread_group(
+        &mut self,
+        group: &str,
+        consumer: &str,
+        streams: &[(String, Option<StreamId>)],
+        count: Option<u64>,
+        block: Option<Duration>,
+        no_ack: bool,
+    ) -> crate::Result<Vec<(String, Vec<GroupEntry>)>> {
+        let mut cmd = XReadGroup::new(group, consumer, streams, count, block);
+        if no_ack {
+            cmd = cmd.no_ack();
+        }
+        self.read_cmd(cmd.into_frame(), parse_group_entries).await
+    }
+
+    /// Acknowledges the pending entries `ids` of the consumer group `group` of the stream stored
+    /// at `key`. Returns the number of entries that were pending.
+    #[instrument(skip(self))]
+    pub async fn xack(&mut self, key: &str, group: &str, ids: &[StreamId]) -> crate::Result<u64> {
+        let frame = XAck::new(key, group, ids).into_frame();
+        Ok(self.integer_cmd(frame).await? as u64)
+    }
+
+    /// Returns a summary of the pending entries of the consumer group `group` of the stream
+    /// stored at `key`.
+    #[instrument(skip(self))]
+    pub async fn xpending_summary(
+        &mut self,
+        key: &str,
+        group: &str,
+    ) -> crate::Result<PendingSummary> {
+        let frame = XPending::new(key, group).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        let response = self.read_response().await?;
+        let summary = match &response {
+            Frame::Array(summary) => summary.as_slice(),
+            _ => return Err(response.to_error()),
+        };
+
+        match summary {
+            [Frame::Integer(count), Frame::Null, Frame::Null, Frame::Null] => Ok(PendingSummary {
+                count: *count as usize,
+                range: None,
+                consumers: vec![],
+            }),
+            [Frame::Integer(count), Frame::Bulk(min), Frame::Bulk(max), Frame::Array(consumers)] => {
+                let consumers = consumers
+                    .iter()
+                    .map(|consumer| match consumer {
+                        Frame::Array(consumer) => match consumer.as_slice() {
+                            [Frame::Bulk(name), Frame::Bulk(count)] => {
+                                let name = String::from_utf8(name.to_vec())?;
+                                let count = atoi::atoi::<usize>(count)
+                                    .ok_or("invalid pending summary response")?;
+                                Ok((name, count))
+                            }
+                            _ => Err("invalid pending summary response".into()),
+                        },
+                        frame => Err(frame.to_error()),
+                    })
+                    .collect::<crate::Result<_>>()?;
+
+                Ok(PendingSummary {
+                    count: *count as usize,
+                    range: Some((parse_stream_id(min)?, parse_stream_id(max)?)),
+                    consumers,
+                })
+            }
+            _ => Err("invalid pending summary response".into()),
+        }
+    }
+
+    /// Returns up to `count` pending entries of the consumer group `group` of the stream stored
+    /// at `key` with an ID between `start` and `end`, only the ones owned by `consumer` if given.
+    #[instrument(skip(self))]
+    pub async fn xpending(
+        &mut self,
+        key: &str,
+        group: &str,
+        start: Bound<StreamId>,
+        end: Bound<StreamId>,
+        count: u64,
+        consumer: Option<&str>,
+    ) -> crate::Result<Vec<PendingEntry>> {
+        let mut cmd = XPending::new(key, group).range(start, end, count);
+        if let Some(consumer) = consumer {
+            cmd = cmd.consumer(consumer);
+        }
+        let frame = cmd.into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        let entries = match self.read_response().await? {
+            Frame::Array(entries) => entries,
+            frame => return Err(frame.to_error()),
+        };
+
+        entries
+            .into_iter()
+            .map(|entry| match entry {
+                Frame::Array(entry) => match entry.as_slice() {
+                    [
+                        Frame::Bulk(id),
+                        Frame::Bulk(consumer),
+                        Frame::Integer(idle),
+                        Frame::Integer(count),
+                    ] => Ok(PendingEntry {
+                        id: parse_stream_id(id)?,
+                        consumer: String::from_utf8(consumer.to_vec())?,
+                        idle: Duration::from_millis(*idle as u64),
+                        delivery_count: *count as u64,
+                    }),
+                    _ => Err("invalid pending entry response".into()),
+                },
+                frame => Err(frame.to_error()),
+            })
+            .collect()
+    }
+
+    /// Transfers the ownership of the pending entries `ids` of the consumer group `group` of the
+    /// stream stored at `key` to `consumer`, if they have been idle for at least `min_idle`.
+    /// Returns the claimed entries.
+    #[instrument(skip(self))]
+    pub async fn xclaim(
+        &mut self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+        min_idle: Duration,
+        ids: &[StreamId],
+    ) -> crate::Result<Vec<StreamEntry>> {
+        let frame = XClaim::new(key, group, consumer, min_idle, ids).into_frame();
+        self.entries_cmd(frame).await
+    }
+
+    /// Claims for `consumer` up to `count` pending entries of the consumer group `group` of the
+    /// stream stored at `key` that have been idle for at least `min_idle`, scanning the pending
+    /// entries from `start`.
+    ///
+    /// Returns the ID to resume the scan from, `0-0` once all pending entries were scanned, the
+    /// claimed entries, and the IDs of the pending entries that were removed from the stream.
+    #[instrument(skip(self))]
+    pub async fn xautoclaim(
+        &mut self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+        min_idle: Duration,
+        start: StreamId,
+        count: Option<u64>,
+    ) -> crate::Result<(StreamId, Vec<StreamEntry>, Vec<StreamId>)> {
+        let frame = XAutoClaim::new(key, group, consumer, min_idle, start, count).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        let response = match self.read_response().await? {
+            Frame::Array(response) => response,
+            frame => return Err(frame.to_error()),
+        };
+
+        let mut parts = response.into_iter();
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(Frame::Bulk(next)), Some(entries), Some(Frame::Array(deleted)), None) => {
+                let deleted = deleted
+                    .iter()
+                    .map(|id| match id {
+                        Frame::Bulk(id) => parse_stream_id(id),
+                        frame => Err(frame.to_error()),
+                    })
+                    .collect::<crate::Result<_>>()?;
+
+                Ok((parse_stream_id(&next)?, parse_entries(entries)?, deleted))
+            }
+            _ => Err("invalid autoclaim response".into()),
+        }
+    }
+
+    /// Sends `frame` and expects an array of `[key, entries]` arrays read from streams, or a
+    /// null response if no entries were read. The entries are parsed with `parse`.
+    async fn read_cmd<T>(
+        &mut self,
+        frame: Frame,
+        parse: fn(Frame) -> crate::Result<Vec<T>>,
+    ) -> crate::Result<Vec<(String, Vec<T>)>> {
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
 
-    Ok(Client { connection })
-}
+        match self.read_response().await? {
+            Frame::Array(streams) => streams
+                .into_iter()
+                .map(|stream| match stream {
+                    Frame::Array(stream) => {
+                        let mut parts = stream.into_iter();
+                        match (parts.next(), parts.next(), parts.next()) {
+                            (Some(Frame::Bulk(key)), Some(entries), None) => {
+                                let key = String::from_utf8(key.to_vec())?;
+                                Ok((key, parse(entries)?))
+                            }
+                            _ => Err("invalid stream read response".into()),
+                        }
+                    }
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            Frame::Null => Ok(vec![]),
+            frame => Err(frame.to_error()),
+        }
+    }
 
-impl Client {
-    /// Get the value of the given `key`.
-    #[instrument(skip(self))]
-    pub async fn get(&mut self, key: &str) -> crate::Result<Option<Bytes>> {
-        let frame = Get::new(key).into_frame();
+    /// Sends `frame` and expects an array of stream entries.
+    async fn entries_cmd(&mut self, frame: Frame) -> crate::Result<Vec<StreamEntry>> {
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        parse_entries(self.read_response().await?)
+    }
 
+    /// Sends `frame` and expects a score or a null response.
+    async fn score_cmd(&mut self, frame: Frame) -> crate::Result<Option<f64>> {
         debug!(request = ?frame);
 
         self.connection.write_frame(&frame).await?;
 
         match self.read_response().await? {
-            Frame::Simple(value) => Ok(Some(value.into())),
-            Frame::Bulk(value) => Ok(Some(value)),
             Frame::Null => Ok(None),
+            frame => parse_score(&frame).map(Some),
+        }
+    }
+
+    /// Sends `frame` and expects an array of members, each followed by its score.
+    async fn scored_members_cmd(&mut self, frame: Frame) -> crate::Result<Vec<(Bytes, f64)>> {
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(values) => values
+                .chunks(2)
+                .map(|pair| match pair {
+                    [Frame::Bulk(member), score] => Ok((member.clone(), parse_score(score)?)),
+                    _ => Err("invalid sorted set members response".into()),
+                })
+                .collect(),
             frame => Err(frame.to_error()),
         }
     }
 
-    /// Set `key` to hold the given `value`.
-    #[instrument(skip(self))]
-    pub async fn set(&mut self, key: &str, value: Bytes) -> crate::Result<()> {
-        self.set_cmd(Set::new(key, value, None)).await
+    /// Sends `frame` and expects an array of set members.
+    async fn members_cmd(&mut self, frame: Frame) -> crate::Result<HashSet<Bytes>> {
+        let members = self.values_cmd(frame).await?;
+        Ok(members.unwrap_or_default().into_iter().collect())
     }
 
-    /// Set `key` to hold the given `value`. The `value` expires after `expiration`.
-    #[instrument(skip(self))]
-    pub async fn set_expires(
-        &mut self,
-        key: &str,
-        value: Bytes,
-        expiration: Duration,
-    ) -> crate::Result<()> {
-        self.set_cmd(Set::new(key, value, Some(expiration))).await
+    /// Sends `frame` and expects an `OK` response.
+    async fn ok_cmd(&mut self, frame: Frame) -> crate::Result<()> {
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
     }
 
-    async fn set_cmd(&mut self, cmd: Set) -> crate::Result<()> {
-        let frame = cmd.into_frame();
+    /// Sends `frame` and expects an array of bulk strings or a null response.
+    async fn values_cmd(&mut self, frame: Frame) -> crate::Result<Option<Vec<Bytes>>> {
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(values) => values
+                .into_iter()
+                .map(|value| match value {
+                    Frame::Bulk(value) => Ok(value),
+                    frame => Err(frame.to_error()),
+                })
+                .collect::<crate::Result<_>>()
+                .map(Some),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
 
+    /// Sends `frame` and expects an array of bulk strings and nulls.
+    async fn optional_values_cmd(&mut self, frame: Frame) -> crate::Result<Vec<Option<Bytes>>> {
         debug!(request = ?frame);
 
         self.connection.write_frame(&frame).await?;
 
-        // On success, the server responds simply with `Ok`. Any other response indicates an error.
         match self.read_response().await? {
-            Frame::Simple(response) if response == "OK" => Ok(()),
+            Frame::Array(values) => values
+                .into_iter()
+                .map(|value| match value {
+                    Frame::Bulk(value) => Ok(Some(value)),
+                    Frame::Null => Ok(None),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Sends `frame` and expects a bulk or null response.
+    async fn value_cmd(&mut self, frame: Frame) -> crate::Result<Option<Bytes>> {
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(value) => Ok(Some(value.into())),
+            Frame::Bulk(value) => Ok(Some(value)),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Sends `frame` and expects an integer response.
+    async fn integer_cmd(&mut self, frame: Frame) -> crate::Result<i64> {
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(response) => Ok(response),
             frame => Err(frame.to_error()),
         }
     }
@@ -95,44 +1367,105 @@ impl Client {
     pub async fn publish(&mut self, channel: &str, message: Bytes) -> crate::Result<u64> {
         let frame = Publish::new(channel, message).into_frame();
 
+        // The number of subscribers is never negative.
+        Ok(self.integer_cmd(frame).await? as u64)
+    }
+
+    /// Returns the channels with at least one subscriber, only the ones matching the glob-style
+    /// `pattern` if given.
+    #[instrument(skip(self))]
+    pub async fn pubsub_channels(&mut self, pattern: Option<&str>) -> crate::Result<Vec<String>> {
+        let frame = PubSub::channels(pattern.map(String::from)).into_frame();
+
+        self.values_cmd(frame)
+            .await?
+            .unwrap_or_default()
+            .into_iter()
+            .map(|channel| Ok(String::from_utf8(channel.to_vec())?))
+            .collect()
+    }
+
+    /// Returns the number of subscribers of each of `channels`, not counting the subscribers to
+    /// patterns.
+    #[instrument(skip(self))]
+    pub async fn pubsub_numsub(
+        &mut self,
+        channels: &[String],
+    ) -> crate::Result<Vec<(String, u64)>> {
+        let frame = PubSub::numsub(channels).into_frame();
+
         debug!(request = ?frame);
 
         self.connection.write_frame(&frame).await?;
 
         match self.read_response().await? {
-            Frame::Integer(response) => Ok(response),
+            Frame::Array(values) => values
+                .chunks(2)
+                .map(|pair| match pair {
+                    [Frame::Bulk(channel), Frame::Integer(count)] => {
+                        Ok((String::from_utf8(channel.to_vec())?, *count as u64))
+                    }
+                    _ => Err("invalid numsub response".into()),
+                })
+                .collect(),
             frame => Err(frame.to_error()),
         }
     }
 
+    /// Returns the number of patterns with at least one subscriber.
+    #[instrument(skip(self))]
+    pub async fn pubsub_numpat(&mut self) -> crate::Result<u64> {
+        Ok(self.integer_cmd(PubSub::numpat().into_frame()).await? as u64)
+    }
+
     /// Subscribe the client to the specified channels.
     ///
     /// Once a client issues a subscribe command, it may no longer issue any
     /// non-pub/sub commands.
     #[instrument(skip(self))]
     pub async fn subscribe(mut self, channels: Vec<String>) -> crate::Result<Subscriber> {
-        self.subscribe_cmd(&channels).await?;
+        let frame = Subscribe::new(&channels).into_frame();
+        self.subscribe_cmd(frame, "subscribe", &channels).await?;
 
         Ok(Subscriber {
             client: self,
             subscribed_channels: channels,
+            subscribed_patterns: vec![],
         })
     }
 
-    async fn subscribe_cmd(&mut self, channels: &[String]) -> crate::Result<()> {
-        let frame = Subscribe::new(&channels).into_frame();
+    /// Subscribe the client to the channels matching the specified glob-style patterns.
+    ///
+    /// As with `subscribe`, the client may no longer issue any non-pub/sub commands.
+    #[instrument(skip(self))]
+    pub async fn psubscribe(mut self, patterns: Vec<String>) -> crate::Result<Subscriber> {
+        let frame = PSubscribe::new(&patterns).into_frame();
+        self.subscribe_cmd(frame, "psubscribe", &patterns).await?;
+
+        Ok(Subscriber {
+            client: self,
+            subscribed_channels: vec![],
+            subscribed_patterns: patterns,
+        })
+    }
 
+    /// Sends the subscription `frame` and expects a `kind` confirmation for each of `names`.
+    async fn subscribe_cmd(
+        &mut self,
+        frame: Frame,
+        kind: &str,
+        names: &[String],
+    ) -> crate::Result<()> {
         debug!(request = ?frame);
 
         self.connection.write_frame(&frame).await?;
 
-        for channel in channels {
+        for name in names {
             let response = self.read_response().await?;
 
             match response {
                 Frame::Array(ref frame) => match frame.as_slice() {
-                    [subscribe, schannel, ..]
-                        if *subscribe == "subscribe" && *schannel == channel => {}
+                    [subscribe, sname, ..] if *subscribe == kind && *sname == name => {}
                     _ => return Err(response.to_error()),
                 },
                 frame => return Err(frame.to_error()),
@@ -142,13 +1475,128 @@ impl Client {
         Ok(())
     }
 
+    /// Runs the Lua `script` on the server with the given `keys` and `args` and returns its
+    /// result. The script is cached, so that it can later be run by `evalsha`.
+    #[instrument(skip(self))]
+    pub async fn eval(
+        &mut self,
+        script: &str,
+        keys: &[String],
+        args: Vec<Bytes>,
+    ) -> crate::Result<Frame> {
+        let script = Bytes::from(script.to_string());
+        self.eval_cmd(Eval::new(script, keys, args)).await
+    }
+
+    /// Runs the cached script whose SHA1 digest is `sha1` with the given `keys` and `args` and
+    /// returns its result.
+    #[instrument(skip(self))]
+    pub async fn evalsha(
+        &mut self,
+        sha1: &str,
+        keys: &[String],
+        args: Vec<Bytes>,
+    ) -> crate::Result<Frame> {
+        self.eval_cmd(Eval::sha1(sha1, keys, args)).await
+    }
+
+    /// Sends an `Eval` command and returns the result of the script.
+    async fn eval_cmd(&mut self, cmd: Eval) -> crate::Result<Frame> {
+        let frame = cmd.into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        self.read_response().await
+    }
+
+    /// Caches the Lua `script` without running it and returns its SHA1 digest.
+    #[instrument(skip(self))]
+    pub async fn script_load(&mut self, script: &str) -> crate::Result<String> {
+        let frame = Script::load(Bytes::from(script.to_string())).into_frame();
+
+        match self.value_cmd(frame).await? {
+            Some(sha1) => Ok(std::str::from_utf8(&sha1)?.to_string()),
+            None => Err("invalid script load response".into()),
+        }
+    }
+
+    /// Returns whether each of the scripts whose SHA1 digests are `sha1s` is cached.
+    #[instrument(skip(self))]
+    pub async fn script_exists(&mut self, sha1s: &[String]) -> crate::Result<Vec<bool>> {
+        let frame = Script::exists(sha1s).into_frame();
+
+        debug!(request = ?frame);
+
+        self.connection.write_frame(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Array(response) => response
+                .into_iter()
+                .map(|exists| match exists {
+                    Frame::Integer(exists) => Ok(exists == 1),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// Removes all the cached scripts.
+    #[instrument(skip(self))]
+    pub async fn script_flush(&mut self) -> crate::Result<()> {
+        self.ok_cmd(Script::flush().into_frame()).await
+    }
+
+    /// Watch the given `keys`, so that the next transaction is aborted if any of them is
+    /// modified, expires or is deleted before it is applied.
+    #[instrument(skip(self))]
+    pub async fn watch(&mut self, keys: &[String]) -> crate::Result<()> {
+        self.ok_cmd(Watch::new(keys).into_frame()).await
+    }
+
+    /// Forget all the keys watched by the connection.
+    #[instrument(skip(self))]
+    pub async fn unwatch(&mut self) -> crate::Result<()> {
+        self.ok_cmd(Unwatch::new().into_frame()).await
+    }
+
+    /// Start building a transaction, whose commands are applied atomically by the server.
+    pub fn transaction(&mut self) -> Transaction<'_> {
+        Transaction {
+            client: self,
+            commands: vec![],
+        }
+    }
+
+    /// Send an arbitrary command frame to the server and return its reply.
+    ///
+    /// Unlike the typed methods, an error reply is returned as `Frame::Error` instead of `Err`,
+    /// so callers can forward user-supplied commands and display whatever the server answers.
+    #[instrument(skip(self))]
+    pub async fn execute(&mut self, frame: &Frame) -> crate::Result<Frame> {
+        debug!(request = ?frame);
+
+        self.connection.write_frame(frame).await?;
+
+        self.read_reply().await
+    }
+
     async fn read_response(&mut self) -> crate::Result<Frame> {
+        match self.read_reply().await? {
+            Frame::Error(msg) => Err(msg.into()),
+            frame => Ok(frame),
+        }
+    }
+
+    /// Reads the next reply frame, returning error replies as `Frame::Error`.
+    async fn read_reply(&mut self) -> crate::Result<Frame> {
         let response = self.connection.read_frame().await?;
 
         debug!(?response);
 
         match response {
-            Some(Frame::Error(msg)) => Err(msg.into()),
             Some(frame) => Ok(frame),
             // `None` here indicates the server has closed the connection without sending a frame.
             // This is unexpected and is represented as a "connection reset by peer" error.
@@ -167,18 +1615,52 @@ impl Subscriber {
         &self.subscribed_channels
     }
 
+    /// Returns the set of patterns currently subscribed to.
+    pub fn get_subscribed_patterns(&self) -> &[String] {
+        &self.subscribed_patterns
+    }
+
     /// Receive the next message published on a subscribed channel, waiting if necessary.
+    ///
+    /// Notifications of missed messages are skipped, use `next_event` to receive them.
     pub async fn next_message(&mut self) -> crate::Result<Option<Message>> {
+        loop {
+            match self.next_event().await? {
+                Some(Event::Message(message)) => return Ok(Some(message)),
+                Some(Event::Lagged { .. }) => {}
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Receive the next message or notification of missed messages, waiting if necessary.
+    pub async fn next_event(&mut self) -> crate::Result<Option<Event>> {
         match self.client.connection.read_frame().await? {
             Some(mframe) => {
                 debug!(?mframe);
 
                 match mframe {
                     Frame::Array(ref frame) => match frame.as_slice() {
-                        [message, channel, content] if *message == "message" => Ok(Some(Message {
-                            channel: channel.to_string(),
-                            content: Bytes::from(content.to_string()),
-                        })),
+                        [message, channel, content] if *message == "message" => {
+                            Ok(Some(Event::Message(Message {
+                                channel: channel.to_string(),
+                                content: Bytes::from(content.to_string()),
+                                pattern: None,
+                            })))
+                        }
+                        [pmessage, pattern, channel, content] if *pmessage == "pmessage" => {
+                            Ok(Some(Event::Message(Message {
+                                channel: channel.to_string(),
+                                content: Bytes::from(content.to_string()),
+                                pattern: Some(pattern.to_string()),
+                            })))
+                        }
+                        [lagged, subscription, Frame::Integer(skipped)] if *lagged == "lagged" => {
+                            Ok(Some(Event::Lagged {
+                                subscription: subscription.to_string(),
+                                skipped: *skipped as u64,
+                            }))
+                        }
                         _ => Err(mframe.to_error()),
                     },
                     frame => Err(frame.to_error()),
@@ -190,11 +1672,11 @@ impl Subscriber {
     }
 
     /// Convert the subscriber into a `Stream` yielding new messages published on
-    /// subscribed channels.
-    pub fn into_stream(mut self) -> impl Stream<Item = crate::Result<Message>> {
+    /// subscribed channels, along with notifications of missed messages.
+    pub fn into_stream(mut self) -> impl Stream<Item = crate::Result<Event>> {
         try_stream! {
-            while let Some(message) = self.next_message().await? {
-                yield message;
+            while let Some(event) = self.next_event().await? {
+                yield event;
             }
         }
     }
@@ -202,7 +1684,10 @@ impl Subscriber {
     /// Subscribe to a list of new channels.
     #[instrument(skip(self))]
     pub async fn subscribe(&mut self, channels: &[String]) -> crate::Result<()> {
-        self.client.subscribe_cmd(channels).await?;
+        let frame = Subscribe::new(channels).into_frame();
+        self.client
+            .subscribe_cmd(frame, "subscribe", channels)
+            .await?;
 
         self.subscribed_channels
             .extend(channels.iter().map(Clone::clone));
@@ -210,21 +1695,61 @@ impl Subscriber {
         Ok(())
     }
 
+    /// Subscribe to a list of new glob-style patterns.
+    #[instrument(skip(self))]
+    pub async fn psubscribe(&mut self, patterns: &[String]) -> crate::Result<()> {
+        let frame = PSubscribe::new(patterns).into_frame();
+        self.client
+            .subscribe_cmd(frame, "psubscribe", patterns)
+            .await?;
+
+        self.subscribed_patterns.extend_from_slice(patterns);
+
+        Ok(())
+    }
+
     /// Unsbuscribe to a list of new channels.
     #[instrument(skip(self))]
     pub async fn unsubscribe(&mut self, channels: &[String]) -> crate::Result<()> {
-        let frame = Unsubscribe::new(&channels).into_frame();
+        let frame = Unsubscribe::new(channels).into_frame();
+        self.unsubscribe_cmd(frame, "unsubscribe", channels, false)
+            .await
+    }
+
+    /// Unsubscribe from a list of patterns, or from all of them if the list is empty.
+    #[instrument(skip(self))]
+    pub async fn punsubscribe(&mut self, patterns: &[String]) -> crate::Result<()> {
+        let frame = PUnsubscribe::new(patterns).into_frame();
+        self.unsubscribe_cmd(frame, "punsubscribe", patterns, true)
+            .await
+    }
 
+    /// Sends the unsubscription `frame` and expects a `kind` confirmation for each of `names`,
+    /// removing them from the subscribed patterns if `patterns` is set, or from the subscribed
+    /// channels otherwise.
+    async fn unsubscribe_cmd(
+        &mut self,
+        frame: Frame,
+        kind: &str,
+        names: &[String],
+        patterns: bool,
+    ) -> crate::Result<()> {
         debug!(request = ?frame);
 
         self.client.connection.write_frame(&frame).await?;
 
-        // If the input channel list is empty, server acknowleges as unsubscribing from
-        // all channels.
-        let num_subs = if channels.is_empty() {
-            self.subscribed_channels.len()
+        let subscribed = if patterns {
+            &mut self.subscribed_patterns
         } else {
-            channels.len()
+            &mut self.subscribed_channels
+        };
+
+        // If the input list is empty, server acknowleges as unsubscribing from all channels or
+        // patterns.
+        let num_subs = if names.is_empty() {
+            subscribed.len()
+        } else {
+            names.len()
         };
 
         for _ in 0..num_subs {
@@ -232,14 +1757,14 @@ impl Subscriber {
 
             match response {
                 Frame::Array(ref frame) => match frame.as_slice() {
-                    [unsubscribe, channel, ..] if *unsubscribe == "unsubscribe" => {
-                        let len = self.subscribed_channels.len();
+                    [unsubscribe, name, ..] if *unsubscribe == kind => {
+                        let len = subscribed.len();
 
                         if len == 0 {
                             return Err(response.to_error());
                         }
 
-                        self.subscribed_channels.retain(|c| *channel != &c[..]);
+                        subscribed.retain(|c| *name != &c[..]);
                     }
                     _ => return Err(response.to_error()),
                 },
@@ -250,3 +1775,180 @@ impl Subscriber {
         Ok(())
     }
 }
+
+impl Transaction<'_> {
+    /// Queue a command getting the value of `key`.
+    pub fn get(&mut self, key: &str) -> &mut Self {
+        self.command(Get::new(key).into_frame())
+    }
+
+    /// Queue a command setting `key` to hold `value`.
+    pub fn set(&mut self, key: &str, value: Bytes) -> &mut Self {
+        self.command(Set::new(key, value, None).into_frame())
+    }
+
+    /// Queue a command setting `key` to hold `value`, which expires after `expiration`.
+    pub fn set_expires(&mut self, key: &str, value: Bytes, expiration: Duration) -> &mut Self {
+        self.command(Set::new(key, value, Some(expiration)).into_frame())
+    }
+
+    /// Queue a command removing the given `keys`.
+    pub fn del(&mut self, keys: &[String]) -> &mut Self {
+        self.command(Del::new(keys).into_frame())
+    }
+
+    /// Queue a command adding `increment` to the integer stored at `key`.
+    pub fn incr_by(&mut self, key: &str, increment: i64) -> &mut Self {
+        self.command(Incr::new(key, increment).into_frame())
+    }
+
+    /// Queue a command setting a timeout on `key`.
+    pub fn expire(&mut self, key: &str, timeout: Duration) -> &mut Self {
+        self.command(Expire::new(key, timeout).into_frame())
+    }
+
+    /// Queue a command inserting `values` at the tail of the list stored at `key`.
+    pub fn rpush(&mut self, key: &str, values: Vec<Bytes>) -> &mut Self {
+        self.command(Push::new(key, values, false).into_frame())
+    }
+
+    /// Queue a command posting `message` to the given `channel`.
+    pub fn publish(&mut self, channel: &str, message: Bytes) -> &mut Self {
+        self.command(Publish::new(channel, message).into_frame())
+    }
+
+    /// Queue an arbitrary command frame.
+    pub fn command(&mut self, frame: Frame) -> &mut Self {
+        self.commands.push(frame);
+        self
+    }
+
+    /// Send the queued commands and apply them atomically. Returns the reply of each command, in
+    /// the order they were queued, or `None` if a key watched with `Client::watch` changed in
+    /// the meantime and the transaction was aborted.
+    ///
+    /// As with `Client::execute`, a command failing when applied is replied with
+    /// `Frame::Error`. If the server refuses to queue a command, the transaction is discarded
+    /// and `Err` is returned.
+    #[instrument(skip(self))]
+    pub async fn exec(&mut self) -> crate::Result<Option<Vec<Frame>>> {
+        let commands = std::mem::take(&mut self.commands);
+
+        self.client.ok_cmd(Multi::new().into_frame()).await?;
+
+        // Send all the commands before reading the replies, so that a single round trip is
+        // needed in the common case.
+        for frame in &commands {
+            debug!(request = ?frame);
+            self.client.connection.write_frame(frame).await?;
+        }
+
+        let mut refused = None;
+        for _ in &commands {
+            match self.client.read_reply().await? {
+                Frame::Simple(response) if response == "QUEUED" => {}
+                frame => {
+                    refused.get_or_insert(frame);
+                }
+            }
+        }
+
+        if let Some(frame) = refused {
+            self.client.ok_cmd(Discard::new().into_frame()).await?;
+            return Err(frame.to_error());
+        }
+
+        let frame = Exec::new().into_frame();
+
+        debug!(request = ?frame);
+
+        self.client.connection.write_frame(&frame).await?;
+
+        match self.client.read_response().await? {
+            Frame::Array(replies) => Ok(Some(replies)),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+}
+
+/// Builds a `ZRange` command without scores.
+fn zrange_cmd(key: &str, by: ZRangeBy, rev: bool, limit: Option<(u64, i64)>) -> ZRange {
+    let mut cmd = ZRange::new(key, by);
+    if rev {
+        cmd = cmd.rev();
+    }
+    if let Some((offset, count)) = limit {
+        cmd = cmd.limit(offset, count);
+    }
+    cmd
+}
+
+/// Parses a sorted set score, received as a double or as a bulk string.
+fn parse_score(frame: &Frame) -> crate::Result<f64> {
+    match frame {
+        Frame::Double(score) => Ok(*score),
+        Frame::Bulk(score) => std::str::from_utf8(score)
+            .ok()
+            .and_then(|score| score.parse().ok())
+            .ok_or_else(|| "invalid score response".into()),
+        frame => Err(frame.to_error()),
+    }
+}
+
+/// Parses a stream ID received as a bulk string.
+fn parse_stream_id(id: &[u8]) -> crate::Result<StreamId> {
+    let parsed = std::str::from_utf8(id).ok().and_then(|id| {
+        let (ms, seq) = id.split_once('-')?;
+        Some(StreamId {
+            ms: ms.parse().ok()?,
+            seq: seq.parse().ok()?,
+        })
+    });
+
+    parsed.ok_or_else(|| "invalid stream ID response".into())
+}
+
+/// Parses an array of stream entries, each being an array made of the entry ID and of an array
+/// of field-value pairs.
+fn parse_entries(frame: Frame) -> crate::Result<Vec<StreamEntry>> {
+    parse_group_entries(frame)?
+        .into_iter()
+        .map(|(id, fields)| match fields {
+            Some(fields) => Ok((id, fields)),
+            None => Err("invalid stream entry response".into()),
+        })
+        .collect()
+}
+
+/// Parses an array of stream entries read by a consumer, as `parse_entries` does, the fields
+/// being null for the pending entries removed from the stream.
+fn parse_group_entries(frame: Frame) -> crate::Result<Vec<GroupEntry>> {
+    let entries = match frame {
+        Frame::Array(entries) => entries,
+        frame => return Err(frame.to_error()),
+    };
+
+    entries
+        .into_iter()
+        .map(|entry| match entry {
+            Frame::Array(entry) => match entry.as_slice() {
+                [Frame::Bulk(id), Frame::Array(values)] => {
+                    let fields = values
+                        .chunks(2)
+                        .map(|pair| match pair {
+                            [Frame::Bulk(field), Frame::Bulk(value)] => {
+                                Ok((field.clone(), value.clone()))
+                            }
+                            _ => Err("invalid stream entry response".into()),
+                        })
+                        .collect::<crate::Result<_>>()?;
+                    Ok((parse_stream_id(id)?, Some(fields)))
+                }
+                [Frame::Bulk(id), Frame::Null] => Ok((parse_stream_id(id)?, None)),
+                _ => Err("invalid stream entry response".into()),
+            },
+            frame => Err(frame.to_error()),
+        })
+        .collect()
+}
@@ -0,0 +1,91 @@
+use crate::{Connection, Db, Parse};
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::fmt;
+
+/// A command implemented outside of indb.
+///
+/// Handlers are registered by name in a `CommandRegistry` given to the server. Commands indb
+/// does not implement are looked up in the registry before being replied to as unknown.
+///
+/// Custom commands cannot be queued in a transaction nor called from a script. Within `MULTI`,
+/// they are rejected and the transaction is aborted, as the `Db` cannot be locked while their
+/// handler awaits. Scripts only see the commands indb implements, `redis.call` replies to
+/// custom commands as unknown.
+#[async_trait]
+pub trait CommandHandler: fmt::Debug + Send {
+    /// Parses the command from a received frame. The command name has already been consumed.
+    ///
+    /// Remaining arguments are rejected once `parse` returns.
+    fn parse(parse: &mut Parse) -> crate::Result<Self>
+    where
+        Self: Sized;
+
+    /// Applies the command to the specified `Db` instance and writes the response to `dst`.
+    ///
+    /// Handlers wait for the exclusive section another client may run, such as a transaction or
+    /// a script, with `Db::enter_section` before calling the `Db`, as the commands indb
+    /// implements do. The section must end before the handler awaits again.
+    async fn apply(self: Box<Self>, db: &Db, dst: &mut Connection) -> crate::Result<()>;
+}
+
+/// Custom commands, by name.
+#[derive(Debug, Default, Clone)]
+pub struct CommandRegistry {
+    parsers: HashMap<String, ParseFn>,
+}
+
+/// Parses a custom command into its type-erased handler.
+type ParseFn = fn(&mut Parse) -> crate::Result<Box<dyn CommandHandler>>;
+
+/// A command parsed by a registered `CommandHandler`.
+#[derive(Debug)]
+pub struct Custom {
+    name: String,
+    handler: Box<dyn CommandHandler>,
+}
+
+impl CommandRegistry {
+    /// Create a new, empty, `CommandRegistry`.
+    pub fn new() -> CommandRegistry {
+        CommandRegistry::default()
+    }
+
+    /// Registers `T` as the handler of the `name` command. Names are case insensitive and a
+    /// previous handler of the same command is replaced.
+    ///
+    /// Commands implemented by indb take precedence, they cannot be overridden.
+    pub fn register<T: CommandHandler + 'static>(&mut self, name: &str) -> &mut CommandRegistry {
+        self.parsers.insert(name.to_lowercase(), parse_handler::<T>);
+        self
+    }
+
+    /// Parses the `name` command with its registered handler, if any.
+    pub(crate) fn parse(&self, name: &str, parse: &mut Parse) -> Option<crate::Result<Custom>> {
+        let parse_fn = self.parsers.get(name)?;
+
+        Some(parse_fn(parse).map(|handler| Custom {
+            name: name.to_string(),
+            handler,
+        }))
+    }
+}
+
+impl Custom {
+    /// Returns the command name.
+    pub(crate) fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Apply the command with its handler.
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        self.handler.apply(db, dst).await
+    }
+}
+
+fn parse_handler<T: CommandHandler + 'static>(
+    parse: &mut Parse,
+) -> crate::Result<Box<dyn CommandHandler>> {
+    Ok(Box::new(T::parse(parse)?))
+}
@@ -0,0 +1,272 @@
+use crate::{Db, Frame, Parse};
+
+use bytes::Bytes;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::Instant;
+use tracing::{debug, instrument};
+
+/// Set a timeout on key. After the timeout has expired, the key is automatically deleted.
+///
+/// The timeout is given either relative to now (EXPIRE, PEXPIRE) or as an absolute unix time
+/// (EXPIREAT, PEXPIREAT). A timeout in the past deletes the key immediately. The value stored
+/// at key is not modified.
+#[derive(Debug)]
+pub struct Expire {
+    key: String,
+    timeout: Timeout,
+}
+
+/// How the timeout of an `Expire` command is expressed.
+#[derive(Debug, Clone, Copy)]
+enum Timeout {
+    /// Seconds from now, as given to EXPIRE.
+    Secs(i64),
+    /// Milliseconds from now, as given to PEXPIRE.
+    Millis(i64),
+    /// Unix time in seconds, as given to EXPIREAT.
+    UnixSecs(i64),
+    /// Unix time in milliseconds, as given to PEXPIREAT.
+    UnixMillis(i64),
+}
+
+/// Returns the remaining time to live of a key that has a timeout.
+///
+/// TTL replies in seconds and PTTL in milliseconds. `-2` is returned if the key does not exist
+/// and `-1` if the key exists but has no associated expire.
+#[derive(Debug)]
+pub struct Ttl {
+    key: String,
+    millis: bool,
+}
+
+/// Remove the existing timeout on key, turning the key from volatile to persistent.
+#[derive(Debug)]
+pub struct Persist {
+    key: String,
+}
+
+impl Expire {
+    /// Create a new `Expire` command which expires `key` after `timeout`.
+    pub fn new(key: impl ToString, timeout: Duration) -> Expire {
+        Expire {
+            key: key.to_string(),
+            timeout: Timeout::Millis(timeout.as_millis() as i64),
+        }
+    }
+
+    /// Create a new `Expire` command which expires `key` at the given point in time.
+    pub fn at(key: impl ToString, when: SystemTime) -> Expire {
+        // A time before the unix epoch is certainly in the past.
+        let millis = when
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_millis() as i64)
+            .unwrap_or(0);
+
+        Expire {
+            key: key.to_string(),
+            timeout: Timeout::UnixMillis(millis),
+        }
+    }
+
+    /// Parses an `Expire` instance from a received frame. `name` is the lowercase command name
+    /// and determines how the timeout is interpreted.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// EXPIRE key seconds
+    /// PEXPIRE key milliseconds
+    /// EXPIREAT key unix-time-seconds
+    /// PEXPIREAT key unix-time-milliseconds
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse, name: &str) -> crate::Result<Expire> {
+        let key = parse.next_string()?;
+        let time = parse.next_signed_int()?;
+
+        let timeout = match name {
+            "expire" => Timeout::Secs(time),
+            "pexpire" => Timeout::Millis(time),
+            "expireat" => Timeout::UnixSecs(time),
+            "pexpireat" => Timeout::UnixMillis(time),
+            _ => return Err(format!("unexpected expire command {}", name).into()),
+        };
+
+        Ok(Expire { key, timeout })
+    }
+
+    /// Apply the `Expire` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match self.timeout.deadline() {
+            Some(deadline) => Frame::Integer(db.expire(&self.key, deadline) as i64),
+            None => Frame::Error(format!(
+                "ERR invalid expire time in '{}' command",
+                self.get_name()
+            )),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    /// Returns the command name.
+    pub(crate) fn get_name(&self) -> &str {
+        match self.timeout {
+            Timeout::Secs(_) => "expire",
+            Timeout::Millis(_) => "pexpire",
+            Timeout::UnixSecs(_) => "expireat",
+            Timeout::UnixMillis(_) => "pexpireat",
+        }
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let time = match self.timeout {
+            Timeout::Secs(time)
+            | Timeout::Millis(time)
+            | Timeout::UnixSecs(time)
+            | Timeout::UnixMillis(time) => time,
+        };
+
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from(self.get_name().as_bytes().to_vec()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_int(time);
+        frame
+    }
+}
+
+impl Timeout {
+    /// Converts the timeout into the instant at which the key expires. Returns `None` if the
+    /// instant is too far in the future to be represented.
+    fn deadline(self) -> Option<Instant> {
+        let (millis, absolute) = match self {
+            Timeout::Secs(secs) => (secs.saturating_mul(1000), false),
+            Timeout::Millis(millis) => (millis, false),
+            Timeout::UnixSecs(secs) => (secs.saturating_mul(1000), true),
+            Timeout::UnixMillis(millis) => (millis, true),
+        };
+
+        // Negative values mean the deadline has already passed.
+        let millis = Duration::from_millis(millis.max(0) as u64);
+
+        if absolute {
+            instant_at(millis)
+        } else {
+            Instant::now().checked_add(millis)
+        }
+    }
+}
+
+/// Converts a unix time, given as the duration since the unix epoch, into an `Instant`. Times
+/// in the past map to now. Returns `None` if the time is too far in the future to be
+/// represented.
+pub(crate) fn instant_at(since_epoch: Duration) -> Option<Instant> {
+    let when = UNIX_EPOCH.checked_add(since_epoch)?;
+    let now = Instant::now();
+
+    match when.duration_since(SystemTime::now()) {
+        Ok(remaining) => now.checked_add(remaining),
+        // The given time is in the past.
+        Err(_) => Some(now),
+    }
+}
+
+impl Ttl {
+    /// Create a new `Ttl` command for `key`. If `millis` is set, the time to live is returned in
+    /// milliseconds instead of seconds.
+    pub fn new(key: impl ToString, millis: bool) -> Ttl {
+        Ttl {
+            key: key.to_string(),
+            millis,
+        }
+    }
+
+    /// Parses a `Ttl` instance from a received frame. `millis` is set when the frame was a PTTL
+    /// command.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// TTL key
+    /// PTTL key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse, millis: bool) -> crate::Result<Ttl> {
+        let key = parse.next_string()?;
+
+        Ok(Ttl { key, millis })
+    }
+
+    /// Apply the `Ttl` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let ttl = match db.ttl(&self.key) {
+            None => -2,
+            Some(None) => -1,
+            Some(Some(ttl)) if self.millis => ttl.as_millis() as i64,
+            // Round to the nearest second.
+            Some(Some(ttl)) => ((ttl.as_millis() + 500) / 1000) as i64,
+        };
+
+        let response = Frame::Integer(ttl);
+
+        debug!(?response);
+
+        response
+    }
+
+    /// Returns the command name.
+    pub(crate) fn get_name(&self) -> &str {
+        if self.millis {
+            "pttl"
+        } else {
+            "ttl"
+        }
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from(self.get_name().as_bytes().to_vec()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
+
+impl Persist {
+    /// Create a new `Persist` command for `key`.
+    pub fn new(key: impl ToString) -> Persist {
+        Persist {
+            key: key.to_string(),
+        }
+    }
+
+    /// Parses a `Persist` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// PERSIST key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Persist> {
+        let key = parse.next_string()?;
+
+        Ok(Persist { key })
+    }
+
+    /// Apply the `Persist` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = Frame::Integer(db.persist(&self.key) as i64);
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("persist".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{Connection, Db, Frame, Parse};
+use crate::{Db, Frame, Parse};
 
 use bytes::Bytes;
 use tracing::{debug, instrument};
@@ -33,22 +33,19 @@ impl Get {
         Ok(Get { key })
     }
 
-    /// Apply the `Get` command to the specified `Db` instance and write the response to `dst`.
-    #[instrument(skip(self, db, dst))]
-    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+    /// Apply the `Get` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
         // Get the value from the shared database state.
-        let response = if let Some(value) = db.get(&self.key) {
-            Frame::Bulk(value)
-        } else {
-            Frame::Null
+        let response = match db.try_get(&self.key) {
+            Ok(Some(value)) => Frame::Bulk(value),
+            Ok(None) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
         };
 
         debug!(?response);
 
-        // Write the response back to the client
-        dst.write_frame(&response).await?;
-
-        Ok(())
+        response
     }
 
     pub(crate) fn into_frame(self) -> Frame {
@@ -0,0 +1,970 @@
+use crate::cmd::stream::{
+    entries_frame, entry_frame, parse_range_bound, parse_stream_id, range_bound_to_bytes,
+};
+use crate::{
+    Connection, Db, Frame, GroupEntry, Parse, ParseError, Shutdown, StreamEntry, StreamId,
+};
+
+use bytes::Bytes;
+use std::ops::Bound;
+use std::time::Duration;
+use tracing::{debug, instrument};
+
+/// Manages the consumer groups of the stream stored at key.
+///
+/// A consumer group delivers each entry of the stream to a single one of its consumers, and
+/// tracks the delivered entries as pending until they are acknowledged with `XACK`.
+#[derive(Debug)]
+pub struct XGroup {
+    key: String,
+    group: String,
+    op: GroupOp,
+}
+
+/// Operation performed by `XGroup`.
+#[derive(Debug)]
+enum GroupOp {
+    /// Create the group, delivering the entries following the ID. `None` stands for `$`, the
+    /// last ID of the stream. The stream is created if `mk_stream` is set.
+    Create {
+        id: Option<StreamId>,
+        mk_stream: bool,
+    },
+    Destroy,
+    CreateConsumer(String),
+    DelConsumer(String),
+    /// Set the last delivered ID of the group. `None` stands for `$`.
+    SetId(Option<StreamId>),
+}
+
+/// Reads entries from one or more streams on behalf of a consumer of a consumer group.
+///
+/// The special `>` ID delivers the entries never delivered to the group, which become pending
+/// for the consumer until acknowledged, unless `NOACK` is given. Other IDs return the pending
+/// entries of the consumer following the ID. With the `BLOCK` option, the connection blocks
+/// until new entries are available, as for `XREAD`.
+#[derive(Debug)]
+pub struct XReadGroup {
+    group: String,
+    consumer: String,
+    /// Keys of the streams to read, along with the ID to read after. `None` stands for `>`.
+    streams: Vec<(String, Option<StreamId>)>,
+    count: Option<u64>,
+    /// Maximum time to block. `None` does not block, and zero blocks indefinitely.
+    block: Option<Duration>,
+    no_ack: bool,
+}
+
+/// Acknowledges pending entries of a consumer group, removing them from the pending entries.
+/// Returns the number of acknowledged entries.
+#[derive(Debug)]
+pub struct XAck {
+    key: String,
+    group: String,
+    ids: Vec<StreamId>,
+}
+
+/// Inspects the pending entries of a consumer group.
+///
+/// Without a range, returns a summary of the pending entries. Otherwise, returns the details of
+/// the pending entries within the range, optionally restricted to a consumer and to the entries
+/// idle for a minimum time.
+#[derive(Debug)]
+pub struct XPending {
+    key: String,
+    group: String,
+    /// Range of IDs and maximum number of entries to return.
+    range: Option<(Bound<StreamId>, Bound<StreamId>, u64)>,
+    consumer: Option<String>,
+    min_idle: Option<Duration>,
+}
+
+/// Transfers the ownership of pending entries of a consumer group to a consumer, if they have
+/// been idle for a minimum time. Returns the claimed entries, or only their IDs if `JUSTID` is
+/// given.
+#[derive(Debug)]
+pub struct XClaim {
+    key: String,
+    group: String,
+    consumer: String,
+    min_idle: Duration,
+    ids: Vec<StreamId>,
+    just_id: bool,
+}
+
+/// Claims the pending entries of a consumer group that have been idle for a minimum time,
+/// scanning the pending entries from the given ID.
+///
+/// Returns the ID to resume the scan from, or `0-0` once all pending entries were scanned,
+/// the claimed entries, and the IDs of the pending entries removed from the stream, which are
+/// dropped from the group.
+#[derive(Debug)]
+pub struct XAutoClaim {
+    key: String,
+    group: String,
+    consumer: String,
+    min_idle: Duration,
+    start: StreamId,
+    count: Option<u64>,
+    just_id: bool,
+}
+
+impl XGroup {
+    /// Create a new `XGroup` command which creates the consumer group `group` for the stream
+    /// stored at `key`, delivering the entries following `id`. A `None` ID delivers only the
+    /// entries added from now on. The stream is created if `mk_stream` is set.
+    pub fn create(
+        key: impl ToString,
+        group: impl ToString,
+        id: Option<StreamId>,
+        mk_stream: bool,
+    ) -> XGroup {
+        XGroup::new(key, group, GroupOp::Create { id, mk_stream })
+    }
+
+    /// Create a new `XGroup` command which destroys the consumer group `group` of the stream
+    /// stored at `key`.
+    pub fn destroy(key: impl ToString, group: impl ToString) -> XGroup {
+        XGroup::new(key, group, GroupOp::Destroy)
+    }
+
+    /// Create a new `XGroup` command which creates the consumer `consumer` in the consumer group
+    /// `group` of the stream stored at `key`.
+    pub fn create_consumer(
+        key: impl ToString,
+        group: impl ToString,
+        consumer: impl ToString,
+    ) -> XGroup {
+        XGroup::new(key, group, GroupOp::CreateConsumer(consumer.to_string()))
+    }
+
+    /// Create a new `XGroup` command which deletes the consumer `consumer` from the consumer
+    /// group `group` of the stream stored at `key`.
+    pub fn del_consumer(
+        key: impl ToString,
+        group: impl ToString,
+        consumer: impl ToString,
+    ) -> XGroup {
+        XGroup::new(key, group, GroupOp::DelConsumer(consumer.to_string()))
+    }
+
+    /// Create a new `XGroup` command which sets the last delivered ID of the consumer group
+    /// `group` of the stream stored at `key`. A `None` ID stands for the last ID of the stream.
+    pub fn set_id(key: impl ToString, group: impl ToString, id: Option<StreamId>) -> XGroup {
+        XGroup::new(key, group, GroupOp::SetId(id))
+    }
+
+    fn new(key: impl ToString, group: impl ToString, op: GroupOp) -> XGroup {
+        XGroup {
+            key: key.to_string(),
+            group: group.to_string(),
+            op,
+        }
+    }
+
+    /// Parses a `XGroup` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// XGROUP CREATE key group <id | $> [MKSTREAM]
+    /// XGROUP DESTROY key group
+    /// XGROUP CREATECONSUMER key group consumer
+    /// XGROUP DELCONSUMER key group consumer
+    /// XGROUP SETID key group <id | $>
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<XGroup> {
+        let subcommand = parse.next_string()?.to_uppercase();
+        let key = parse.next_string()?;
+        let group = parse.next_string()?;
+
+        let op = match &subcommand[..] {
+            "CREATE" => {
+                let id = parse_group_id(&parse.next_string()?)?;
+
+                let mk_stream = match parse.next_string() {
+                    Ok(option) if option.to_uppercase() == "MKSTREAM" => true,
+                    Ok(option) => {
+                        return Err(
+                            format!("XGROUP command error: unsupported option {}", option).into(),
+                        )
+                    }
+                    Err(ParseError::EndOfStream) => false,
+                    Err(err) => return Err(err.into()),
+                };
+
+                GroupOp::Create { id, mk_stream }
+            }
+            "DESTROY" => GroupOp::Destroy,
+            "CREATECONSUMER" => GroupOp::CreateConsumer(parse.next_string()?),
+            "DELCONSUMER" => GroupOp::DelConsumer(parse.next_string()?),
+            "SETID" => GroupOp::SetId(parse_group_id(&parse.next_string()?)?),
+            _ => {
+                return Err(format!(
+                    "XGROUP command error: unsupported subcommand {}",
+                    subcommand
+                )
+                .into())
+            }
+        };
+
+        Ok(XGroup { key, group, op })
+    }
+
+    /// Apply the `XGroup` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let (key, group) = (&self.key, &self.group);
+
+        let response = match self.op {
+            GroupOp::Create { id, mk_stream } => db
+                .xgroup_create(key, group, id, mk_stream)
+                .map(|()| Frame::Simple("OK".to_string())),
+            GroupOp::Destroy => db
+                .xgroup_destroy(key, group)
+                .map(|destroyed| Frame::Integer(destroyed as i64)),
+            GroupOp::CreateConsumer(consumer) => db
+                .xgroup_create_consumer(key, group, &consumer)
+                .map(|created| Frame::Integer(created as i64)),
+            GroupOp::DelConsumer(consumer) => db
+                .xgroup_del_consumer(key, group, &consumer)
+                .map(|pending| Frame::Integer(pending as i64)),
+            GroupOp::SetId(id) => db
+                .xgroup_set_id(key, group, id)
+                .map(|()| Frame::Simple("OK".to_string())),
+        };
+
+        let response = response.unwrap_or_else(|err| Frame::Error(err.to_string()));
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("xgroup".as_bytes()));
+        let subcommand = match self.op {
+            GroupOp::Create { .. } => "create",
+            GroupOp::Destroy => "destroy",
+            GroupOp::CreateConsumer(_) => "createconsumer",
+            GroupOp::DelConsumer(_) => "delconsumer",
+            GroupOp::SetId(_) => "setid",
+        };
+        frame.push_bulk(Bytes::from(subcommand.as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.group.into_bytes()));
+        match self.op {
+            GroupOp::Create { id, mk_stream } => {
+                frame.push_bulk(group_id_to_bytes(id));
+                if mk_stream {
+                    frame.push_bulk(Bytes::from("mkstream".as_bytes()));
+                }
+            }
+            GroupOp::Destroy => {}
+            GroupOp::CreateConsumer(consumer) | GroupOp::DelConsumer(consumer) => {
+                frame.push_bulk(Bytes::from(consumer.into_bytes()));
+            }
+            GroupOp::SetId(id) => frame.push_bulk(group_id_to_bytes(id)),
+        }
+        frame
+    }
+}
+
+impl XReadGroup {
+    /// Create a new `XReadGroup` command which reads entries from each of `streams` on behalf of
+    /// `consumer` of the consumer group `group`, at most `count` per stream if given. A `None`
+    /// ID reads the entries never delivered to the group, other IDs read the pending entries of
+    /// the consumer following the ID.
+    ///
+    /// If `block` is given and no entries are available, the command blocks for at most that
+    /// duration, or indefinitely if it is zero.
+    pub fn new(
+        group: impl ToString,
+        consumer: impl ToString,
+        streams: &[(String, Option<StreamId>)],
+        count: Option<u64>,
+        block: Option<Duration>,
+    ) -> XReadGroup {
+        XReadGroup {
+            group: group.to_string(),
+            consumer: consumer.to_string(),
+            streams: streams.to_vec(),
+            count,
+            block,
+            no_ack: false,
+        }
+    }
+
+    /// Do not add the delivered entries to the pending entries of the group, as if they were
+    /// acknowledged right away.
+    pub fn no_ack(mut self) -> XReadGroup {
+        self.no_ack = true;
+        self
+    }
+
+    /// Parses a `XReadGroup` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// XREADGROUP GROUP group consumer [COUNT count] [BLOCK milliseconds] [NOACK]
+    ///     STREAMS key [key ...] id [id ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<XReadGroup> {
+        let option = parse.next_string()?;
+        if option.to_uppercase() != "GROUP" {
+            return Err(format!("XREADGROUP command error: expected GROUP, got {}", option).into());
+        }
+
+        let group = parse.next_string()?;
+        let consumer = parse.next_string()?;
+
+        let mut count = None;
+        let mut block = None;
+        let mut no_ack = false;
+
+        loop {
+            let option = parse.next_string()?.to_uppercase();
+
+            match &option[..] {
+                "COUNT" => count = Some(parse.next_int()?),
+                "BLOCK" => block = Some(Duration::from_millis(parse.next_int()?)),
+                "NOACK" => no_ack = true,
+                "STREAMS" => break,
+                _ => {
+                    return Err(
+                        format!("XREADGROUP command error: unsupported option {}", option).into(),
+                    )
+                }
+            }
+        }
+
+        // The keys are followed by as many IDs.
+        let mut keys = vec![parse.next_string()?];
+        keys.extend(parse.rest_strings()?);
+
+        if keys.len() % 2 != 0 {
+            return Err(
+                "XREADGROUP command error: each stream key must be followed by an ID".into(),
+            );
+        }
+
+        let ids = keys.split_off(keys.len() / 2);
+        let streams = keys
+            .into_iter()
+            .zip(ids)
+            .map(|(key, id)| match &id[..] {
+                ">" => Ok((key, None)),
+                id => Ok((key, Some(parse_stream_id(id, 0)?))),
+            })
+            .collect::<crate::Result<_>>()?;
+
+        Ok(XReadGroup {
+            group,
+            consumer,
+            streams,
+            count,
+            block,
+            no_ack,
+        })
+    }
+
+    /// Apply the `XReadGroup` command to the specified `Db` instance and write the response to
+    /// `dst`.
+    ///
+    /// The connection is closed without a response if the server shuts down while blocked.
+    #[instrument(skip(self, db, dst, shutdown))]
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        shutdown: &mut Shutdown,
+    ) -> crate::Result<()> {
+        let block = match self.block {
+            Some(block) => block,
+            // Without `BLOCK`, the streams are read once as within a transaction.
+            None => {
+                let response = self.execute(db);
+                dst.write_frame(&response).await?;
+                return Ok(());
+            }
+        };
+
+        let count = self.count.map(|count| count as usize);
+        // A timeout of zero blocks forever.
+        let timeout = Some(block).filter(|block| !block.is_zero());
+
+        let read = db
+            .blocking_xread_group(
+                &self.group,
+                &self.consumer,
+                &self.streams,
+                count,
+                self.no_ack,
+                timeout,
+                shutdown.recv(),
+            )
+            .await;
+
+        let response = match read {
+            Ok(read) if read.is_empty() && shutdown.is_shutdown() => return Ok(()),
+            Ok(read) if read.is_empty() => Frame::Null,
+            Ok(read) => group_read_frame(read),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Apply the `XReadGroup` command to the specified `Db` instance without blocking and return
+    /// the response, as done within a transaction.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let count = self.count.map(|count| count as usize);
+
+        let read = db.xread_group(
+            &self.group,
+            &self.consumer,
+            &self.streams,
+            count,
+            self.no_ack,
+        );
+        let response = match read {
+            Ok(read) if read.is_empty() => Frame::Null,
+            Ok(read) => group_read_frame(read),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("xreadgroup".as_bytes()));
+        frame.push_bulk(Bytes::from("group".as_bytes()));
+        frame.push_bulk(Bytes::from(self.group.into_bytes()));
+        frame.push_bulk(Bytes::from(self.consumer.into_bytes()));
+        if let Some(count) = self.count {
+            frame.push_bulk(Bytes::from("count".as_bytes()));
+            frame.push_int(count as i64);
+        }
+        if let Some(block) = self.block {
+            frame.push_bulk(Bytes::from("block".as_bytes()));
+            frame.push_int(block.as_millis() as i64);
+        }
+        if self.no_ack {
+            frame.push_bulk(Bytes::from("noack".as_bytes()));
+        }
+        frame.push_bulk(Bytes::from("streams".as_bytes()));
+        for (key, _) in &self.streams {
+            frame.push_bulk(Bytes::from(key.clone().into_bytes()));
+        }
+        for (_, id) in self.streams {
+            let id = id.map_or_else(|| ">".to_string(), |id| id.to_string());
+            frame.push_bulk(Bytes::from(id));
+        }
+        frame
+    }
+}
+
+impl XAck {
+    /// Create a new `XAck` command which acknowledges the pending entries `ids` of the consumer
+    /// group `group` of the stream stored at `key`.
+    pub fn new(key: impl ToString, group: impl ToString, ids: &[StreamId]) -> XAck {
+        XAck {
+            key: key.to_string(),
+            group: group.to_string(),
+            ids: ids.to_vec(),
+        }
+    }
+
+    /// Parses a `XAck` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// XACK key group id [id ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<XAck> {
+        let key = parse.next_string()?;
+        let group = parse.next_string()?;
+
+        let mut ids = vec![parse_stream_id(&parse.next_string()?, 0)?];
+        for id in parse.rest_strings()? {
+            ids.push(parse_stream_id(&id, 0)?);
+        }
+
+        Ok(XAck { key, group, ids })
+    }
+
+    /// Apply the `XAck` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.xack(&self.key, &self.group, &self.ids) {
+            Ok(acknowledged) => Frame::Integer(acknowledged as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("xack".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.group.into_bytes()));
+        for id in self.ids {
+            frame.push_bulk(Bytes::from(id.to_string()));
+        }
+        frame
+    }
+}
+
+impl XPending {
+    /// Create a new `XPending` command which returns a summary of the pending entries of the
+    /// consumer group `group` of the stream stored at `key`.
+    pub fn new(key: impl ToString, group: impl ToString) -> XPending {
+        XPending {
+            key: key.to_string(),
+            group: group.to_string(),
+            range: None,
+            consumer: None,
+            min_idle: None,
+        }
+    }
+
+    /// Return the details of up to `count` pending entries with an ID between `start` and `end`
+    /// instead of a summary.
+    pub fn range(mut self, start: Bound<StreamId>, end: Bound<StreamId>, count: u64) -> XPending {
+        self.range = Some((start, end, count));
+        self
+    }
+
+    /// Only return the pending entries owned by `consumer`. Requires a range.
+    pub fn consumer(mut self, consumer: impl ToString) -> XPending {
+        self.consumer = Some(consumer.to_string());
+        self
+    }
+
+    /// Only return the pending entries idle for at least `min_idle`. Requires a range.
+    pub fn min_idle(mut self, min_idle: Duration) -> XPending {
+        self.min_idle = Some(min_idle);
+        self
+    }
+
+    /// Parses a `XPending` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// XPENDING key group [[IDLE min-idle-time] start end count [consumer]]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<XPending> {
+        let key = parse.next_string()?;
+        let group = parse.next_string()?;
+
+        let mut pending = XPending::new(key, group);
+
+        let mut start = match parse.next_string() {
+            Ok(start) => start,
+            Err(ParseError::EndOfStream) => return Ok(pending),
+            Err(err) => return Err(err.into()),
+        };
+
+        if start.to_uppercase() == "IDLE" {
+            pending.min_idle = Some(Duration::from_millis(parse.next_int()?));
+            start = parse.next_string()?;
+        }
+
+        let start = parse_range_bound(&start, 0)?;
+        let end = parse_range_bound(&parse.next_string()?, u64::MAX)?;
+        pending.range = Some((start, end, parse.next_int()?));
+
+        pending.consumer = match parse.next_string() {
+            Ok(consumer) => Some(consumer),
+            Err(ParseError::EndOfStream) => None,
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(pending)
+    }
+
+    /// Apply the `XPending` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match self.range {
+            None => db.xpending_summary(&self.key, &self.group).map(|summary| {
+                let count = Frame::Integer(summary.count as i64);
+
+                match summary.range {
+                    Some((min, max)) => {
+                        let consumers = summary
+                            .consumers
+                            .into_iter()
+                            .map(|(name, count)| {
+                                Frame::Array(vec![
+                                    Frame::Bulk(Bytes::from(name)),
+                                    Frame::Bulk(Bytes::from(count.to_string())),
+                                ])
+                            })
+                            .collect();
+
+                        Frame::Array(vec![
+                            count,
+                            Frame::Bulk(Bytes::from(min.to_string())),
+                            Frame::Bulk(Bytes::from(max.to_string())),
+                            Frame::Array(consumers),
+                        ])
+                    }
+                    None => Frame::Array(vec![count, Frame::Null, Frame::Null, Frame::Null]),
+                }
+            }),
+            Some((start, end, count)) => db
+                .xpending(
+                    &self.key,
+                    &self.group,
+                    start,
+                    end,
+                    count as usize,
+                    self.consumer.as_deref(),
+                    self.min_idle,
+                )
+                .map(|entries| {
+                    let entries = entries
+                        .into_iter()
+                        .map(|entry| {
+                            Frame::Array(vec![
+                                Frame::Bulk(Bytes::from(entry.id.to_string())),
+                                Frame::Bulk(Bytes::from(entry.consumer)),
+                                Frame::Integer(entry.idle.as_millis() as i64),
+                                Frame::Integer(entry.delivery_count as i64),
+                            ])
+                        })
+                        .collect();
+
+                    Frame::Array(entries)
+                }),
+        };
+
+        let response = response.unwrap_or_else(|err| Frame::Error(err.to_string()));
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("xpending".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.group.into_bytes()));
+        if let Some((start, end, count)) = self.range {
+            if let Some(min_idle) = self.min_idle {
+                frame.push_bulk(Bytes::from("idle".as_bytes()));
+                frame.push_int(min_idle.as_millis() as i64);
+            }
+            frame.push_bulk(range_bound_to_bytes(start, "-"));
+            frame.push_bulk(range_bound_to_bytes(end, "+"));
+            frame.push_int(count as i64);
+            if let Some(consumer) = self.consumer {
+                frame.push_bulk(Bytes::from(consumer.into_bytes()));
+            }
+        }
+        frame
+    }
+}
+
+impl XClaim {
+    /// Create a new `XClaim` command which transfers the ownership of the pending entries `ids`
+    /// of the consumer group `group` of the stream stored at `key` to `consumer`, if they have
+    /// been idle for at least `min_idle`.
+    pub fn new(
+        key: impl ToString,
+        group: impl ToString,
+        consumer: impl ToString,
+        min_idle: Duration,
+        ids: &[StreamId],
+    ) -> XClaim {
+        XClaim {
+            key: key.to_string(),
+            group: group.to_string(),
+            consumer: consumer.to_string(),
+            min_idle,
+            ids: ids.to_vec(),
+            just_id: false,
+        }
+    }
+
+    /// Return only the IDs of the claimed entries, without incrementing their delivery count.
+    pub fn just_id(mut self) -> XClaim {
+        self.just_id = true;
+        self
+    }
+
+    /// Parses a `XClaim` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// XCLAIM key group consumer min-idle-time id [id ...] [JUSTID]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<XClaim> {
+        let key = parse.next_string()?;
+        let group = parse.next_string()?;
+        let consumer = parse.next_string()?;
+        let min_idle = Duration::from_millis(parse.next_int()?);
+
+        let mut ids = vec![parse_stream_id(&parse.next_string()?, 0)?];
+        let mut just_id = false;
+
+        for arg in parse.rest_strings()? {
+            if arg.to_uppercase() == "JUSTID" {
+                just_id = true;
+            } else if arg.starts_with(|c: char| c.is_ascii_digit()) && !just_id {
+                ids.push(parse_stream_id(&arg, 0)?);
+            } else {
+                return Err(format!("XCLAIM command error: unsupported option {}", arg).into());
+            }
+        }
+
+        Ok(XClaim {
+            key,
+            group,
+            consumer,
+            min_idle,
+            ids,
+            just_id,
+        })
+    }
+
+    /// Apply the `XClaim` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let claimed = db.xclaim(
+            &self.key,
+            &self.group,
+            &self.consumer,
+            self.min_idle,
+            &self.ids,
+            self.just_id,
+        );
+
+        let response = match claimed {
+            Ok(claimed) => claimed_frame(claimed, self.just_id),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("xclaim".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.group.into_bytes()));
+        frame.push_bulk(Bytes::from(self.consumer.into_bytes()));
+        frame.push_int(self.min_idle.as_millis() as i64);
+        for id in self.ids {
+            frame.push_bulk(Bytes::from(id.to_string()));
+        }
+        if self.just_id {
+            frame.push_bulk(Bytes::from("justid".as_bytes()));
+        }
+        frame
+    }
+}
+
+impl XAutoClaim {
+    /// Create a new `XAutoClaim` command which claims for `consumer` up to `count` pending
+    /// entries of the consumer group `group` of the stream stored at `key` that have been idle
+    /// for at least `min_idle`, scanning the pending entries from `start`.
+    pub fn new(
+        key: impl ToString,
+        group: impl ToString,
+        consumer: impl ToString,
+        min_idle: Duration,
+        start: StreamId,
+        count: Option<u64>,
+    ) -> XAutoClaim {
+        XAutoClaim {
+            key: key.to_string(),
+            group: group.to_string(),
+            consumer: consumer.to_string(),
+            min_idle,
+            start,
+            count,
+            just_id: false,
+        }
+    }
+
+    /// Return only the IDs of the claimed entries, without incrementing their delivery count.
+    pub fn just_id(mut self) -> XAutoClaim {
+        self.just_id = true;
+        self
+    }
+
+    /// Parses a `XAutoClaim` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// XAUTOCLAIM key group consumer min-idle-time start [COUNT count] [JUSTID]
+    /// ```
+    ///
+    /// The count defaults to 100, and must be positive.
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<XAutoClaim> {
+        let key = parse.next_string()?;
+        let group = parse.next_string()?;
+        let consumer = parse.next_string()?;
+        let min_idle = Duration::from_millis(parse.next_int()?);
+        let start = match parse.next_string()?.as_str() {
+            "-" => StreamId::default(),
+            start => parse_stream_id(start, 0)?,
+        };
+
+        let mut count = None;
+        let mut just_id = false;
+
+        loop {
+            let option = match parse.next_string() {
+                Ok(option) => option.to_uppercase(),
+                Err(ParseError::EndOfStream) => break,
+                Err(err) => return Err(err.into()),
+            };
+
+            match &option[..] {
+                "COUNT" => match parse.next_int()? {
+                    0 => return Err("COUNT must be > 0".into()),
+                    n => count = Some(n),
+                },
+                "JUSTID" => just_id = true,
+                _ => {
+                    return Err(
+                        format!("XAUTOCLAIM command error: unsupported option {}", option).into(),
+                    )
+                }
+            }
+        }
+
+        Ok(XAutoClaim {
+            key,
+            group,
+            consumer,
+            min_idle,
+            start,
+            count,
+            just_id,
+        })
+    }
+
+    /// Apply the `XAutoClaim` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let claimed = db.xautoclaim(
+            &self.key,
+            &self.group,
+            &self.consumer,
+            self.min_idle,
+            self.start,
+            self.count.unwrap_or(100) as usize,
+            self.just_id,
+        );
+
+        let response = match claimed {
+            Ok((next, claimed, deleted)) => {
+                let deleted = deleted
+                    .into_iter()
+                    .map(|id| Frame::Bulk(Bytes::from(id.to_string())))
+                    .collect();
+
+                Frame::Array(vec![
+                    Frame::Bulk(Bytes::from(next.to_string())),
+                    claimed_frame(claimed, self.just_id),
+                    Frame::Array(deleted),
+                ])
+            }
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("xautoclaim".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.group.into_bytes()));
+        frame.push_bulk(Bytes::from(self.consumer.into_bytes()));
+        frame.push_int(self.min_idle.as_millis() as i64);
+        frame.push_bulk(Bytes::from(self.start.to_string()));
+        if let Some(count) = self.count {
+            frame.push_bulk(Bytes::from("count".as_bytes()));
+            frame.push_int(count as i64);
+        }
+        if self.just_id {
+            frame.push_bulk(Bytes::from("justid".as_bytes()));
+        }
+        frame
+    }
+}
+
+/// Encodes the entries read by a consumer from several streams as an array of `[key, entries]`
+/// arrays. Pending entries removed from the stream are encoded with nil fields.
+fn group_read_frame(read: Vec<(String, Vec<GroupEntry>)>) -> Frame {
+    let streams = read
+        .into_iter()
+        .map(|(key, entries)| {
+            let entries = entries
+                .into_iter()
+                .map(|(id, fields)| entry_frame(id, fields))
+                .collect();
+            Frame::Array(vec![Frame::Bulk(Bytes::from(key)), Frame::Array(entries)])
+        })
+        .collect();
+
+    Frame::Array(streams)
+}
+
+/// Encodes claimed entries, or only their IDs if `just_id` is set.
+fn claimed_frame(claimed: Vec<StreamEntry>, just_id: bool) -> Frame {
+    if !just_id {
+        return entries_frame(claimed);
+    }
+
+    let ids = claimed
+        .into_iter()
+        .map(|(id, _)| Frame::Bulk(Bytes::from(id.to_string())))
+        .collect();
+
+    Frame::Array(ids)
+}
+
+/// Parses the ID argument of `XGROUP CREATE` and `XGROUP SETID`, where `$` stands for the last
+/// ID of the stream.
+fn parse_group_id(id: &str) -> crate::Result<Option<StreamId>> {
+    match id {
+        "$" => Ok(None),
+        id => Ok(Some(parse_stream_id(id, 0)?)),
+    }
+}
+
+/// Encodes the ID argument of `XGROUP CREATE` and `XGROUP SETID`.
+fn group_id_to_bytes(id: Option<StreamId>) -> Bytes {
+    id.map_or_else(
+        || Bytes::from("$".as_bytes()),
+        |id| Bytes::from(id.to_string()),
+    )
+}
@@ -0,0 +1,571 @@
+use crate::{Db, Frame, Parse, ParseError};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Sets the specified fields to their respective values in the hash stored at key.
+///
+/// Existing fields are overwritten. If key does not exist, a new hash is created. Returns the
+/// number of fields that were added.
+#[derive(Debug)]
+pub struct HSet {
+    key: String,
+    pairs: Vec<(String, Bytes)>,
+}
+
+/// Returns the value associated with field in the hash stored at key.
+#[derive(Debug)]
+pub struct HGet {
+    key: String,
+    field: String,
+}
+
+/// Returns the values associated with the specified fields in the hash stored at key.
+///
+/// `Null` is returned for every field that does not exist in the hash.
+#[derive(Debug)]
+pub struct HMGet {
+    key: String,
+    fields: Vec<String>,
+}
+
+/// Removes the specified fields from the hash stored at key.
+///
+/// Fields that do not exist are ignored. The key is removed once the hash is empty. Returns
+/// the number of fields that were removed.
+#[derive(Debug)]
+pub struct HDel {
+    key: String,
+    fields: Vec<String>,
+}
+
+/// Returns all fields and values of the hash stored at key.
+///
+/// Every field name is followed by its value in the reply.
+#[derive(Debug)]
+pub struct HGetAll {
+    key: String,
+}
+
+/// Increments the number stored at field in the hash stored at key by increment.
+///
+/// If field does not exist, it is set to 0 before performing the operation.
+#[derive(Debug)]
+pub struct HIncrBy {
+    key: String,
+    field: String,
+    increment: i64,
+}
+
+/// Returns if field is an existing field in the hash stored at key.
+#[derive(Debug)]
+pub struct HExists {
+    key: String,
+    field: String,
+}
+
+/// Returns all field names in the hash stored at key.
+#[derive(Debug)]
+pub struct HKeys {
+    key: String,
+}
+
+/// Returns all values in the hash stored at key.
+#[derive(Debug)]
+pub struct HVals {
+    key: String,
+}
+
+/// Returns the number of fields contained in the hash stored at key.
+#[derive(Debug)]
+pub struct HLen {
+    key: String,
+}
+
+impl HSet {
+    /// Create a new `HSet` command which sets `pairs` in the hash stored at `key`.
+    pub fn new(key: impl ToString, pairs: &[(String, Bytes)]) -> HSet {
+        HSet {
+            key: key.to_string(),
+            pairs: pairs.to_vec(),
+        }
+    }
+
+    /// Parses a `HSet` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HSET key field value [field value ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<HSet> {
+        let key = parse.next_string()?;
+
+        let mut pairs = vec![(parse.next_string()?, parse.next_bytes()?)];
+
+        loop {
+            match parse.next_string() {
+                Ok(field) => pairs.push((field, parse.next_bytes()?)),
+                // `EndOfStream` indicates there is no further data to parse.
+                Err(ParseError::EndOfStream) => break,
+                // Bubble up all other errors, which are replied to the client.
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(HSet { key, pairs })
+    }
+
+    /// Apply the `HSet` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.hset(&self.key, self.pairs) {
+            Ok(added) => Frame::Integer(added as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hset".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        for (field, value) in self.pairs {
+            frame.push_bulk(Bytes::from(field.into_bytes()));
+            frame.push_bulk(value);
+        }
+        frame
+    }
+}
+
+impl HGet {
+    /// Create a new `HGet` command which reads `field` in the hash stored at `key`.
+    pub fn new(key: impl ToString, field: impl ToString) -> HGet {
+        HGet {
+            key: key.to_string(),
+            field: field.to_string(),
+        }
+    }
+
+    /// Parses a `HGet` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HGET key field
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<HGet> {
+        let key = parse.next_string()?;
+        let field = parse.next_string()?;
+
+        Ok(HGet { key, field })
+    }
+
+    /// Apply the `HGet` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.hget(&self.key, &self.field) {
+            Ok(Some(value)) => Frame::Bulk(value),
+            Ok(None) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hget".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.field.into_bytes()));
+        frame
+    }
+}
+
+impl HMGet {
+    /// Create a new `HMGet` command which reads `fields` in the hash stored at `key`.
+    pub fn new(key: impl ToString, fields: &[String]) -> HMGet {
+        HMGet {
+            key: key.to_string(),
+            fields: fields.to_vec(),
+        }
+    }
+
+    /// Parses a `HMGet` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HMGET key field [field ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<HMGet> {
+        let key = parse.next_string()?;
+
+        let mut fields = vec![parse.next_string()?];
+        fields.extend(parse.rest_strings()?);
+
+        Ok(HMGet { key, fields })
+    }
+
+    /// Apply the `HMGet` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.hmget(&self.key, &self.fields) {
+            Ok(values) => Frame::Array(
+                values
+                    .into_iter()
+                    .map(|value| value.map(Frame::Bulk).unwrap_or(Frame::Null))
+                    .collect(),
+            ),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hmget".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        for field in self.fields {
+            frame.push_bulk(Bytes::from(field.into_bytes()));
+        }
+        frame
+    }
+}
+
+impl HDel {
+    /// Create a new `HDel` command which removes `fields` from the hash stored at `key`.
+    pub fn new(key: impl ToString, fields: &[String]) -> HDel {
+        HDel {
+            key: key.to_string(),
+            fields: fields.to_vec(),
+        }
+    }
+
+    /// Parses a `HDel` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HDEL key field [field ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<HDel> {
+        let key = parse.next_string()?;
+
+        let mut fields = vec![parse.next_string()?];
+        fields.extend(parse.rest_strings()?);
+
+        Ok(HDel { key, fields })
+    }
+
+    /// Apply the `HDel` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.hdel(&self.key, &self.fields) {
+            Ok(removed) => Frame::Integer(removed as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hdel".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        for field in self.fields {
+            frame.push_bulk(Bytes::from(field.into_bytes()));
+        }
+        frame
+    }
+}
+
+impl HGetAll {
+    /// Create a new `HGetAll` command which reads the whole hash stored at `key`.
+    pub fn new(key: impl ToString) -> HGetAll {
+        HGetAll {
+            key: key.to_string(),
+        }
+    }
+
+    /// Parses a `HGetAll` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HGETALL key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<HGetAll> {
+        let key = parse.next_string()?;
+
+        Ok(HGetAll { key })
+    }
+
+    /// Apply the `HGetAll` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.hgetall(&self.key) {
+            Ok(pairs) => {
+                let mut frame = Frame::array();
+                for (field, value) in pairs {
+                    frame.push_bulk(Bytes::from(field.into_bytes()));
+                    frame.push_bulk(value);
+                }
+                frame
+            }
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hgetall".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
+
+impl HIncrBy {
+    /// Create a new `HIncrBy` command which adds `increment` to `field` in the hash stored at
+    /// `key`.
+    pub fn new(key: impl ToString, field: impl ToString, increment: i64) -> HIncrBy {
+        HIncrBy {
+            key: key.to_string(),
+            field: field.to_string(),
+            increment,
+        }
+    }
+
+    /// Parses a `HIncrBy` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HINCRBY key field increment
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<HIncrBy> {
+        let key = parse.next_string()?;
+        let field = parse.next_string()?;
+        let increment = parse.next_signed_int()?;
+
+        Ok(HIncrBy {
+            key,
+            field,
+            increment,
+        })
+    }
+
+    /// Apply the `HIncrBy` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.hincr_by(&self.key, &self.field, self.increment) {
+            Ok(value) => Frame::Integer(value),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hincrby".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.field.into_bytes()));
+        frame.push_int(self.increment);
+        frame
+    }
+}
+
+impl HExists {
+    /// Create a new `HExists` command which checks whether `field` exists in the hash stored at
+    /// `key`.
+    pub fn new(key: impl ToString, field: impl ToString) -> HExists {
+        HExists {
+            key: key.to_string(),
+            field: field.to_string(),
+        }
+    }
+
+    /// Parses a `HExists` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HEXISTS key field
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<HExists> {
+        let key = parse.next_string()?;
+        let field = parse.next_string()?;
+
+        Ok(HExists { key, field })
+    }
+
+    /// Apply the `HExists` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.hexists(&self.key, &self.field) {
+            Ok(exists) => Frame::Integer(exists as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hexists".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.field.into_bytes()));
+        frame
+    }
+}
+
+impl HKeys {
+    /// Create a new `HKeys` command which reads the field names of the hash stored at `key`.
+    pub fn new(key: impl ToString) -> HKeys {
+        HKeys {
+            key: key.to_string(),
+        }
+    }
+
+    /// Parses a `HKeys` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HKEYS key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<HKeys> {
+        let key = parse.next_string()?;
+
+        Ok(HKeys { key })
+    }
+
+    /// Apply the `HKeys` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.hkeys(&self.key) {
+            Ok(fields) => {
+                let mut frame = Frame::array();
+                for field in fields {
+                    frame.push_bulk(Bytes::from(field.into_bytes()));
+                }
+                frame
+            }
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hkeys".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
+
+impl HVals {
+    /// Create a new `HVals` command which reads the values of the hash stored at `key`.
+    pub fn new(key: impl ToString) -> HVals {
+        HVals {
+            key: key.to_string(),
+        }
+    }
+
+    /// Parses a `HVals` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HVALS key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<HVals> {
+        let key = parse.next_string()?;
+
+        Ok(HVals { key })
+    }
+
+    /// Apply the `HVals` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.hvals(&self.key) {
+            Ok(values) => Frame::Array(values.into_iter().map(Frame::Bulk).collect()),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hvals".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
+
+impl HLen {
+    /// Create a new `HLen` command which reads the number of fields of the hash stored at `key`.
+    pub fn new(key: impl ToString) -> HLen {
+        HLen {
+            key: key.to_string(),
+        }
+    }
+
+    /// Parses a `HLen` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HLEN key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<HLen> {
+        let key = parse.next_string()?;
+
+        Ok(HLen { key })
+    }
+
+    /// Apply the `HLen` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.hlen(&self.key) {
+            Ok(len) => Frame::Integer(len as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hlen".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
@@ -0,0 +1,144 @@
+use crate::{Db, Frame, Parse};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Increments the number stored at key by an integer.
+///
+/// Received as INCR, DECR, INCRBY or DECRBY. If the key does not exist, it is set to 0 before
+/// performing the operation. An error is returned if the key contains a value that can not be
+/// represented as a 64 bit signed integer.
+#[derive(Debug)]
+pub struct Incr {
+    key: String,
+    increment: i64,
+    /// Name of the command the frame was received as.
+    name: &'static str,
+}
+
+/// Increment the string representing a floating point number stored at key by the specified
+/// increment.
+///
+/// If the key does not exist, it is set to 0 before performing the operation. The new value is
+/// returned as a bulk string.
+#[derive(Debug)]
+pub struct IncrByFloat {
+    key: String,
+    increment: f64,
+}
+
+impl Incr {
+    /// Create a new `Incr` command which adds `increment` to the value stored at `key`.
+    pub fn new(key: impl ToString, increment: i64) -> Incr {
+        Incr {
+            key: key.to_string(),
+            increment,
+            name: "incrby",
+        }
+    }
+
+    /// Parses an `Incr` instance from a received frame. `name` is the lowercase command name.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// INCR key
+    /// DECR key
+    /// INCRBY key increment
+    /// DECRBY key decrement
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse, name: &str) -> crate::Result<Incr> {
+        let key = parse.next_string()?;
+
+        let (increment, name) = match name {
+            "incr" => (1, "incr"),
+            "decr" => (-1, "decr"),
+            "incrby" => (parse.next_signed_int()?, "incrby"),
+            "decrby" => {
+                let decrement = parse.next_signed_int()?;
+                let increment = decrement
+                    .checked_neg()
+                    .ok_or("DECRBY command error: decrement is out of range")?;
+                (increment, "decrby")
+            }
+            _ => return Err(format!("unexpected increment command {}", name).into()),
+        };
+
+        Ok(Incr {
+            key,
+            increment,
+            name,
+        })
+    }
+
+    /// Apply the `Incr` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.incr_by(&self.key, self.increment) {
+            Ok(value) => Frame::Integer(value),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    /// Returns the command name.
+    pub(crate) fn get_name(&self) -> &str {
+        self.name
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("incrby".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_int(self.increment);
+        frame
+    }
+}
+
+impl IncrByFloat {
+    /// Create a new `IncrByFloat` command which adds `increment` to the value stored at `key`.
+    pub fn new(key: impl ToString, increment: f64) -> IncrByFloat {
+        IncrByFloat {
+            key: key.to_string(),
+            increment,
+        }
+    }
+
+    /// Parses an `IncrByFloat` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// INCRBYFLOAT key increment
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<IncrByFloat> {
+        let key = parse.next_string()?;
+        let increment = parse.next_float()?;
+
+        Ok(IncrByFloat { key, increment })
+    }
+
+    /// Apply the `IncrByFloat` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.incr_by_float(&self.key, self.increment) {
+            Ok(value) => Frame::Bulk(Bytes::from(value.to_string())),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("incrbyfloat".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.increment.to_string()));
+        frame
+    }
+}
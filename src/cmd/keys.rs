@@ -0,0 +1,304 @@
+use crate::{Db, Frame, Parse, ParseError};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Removes the specified keys. A key is ignored if it does not exist.
+#[derive(Debug)]
+pub struct Del {
+    keys: Vec<String>,
+}
+
+/// Returns the number of the specified keys that exist.
+///
+/// A key mentioned multiple times is also counted multiple times.
+#[derive(Debug)]
+pub struct Exists {
+    keys: Vec<String>,
+}
+
+/// Returns the string representation of the type of the value stored at key.
+///
+/// `none` is returned when the key does not exist.
+#[derive(Debug)]
+pub struct Type {
+    key: String,
+}
+
+/// Renames key to newkey.
+///
+/// An error is returned when key does not exist. If newkey already exists it is overwritten,
+/// unless the command was received as RENAMENX, in which case nothing is done.
+#[derive(Debug)]
+pub struct Rename {
+    key: String,
+    newkey: String,
+    nx: bool,
+}
+
+/// Copies the value stored at the source key to the destination key.
+///
+/// The destination key is only overwritten when the `REPLACE` option is given.
+#[derive(Debug)]
+pub struct Copy {
+    source: String,
+    destination: String,
+    replace: bool,
+}
+
+impl Del {
+    /// Create a new `Del` command which removes `keys`.
+    pub fn new(keys: &[String]) -> Del {
+        Del {
+            keys: keys.to_vec(),
+        }
+    }
+
+    /// Parses a `Del` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// DEL key [key ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Del> {
+        let mut keys = vec![parse.next_string()?];
+        keys.extend(parse.rest_strings()?);
+
+        Ok(Del { keys })
+    }
+
+    /// Apply the `Del` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = Frame::Integer(db.del(&self.keys) as i64);
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("del".as_bytes()));
+        for key in self.keys {
+            frame.push_bulk(Bytes::from(key.into_bytes()));
+        }
+        frame
+    }
+}
+
+impl Exists {
+    /// Create a new `Exists` command which checks `keys`.
+    pub fn new(keys: &[String]) -> Exists {
+        Exists {
+            keys: keys.to_vec(),
+        }
+    }
+
+    /// Parses an `Exists` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// EXISTS key [key ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Exists> {
+        let mut keys = vec![parse.next_string()?];
+        keys.extend(parse.rest_strings()?);
+
+        Ok(Exists { keys })
+    }
+
+    /// Apply the `Exists` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = Frame::Integer(db.exists(&self.keys) as i64);
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("exists".as_bytes()));
+        for key in self.keys {
+            frame.push_bulk(Bytes::from(key.into_bytes()));
+        }
+        frame
+    }
+}
+
+impl Type {
+    /// Create a new `Type` command which inspects `key`.
+    pub fn new(key: impl ToString) -> Type {
+        Type {
+            key: key.to_string(),
+        }
+    }
+
+    /// Parses a `Type` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// TYPE key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Type> {
+        let key = parse.next_string()?;
+
+        Ok(Type { key })
+    }
+
+    /// Apply the `Type` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let name = db.key_type(&self.key).unwrap_or("none");
+        let response = Frame::Simple(name.to_string());
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("type".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
+
+impl Rename {
+    /// Create a new `Rename` command. If `nx` is set, `newkey` is only written if it does not
+    /// exist yet.
+    pub fn new(key: impl ToString, newkey: impl ToString, nx: bool) -> Rename {
+        Rename {
+            key: key.to_string(),
+            newkey: newkey.to_string(),
+            nx,
+        }
+    }
+
+    /// Parses a `Rename` instance from a received frame. `nx` is set when the frame was a
+    /// RENAMENX command.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// RENAME key newkey
+    /// RENAMENX key newkey
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse, nx: bool) -> crate::Result<Rename> {
+        let key = parse.next_string()?;
+        let newkey = parse.next_string()?;
+
+        Ok(Rename { key, newkey, nx })
+    }
+
+    /// Apply the `Rename` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.rename(&self.key, self.newkey, self.nx) {
+            None => Frame::Error("ERR no such key".to_string()),
+            Some(renamed) if self.nx => Frame::Integer(renamed as i64),
+            Some(_) => Frame::Simple("OK".to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    /// Returns the command name.
+    pub(crate) fn get_name(&self) -> &str {
+        if self.nx {
+            "renamenx"
+        } else {
+            "rename"
+        }
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from(self.get_name().as_bytes().to_vec()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.newkey.into_bytes()));
+        frame
+    }
+}
+
+impl Copy {
+    /// Create a new `Copy` command. If `replace` is set, an existing `destination` is
+    /// overwritten.
+    pub fn new(source: impl ToString, destination: impl ToString, replace: bool) -> Copy {
+        Copy {
+            source: source.to_string(),
+            destination: destination.to_string(),
+            replace,
+        }
+    }
+
+    /// Parses a `Copy` instance from a received frame.
+    ///
+    /// Only the default database exists, so `DB` is accepted for compatibility but must be `0`.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// COPY source destination [DB destination-db] [REPLACE]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Copy> {
+        let source = parse.next_string()?;
+        let destination = parse.next_string()?;
+        let mut replace = false;
+
+        loop {
+            match parse.next_string() {
+                Ok(s) if s.to_uppercase() == "REPLACE" => replace = true,
+                Ok(s) if s.to_uppercase() == "DB" => {
+                    if parse.next_int()? != 0 {
+                        return Err("COPY command error: only database 0 is supported".into());
+                    }
+                }
+                Ok(s) => return Err(format!("COPY command error: unsupported option {}", s).into()),
+                Err(ParseError::EndOfStream) => break,
+                // All other errors are replied to the client.
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(Copy {
+            source,
+            destination,
+            replace,
+        })
+    }
+
+    /// Apply the `Copy` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = if self.source == self.destination {
+            Frame::Error("ERR source and destination objects are the same".to_string())
+        } else {
+            let copied = db.copy(&self.source, self.destination, self.replace);
+            Frame::Integer(copied as i64)
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("copy".as_bytes()));
+        frame.push_bulk(Bytes::from(self.source.into_bytes()));
+        frame.push_bulk(Bytes::from(self.destination.into_bytes()));
+        if self.replace {
+            frame.push_bulk(Bytes::from("replace".as_bytes()));
+        }
+        frame
+    }
+}
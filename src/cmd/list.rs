@@ -0,0 +1,812 @@
+use crate::{Connection, Db, Frame, Parse, ParseError, Shutdown};
+
+use bytes::Bytes;
+use std::time::Duration;
+use tracing::{debug, instrument};
+
+/// Insert all the specified values at the head or the tail of the list stored at key.
+///
+/// Received as LPUSH or RPUSH. If key does not exist, it is created as an empty list before
+/// performing the push operation. Values are inserted one after the other, so `LPUSH key a b c`
+/// results in the list `c b a`.
+#[derive(Debug)]
+pub struct Push {
+    key: String,
+    values: Vec<Bytes>,
+    /// Push to the head of the list (`LPUSH`) rather than to its tail (`RPUSH`).
+    front: bool,
+}
+
+/// Removes and returns the first or the last elements of the list stored at key.
+///
+/// Received as LPOP or RPOP. Without a count, a single element is returned as a bulk string.
+/// With a count, up to count elements are returned as an array.
+#[derive(Debug)]
+pub struct Pop {
+    key: String,
+    count: Option<u64>,
+    /// Pop from the head of the list (`LPOP`) rather than from its tail (`RPOP`).
+    front: bool,
+}
+
+/// Blocking variant of `LPOP` and `RPOP`.
+///
+/// Received as BLPOP or BRPOP. An element is popped from the first non-empty list among the
+/// given keys. If all of them are empty, the connection blocks until another client pushes to
+/// one of the lists or the timeout elapses. Clients blocked on the same key are served in the
+/// order they blocked.
+#[derive(Debug)]
+pub struct BPop {
+    keys: Vec<String>,
+    /// Maximum time to block. `None` blocks indefinitely.
+    timeout: Option<Duration>,
+    /// Pop from the head of the list (`BLPOP`) rather than from its tail (`BRPOP`).
+    front: bool,
+}
+
+/// Atomically pops an element from the source list and pushes it to the destination list,
+/// blocking until the source list holds elements or the timeout elapses.
+#[derive(Debug)]
+pub struct BLMove {
+    source: String,
+    destination: String,
+    /// Pop from the head of the source list (`LEFT`) rather than from its tail (`RIGHT`).
+    from_front: bool,
+    /// Push to the head of the destination list (`LEFT`) rather than to its tail (`RIGHT`).
+    to_front: bool,
+    /// Maximum time to block. `None` blocks indefinitely.
+    timeout: Option<Duration>,
+}
+
+/// Returns the specified elements of the list stored at key.
+///
+/// The offsets start and stop are zero-based indexes and both inclusive. Negative offsets count
+/// from the end of the list, so -1 is the last element.
+#[derive(Debug)]
+pub struct LRange {
+    key: String,
+    start: i64,
+    stop: i64,
+}
+
+/// Returns the length of the list stored at key.
+///
+/// If key does not exist, it is interpreted as an empty list and 0 is returned.
+#[derive(Debug)]
+pub struct LLen {
+    key: String,
+}
+
+/// Returns the element at index in the list stored at key.
+///
+/// Negative indices count from the end of the list. `Null` is returned when index is out of
+/// range.
+#[derive(Debug)]
+pub struct LIndex {
+    key: String,
+    index: i64,
+}
+
+/// Sets the list element at index to value.
+///
+/// An error is returned for out of range indexes or when the key does not exist.
+#[derive(Debug)]
+pub struct LSet {
+    key: String,
+    index: i64,
+    value: Bytes,
+}
+
+/// Removes the first count occurrences of elements equal to value from the list stored at key.
+///
+/// A positive count removes elements moving from head to tail, a negative count removes
+/// elements moving from tail to head, and 0 removes all matching elements.
+#[derive(Debug)]
+pub struct LRem {
+    key: String,
+    count: i64,
+    value: Bytes,
+}
+
+/// Trims an existing list so that it will contain only the specified range of elements.
+///
+/// Offsets are interpreted the same way as in `LRANGE`. Out of range offsets produce an empty
+/// list, which removes the key.
+#[derive(Debug)]
+pub struct LTrim {
+    key: String,
+    start: i64,
+    stop: i64,
+}
+
+impl Push {
+    /// Create a new `Push` command which inserts `values` at the head of the list stored at
+    /// `key` if `front` is set, or at its tail otherwise.
+    pub fn new(key: impl ToString, values: Vec<Bytes>, front: bool) -> Push {
+        Push {
+            key: key.to_string(),
+            values,
+            front,
+        }
+    }
+
+    /// Parses a `Push` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LPUSH key element [element ...]
+    /// RPUSH key element [element ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse, front: bool) -> crate::Result<Push> {
+        let key = parse.next_string()?;
+
+        let mut values = vec![parse.next_bytes()?];
+        values.extend(parse.rest_bytes()?);
+
+        Ok(Push { key, values, front })
+    }
+
+    /// Apply the `Push` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.push(&self.key, self.values, self.front) {
+            Ok(len) => Frame::Integer(len as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    /// Returns the command name.
+    pub(crate) fn get_name(&self) -> &str {
+        if self.front {
+            "lpush"
+        } else {
+            "rpush"
+        }
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from(self.get_name().as_bytes().to_vec()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        for value in self.values {
+            frame.push_bulk(value);
+        }
+        frame
+    }
+}
+
+impl Pop {
+    /// Create a new `Pop` command which removes elements from the head of the list stored at
+    /// `key` if `front` is set, or from its tail otherwise.
+    pub fn new(key: impl ToString, count: Option<u64>, front: bool) -> Pop {
+        Pop {
+            key: key.to_string(),
+            count,
+            front,
+        }
+    }
+
+    /// Parses a `Pop` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LPOP key [count]
+    /// RPOP key [count]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse, front: bool) -> crate::Result<Pop> {
+        let key = parse.next_string()?;
+
+        let count = match parse.next_int() {
+            Ok(count) => Some(count),
+            Err(ParseError::EndOfStream) => None,
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(Pop { key, count, front })
+    }
+
+    /// Apply the `Pop` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let count = self.count.unwrap_or(1) as usize;
+
+        let response = match db.pop(&self.key, count, self.front) {
+            Ok(Some(values)) if self.count.is_some() => {
+                Frame::Array(values.into_iter().map(Frame::Bulk).collect())
+            }
+            Ok(Some(mut values)) => values.pop().map(Frame::Bulk).unwrap_or(Frame::Null),
+            Ok(None) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    /// Returns the command name.
+    pub(crate) fn get_name(&self) -> &str {
+        if self.front {
+            "lpop"
+        } else {
+            "rpop"
+        }
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from(self.get_name().as_bytes().to_vec()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        if let Some(count) = self.count {
+            frame.push_int(count as i64);
+        }
+        frame
+    }
+}
+
+impl BPop {
+    /// Create a new `BPop` command which pops an element from the head of the first non-empty
+    /// list among `keys` if `front` is set, or from its tail otherwise. A `timeout` of `None`
+    /// blocks indefinitely.
+    pub fn new(keys: &[String], timeout: Option<Duration>, front: bool) -> BPop {
+        BPop {
+            keys: keys.to_vec(),
+            timeout,
+            front,
+        }
+    }
+
+    /// Parses a `BPop` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// BLPOP key [key ...] timeout
+    /// BRPOP key [key ...] timeout
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse, front: bool) -> crate::Result<BPop> {
+        let mut keys = vec![parse.next_string()?];
+        keys.extend(parse.rest_strings()?);
+
+        // The timeout is the last argument and at least one key must precede it.
+        let timeout = match keys.pop() {
+            Some(timeout) if !keys.is_empty() => parse_timeout(&timeout)?,
+            _ => return Err("protocol error: expected timeout after the keys".into()),
+        };
+
+        Ok(BPop {
+            keys,
+            timeout,
+            front,
+        })
+    }
+
+    /// Apply the `BPop` command to the specified `Db` instance and write the response to `dst`.
+    ///
+    /// The connection is closed without a response if the server shuts down while blocked.
+    #[instrument(skip(self, db, dst, shutdown))]
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        shutdown: &mut Shutdown,
+    ) -> crate::Result<()> {
+        let mut closed = false;
+        let cancel = cancelled(shutdown, dst, &mut closed);
+
+        let popped = db
+            .blocking_pop(self.keys, self.front, self.timeout, cancel)
+            .await;
+
+        let response = match popped {
+            Ok(Some((key, value))) => {
+                let mut frame = Frame::array();
+                frame.push_bulk(Bytes::from(key.into_bytes()));
+                frame.push_bulk(value);
+                frame
+            }
+            Ok(None) if closed || shutdown.is_shutdown() => return Ok(()),
+            Ok(None) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Apply the `BPop` command to the specified `Db` instance without blocking and return the
+    /// response, as done within a transaction.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.pop_first(&self.keys, self.front) {
+            Ok(Some((key, value))) => {
+                let mut frame = Frame::array();
+                frame.push_bulk(Bytes::from(key.into_bytes()));
+                frame.push_bulk(value);
+                frame
+            }
+            Ok(None) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    /// Returns the command name.
+    pub(crate) fn get_name(&self) -> &str {
+        if self.front {
+            "blpop"
+        } else {
+            "brpop"
+        }
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from(self.get_name().as_bytes().to_vec()));
+        for key in self.keys {
+            frame.push_bulk(Bytes::from(key.into_bytes()));
+        }
+        frame.push_bulk(timeout_to_bytes(self.timeout));
+        frame
+    }
+}
+
+impl BLMove {
+    /// Create a new `BLMove` command which moves an element from the list stored at `source` to
+    /// the list stored at `destination`. A `timeout` of `None` blocks indefinitely.
+    pub fn new(
+        source: impl ToString,
+        destination: impl ToString,
+        from_front: bool,
+        to_front: bool,
+        timeout: Option<Duration>,
+    ) -> BLMove {
+        BLMove {
+            source: source.to_string(),
+            destination: destination.to_string(),
+            from_front,
+            to_front,
+            timeout,
+        }
+    }
+
+    /// Parses a `BLMove` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// BLMOVE source destination LEFT|RIGHT LEFT|RIGHT timeout
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<BLMove> {
+        let source = parse.next_string()?;
+        let destination = parse.next_string()?;
+        let from_front = parse_side(&parse.next_string()?)?;
+        let to_front = parse_side(&parse.next_string()?)?;
+        let timeout = parse_timeout(&parse.next_string()?)?;
+
+        Ok(BLMove {
+            source,
+            destination,
+            from_front,
+            to_front,
+            timeout,
+        })
+    }
+
+    /// Apply the `BLMove` command to the specified `Db` instance and write the response to
+    /// `dst`.
+    ///
+    /// The connection is closed without a response if the server shuts down while blocked.
+    #[instrument(skip(self, db, dst, shutdown))]
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        shutdown: &mut Shutdown,
+    ) -> crate::Result<()> {
+        let mut closed = false;
+        let cancel = cancelled(shutdown, dst, &mut closed);
+
+        let moved = db
+            .blocking_move(
+                self.source,
+                self.destination,
+                self.from_front,
+                self.to_front,
+                self.timeout,
+                cancel,
+            )
+            .await;
+
+        let response = match moved {
+            Ok(Some(value)) => Frame::Bulk(value),
+            Ok(None) if closed || shutdown.is_shutdown() => return Ok(()),
+            Ok(None) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Apply the `BLMove` command to the specified `Db` instance without blocking and return the
+    /// response, as done within a transaction.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let moved = db.lmove(
+            self.source,
+            self.destination,
+            self.from_front,
+            self.to_front,
+        );
+
+        let response = match moved {
+            Ok(Some(value)) => Frame::Bulk(value),
+            Ok(None) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let side = |front| if front { "left" } else { "right" };
+
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("blmove".as_bytes()));
+        frame.push_bulk(Bytes::from(self.source.into_bytes()));
+        frame.push_bulk(Bytes::from(self.destination.into_bytes()));
+        frame.push_bulk(Bytes::from(side(self.from_front).as_bytes()));
+        frame.push_bulk(Bytes::from(side(self.to_front).as_bytes()));
+        frame.push_bulk(timeout_to_bytes(self.timeout));
+        frame
+    }
+}
+
+impl LRange {
+    /// Create a new `LRange` command which reads the elements of the list stored at `key`
+    /// between `start` and `stop`.
+    pub fn new(key: impl ToString, start: i64, stop: i64) -> LRange {
+        LRange {
+            key: key.to_string(),
+            start,
+            stop,
+        }
+    }
+
+    /// Parses a `LRange` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LRANGE key start stop
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<LRange> {
+        let key = parse.next_string()?;
+        let start = parse.next_signed_int()?;
+        let stop = parse.next_signed_int()?;
+
+        Ok(LRange { key, start, stop })
+    }
+
+    /// Apply the `LRange` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.lrange(&self.key, self.start, self.stop) {
+            Ok(values) => Frame::Array(values.into_iter().map(Frame::Bulk).collect()),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("lrange".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_int(self.start);
+        frame.push_int(self.stop);
+        frame
+    }
+}
+
+impl LLen {
+    /// Create a new `LLen` command which reads the length of the list stored at `key`.
+    pub fn new(key: impl ToString) -> LLen {
+        LLen {
+            key: key.to_string(),
+        }
+    }
+
+    /// Parses a `LLen` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LLEN key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<LLen> {
+        let key = parse.next_string()?;
+
+        Ok(LLen { key })
+    }
+
+    /// Apply the `LLen` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.llen(&self.key) {
+            Ok(len) => Frame::Integer(len as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("llen".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
+
+impl LIndex {
+    /// Create a new `LIndex` command which reads the element at `index` in the list stored at
+    /// `key`.
+    pub fn new(key: impl ToString, index: i64) -> LIndex {
+        LIndex {
+            key: key.to_string(),
+            index,
+        }
+    }
+
+    /// Parses a `LIndex` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LINDEX key index
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<LIndex> {
+        let key = parse.next_string()?;
+        let index = parse.next_signed_int()?;
+
+        Ok(LIndex { key, index })
+    }
+
+    /// Apply the `LIndex` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.lindex(&self.key, self.index) {
+            Ok(Some(value)) => Frame::Bulk(value),
+            Ok(None) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("lindex".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_int(self.index);
+        frame
+    }
+}
+
+impl LSet {
+    /// Create a new `LSet` command which replaces the element at `index` in the list stored at
+    /// `key` with `value`.
+    pub fn new(key: impl ToString, index: i64, value: Bytes) -> LSet {
+        LSet {
+            key: key.to_string(),
+            index,
+            value,
+        }
+    }
+
+    /// Parses a `LSet` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LSET key index element
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<LSet> {
+        let key = parse.next_string()?;
+        let index = parse.next_signed_int()?;
+        let value = parse.next_bytes()?;
+
+        Ok(LSet { key, index, value })
+    }
+
+    /// Apply the `LSet` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.lset(&self.key, self.index, self.value) {
+            Ok(()) => Frame::Simple("OK".to_string()),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("lset".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_int(self.index);
+        frame.push_bulk(self.value);
+        frame
+    }
+}
+
+impl LRem {
+    /// Create a new `LRem` command which removes up to `count` occurrences of `value` from the
+    /// list stored at `key`.
+    pub fn new(key: impl ToString, count: i64, value: Bytes) -> LRem {
+        LRem {
+            key: key.to_string(),
+            count,
+            value,
+        }
+    }
+
+    /// Parses a `LRem` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LREM key count element
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<LRem> {
+        let key = parse.next_string()?;
+        let count = parse.next_signed_int()?;
+        let value = parse.next_bytes()?;
+
+        Ok(LRem { key, count, value })
+    }
+
+    /// Apply the `LRem` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.lrem(&self.key, self.count, &self.value) {
+            Ok(removed) => Frame::Integer(removed as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("lrem".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_int(self.count);
+        frame.push_bulk(self.value);
+        frame
+    }
+}
+
+impl LTrim {
+    /// Create a new `LTrim` command which trims the list stored at `key` to the elements
+    /// between `start` and `stop`.
+    pub fn new(key: impl ToString, start: i64, stop: i64) -> LTrim {
+        LTrim {
+            key: key.to_string(),
+            start,
+            stop,
+        }
+    }
+
+    /// Parses a `LTrim` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LTRIM key start stop
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<LTrim> {
+        let key = parse.next_string()?;
+        let start = parse.next_signed_int()?;
+        let stop = parse.next_signed_int()?;
+
+        Ok(LTrim { key, start, stop })
+    }
+
+    /// Apply the `LTrim` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.ltrim(&self.key, self.start, self.stop) {
+            Ok(()) => Frame::Simple("OK".to_string()),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("ltrim".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_int(self.start);
+        frame.push_int(self.stop);
+        frame
+    }
+}
+
+/// Parses the timeout of a blocking command, given in seconds as a floating point number. A
+/// timeout of zero blocks indefinitely.
+fn parse_timeout(timeout: &str) -> crate::Result<Option<Duration>> {
+    let secs: f64 = timeout
+        .parse()
+        .map_err(|_| "timeout is not a float or out of range")?;
+
+    if secs < 0.0 {
+        return Err("timeout is negative".into());
+    }
+
+    if secs == 0.0 {
+        return Ok(None);
+    }
+
+    match Duration::try_from_secs_f64(secs) {
+        Ok(timeout) => Ok(Some(timeout)),
+        Err(_) => Err("timeout is not a float or out of range".into()),
+    }
+}
+
+/// Encodes the timeout of a blocking command in seconds.
+fn timeout_to_bytes(timeout: Option<Duration>) -> Bytes {
+    let secs = timeout.map_or(0.0, |timeout| timeout.as_secs_f64());
+    Bytes::from(secs.to_string())
+}
+
+/// Parses the `LEFT` or `RIGHT` argument of a move command. Returns `true` for `LEFT`.
+fn parse_side(side: &str) -> crate::Result<bool> {
+    match &side.to_uppercase()[..] {
+        "LEFT" => Ok(true),
+        "RIGHT" => Ok(false),
+        _ => Err(format!("protocol error: expected LEFT or RIGHT but got {}", side).into()),
+    }
+}
+
+/// Completes when the server shuts down or the client closes the connection, in which case
+/// `closed` is set. A blocked client is thus unblocked as soon as it goes away, before an element
+/// is handed over to it and lost.
+async fn cancelled(shutdown: &mut Shutdown, dst: &mut Connection, closed: &mut bool) {
+    tokio::select! {
+        _ = shutdown.recv() => {}
+        _ = dst.closed() => *closed = true,
+    }
+}
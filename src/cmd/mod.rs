@@ -1,31 +1,148 @@
 //! Redis commands implementation.
 
+mod custom;
+pub use custom::{CommandHandler, CommandRegistry, Custom};
+
+mod expire;
+pub use expire::{Expire, Persist, Ttl};
+
 mod get;
 pub use get::Get;
 
 mod set;
 pub use set::Set;
 
+mod group;
+pub use group::{XAck, XAutoClaim, XClaim, XGroup, XPending, XReadGroup};
+
+mod hash;
+pub use hash::{HDel, HExists, HGet, HGetAll, HIncrBy, HKeys, HLen, HMGet, HSet, HVals};
+
+mod incr;
+pub use incr::{Incr, IncrByFloat};
+
+mod keys;
+pub use keys::{Copy, Del, Exists, Rename, Type};
+
+mod list;
+pub use list::{BLMove, BPop, LIndex, LLen, LRange, LRem, LSet, LTrim, Pop, Push};
+
 mod publish;
 pub use publish::Publish;
 
+mod pubsub;
+pub use pubsub::PubSub;
+
+mod script;
+pub use script::{Eval, Script};
+
+mod sets;
+pub use sets::{SAdd, SCard, SCombine, SIsMember, SMembers, SRem};
+
+mod stream;
+pub use stream::{XAdd, XLen, XRange, XRead, XTrim};
+
+mod string;
+pub use string::{Append, GetDel, GetEx, GetRange, MGet, MSet, SetRange, Strlen};
+
 mod subscribe;
-pub use subscribe::{Subscribe, Unsubscribe};
+pub use subscribe::{PSubscribe, PUnsubscribe, Subscribe, Unsubscribe};
+
+mod transaction;
+pub(crate) use transaction::Transaction;
+pub use transaction::{Discard, Exec, Multi, Unwatch, Watch};
 
 mod unknown;
 pub use unknown::Unknown;
 
-use crate::{Connection, Db, Frame, Parse, ParseError, Shutdown};
+mod zset;
+pub use zset::{ZAdd, ZCount, ZIncrBy, ZPop, ZRange, ZRank, ZRem, ZScore};
+
+use crate::{Connection, Db, Frame, Parse, ParseError, SetOp, Shutdown};
 
 /// Supported Redis commands.
 #[derive(Debug)]
 pub enum Command {
+    Append(Append),
+    BLMove(BLMove),
+    BPop(BPop),
+    Copy(Copy),
+    Custom(Custom),
+    Del(Del),
+    Discard(Discard),
+    Eval(Eval),
+    Exec(Exec),
+    Exists(Exists),
+    Expire(Expire),
     Get(Get),
+    GetDel(GetDel),
+    GetEx(GetEx),
+    GetRange(GetRange),
+    HDel(HDel),
+    HExists(HExists),
+    HGet(HGet),
+    HGetAll(HGetAll),
+    HIncrBy(HIncrBy),
+    HKeys(HKeys),
+    HLen(HLen),
+    HMGet(HMGet),
+    HSet(HSet),
+    HVals(HVals),
+    Incr(Incr),
+    IncrByFloat(IncrByFloat),
+    LIndex(LIndex),
+    LLen(LLen),
+    LRange(LRange),
+    LRem(LRem),
+    LSet(LSet),
+    LTrim(LTrim),
+    MGet(MGet),
+    MSet(MSet),
+    Multi(Multi),
+    Persist(Persist),
+    Pop(Pop),
+    PSubscribe(PSubscribe),
     Publish(Publish),
+    PubSub(PubSub),
+    PUnsubscribe(PUnsubscribe),
+    Push(Push),
+    Rename(Rename),
+    SAdd(SAdd),
+    SCard(SCard),
+    SCombine(SCombine),
+    Script(Script),
     Set(Set),
+    SetRange(SetRange),
+    SIsMember(SIsMember),
+    SMembers(SMembers),
+    SRem(SRem),
+    Strlen(Strlen),
     Subscribe(Subscribe),
+    Ttl(Ttl),
+    Type(Type),
     Unsubscribe(Unsubscribe),
     Unknown(Unknown),
+    Unwatch(Unwatch),
+    Watch(Watch),
+    XAck(XAck),
+    XAdd(XAdd),
+    XAutoClaim(XAutoClaim),
+    XClaim(XClaim),
+    XGroup(XGroup),
+    XLen(XLen),
+    XPending(XPending),
+    XRange(XRange),
+    XRead(XRead),
+    XReadGroup(XReadGroup),
+    XTrim(XTrim),
+    ZAdd(ZAdd),
+    ZCount(ZCount),
+    ZIncrBy(ZIncrBy),
+    ZPop(ZPop),
+    ZRange(ZRange),
+    ZRank(ZRank),
+    ZRem(ZRem),
+    ZScore(ZScore),
 }
 
 impl Command {
@@ -33,6 +150,12 @@ impl Command {
     ///
     /// The frame must be the array variant and represent a supported Redis command.
     pub fn from_frame(frame: Frame) -> crate::Result<Command> {
+        Command::from_frame_with(frame, &CommandRegistry::default())
+    }
+
+    /// Parse a command from a received frame, as `from_frame` does. Commands indb does not
+    /// implement are parsed by the handlers registered in `registry`, if any.
+    pub fn from_frame_with(frame: Frame, registry: &CommandRegistry) -> crate::Result<Command> {
         let mut parse = Parse::new(frame)?;
 
         // All Redis commands begin with the command name as a string. The name is read and
@@ -43,9 +166,109 @@ impl Command {
             "get" => Command::Get(Get::parse_frames(&mut parse)?),
             "set" => Command::Set(Set::parse_frames(&mut parse)?),
             "publish" => Command::Publish(Publish::parse_frames(&mut parse)?),
+            "pubsub" => Command::PubSub(PubSub::parse_frames(&mut parse)?),
+            "del" => Command::Del(Del::parse_frames(&mut parse)?),
+            "exists" => Command::Exists(Exists::parse_frames(&mut parse)?),
+            "type" => Command::Type(Type::parse_frames(&mut parse)?),
+            "rename" => Command::Rename(Rename::parse_frames(&mut parse, false)?),
+            "renamenx" => Command::Rename(Rename::parse_frames(&mut parse, true)?),
+            "copy" => Command::Copy(Copy::parse_frames(&mut parse)?),
+            "expire" | "pexpire" | "expireat" | "pexpireat" => {
+                Command::Expire(Expire::parse_frames(&mut parse, &command_name)?)
+            }
+            "ttl" => Command::Ttl(Ttl::parse_frames(&mut parse, false)?),
+            "pttl" => Command::Ttl(Ttl::parse_frames(&mut parse, true)?),
+            "persist" => Command::Persist(Persist::parse_frames(&mut parse)?),
+            "incr" | "decr" | "incrby" | "decrby" => {
+                Command::Incr(Incr::parse_frames(&mut parse, &command_name)?)
+            }
+            "incrbyfloat" => Command::IncrByFloat(IncrByFloat::parse_frames(&mut parse)?),
+            "append" => Command::Append(Append::parse_frames(&mut parse)?),
+            "strlen" => Command::Strlen(Strlen::parse_frames(&mut parse)?),
+            "getrange" => Command::GetRange(GetRange::parse_frames(&mut parse)?),
+            "setrange" => Command::SetRange(SetRange::parse_frames(&mut parse)?),
+            "getdel" => Command::GetDel(GetDel::parse_frames(&mut parse)?),
+            "getex" => Command::GetEx(GetEx::parse_frames(&mut parse)?),
+            "mget" => Command::MGet(MGet::parse_frames(&mut parse)?),
+            "mset" => Command::MSet(MSet::parse_frames(&mut parse, false)?),
+            "msetnx" => Command::MSet(MSet::parse_frames(&mut parse, true)?),
+            "lpush" => Command::Push(Push::parse_frames(&mut parse, true)?),
+            "rpush" => Command::Push(Push::parse_frames(&mut parse, false)?),
+            "lpop" => Command::Pop(Pop::parse_frames(&mut parse, true)?),
+            "rpop" => Command::Pop(Pop::parse_frames(&mut parse, false)?),
+            "blpop" => Command::BPop(BPop::parse_frames(&mut parse, true)?),
+            "brpop" => Command::BPop(BPop::parse_frames(&mut parse, false)?),
+            "blmove" => Command::BLMove(BLMove::parse_frames(&mut parse)?),
+            "lrange" => Command::LRange(LRange::parse_frames(&mut parse)?),
+            "llen" => Command::LLen(LLen::parse_frames(&mut parse)?),
+            "lindex" => Command::LIndex(LIndex::parse_frames(&mut parse)?),
+            "lset" => Command::LSet(LSet::parse_frames(&mut parse)?),
+            "lrem" => Command::LRem(LRem::parse_frames(&mut parse)?),
+            "ltrim" => Command::LTrim(LTrim::parse_frames(&mut parse)?),
+            "hset" => Command::HSet(HSet::parse_frames(&mut parse)?),
+            "hget" => Command::HGet(HGet::parse_frames(&mut parse)?),
+            "hmget" => Command::HMGet(HMGet::parse_frames(&mut parse)?),
+            "hdel" => Command::HDel(HDel::parse_frames(&mut parse)?),
+            "hgetall" => Command::HGetAll(HGetAll::parse_frames(&mut parse)?),
+            "hincrby" => Command::HIncrBy(HIncrBy::parse_frames(&mut parse)?),
+            "hexists" => Command::HExists(HExists::parse_frames(&mut parse)?),
+            "hkeys" => Command::HKeys(HKeys::parse_frames(&mut parse)?),
+            "hvals" => Command::HVals(HVals::parse_frames(&mut parse)?),
+            "hlen" => Command::HLen(HLen::parse_frames(&mut parse)?),
+            "sadd" => Command::SAdd(SAdd::parse_frames(&mut parse)?),
+            "srem" => Command::SRem(SRem::parse_frames(&mut parse)?),
+            "smembers" => Command::SMembers(SMembers::parse_frames(&mut parse)?),
+            "sismember" => Command::SIsMember(SIsMember::parse_frames(&mut parse)?),
+            "scard" => Command::SCard(SCard::parse_frames(&mut parse)?),
+            "sinter" => Command::SCombine(SCombine::parse_frames(&mut parse, SetOp::Inter, false)?),
+            "sunion" => Command::SCombine(SCombine::parse_frames(&mut parse, SetOp::Union, false)?),
+            "sdiff" => Command::SCombine(SCombine::parse_frames(&mut parse, SetOp::Diff, false)?),
+            "sinterstore" => {
+                Command::SCombine(SCombine::parse_frames(&mut parse, SetOp::Inter, true)?)
+            }
+            "sunionstore" => {
+                Command::SCombine(SCombine::parse_frames(&mut parse, SetOp::Union, true)?)
+            }
+            "sdiffstore" => {
+                Command::SCombine(SCombine::parse_frames(&mut parse, SetOp::Diff, true)?)
+            }
+            "zadd" => Command::ZAdd(ZAdd::parse_frames(&mut parse)?),
+            "zrange" => Command::ZRange(ZRange::parse_frames(&mut parse)?),
+            "zrank" => Command::ZRank(ZRank::parse_frames(&mut parse)?),
+            "zscore" => Command::ZScore(ZScore::parse_frames(&mut parse)?),
+            "zrem" => Command::ZRem(ZRem::parse_frames(&mut parse)?),
+            "zcount" => Command::ZCount(ZCount::parse_frames(&mut parse)?),
+            "zincrby" => Command::ZIncrBy(ZIncrBy::parse_frames(&mut parse)?),
+            "zpopmin" => Command::ZPop(ZPop::parse_frames(&mut parse, false)?),
+            "zpopmax" => Command::ZPop(ZPop::parse_frames(&mut parse, true)?),
+            "xadd" => Command::XAdd(XAdd::parse_frames(&mut parse)?),
+            "xrange" => Command::XRange(XRange::parse_frames(&mut parse, false)?),
+            "xrevrange" => Command::XRange(XRange::parse_frames(&mut parse, true)?),
+            "xlen" => Command::XLen(XLen::parse_frames(&mut parse)?),
+            "xtrim" => Command::XTrim(XTrim::parse_frames(&mut parse)?),
+            "xread" => Command::XRead(XRead::parse_frames(&mut parse)?),
+            "xgroup" => Command::XGroup(XGroup::parse_frames(&mut parse)?),
+            "xreadgroup" => Command::XReadGroup(XReadGroup::parse_frames(&mut parse)?),
+            "xack" => Command::XAck(XAck::parse_frames(&mut parse)?),
+            "xpending" => Command::XPending(XPending::parse_frames(&mut parse)?),
+            "xclaim" => Command::XClaim(XClaim::parse_frames(&mut parse)?),
+            "xautoclaim" => Command::XAutoClaim(XAutoClaim::parse_frames(&mut parse)?),
+            "multi" => Command::Multi(Multi::parse_frames(&mut parse)?),
+            "exec" => Command::Exec(Exec::parse_frames(&mut parse)?),
+            "discard" => Command::Discard(Discard::parse_frames(&mut parse)?),
+            "eval" => Command::Eval(Eval::parse_frames(&mut parse, false)?),
+            "evalsha" => Command::Eval(Eval::parse_frames(&mut parse, true)?),
+            "script" => Command::Script(Script::parse_frames(&mut parse)?),
+            "watch" => Command::Watch(Watch::parse_frames(&mut parse)?),
+            "unwatch" => Command::Unwatch(Unwatch::parse_frames(&mut parse)?),
             "subscribe" => Command::Subscribe(Subscribe::parse_frames(&mut parse)?),
             "unsubscribe" => Command::Unsubscribe(Unsubscribe::parse_frames(&mut parse)?),
-            _ => return Ok(Command::Unknown(Unknown::new(command_name))),
+            "psubscribe" => Command::PSubscribe(PSubscribe::parse_frames(&mut parse)?),
+            "punsubscribe" => Command::PUnsubscribe(PUnsubscribe::parse_frames(&mut parse)?),
+            _ => match registry.parse(&command_name, &mut parse) {
+                Some(cmd) => Command::Custom(cmd?),
+                None => return Ok(Command::Unknown(Unknown::new(command_name))),
+            },
         };
 
         // Check if there is any remaining uncomsumed fields in the `Parse`.
@@ -55,22 +278,139 @@ impl Command {
     }
 
     /// Apply the command to the specified `Db` instance and write the response to `dst`.
+    ///
+    /// Between `MULTI` and `EXEC`, commands are queued in `transaction` instead, except for
+    /// the transaction commands themselves.
     pub(crate) async fn apply(
         self,
         db: &Db,
         dst: &mut Connection,
         shutdown: &mut Shutdown,
+        transaction: &mut Transaction,
     ) -> crate::Result<()> {
         use Command::*;
+
+        if transaction.is_active() && !matches!(self, Multi(_) | Exec(_) | Discard(_) | Watch(_)) {
+            return transaction.queue(self, dst).await;
+        }
+
+        let response = match self {
+            BPop(cmd) => return cmd.apply(db, dst, shutdown).await,
+            BLMove(cmd) => return cmd.apply(db, dst, shutdown).await,
+            XRead(cmd) => return cmd.apply(db, dst, shutdown).await,
+            XReadGroup(cmd) => return cmd.apply(db, dst, shutdown).await,
+            Eval(cmd) => return cmd.apply(db, dst).await,
+            Subscribe(cmd) => return cmd.apply(db, dst, shutdown).await,
+            PSubscribe(cmd) => return cmd.apply(db, dst, shutdown).await,
+            Multi(cmd) => return cmd.apply(dst, transaction).await,
+            Exec(cmd) => return cmd.apply(db, dst, transaction).await,
+            Discard(cmd) => return cmd.apply(dst, transaction).await,
+            Watch(cmd) => return cmd.apply(db, dst, transaction).await,
+            Unwatch(cmd) => return cmd.apply(dst, transaction).await,
+            Custom(cmd) => return cmd.apply(db, dst).await,
+            Unknown(cmd) => return cmd.apply(dst).await,
+            // `Unsubcribe` and `PUnsubscribe` cannot be applied. They may only be received from
+            // the context of a `Subscribe` or `PSubscribe` command.
+            Unsubscribe(_) => return Err("`Unsubscribe` is unsupported in this context".into()),
+            PUnsubscribe(_) => return Err("`PUnsubscribe` is unsupported in this context".into()),
+            cmd => {
+                // Entered asynchronously, so that the worker is not blocked while another
+                // client runs an exclusive section.
+                let _section = db.enter_section().await;
+                cmd.execute(db)
+            }
+        };
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Apply the command to the specified `Db` instance and return the response, as done within
+    /// a transaction.
+    ///
+    /// Blocking commands return right away as if their timeout elapsed. Commands changing the
+    /// state of the connection, such as `SUBSCRIBE` or `MULTI`, cannot be applied and an error
+    /// is returned instead.
+    pub fn execute(self, db: &Db) -> Frame {
+        use Command::*;
         match self {
-            Get(cmd) => cmd.apply(db, dst).await,
-            Set(cmd) => cmd.apply(db, dst).await,
-            Publish(cmd) => cmd.apply(db, dst).await,
-            Subscribe(cmd) => cmd.apply(db, dst, shutdown).await,
-            Unknown(cmd) => cmd.apply(dst).await,
-            // `Unsubcribe` cannot be applied. It may only be received from the context of a
-            // `Subscribe` command.
-            Unsubscribe(_) => Err("`Unsubscribe` is unsupported in this context".into()),
+            Get(cmd) => cmd.execute(db),
+            Set(cmd) => cmd.execute(db),
+            Publish(cmd) => cmd.execute(db),
+            PubSub(cmd) => cmd.execute(db),
+            Del(cmd) => cmd.execute(db),
+            Exists(cmd) => cmd.execute(db),
+            Type(cmd) => cmd.execute(db),
+            Rename(cmd) => cmd.execute(db),
+            Copy(cmd) => cmd.execute(db),
+            Expire(cmd) => cmd.execute(db),
+            Ttl(cmd) => cmd.execute(db),
+            Persist(cmd) => cmd.execute(db),
+            Incr(cmd) => cmd.execute(db),
+            IncrByFloat(cmd) => cmd.execute(db),
+            Append(cmd) => cmd.execute(db),
+            Strlen(cmd) => cmd.execute(db),
+            GetRange(cmd) => cmd.execute(db),
+            SetRange(cmd) => cmd.execute(db),
+            GetDel(cmd) => cmd.execute(db),
+            GetEx(cmd) => cmd.execute(db),
+            MGet(cmd) => cmd.execute(db),
+            MSet(cmd) => cmd.execute(db),
+            Push(cmd) => cmd.execute(db),
+            Pop(cmd) => cmd.execute(db),
+            LRange(cmd) => cmd.execute(db),
+            LLen(cmd) => cmd.execute(db),
+            LIndex(cmd) => cmd.execute(db),
+            LSet(cmd) => cmd.execute(db),
+            LRem(cmd) => cmd.execute(db),
+            LTrim(cmd) => cmd.execute(db),
+            HSet(cmd) => cmd.execute(db),
+            HGet(cmd) => cmd.execute(db),
+            HMGet(cmd) => cmd.execute(db),
+            HDel(cmd) => cmd.execute(db),
+            HGetAll(cmd) => cmd.execute(db),
+            HIncrBy(cmd) => cmd.execute(db),
+            HExists(cmd) => cmd.execute(db),
+            HKeys(cmd) => cmd.execute(db),
+            HVals(cmd) => cmd.execute(db),
+            HLen(cmd) => cmd.execute(db),
+            SAdd(cmd) => cmd.execute(db),
+            SRem(cmd) => cmd.execute(db),
+            SMembers(cmd) => cmd.execute(db),
+            SIsMember(cmd) => cmd.execute(db),
+            SCard(cmd) => cmd.execute(db),
+            SCombine(cmd) => cmd.execute(db),
+            ZAdd(cmd) => cmd.execute(db),
+            ZRange(cmd) => cmd.execute(db),
+            ZRank(cmd) => cmd.execute(db),
+            ZScore(cmd) => cmd.execute(db),
+            ZRem(cmd) => cmd.execute(db),
+            ZCount(cmd) => cmd.execute(db),
+            ZIncrBy(cmd) => cmd.execute(db),
+            ZPop(cmd) => cmd.execute(db),
+            XAdd(cmd) => cmd.execute(db),
+            XRange(cmd) => cmd.execute(db),
+            XLen(cmd) => cmd.execute(db),
+            XTrim(cmd) => cmd.execute(db),
+            XGroup(cmd) => cmd.execute(db),
+            XAck(cmd) => cmd.execute(db),
+            XPending(cmd) => cmd.execute(db),
+            XClaim(cmd) => cmd.execute(db),
+            XAutoClaim(cmd) => cmd.execute(db),
+            Eval(cmd) => cmd.execute(db),
+            Script(cmd) => cmd.execute(db),
+            BPop(cmd) => cmd.execute(db),
+            BLMove(cmd) => cmd.execute(db),
+            XRead(cmd) => cmd.execute(db),
+            XReadGroup(cmd) => cmd.execute(db),
+            Unknown(cmd) => cmd.execute(),
+            Unwatch(cmd) => cmd.execute(),
+            Subscribe(_) | PSubscribe(_) | Unsubscribe(_) | PUnsubscribe(_) | Multi(_)
+            | Exec(_) | Discard(_) | Watch(_) | Custom(_) => Frame::Error(format!(
+                "ERR '{}' is not allowed in this context",
+                self.get_name()
+            )),
         }
     }
 
@@ -80,8 +420,82 @@ impl Command {
             Command::Get(_) => "get",
             Command::Set(_) => "set",
             Command::Publish(_) => "publish",
+            Command::PubSub(_) => "pubsub",
+            Command::Del(_) => "del",
+            Command::Exists(_) => "exists",
+            Command::Type(_) => "type",
+            Command::Rename(cmd) => cmd.get_name(),
+            Command::Copy(_) => "copy",
+            Command::Custom(cmd) => cmd.get_name(),
+            Command::Expire(cmd) => cmd.get_name(),
+            Command::Ttl(cmd) => cmd.get_name(),
+            Command::Persist(_) => "persist",
+            Command::Incr(cmd) => cmd.get_name(),
+            Command::IncrByFloat(_) => "incrbyfloat",
+            Command::Append(_) => "append",
+            Command::Strlen(_) => "strlen",
+            Command::GetRange(_) => "getrange",
+            Command::SetRange(_) => "setrange",
+            Command::GetDel(_) => "getdel",
+            Command::GetEx(_) => "getex",
+            Command::MGet(_) => "mget",
+            Command::MSet(cmd) => cmd.get_name(),
+            Command::Push(cmd) => cmd.get_name(),
+            Command::Pop(cmd) => cmd.get_name(),
+            Command::BPop(cmd) => cmd.get_name(),
+            Command::BLMove(_) => "blmove",
+            Command::LRange(_) => "lrange",
+            Command::LLen(_) => "llen",
+            Command::LIndex(_) => "lindex",
+            Command::LSet(_) => "lset",
+            Command::LRem(_) => "lrem",
+            Command::LTrim(_) => "ltrim",
+            Command::HSet(_) => "hset",
+            Command::HGet(_) => "hget",
+            Command::HMGet(_) => "hmget",
+            Command::HDel(_) => "hdel",
+            Command::HGetAll(_) => "hgetall",
+            Command::HIncrBy(_) => "hincrby",
+            Command::HExists(_) => "hexists",
+            Command::HKeys(_) => "hkeys",
+            Command::HVals(_) => "hvals",
+            Command::HLen(_) => "hlen",
+            Command::SAdd(_) => "sadd",
+            Command::SRem(_) => "srem",
+            Command::SMembers(_) => "smembers",
+            Command::SIsMember(_) => "sismember",
+            Command::SCard(_) => "scard",
+            Command::SCombine(cmd) => cmd.get_name(),
+            Command::ZAdd(_) => "zadd",
+            Command::ZRange(_) => "zrange",
+            Command::ZRank(_) => "zrank",
+            Command::ZScore(_) => "zscore",
+            Command::ZRem(_) => "zrem",
+            Command::ZCount(_) => "zcount",
+            Command::ZIncrBy(_) => "zincrby",
+            Command::ZPop(cmd) => cmd.get_name(),
+            Command::XAdd(_) => "xadd",
+            Command::XRange(cmd) => cmd.get_name(),
+            Command::XLen(_) => "xlen",
+            Command::XTrim(_) => "xtrim",
+            Command::XRead(_) => "xread",
+            Command::XGroup(_) => "xgroup",
+            Command::XReadGroup(_) => "xreadgroup",
+            Command::XAck(_) => "xack",
+            Command::XPending(_) => "xpending",
+            Command::XClaim(_) => "xclaim",
+            Command::XAutoClaim(_) => "xautoclaim",
             Command::Subscribe(_) => "subscribe",
             Command::Unsubscribe(_) => "unsubscribe",
+            Command::PSubscribe(_) => "psubscribe",
+            Command::PUnsubscribe(_) => "punsubscribe",
+            Command::Multi(_) => "multi",
+            Command::Exec(_) => "exec",
+            Command::Discard(_) => "discard",
+            Command::Eval(cmd) => cmd.get_name(),
+            Command::Script(_) => "script",
+            Command::Watch(_) => "watch",
+            Command::Unwatch(_) => "unwatch",
             Command::Unknown(cmd) => cmd.get_name(),
         }
     }
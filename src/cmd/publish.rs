@@ -1,4 +1,4 @@
-use crate::{Connection, Db, Frame, Parse};
+use crate::{Db, Frame, Parse};
 
 use bytes::Bytes;
 
@@ -28,16 +28,11 @@ impl Publish {
         Ok(Publish { channel, message })
     }
 
-    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+    pub(crate) fn execute(self, db: &Db) -> Frame {
         let num_subscribers = db.publish(&self.channel, self.message);
 
         // The number of subscribers is just a hint.
-        let response = Frame::Integer(num_subscribers as u64);
-
-        // Write the response to the client.
-        dst.write_frame(&response).await?;
-
-        Ok(())
+        Frame::Integer(num_subscribers as i64)
     }
 
     pub(crate) fn into_frame(self) -> Frame {
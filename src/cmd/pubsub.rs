@@ -0,0 +1,131 @@
+use crate::{Db, Frame, Parse, ParseError};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Inspects the state of the pub/sub subsystem.
+///
+/// `PUBSUB CHANNELS` lists the channels with at least one subscriber, `PUBSUB NUMSUB` returns
+/// the number of subscribers of the given channels and `PUBSUB NUMPAT` returns the number of
+/// patterns with at least one subscriber.
+#[derive(Debug)]
+pub struct PubSub {
+    op: PubSubOp,
+}
+
+/// Introspection performed by `PubSub`.
+#[derive(Debug)]
+enum PubSubOp {
+    /// List the active channels, only the ones matching the glob-style pattern if given.
+    Channels(Option<String>),
+    NumSub(Vec<String>),
+    NumPat,
+}
+
+impl PubSub {
+    /// Create a new `PubSub` command which lists the channels with at least one subscriber,
+    /// only the ones matching the glob-style `pattern` if given.
+    pub fn channels(pattern: Option<String>) -> PubSub {
+        PubSub {
+            op: PubSubOp::Channels(pattern),
+        }
+    }
+
+    /// Create a new `PubSub` command which returns the number of subscribers of each of
+    /// `channels`.
+    pub fn numsub(channels: &[String]) -> PubSub {
+        PubSub {
+            op: PubSubOp::NumSub(channels.to_vec()),
+        }
+    }
+
+    /// Create a new `PubSub` command which returns the number of patterns with at least one
+    /// subscriber.
+    pub fn numpat() -> PubSub {
+        PubSub {
+            op: PubSubOp::NumPat,
+        }
+    }
+
+    /// Parses a `PubSub` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// PUBSUB CHANNELS [pattern]
+    /// PUBSUB NUMSUB [channel [channel ...]]
+    /// PUBSUB NUMPAT
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<PubSub> {
+        let subcommand = parse.next_string()?.to_uppercase();
+
+        let op = match &subcommand[..] {
+            "CHANNELS" => match parse.next_string() {
+                Ok(pattern) => PubSubOp::Channels(Some(pattern)),
+                Err(ParseError::EndOfStream) => PubSubOp::Channels(None),
+                Err(err) => return Err(err.into()),
+            },
+            "NUMSUB" => PubSubOp::NumSub(parse.rest_strings()?),
+            "NUMPAT" => PubSubOp::NumPat,
+            _ => {
+                return Err(format!(
+                    "PUBSUB command error: unsupported subcommand {}",
+                    subcommand
+                )
+                .into())
+            }
+        };
+
+        Ok(PubSub { op })
+    }
+
+    /// Apply the `PubSub` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match self.op {
+            PubSubOp::Channels(pattern) => {
+                let mut response = Frame::array();
+                for channel in db.pubsub_channels(pattern.as_deref()) {
+                    response.push_bulk(Bytes::from(channel));
+                }
+                response
+            }
+            PubSubOp::NumSub(channels) => {
+                let counts = db.pubsub_numsub(&channels);
+
+                let mut response = Frame::array();
+                for (channel, count) in channels.into_iter().zip(counts) {
+                    response.push_bulk(Bytes::from(channel));
+                    response.push_int(count as i64);
+                }
+                response
+            }
+            PubSubOp::NumPat => Frame::Integer(db.pubsub_numpat() as i64),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("pubsub".as_bytes()));
+        match self.op {
+            PubSubOp::Channels(pattern) => {
+                frame.push_bulk(Bytes::from("channels".as_bytes()));
+                if let Some(pattern) = pattern {
+                    frame.push_bulk(Bytes::from(pattern.into_bytes()));
+                }
+            }
+            PubSubOp::NumSub(channels) => {
+                frame.push_bulk(Bytes::from("numsub".as_bytes()));
+                for channel in channels {
+                    frame.push_bulk(Bytes::from(channel.into_bytes()));
+                }
+            }
+            PubSubOp::NumPat => frame.push_bulk(Bytes::from("numpat".as_bytes())),
+        }
+        frame
+    }
+}
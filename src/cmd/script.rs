@@ -0,0 +1,480 @@
+use crate::cmd::{Command, Parse, ParseError};
+use crate::{db, frame, Connection, Db, Frame};
+
+use bytes::Bytes;
+use mlua::{Function, Lua, Table, Value, Variadic};
+use tokio::task;
+use tracing::{debug, instrument};
+
+/// Runs a Lua script on the server, atomically.
+///
+/// The script accesses the given keys and arguments through the `KEYS` and `ARGV` tables, and
+/// applies commands with `redis.call`, which raises an error if the command fails, or
+/// `redis.pcall`, which returns the error instead. `EVAL` caches the script, which `EVALSHA`
+/// then runs by its SHA1 digest. Scripts may neither set globals nor modify the libraries.
+///
+/// Scripts running for longer than the time limit of the server are aborted with an error,
+/// unless they already modified the keyspace, in which case they run to completion.
+#[derive(Debug)]
+pub struct Eval {
+    script: Source,
+    keys: Vec<String>,
+    args: Vec<Bytes>,
+}
+
+/// Script run by `Eval`.
+#[derive(Debug)]
+enum Source {
+    /// The body of the script.
+    Body(Bytes),
+    /// The SHA1 digest of a cached script.
+    Sha1(String),
+}
+
+/// Manages the cache of Lua scripts.
+///
+/// `SCRIPT LOAD` caches a script without running it and returns its SHA1 digest, `SCRIPT
+/// EXISTS` checks whether scripts are cached and `SCRIPT FLUSH` empties the cache.
+#[derive(Debug)]
+pub struct Script {
+    op: ScriptOp,
+}
+
+/// Operation performed by `Script`.
+#[derive(Debug)]
+enum ScriptOp {
+    Load(Bytes),
+    Exists(Vec<String>),
+    Flush,
+}
+
+impl Eval {
+    /// Create a new `Eval` command which runs `script` with the given `keys` and `args`.
+    pub fn new(script: Bytes, keys: &[String], args: Vec<Bytes>) -> Eval {
+        Eval {
+            script: Source::Body(script),
+            keys: keys.to_vec(),
+            args,
+        }
+    }
+
+    /// Create a new `Eval` command which runs the cached script whose SHA1 digest is `sha1`
+    /// with the given `keys` and `args`.
+    pub fn sha1(sha1: &str, keys: &[String], args: Vec<Bytes>) -> Eval {
+        Eval {
+            script: Source::Sha1(sha1.to_string()),
+            keys: keys.to_vec(),
+            args,
+        }
+    }
+
+    /// Parses an `Eval` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// EVAL script numkeys [key [key ...]] [arg [arg ...]]
+    /// EVALSHA sha1 numkeys [key [key ...]] [arg [arg ...]]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse, by_sha1: bool) -> crate::Result<Eval> {
+        let script = if by_sha1 {
+            Source::Sha1(parse.next_string()?)
+        } else {
+            Source::Body(parse.next_bytes()?)
+        };
+
+        let numkeys = parse.next_signed_int()?;
+        if numkeys < 0 {
+            return Err("Number of keys can't be negative".into());
+        }
+
+        let mut keys = vec![];
+        for _ in 0..numkeys {
+            match parse.next_string() {
+                Ok(key) => keys.push(key),
+                Err(ParseError::EndOfStream) => {
+                    return Err("Number of keys can't be greater than number of args".into())
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        let args = parse.rest_bytes()?;
+
+        Ok(Eval { script, keys, args })
+    }
+
+    /// Apply the `Eval` command to the specified `Db` instance and write the response to
+    /// `dst`.
+    ///
+    /// The script runs on a thread dedicated to blocking operations, as the `Db` stays locked
+    /// until it returns.
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let db = db.clone();
+        let response = task::spawn_blocking(move || self.execute(&db)).await?;
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Apply the `Eval` command to the specified `Db` instance and return the response.
+    ///
+    /// No other client accesses the `Db` while the script runs.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let Eval { script, keys, args } = self;
+
+        // The script is only cached once it compiled.
+        let sha1 = match script {
+            Source::Body(script) => db.script_load(script),
+            Source::Sha1(sha1) => Ok(sha1),
+        };
+
+        let response = match sha1 {
+            Ok(sha1) => db
+                .exclusive(|| db.run_script(&sha1, |lua, function| run(lua, function, keys, args)))
+                .map(|res| res.unwrap_or_else(|err| error_reply(&err)))
+                .unwrap_or_else(|| {
+                    Frame::Error("NOSCRIPT No matching script. Please use EVAL.".to_string())
+                }),
+            Err(err) => error_reply(&err),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    /// Returns the command name.
+    pub(crate) fn get_name(&self) -> &str {
+        match self.script {
+            Source::Body(_) => "eval",
+            Source::Sha1(_) => "evalsha",
+        }
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from(self.get_name().as_bytes().to_vec()));
+        match self.script {
+            Source::Body(script) => frame.push_bulk(script),
+            Source::Sha1(sha1) => frame.push_bulk(Bytes::from(sha1.into_bytes())),
+        }
+        frame.push_int(self.keys.len() as i64);
+        for key in self.keys {
+            frame.push_bulk(Bytes::from(key.into_bytes()));
+        }
+        for arg in self.args {
+            frame.push_bulk(arg);
+        }
+        frame
+    }
+}
+
+impl Script {
+    /// Create a new `Script` command which caches `script` without running it.
+    pub fn load(script: Bytes) -> Script {
+        Script {
+            op: ScriptOp::Load(script),
+        }
+    }
+
+    /// Create a new `Script` command which checks whether the scripts whose SHA1 digests are
+    /// `sha1s` are cached.
+    pub fn exists(sha1s: &[String]) -> Script {
+        Script {
+            op: ScriptOp::Exists(sha1s.to_vec()),
+        }
+    }
+
+    /// Create a new `Script` command which removes all the cached scripts.
+    pub fn flush() -> Script {
+        Script {
+            op: ScriptOp::Flush,
+        }
+    }
+
+    /// Parses a `Script` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SCRIPT LOAD script
+    /// SCRIPT EXISTS sha1 [sha1 ...]
+    /// SCRIPT FLUSH [ASYNC | SYNC]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Script> {
+        let subcommand = parse.next_string()?.to_uppercase();
+
+        let op = match &subcommand[..] {
+            "LOAD" => ScriptOp::Load(parse.next_bytes()?),
+            "EXISTS" => {
+                let mut sha1s = vec![parse.next_string()?];
+                sha1s.extend(parse.rest_strings()?);
+                ScriptOp::Exists(sha1s)
+            }
+            "FLUSH" => {
+                // The cache is always flushed synchronously, the mode is only validated.
+                match parse.next_string() {
+                    Ok(mode) if mode.eq_ignore_ascii_case("async") => {}
+                    Ok(mode) if mode.eq_ignore_ascii_case("sync") => {}
+                    Ok(mode) => {
+                        return Err(
+                            format!("SCRIPT command error: unsupported mode {}", mode).into()
+                        )
+                    }
+                    Err(ParseError::EndOfStream) => {}
+                    Err(err) => return Err(err.into()),
+                }
+                ScriptOp::Flush
+            }
+            _ => {
+                return Err(format!(
+                    "SCRIPT command error: unsupported subcommand {}",
+                    subcommand
+                )
+                .into())
+            }
+        };
+
+        Ok(Script { op })
+    }
+
+    /// Apply the `Script` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match self.op {
+            ScriptOp::Load(script) => match db.script_load(script) {
+                Ok(sha1) => Frame::Bulk(Bytes::from(sha1)),
+                Err(err) => error_reply(&err),
+            },
+            ScriptOp::Exists(sha1s) => {
+                let mut response = Frame::array();
+                for exists in db.script_exists(&sha1s) {
+                    response.push_int(exists as i64);
+                }
+                response
+            }
+            ScriptOp::Flush => {
+                db.script_flush();
+                Frame::Simple("OK".to_string())
+            }
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("script".as_bytes()));
+        match self.op {
+            ScriptOp::Load(script) => {
+                frame.push_bulk(Bytes::from("load".as_bytes()));
+                frame.push_bulk(script);
+            }
+            ScriptOp::Exists(sha1s) => {
+                frame.push_bulk(Bytes::from("exists".as_bytes()));
+                for sha1 in sha1s {
+                    frame.push_bulk(Bytes::from(sha1.into_bytes()));
+                }
+            }
+            ScriptOp::Flush => frame.push_bulk(Bytes::from("flush".as_bytes())),
+        }
+        frame
+    }
+}
+
+/// Runs `function`, compiled from a script, and converts the value it returns into a frame.
+///
+/// The script runs in its own read-only environment holding `KEYS`, `ARGV` and the `redis`
+/// library, which falls back to the read-only globals of the interpreter. Each script gets its own
+/// `redis` library, so that no script sees the changes of another.
+fn run(lua: &Lua, function: Function, keys: Vec<String>, args: Vec<Bytes>) -> mlua::Result<Frame> {
+    let args = args
+        .iter()
+        .map(|arg| lua.create_string(arg))
+        .collect::<mlua::Result<Vec<_>>>()?;
+
+    let env = lua.create_table()?;
+    env.set("KEYS", keys)?;
+    env.set("ARGV", args)?;
+    env.set("redis", redis_lib(lua)?)?;
+
+    let env = db::read_only(lua, env, lua.globals())?;
+
+    function.set_environment(env)?;
+    let value = function.call(())?;
+
+    to_frame(value)
+}
+
+/// Returns the `redis` library, through which scripts apply commands to the `Db` running them.
+fn redis_lib(lua: &Lua) -> mlua::Result<Table<'_>> {
+    let redis = lua.create_table()?;
+    redis.set(
+        "call",
+        lua.create_function(|lua, args| call(lua, args, true))?,
+    )?;
+    redis.set(
+        "pcall",
+        lua.create_function(|lua, args| call(lua, args, false))?,
+    )?;
+    redis.set(
+        "status_reply",
+        lua.create_function(|lua, status: mlua::String| reply_table(lua, "ok", status))?,
+    )?;
+    redis.set(
+        "error_reply",
+        lua.create_function(|lua, err: mlua::String| reply_table(lua, "err", err))?,
+    )?;
+
+    Ok(redis)
+}
+
+/// Applies the command made of `args` on behalf of a script to the `Db` running it, and converts
+/// its reply into a Lua value.
+///
+/// If the command fails and `raise` is set, the error is raised in the script, otherwise it is
+/// returned as a table with an `err` field.
+fn call<'lua>(
+    lua: &'lua Lua,
+    args: Variadic<Value<'lua>>,
+    raise: bool,
+) -> mlua::Result<Value<'lua>> {
+    // `unwrap` is safe as scripts only run through `Db::run_script`.
+    let db = lua.app_data_ref::<Db>().unwrap();
+
+    // Raised even by `redis.pcall`, as the script must be aborted.
+    db.check_time_limit(lua)?;
+
+    let response = match command_frame(args) {
+        Ok(frame) => match Command::from_frame(frame) {
+            Ok(Command::Eval(_)) | Ok(Command::Script(_)) => {
+                Frame::Error("ERR This command is not allowed from script".to_string())
+            }
+            Ok(cmd) => cmd.execute(&db),
+            Err(err) => Frame::Error(format!("ERR {}", err)),
+        },
+        Err(err) => Frame::Error(err.to_string()),
+    };
+
+    match response {
+        Frame::Error(err) if raise => Err(mlua::Error::external(err)),
+        response => to_lua(lua, response),
+    }
+}
+
+/// Builds the frame of the command called by a script. Numbers are converted into strings as
+/// Lua would.
+fn command_frame(args: Variadic<Value>) -> crate::Result<Frame> {
+    if args.is_empty() {
+        return Err("ERR Please specify at least one argument for this redis lib call".into());
+    }
+
+    let mut frame = Frame::array();
+    for arg in args.iter() {
+        let arg = match arg {
+            Value::String(arg) => Bytes::copy_from_slice(arg.as_bytes()),
+            Value::Integer(arg) => Bytes::from(arg.to_string()),
+            Value::Number(arg) => Bytes::from(arg.to_string()),
+            _ => {
+                return Err(
+                    "ERR Lua redis lib command arguments must be strings or integers".into(),
+                )
+            }
+        };
+        frame.push_bulk(arg);
+    }
+
+    Ok(frame)
+}
+
+/// Returns a table with `message` as the given field, as returned by `redis.status_reply` and
+/// `redis.error_reply`.
+fn reply_table<'lua>(
+    lua: &'lua Lua,
+    field: &str,
+    message: mlua::String<'lua>,
+) -> mlua::Result<Table<'lua>> {
+    let table = lua.create_table()?;
+    table.set(field, message)?;
+    Ok(table)
+}
+
+/// Converts a command reply into a Lua value.
+///
+/// Status replies and errors are converted into tables with an `ok` or `err` field, and `Null`
+/// into `false`.
+fn to_lua(lua: &Lua, frame: Frame) -> mlua::Result<Value<'_>> {
+    let value = match frame {
+        Frame::Simple(status) => Value::Table(reply_table(lua, "ok", lua.create_string(status)?)?),
+        Frame::Error(err) => Value::Table(reply_table(lua, "err", lua.create_string(err)?)?),
+        Frame::Integer(value) => Value::Integer(value),
+        Frame::Bulk(value) => Value::String(lua.create_string(&value)?),
+        Frame::Null => Value::Boolean(false),
+        Frame::Array(frames) => {
+            let table = lua.create_table()?;
+            for (i, frame) in frames.into_iter().enumerate() {
+                table.raw_set(i + 1, to_lua(lua, frame)?)?;
+            }
+            Value::Table(table)
+        }
+        // Doubles are strings in RESP2.
+        Frame::Double(value) => Value::String(lua.create_string(frame::format_double(value))?),
+    };
+
+    Ok(value)
+}
+
+/// Converts a value returned by a script into a frame.
+///
+/// Numbers are truncated to integers, `true` is converted into `1` and `false` into `Null`.
+/// Tables with an `ok` or `err` field are converted into status replies or errors, other tables
+/// into arrays stopping at the first `nil`.
+fn to_frame(value: Value) -> mlua::Result<Frame> {
+    let frame = match value {
+        Value::Boolean(true) => Frame::Integer(1),
+        Value::Integer(value) => Frame::Integer(value),
+        Value::Number(value) => Frame::Integer(value as i64),
+        Value::String(value) => Frame::Bulk(Bytes::copy_from_slice(value.as_bytes())),
+        Value::Table(table) => {
+            if let Value::String(err) = table.raw_get("err")? {
+                Frame::Error(err.to_string_lossy().into_owned())
+            } else if let Value::String(status) = table.raw_get("ok")? {
+                Frame::Simple(status.to_string_lossy().into_owned())
+            } else {
+                Frame::Array(
+                    table
+                        .sequence_values()
+                        .map(|value| value.and_then(to_frame))
+                        .collect::<mlua::Result<_>>()?,
+                )
+            }
+        }
+        _ => Frame::Null,
+    };
+
+    Ok(frame)
+}
+
+/// Returns the reply to a script failing to compile or run with `err`.
+///
+/// Errors raised by `redis.call` are replied as is. Other errors are reduced to their first line,
+/// leaving out the Lua stack traceback which error frames cannot hold.
+fn error_reply(err: &mlua::Error) -> Frame {
+    match err {
+        mlua::Error::SyntaxError { message, .. } => {
+            Frame::Error(format!("ERR Error compiling script: {}", message))
+        }
+        mlua::Error::CallbackError { cause, .. } => error_reply(cause),
+        mlua::Error::ExternalError(err) => Frame::Error(err.to_string()),
+        err => {
+            let err = err.to_string();
+            let message = err.lines().next().unwrap_or_default();
+            Frame::Error(format!("ERR Error running script: {}", message))
+        }
+    }
+}
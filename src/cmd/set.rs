@@ -1,13 +1,16 @@
-use crate::{Connection, Db, Frame, Parse, ParseError};
+use crate::cmd::expire::instant_at;
+use crate::{Db, Frame, Parse, ParseError, SetCondition, SetExpiration};
 
 use bytes::Bytes;
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::Instant;
 use tracing::{debug, instrument};
 
 /// Set key to hold the string value.
 ///
 /// If key already holds a value, it is overwritten, regardless of its type. Any previous time to
-/// live associated with the key is discarded on successful SET operation.
+/// live associated with the key is discarded on successful SET operation, unless `KEEPTTL` is
+/// given.
 #[derive(Debug)]
 pub struct Set {
     key: String,
@@ -17,9 +20,21 @@ pub struct Set {
 
 #[derive(Debug)]
 struct Opts {
-    expire: Option<Duration>,
+    expire: Option<Expiry>,
     nx: bool,
     xx: bool,
+    get: bool,
+}
+
+/// Expiration requested by a `Set` command.
+#[derive(Debug, Clone, Copy)]
+enum Expiry {
+    /// Expire after the given duration (`EX`, `PX`).
+    In(Duration),
+    /// Expire at the given unix time (`EXAT`, `PXAT`), as the duration since the unix epoch.
+    At(Duration),
+    /// Retain the time to live of the existing value (`KEEPTTL`).
+    Keep,
 }
 
 impl Set {
@@ -28,13 +43,48 @@ impl Set {
             key: key.to_string(),
             value,
             options: Opts {
-                expire,
+                expire: expire.map(Expiry::In),
                 nx: false,
                 xx: false,
+                get: false,
             },
         }
     }
 
+    /// Only set the key if it does not already exist.
+    pub fn nx(mut self) -> Set {
+        self.options.nx = true;
+        self.options.xx = false;
+        self
+    }
+
+    /// Only set the key if it already exists.
+    pub fn xx(mut self) -> Set {
+        self.options.xx = true;
+        self.options.nx = false;
+        self
+    }
+
+    /// Return the value previously stored at the key instead of `OK`.
+    pub fn get(mut self) -> Set {
+        self.options.get = true;
+        self
+    }
+
+    /// Retain the time to live of the existing value.
+    pub fn keep_ttl(mut self) -> Set {
+        self.options.expire = Some(Expiry::Keep);
+        self
+    }
+
+    /// Expire the key at the given point in time.
+    pub fn expire_at(mut self, when: SystemTime) -> Set {
+        // A time before the unix epoch is certainly in the past.
+        let since_epoch = when.duration_since(UNIX_EPOCH).unwrap_or_default();
+        self.options.expire = Some(Expiry::At(since_epoch));
+        self
+    }
+
     pub fn key(&self) -> &str {
         &self.key
     }
@@ -43,10 +93,25 @@ impl Set {
         &self.value
     }
 
+    /// Returns the relative expiration given by `EX` or `PX`, if any.
     pub fn expire(&self) -> Option<Duration> {
-        self.options.expire
+        match self.options.expire {
+            Some(Expiry::In(duration)) => Some(duration),
+            _ => None,
+        }
     }
 
+    /// Parses a `Set` instance from a received frame.
+    ///
+    /// Options may be given in any order. At most one of `NX` and `XX` and at most one of the
+    /// expiration options may be present.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SET key value [NX | XX] [GET] [EX seconds | PX milliseconds |
+    ///     EXAT unix-time-seconds | PXAT unix-time-milliseconds | KEEPTTL]
+    /// ```
     pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Set> {
         // Read the key to set. This is required.
         let key = parse.next_string()?;
@@ -58,51 +123,49 @@ impl Set {
         let mut expire = None;
         let mut nx = false;
         let mut xx = false;
+        let mut get = false;
 
-        match parse.next_string() {
-            Ok(s) if s.to_uppercase() == "EX" => {
-                // Expire time is given in seconds. The next value is an integer.
-                let secs = parse.next_int()?;
-                expire = Some(Duration::from_secs(secs));
-            }
-            Ok(s) if s.to_uppercase() == "PX" => {
-                // Expire time is given in milliseconds. The next value is an integer.
-                let ms = parse.next_int()?;
-                expire = Some(Duration::from_millis(ms));
-            }
-            Ok(s) if s.to_uppercase() == "NX" => {
-                nx = true;
-            }
-            Ok(s) if s.to_uppercase() == "XX" => {
-                xx = true;
-            }
-            Ok(s) => return Err(format!("SET command error: unsupported option {}", s).into()),
-            Err(ParseError::EndOfStream) => {}
-            // All other errors result in the connection being terminated.
-            Err(err) => return Err(err.into()),
-        }
+        loop {
+            let option = match parse.next_string() {
+                Ok(s) => s.to_uppercase(),
+                Err(ParseError::EndOfStream) => break,
+                // All other errors are replied to the client.
+                Err(err) => return Err(err.into()),
+            };
 
-        match parse.next_string() {
-            Ok(s) if s.to_uppercase() == "EX" => {
+            let expiry = match &option[..] {
+                "NX" => {
+                    nx = true;
+                    continue;
+                }
+                "XX" => {
+                    xx = true;
+                    continue;
+                }
+                "GET" => {
+                    get = true;
+                    continue;
+                }
                 // Expire time is given in seconds. The next value is an integer.
-                let secs = parse.next_int()?;
-                expire = Some(Duration::from_secs(secs));
-            }
-            Ok(s) if s.to_uppercase() == "PX" => {
+                "EX" => Expiry::In(Duration::from_secs(parse.next_int()?)),
                 // Expire time is given in milliseconds. The next value is an integer.
-                let ms = parse.next_int()?;
-                expire = Some(Duration::from_millis(ms));
-            }
-            Ok(s) if s.to_uppercase() == "NX" => {
-                nx = true;
-            }
-            Ok(s) if s.to_uppercase() == "XX" => {
-                xx = true;
+                "PX" => Expiry::In(Duration::from_millis(parse.next_int()?)),
+                // Expire time is given as unix time in seconds.
+                "EXAT" => Expiry::At(Duration::from_secs(parse.next_int()?)),
+                // Expire time is given as unix time in milliseconds.
+                "PXAT" => Expiry::At(Duration::from_millis(parse.next_int()?)),
+                "KEEPTTL" => Expiry::Keep,
+                _ => return Err(format!("SET command error: unsupported option {}", option).into()),
+            };
+
+            // Only a single expiration option may be given.
+            if expire.replace(expiry).is_some() {
+                return Err(
+                    "SET command error: only one of `EX`, `PX`, `EXAT`, `PXAT` and `KEEPTTL` \
+                     can be given"
+                        .into(),
+                );
             }
-            Ok(s) => return Err(format!("SET command error: unsupported option {}", s).into()),
-            Err(ParseError::EndOfStream) => {}
-            // All other errors result in the connection being terminated.
-            Err(err) => return Err(err.into()),
         }
 
         // `NX` and `XX` can not be set at the same time.
@@ -113,29 +176,59 @@ impl Set {
         Ok(Set {
             key,
             value,
-            options: Opts { expire, nx, xx },
+            options: Opts {
+                expire,
+                nx,
+                xx,
+                get,
+            },
         })
     }
 
-    /// Apply the `Set` command to the specific `Db` instance and write the response to `dst`.
-    #[instrument(skip(self, db, dst))]
-    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
-        if self.options.nx && db.get(&self.key).is_some()
-            || self.options.xx && db.get(&self.key).is_none()
-        {
-            // Return `Null` if `nx` or `xx` condition was not met.
-            let response = Frame::Null;
-            debug!(?response);
-            dst.write_frame(&response).await?;
+    /// Apply the `Set` command to the specific `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let condition = if self.options.nx {
+            SetCondition::NotExists
+        } else if self.options.xx {
+            SetCondition::Exists
         } else {
-            db.set(self.key, self.value, self.options.expire);
+            SetCondition::Always
+        };
 
-            let response = Frame::Simple("OK".to_string());
-            debug!(?response);
-            dst.write_frame(&response).await?;
-        }
+        let expiration = match self.options.expire {
+            None => Some(SetExpiration::Persist),
+            Some(Expiry::In(duration)) => {
+                Instant::now().checked_add(duration).map(SetExpiration::At)
+            }
+            Some(Expiry::At(since_epoch)) => instant_at(since_epoch).map(SetExpiration::At),
+            Some(Expiry::Keep) => Some(SetExpiration::Keep),
+        };
 
-        Ok(())
+        // The expiration is too far in the future to be represented.
+        let expiration = match expiration {
+            Some(expiration) => expiration,
+            None => {
+                let response = Frame::Error("ERR invalid expire time in 'set' command".to_string());
+                debug!(?response);
+                return response;
+            }
+        };
+
+        let get = self.options.get;
+
+        let response = match db.set_if(self.key, self.value, expiration, condition, get) {
+            // `GET` replies with the previous value, whether or not the write took place.
+            Ok((_, prev)) if get => prev.map(Frame::Bulk).unwrap_or(Frame::Null),
+            Ok((true, _)) => Frame::Simple("OK".to_string()),
+            // Return `Null` if `nx` or `xx` condition was not met.
+            Ok((false, _)) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
     }
 
     pub(crate) fn into_frame(self) -> Frame {
@@ -143,9 +236,26 @@ impl Set {
         frame.push_bulk(Bytes::from("set".as_bytes()));
         frame.push_bulk(Bytes::from(self.key.into_bytes()));
         frame.push_bulk(self.value);
-        if let Some(ms) = self.options.expire {
-            frame.push_bulk(Bytes::from("px".as_bytes()));
-            frame.push_int(ms.as_millis() as u64);
+        match self.options.expire {
+            Some(Expiry::In(ms)) => {
+                frame.push_bulk(Bytes::from("px".as_bytes()));
+                frame.push_int(ms.as_millis() as i64);
+            }
+            Some(Expiry::At(since_epoch)) => {
+                frame.push_bulk(Bytes::from("pxat".as_bytes()));
+                frame.push_int(since_epoch.as_millis() as i64);
+            }
+            Some(Expiry::Keep) => frame.push_bulk(Bytes::from("keepttl".as_bytes())),
+            None => {}
+        }
+        if self.options.nx {
+            frame.push_bulk(Bytes::from("nx".as_bytes()));
+        }
+        if self.options.xx {
+            frame.push_bulk(Bytes::from("xx".as_bytes()));
+        }
+        if self.options.get {
+            frame.push_bulk(Bytes::from("get".as_bytes()));
         }
         frame
     }
@@ -0,0 +1,375 @@
+use crate::{Db, Frame, Parse, SetOp};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Add the specified members to the set stored at key.
+///
+/// Members that are already part of the set are ignored. If key does not exist, a new set is
+/// created. Returns the number of members that were added.
+#[derive(Debug)]
+pub struct SAdd {
+    key: String,
+    members: Vec<Bytes>,
+}
+
+/// Remove the specified members from the set stored at key.
+///
+/// Members that are not part of the set are ignored. The key is removed once the set is empty.
+/// Returns the number of members that were removed.
+#[derive(Debug)]
+pub struct SRem {
+    key: String,
+    members: Vec<Bytes>,
+}
+
+/// Returns all the members of the set stored at key.
+#[derive(Debug)]
+pub struct SMembers {
+    key: String,
+}
+
+/// Returns if member is a member of the set stored at key.
+#[derive(Debug)]
+pub struct SIsMember {
+    key: String,
+    member: Bytes,
+}
+
+/// Returns the number of members of the set stored at key.
+#[derive(Debug)]
+pub struct SCard {
+    key: String,
+}
+
+/// Returns the members of the set resulting from the intersection, union or difference of the
+/// sets stored at the given keys.
+///
+/// Received as SINTER, SUNION or SDIFF, or as SINTERSTORE, SUNIONSTORE or SDIFFSTORE in which
+/// case the result is stored at destination and its size is returned. All keys are read under
+/// a single lock acquisition, so the result reflects a consistent snapshot of the sets.
+#[derive(Debug)]
+pub struct SCombine {
+    op: SetOp,
+    keys: Vec<String>,
+    /// Key to store the result at, for the `*STORE` variants.
+    destination: Option<String>,
+}
+
+impl SAdd {
+    /// Create a new `SAdd` command which adds `members` to the set stored at `key`.
+    pub fn new(key: impl ToString, members: Vec<Bytes>) -> SAdd {
+        SAdd {
+            key: key.to_string(),
+            members,
+        }
+    }
+
+    /// Parses a `SAdd` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SADD key member [member ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<SAdd> {
+        let key = parse.next_string()?;
+
+        let mut members = vec![parse.next_bytes()?];
+        members.extend(parse.rest_bytes()?);
+
+        Ok(SAdd { key, members })
+    }
+
+    /// Apply the `SAdd` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.sadd(&self.key, self.members) {
+            Ok(added) => Frame::Integer(added as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("sadd".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        for member in self.members {
+            frame.push_bulk(member);
+        }
+        frame
+    }
+}
+
+impl SRem {
+    /// Create a new `SRem` command which removes `members` from the set stored at `key`.
+    pub fn new(key: impl ToString, members: Vec<Bytes>) -> SRem {
+        SRem {
+            key: key.to_string(),
+            members,
+        }
+    }
+
+    /// Parses a `SRem` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SREM key member [member ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<SRem> {
+        let key = parse.next_string()?;
+
+        let mut members = vec![parse.next_bytes()?];
+        members.extend(parse.rest_bytes()?);
+
+        Ok(SRem { key, members })
+    }
+
+    /// Apply the `SRem` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.srem(&self.key, &self.members) {
+            Ok(removed) => Frame::Integer(removed as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("srem".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        for member in self.members {
+            frame.push_bulk(member);
+        }
+        frame
+    }
+}
+
+impl SMembers {
+    /// Create a new `SMembers` command which reads the members of the set stored at `key`.
+    pub fn new(key: impl ToString) -> SMembers {
+        SMembers {
+            key: key.to_string(),
+        }
+    }
+
+    /// Parses a `SMembers` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SMEMBERS key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<SMembers> {
+        let key = parse.next_string()?;
+
+        Ok(SMembers { key })
+    }
+
+    /// Apply the `SMembers` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.smembers(&self.key) {
+            Ok(members) => Frame::Array(members.into_iter().map(Frame::Bulk).collect()),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("smembers".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
+
+impl SIsMember {
+    /// Create a new `SIsMember` command which checks whether `member` belongs to the set stored
+    /// at `key`.
+    pub fn new(key: impl ToString, member: Bytes) -> SIsMember {
+        SIsMember {
+            key: key.to_string(),
+            member,
+        }
+    }
+
+    /// Parses a `SIsMember` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SISMEMBER key member
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<SIsMember> {
+        let key = parse.next_string()?;
+        let member = parse.next_bytes()?;
+
+        Ok(SIsMember { key, member })
+    }
+
+    /// Apply the `SIsMember` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.sismember(&self.key, &self.member) {
+            Ok(is_member) => Frame::Integer(is_member as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("sismember".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(self.member);
+        frame
+    }
+}
+
+impl SCard {
+    /// Create a new `SCard` command which reads the number of members of the set stored at
+    /// `key`.
+    pub fn new(key: impl ToString) -> SCard {
+        SCard {
+            key: key.to_string(),
+        }
+    }
+
+    /// Parses a `SCard` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SCARD key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<SCard> {
+        let key = parse.next_string()?;
+
+        Ok(SCard { key })
+    }
+
+    /// Apply the `SCard` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.scard(&self.key) {
+            Ok(len) => Frame::Integer(len as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("scard".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
+
+impl SCombine {
+    /// Create a new `SCombine` command which combines the sets stored at `keys` according to
+    /// `op`. If `destination` is given, the result is stored there instead of being returned.
+    pub fn new(op: SetOp, keys: &[String], destination: Option<String>) -> SCombine {
+        SCombine {
+            op,
+            keys: keys.to_vec(),
+            destination,
+        }
+    }
+
+    /// Parses a `SCombine` instance from a received frame. `store` is set when the frame was one
+    /// of the `*STORE` variants.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SINTER key [key ...]
+    /// SUNION key [key ...]
+    /// SDIFF key [key ...]
+    /// SINTERSTORE destination key [key ...]
+    /// SUNIONSTORE destination key [key ...]
+    /// SDIFFSTORE destination key [key ...]
+    /// ```
+    pub(crate) fn parse_frames(
+        parse: &mut Parse,
+        op: SetOp,
+        store: bool,
+    ) -> crate::Result<SCombine> {
+        let destination = if store {
+            Some(parse.next_string()?)
+        } else {
+            None
+        };
+
+        let mut keys = vec![parse.next_string()?];
+        keys.extend(parse.rest_strings()?);
+
+        Ok(SCombine {
+            op,
+            keys,
+            destination,
+        })
+    }
+
+    /// Apply the `SCombine` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match self.destination {
+            Some(destination) => match db.combine_store(self.op, destination, &self.keys) {
+                Ok(len) => Frame::Integer(len as i64),
+                Err(err) => Frame::Error(err.to_string()),
+            },
+            None => match db.combine(self.op, &self.keys) {
+                Ok(members) => Frame::Array(members.into_iter().map(Frame::Bulk).collect()),
+                Err(err) => Frame::Error(err.to_string()),
+            },
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    /// Returns the command name.
+    pub(crate) fn get_name(&self) -> &str {
+        match (self.op, self.destination.is_some()) {
+            (SetOp::Inter, false) => "sinter",
+            (SetOp::Union, false) => "sunion",
+            (SetOp::Diff, false) => "sdiff",
+            (SetOp::Inter, true) => "sinterstore",
+            (SetOp::Union, true) => "sunionstore",
+            (SetOp::Diff, true) => "sdiffstore",
+        }
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from(self.get_name().as_bytes().to_vec()));
+        if let Some(destination) = self.destination {
+            frame.push_bulk(Bytes::from(destination.into_bytes()));
+        }
+        for key in self.keys {
+            frame.push_bulk(Bytes::from(key.into_bytes()));
+        }
+        frame
+    }
+}
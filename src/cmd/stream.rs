@@ -0,0 +1,654 @@
+use crate::db::{parse_int, DbError};
+use crate::{Connection, Db, Frame, Parse, ParseError, Shutdown, StreamEntry, StreamId, XAddId};
+
+use bytes::Bytes;
+use std::convert::TryFrom;
+use std::ops::Bound;
+use std::time::Duration;
+use tracing::{debug, instrument};
+
+/// Appends an entry made of the given field-value pairs to the stream stored at key.
+///
+/// If key does not exist, a new stream is created unless `NOMKSTREAM` is given. The ID of the
+/// entry is generated from the current time when given as `*`, and must otherwise be greater
+/// than the ID of any entry ever added to the stream. Returns the ID of the new entry.
+#[derive(Debug)]
+pub struct XAdd {
+    key: String,
+    id: XAddId,
+    fields: Vec<(Bytes, Bytes)>,
+    /// Number of entries to trim the stream to after adding the entry.
+    max_len: Option<u64>,
+    no_mk_stream: bool,
+}
+
+/// Returns the entries of the stream stored at key with an ID within the given range.
+///
+/// Received as XRANGE or XREVRANGE, in which case entries are returned from the greatest ID to
+/// the smallest and the end of the range is given first.
+#[derive(Debug)]
+pub struct XRange {
+    key: String,
+    start: Bound<StreamId>,
+    end: Bound<StreamId>,
+    count: Option<u64>,
+    /// Return the entries in decreasing ID order (`XREVRANGE`).
+    rev: bool,
+}
+
+/// Returns the number of entries of the stream stored at key.
+#[derive(Debug)]
+pub struct XLen {
+    key: String,
+}
+
+/// Trims the stream stored at key by removing its oldest entries until at most the given number
+/// of entries remain. Returns the number of removed entries.
+#[derive(Debug)]
+pub struct XTrim {
+    key: String,
+    max_len: u64,
+}
+
+/// Returns the entries with an ID greater than the given ones from one or more streams.
+///
+/// With the `BLOCK` option, the connection blocks until an entry is added to one of the streams
+/// or the timeout elapses, in the same way as the connection waits for messages in `SUBSCRIBE`.
+/// The special `$` ID reads only the entries added after the command was received.
+#[derive(Debug)]
+pub struct XRead {
+    /// Keys of the streams to read, along with the ID to read after. `None` stands for `$`.
+    streams: Vec<(String, Option<StreamId>)>,
+    count: Option<u64>,
+    /// Maximum time to block. `None` does not block, and zero blocks indefinitely.
+    block: Option<Duration>,
+}
+
+impl XAdd {
+    /// Create a new `XAdd` command which appends an entry made of `fields` to the stream stored
+    /// at `key`, then trims the stream to `max_len` entries if given.
+    pub fn new(
+        key: impl ToString,
+        id: XAddId,
+        fields: Vec<(Bytes, Bytes)>,
+        max_len: Option<u64>,
+    ) -> XAdd {
+        XAdd {
+            key: key.to_string(),
+            id,
+            fields,
+            max_len,
+            no_mk_stream: false,
+        }
+    }
+
+    /// Parses a `XAdd` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// XADD key [NOMKSTREAM] [MAXLEN [= | ~] threshold] <* | id> field value [field value ...]
+    /// ```
+    ///
+    /// The approximate trimming requested by `~` is performed exactly.
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<XAdd> {
+        let key = parse.next_string()?;
+
+        let mut max_len = None;
+        let mut no_mk_stream = false;
+
+        // Options come first, the first argument that is not an option is the ID.
+        let id = loop {
+            let arg = parse.next_string()?;
+
+            match &arg.to_uppercase()[..] {
+                "NOMKSTREAM" => no_mk_stream = true,
+                "MAXLEN" => max_len = Some(parse_max_len(parse)?),
+                _ => break parse_xadd_id(&arg)?,
+            }
+        };
+
+        let mut fields = vec![];
+        loop {
+            let field = match parse.next_bytes() {
+                Ok(field) => field,
+                Err(ParseError::EndOfStream) if !fields.is_empty() => break,
+                Err(err) => return Err(err.into()),
+            };
+
+            fields.push((field, parse.next_bytes()?));
+        }
+
+        Ok(XAdd {
+            key,
+            id,
+            fields,
+            max_len,
+            no_mk_stream,
+        })
+    }
+
+    /// Apply the `XAdd` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let max_len = self.max_len.map(|max_len| max_len as usize);
+
+        let response = match db.xadd(&self.key, self.id, self.fields, max_len, self.no_mk_stream) {
+            Ok(Some(id)) => Frame::Bulk(Bytes::from(id.to_string())),
+            Ok(None) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("xadd".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        if self.no_mk_stream {
+            frame.push_bulk(Bytes::from("nomkstream".as_bytes()));
+        }
+        if let Some(max_len) = self.max_len {
+            frame.push_bulk(Bytes::from("maxlen".as_bytes()));
+            frame.push_int(max_len as i64);
+        }
+        let id = match self.id {
+            XAddId::Auto => "*".to_string(),
+            XAddId::Ms(ms) => format!("{}-*", ms),
+            XAddId::Explicit(id) => id.to_string(),
+        };
+        frame.push_bulk(Bytes::from(id));
+        for (field, value) in self.fields {
+            frame.push_bulk(field);
+            frame.push_bulk(value);
+        }
+        frame
+    }
+}
+
+impl XRange {
+    /// Create a new `XRange` command which reads the entries of the stream stored at `key` with
+    /// an ID between `start` and `end`, returning at most `count` entries if given. Entries are
+    /// returned in decreasing ID order if `rev` is set.
+    pub fn new(
+        key: impl ToString,
+        start: Bound<StreamId>,
+        end: Bound<StreamId>,
+        count: Option<u64>,
+        rev: bool,
+    ) -> XRange {
+        XRange {
+            key: key.to_string(),
+            start,
+            end,
+            count,
+            rev,
+        }
+    }
+
+    /// Parses a `XRange` instance from a received frame. `rev` is set when the frame was
+    /// `XREVRANGE`.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// XRANGE key start end [COUNT count]
+    /// XREVRANGE key end start [COUNT count]
+    /// ```
+    ///
+    /// `-` and `+` stand for the smallest and the greatest possible IDs, and IDs prefixed with
+    /// `(` are excluded from the range. The sequence number of an ID may be omitted.
+    pub(crate) fn parse_frames(parse: &mut Parse, rev: bool) -> crate::Result<XRange> {
+        let key = parse.next_string()?;
+        let first = parse.next_string()?;
+        let second = parse.next_string()?;
+
+        let (start, end) = if rev {
+            (
+                parse_range_bound(&second, 0)?,
+                parse_range_bound(&first, u64::MAX)?,
+            )
+        } else {
+            (
+                parse_range_bound(&first, 0)?,
+                parse_range_bound(&second, u64::MAX)?,
+            )
+        };
+
+        let count = match parse.next_string() {
+            Ok(option) if option.to_uppercase() == "COUNT" => Some(parse.next_int()?),
+            Ok(option) => {
+                return Err(format!("XRANGE command error: unsupported option {}", option).into())
+            }
+            Err(ParseError::EndOfStream) => None,
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(XRange {
+            key,
+            start,
+            end,
+            count,
+            rev,
+        })
+    }
+
+    /// Apply the `XRange` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let count = self.count.map(|count| count as usize);
+
+        let response = match db.xrange(&self.key, self.start, self.end, count, self.rev) {
+            Ok(entries) => entries_frame(entries),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    /// Returns the command name.
+    pub(crate) fn get_name(&self) -> &str {
+        if self.rev {
+            "xrevrange"
+        } else {
+            "xrange"
+        }
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from(self.get_name().as_bytes().to_vec()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        let start = range_bound_to_bytes(self.start, "-");
+        let end = range_bound_to_bytes(self.end, "+");
+        if self.rev {
+            frame.push_bulk(end);
+            frame.push_bulk(start);
+        } else {
+            frame.push_bulk(start);
+            frame.push_bulk(end);
+        }
+        if let Some(count) = self.count {
+            frame.push_bulk(Bytes::from("count".as_bytes()));
+            frame.push_int(count as i64);
+        }
+        frame
+    }
+}
+
+impl XLen {
+    /// Create a new `XLen` command which reads the number of entries of the stream stored at
+    /// `key`.
+    pub fn new(key: impl ToString) -> XLen {
+        XLen {
+            key: key.to_string(),
+        }
+    }
+
+    /// Parses a `XLen` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// XLEN key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<XLen> {
+        let key = parse.next_string()?;
+
+        Ok(XLen { key })
+    }
+
+    /// Apply the `XLen` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.xlen(&self.key) {
+            Ok(len) => Frame::Integer(len as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("xlen".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
+
+impl XTrim {
+    /// Create a new `XTrim` command which trims the stream stored at `key` to `max_len` entries.
+    pub fn new(key: impl ToString, max_len: u64) -> XTrim {
+        XTrim {
+            key: key.to_string(),
+            max_len,
+        }
+    }
+
+    /// Parses a `XTrim` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// XTRIM key MAXLEN [= | ~] threshold
+    /// ```
+    ///
+    /// The approximate trimming requested by `~` is performed exactly.
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<XTrim> {
+        let key = parse.next_string()?;
+
+        let strategy = parse.next_string()?;
+        if strategy.to_uppercase() != "MAXLEN" {
+            return Err(format!("XTRIM command error: unsupported strategy {}", strategy).into());
+        }
+
+        let max_len = parse_max_len(parse)?;
+
+        Ok(XTrim { key, max_len })
+    }
+
+    /// Apply the `XTrim` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.xtrim(&self.key, self.max_len as usize) {
+            Ok(removed) => Frame::Integer(removed as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("xtrim".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from("maxlen".as_bytes()));
+        frame.push_int(self.max_len as i64);
+        frame
+    }
+}
+
+impl XRead {
+    /// Create a new `XRead` command which reads the entries with an ID greater than the given
+    /// one from each of `streams`, at most `count` per stream if given. A `None` ID reads only
+    /// the entries added after the command is received.
+    ///
+    /// If `block` is given and no entries are available, the command blocks for at most that
+    /// duration, or indefinitely if it is zero.
+    pub fn new(
+        streams: &[(String, Option<StreamId>)],
+        count: Option<u64>,
+        block: Option<Duration>,
+    ) -> XRead {
+        XRead {
+            streams: streams.to_vec(),
+            count,
+            block,
+        }
+    }
+
+    /// Parses a `XRead` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// XREAD [COUNT count] [BLOCK milliseconds] STREAMS key [key ...] id [id ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<XRead> {
+        let mut count = None;
+        let mut block = None;
+
+        loop {
+            let option = parse.next_string()?.to_uppercase();
+
+            match &option[..] {
+                "COUNT" => count = Some(parse.next_int()?),
+                "BLOCK" => block = Some(Duration::from_millis(parse.next_int()?)),
+                "STREAMS" => break,
+                _ => {
+                    return Err(
+                        format!("XREAD command error: unsupported option {}", option).into(),
+                    )
+                }
+            }
+        }
+
+        // The keys are followed by as many IDs.
+        let mut keys = vec![parse.next_string()?];
+        keys.extend(parse.rest_strings()?);
+
+        if keys.len() % 2 != 0 {
+            return Err("XREAD command error: each stream key must be followed by an ID".into());
+        }
+
+        let ids = keys.split_off(keys.len() / 2);
+        let streams = keys
+            .into_iter()
+            .zip(ids)
+            .map(|(key, id)| match &id[..] {
+                "$" => Ok((key, None)),
+                id => Ok((key, Some(parse_stream_id(id, 0)?))),
+            })
+            .collect::<crate::Result<_>>()?;
+
+        Ok(XRead {
+            streams,
+            count,
+            block,
+        })
+    }
+
+    /// Apply the `XRead` command to the specified `Db` instance and write the response to `dst`.
+    ///
+    /// The connection is closed without a response if the server shuts down while blocked.
+    #[instrument(skip(self, db, dst, shutdown))]
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        shutdown: &mut Shutdown,
+    ) -> crate::Result<()> {
+        let block = match self.block {
+            Some(block) => block,
+            // Without `BLOCK`, the streams are read once as within a transaction.
+            None => {
+                let response = self.execute(db);
+                dst.write_frame(&response).await?;
+                return Ok(());
+            }
+        };
+
+        let count = self.count.map(|count| count as usize);
+        // A timeout of zero blocks forever.
+        let timeout = Some(block).filter(|block| !block.is_zero());
+
+        let read = db
+            .blocking_xread(&self.streams, count, timeout, shutdown.recv())
+            .await;
+
+        let response = match read {
+            Ok(read) if read.is_empty() && shutdown.is_shutdown() => return Ok(()),
+            Ok(read) if read.is_empty() => Frame::Null,
+            Ok(read) => read_frame(read),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Apply the `XRead` command to the specified `Db` instance without blocking and return the
+    /// response, as done within a transaction.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let count = self.count.map(|count| count as usize);
+
+        let response = match self
+            .resolve_ids(db)
+            .and_then(|streams| db.xread(&streams, count))
+        {
+            Ok(read) if read.is_empty() => Frame::Null,
+            Ok(read) => read_frame(read),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    /// Replaces the `$` IDs by the last ID of their stream.
+    fn resolve_ids(&self, db: &Db) -> Result<Vec<(String, StreamId)>, DbError> {
+        let keys: Vec<String> = self.streams.iter().map(|(key, _)| key.clone()).collect();
+        let last_ids = db.xlast_ids(&keys)?;
+
+        Ok(self
+            .streams
+            .iter()
+            .zip(last_ids)
+            .map(|((key, id), last_id)| (key.clone(), id.unwrap_or(last_id)))
+            .collect())
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("xread".as_bytes()));
+        if let Some(count) = self.count {
+            frame.push_bulk(Bytes::from("count".as_bytes()));
+            frame.push_int(count as i64);
+        }
+        if let Some(block) = self.block {
+            frame.push_bulk(Bytes::from("block".as_bytes()));
+            frame.push_int(block.as_millis() as i64);
+        }
+        frame.push_bulk(Bytes::from("streams".as_bytes()));
+        for (key, _) in &self.streams {
+            frame.push_bulk(Bytes::from(key.clone().into_bytes()));
+        }
+        for (_, id) in self.streams {
+            let id = id.map_or_else(|| "$".to_string(), |id| id.to_string());
+            frame.push_bulk(Bytes::from(id));
+        }
+        frame
+    }
+}
+
+/// Encodes stream entries as an array of `[id, [field, value, ...]]` arrays.
+pub(crate) fn entries_frame(entries: Vec<StreamEntry>) -> Frame {
+    let entries = entries
+        .into_iter()
+        .map(|(id, fields)| entry_frame(id, Some(fields)))
+        .collect();
+
+    Frame::Array(entries)
+}
+
+/// Encodes a stream entry as an `[id, [field, value, ...]]` array, or as an `[id, nil]` array if
+/// its fields are missing.
+pub(crate) fn entry_frame(id: StreamId, fields: Option<Vec<(Bytes, Bytes)>>) -> Frame {
+    let values = match fields {
+        Some(fields) => {
+            let mut values = Frame::array();
+            for (field, value) in fields {
+                values.push_bulk(field);
+                values.push_bulk(value);
+            }
+            values
+        }
+        None => Frame::Null,
+    };
+
+    Frame::Array(vec![Frame::Bulk(Bytes::from(id.to_string())), values])
+}
+
+/// Encodes the entries read from several streams as an array of `[key, entries]` arrays.
+pub(crate) fn read_frame(read: Vec<(String, Vec<StreamEntry>)>) -> Frame {
+    let streams = read
+        .into_iter()
+        .map(|(key, entries)| {
+            Frame::Array(vec![Frame::Bulk(Bytes::from(key)), entries_frame(entries)])
+        })
+        .collect();
+
+    Frame::Array(streams)
+}
+
+/// Parses a stream ID given as `ms-seq` or `ms`, in which case the sequence number is `seq`.
+pub(crate) fn parse_stream_id(id: &str, seq: u64) -> crate::Result<StreamId> {
+    const MSG: &str = "Invalid stream ID specified as stream command argument";
+
+    let (ms, seq) = match id.split_once('-') {
+        Some((ms, seq)) => (ms, seq.parse().map_err(|_| MSG)?),
+        None => (id, seq),
+    };
+    let ms = ms.parse().map_err(|_| MSG)?;
+
+    Ok(StreamId { ms, seq })
+}
+
+/// Parses the ID argument of `XADD`, which is either `*`, `ms-*` or an explicit ID.
+fn parse_xadd_id(id: &str) -> crate::Result<XAddId> {
+    if id == "*" {
+        return Ok(XAddId::Auto);
+    }
+
+    match id.strip_suffix("-*") {
+        Some(ms) => Ok(XAddId::Ms(ms.parse().map_err(|_| {
+            "Invalid stream ID specified as stream command argument"
+        })?)),
+        None => Ok(XAddId::Explicit(parse_stream_id(id, 0)?)),
+    }
+}
+
+/// Parses a bound of a range of IDs. `-` and `+` stand for the smallest and the greatest
+/// possible IDs and an ID prefixed with `(` is excluded from the range. `seq` is the sequence
+/// number used when the ID omits it.
+pub(crate) fn parse_range_bound(bound: &str, seq: u64) -> crate::Result<Bound<StreamId>> {
+    match bound {
+        "-" => Ok(Bound::Included(StreamId::default())),
+        "+" => Ok(Bound::Included(StreamId {
+            ms: u64::MAX,
+            seq: u64::MAX,
+        })),
+        _ => match bound.strip_prefix('(') {
+            Some(id) => Ok(Bound::Excluded(parse_stream_id(id, seq)?)),
+            None => Ok(Bound::Included(parse_stream_id(bound, seq)?)),
+        },
+    }
+}
+
+/// Encodes a bound of a range of IDs, using `unbounded` for `Bound::Unbounded`.
+pub(crate) fn range_bound_to_bytes(bound: Bound<StreamId>, unbounded: &'static str) -> Bytes {
+    match bound {
+        Bound::Included(id) => Bytes::from(id.to_string()),
+        Bound::Excluded(id) => Bytes::from(format!("({}", id)),
+        Bound::Unbounded => Bytes::from(unbounded.as_bytes()),
+    }
+}
+
+/// Parses the `[= | ~] threshold` arguments following `MAXLEN`.
+fn parse_max_len(parse: &mut Parse) -> crate::Result<u64> {
+    let arg = parse.next_string()?;
+
+    let threshold = match &arg[..] {
+        "=" | "~" => parse.next_string()?,
+        _ => arg,
+    };
+
+    parse_int(threshold.as_bytes())
+        .and_then(|threshold| u64::try_from(threshold).ok())
+        .ok_or_else(|| "value is not an integer or out of range".into())
+}
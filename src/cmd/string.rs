@@ -0,0 +1,534 @@
+use crate::cmd::expire::instant_at;
+use crate::{Db, Frame, Parse, ParseError, SetExpiration};
+
+use bytes::Bytes;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{debug, instrument};
+
+/// Appends the value at the end of the string stored at key.
+///
+/// If key does not exist it is created and set as an empty string first, so APPEND behaves
+/// like SET in this case.
+#[derive(Debug)]
+pub struct Append {
+    key: String,
+    value: Bytes,
+}
+
+/// Returns the length of the string value stored at key, or `0` if the key does not exist.
+#[derive(Debug)]
+pub struct Strlen {
+    key: String,
+}
+
+/// Returns the substring of the string value stored at key, determined by the offsets start
+/// and end (both are inclusive).
+///
+/// Negative offsets provide an offset starting from the end of the string.
+#[derive(Debug)]
+pub struct GetRange {
+    key: String,
+    start: i64,
+    end: i64,
+}
+
+/// Overwrites part of the string stored at key, starting at the specified offset, for the entire
+/// length of value.
+///
+/// If the offset is larger than the current length of the string, the string is padded with
+/// zero-bytes.
+#[derive(Debug)]
+pub struct SetRange {
+    key: String,
+    offset: u64,
+    value: Bytes,
+}
+
+/// Get the value of key and delete the key.
+#[derive(Debug)]
+pub struct GetDel {
+    key: String,
+}
+
+/// Get the value of key and optionally set its expiration.
+#[derive(Debug)]
+pub struct GetEx {
+    key: String,
+    expire: Option<GetExpiry>,
+}
+
+/// Expiration requested by a `GetEx` command.
+#[derive(Debug, Clone, Copy)]
+enum GetExpiry {
+    /// Expire after the given duration (`EX`, `PX`).
+    In(Duration),
+    /// Expire at the given unix time (`EXAT`, `PXAT`), as the duration since the unix epoch.
+    At(Duration),
+    /// Remove the time to live (`PERSIST`).
+    Persist,
+}
+
+/// Returns the values of all specified keys.
+///
+/// The special value nil is returned for every key that does not hold a value.
+#[derive(Debug)]
+pub struct MGet {
+    keys: Vec<String>,
+}
+
+/// Sets the given keys to their respective values, replacing existing values.
+///
+/// When received as MSETNX, no key is set at all if any of the keys already exists. All keys
+/// are set atomically, so clients never see that some keys were updated while others are
+/// unchanged.
+#[derive(Debug)]
+pub struct MSet {
+    pairs: Vec<(String, Bytes)>,
+    nx: bool,
+}
+
+impl Append {
+    /// Create a new `Append` command which appends `value` to `key`.
+    pub fn new(key: impl ToString, value: Bytes) -> Append {
+        Append {
+            key: key.to_string(),
+            value,
+        }
+    }
+
+    /// Parses an `Append` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// APPEND key value
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Append> {
+        let key = parse.next_string()?;
+        let value = parse.next_bytes()?;
+
+        Ok(Append { key, value })
+    }
+
+    /// Apply the `Append` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.append(&self.key, &self.value) {
+            Ok(len) => Frame::Integer(len as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("append".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(self.value);
+        frame
+    }
+}
+
+impl Strlen {
+    /// Create a new `Strlen` command for `key`.
+    pub fn new(key: impl ToString) -> Strlen {
+        Strlen {
+            key: key.to_string(),
+        }
+    }
+
+    /// Parses a `Strlen` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// STRLEN key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Strlen> {
+        let key = parse.next_string()?;
+
+        Ok(Strlen { key })
+    }
+
+    /// Apply the `Strlen` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.strlen(&self.key) {
+            Ok(len) => Frame::Integer(len as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("strlen".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
+
+impl GetRange {
+    /// Create a new `GetRange` command which reads `key` from `start` to `end` (inclusive).
+    pub fn new(key: impl ToString, start: i64, end: i64) -> GetRange {
+        GetRange {
+            key: key.to_string(),
+            start,
+            end,
+        }
+    }
+
+    /// Parses a `GetRange` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// GETRANGE key start end
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<GetRange> {
+        let key = parse.next_string()?;
+        let start = parse.next_signed_int()?;
+        let end = parse.next_signed_int()?;
+
+        Ok(GetRange { key, start, end })
+    }
+
+    /// Apply the `GetRange` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.get_range(&self.key, self.start, self.end) {
+            Ok(value) => Frame::Bulk(value),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("getrange".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_int(self.start);
+        frame.push_int(self.end);
+        frame
+    }
+}
+
+impl SetRange {
+    /// Create a new `SetRange` command which writes `value` into `key` at `offset`.
+    pub fn new(key: impl ToString, offset: u64, value: Bytes) -> SetRange {
+        SetRange {
+            key: key.to_string(),
+            offset,
+            value,
+        }
+    }
+
+    /// Parses a `SetRange` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SETRANGE key offset value
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<SetRange> {
+        let key = parse.next_string()?;
+        let offset = parse.next_int()?;
+        let value = parse.next_bytes()?;
+
+        Ok(SetRange { key, offset, value })
+    }
+
+    /// Apply the `SetRange` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.set_range(&self.key, self.offset as usize, &self.value) {
+            Ok(len) => Frame::Integer(len as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("setrange".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_int(self.offset as i64);
+        frame.push_bulk(self.value);
+        frame
+    }
+}
+
+impl GetDel {
+    /// Create a new `GetDel` command for `key`.
+    pub fn new(key: impl ToString) -> GetDel {
+        GetDel {
+            key: key.to_string(),
+        }
+    }
+
+    /// Parses a `GetDel` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// GETDEL key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<GetDel> {
+        let key = parse.next_string()?;
+
+        Ok(GetDel { key })
+    }
+
+    /// Apply the `GetDel` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.get_del(&self.key) {
+            Ok(Some(value)) => Frame::Bulk(value),
+            Ok(None) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("getdel".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
+
+impl GetEx {
+    /// Create a new `GetEx` command which reads `key` and expires it after `timeout`.
+    pub fn new(key: impl ToString, timeout: Duration) -> GetEx {
+        GetEx {
+            key: key.to_string(),
+            expire: Some(GetExpiry::In(timeout)),
+        }
+    }
+
+    /// Create a new `GetEx` command which reads `key` and removes its time to live.
+    pub fn persist(key: impl ToString) -> GetEx {
+        GetEx {
+            key: key.to_string(),
+            expire: Some(GetExpiry::Persist),
+        }
+    }
+
+    /// Parses a `GetEx` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// GETEX key [EX seconds | PX milliseconds | EXAT unix-time-seconds |
+    ///     PXAT unix-time-milliseconds | PERSIST]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<GetEx> {
+        let key = parse.next_string()?;
+
+        let expire = match parse.next_string() {
+            Ok(s) => match &s.to_uppercase()[..] {
+                "EX" => Some(GetExpiry::In(Duration::from_secs(parse.next_int()?))),
+                "PX" => Some(GetExpiry::In(Duration::from_millis(parse.next_int()?))),
+                "EXAT" => Some(GetExpiry::At(Duration::from_secs(parse.next_int()?))),
+                "PXAT" => Some(GetExpiry::At(Duration::from_millis(parse.next_int()?))),
+                "PERSIST" => Some(GetExpiry::Persist),
+                _ => return Err(format!("GETEX command error: unsupported option {}", s).into()),
+            },
+            Err(ParseError::EndOfStream) => None,
+            // All other errors are replied to the client.
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(GetEx { key, expire })
+    }
+
+    /// Apply the `GetEx` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let expiration = match self.expire {
+            None => Some(SetExpiration::Keep),
+            Some(GetExpiry::In(duration)) => {
+                Instant::now().checked_add(duration).map(SetExpiration::At)
+            }
+            Some(GetExpiry::At(since_epoch)) => instant_at(since_epoch).map(SetExpiration::At),
+            Some(GetExpiry::Persist) => Some(SetExpiration::Persist),
+        };
+
+        // The expiration is too far in the future to be represented.
+        let expiration = match expiration {
+            Some(expiration) => expiration,
+            None => {
+                let response =
+                    Frame::Error("ERR invalid expire time in 'getex' command".to_string());
+                debug!(?response);
+                return response;
+            }
+        };
+
+        let response = match db.get_ex(&self.key, expiration) {
+            Ok(Some(value)) => Frame::Bulk(value),
+            Ok(None) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("getex".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        match self.expire {
+            Some(GetExpiry::In(ms)) => {
+                frame.push_bulk(Bytes::from("px".as_bytes()));
+                frame.push_int(ms.as_millis() as i64);
+            }
+            Some(GetExpiry::At(since_epoch)) => {
+                frame.push_bulk(Bytes::from("pxat".as_bytes()));
+                frame.push_int(since_epoch.as_millis() as i64);
+            }
+            Some(GetExpiry::Persist) => frame.push_bulk(Bytes::from("persist".as_bytes())),
+            None => {}
+        }
+        frame
+    }
+}
+
+impl MGet {
+    /// Create a new `MGet` command which reads `keys`.
+    pub fn new(keys: &[String]) -> MGet {
+        MGet {
+            keys: keys.to_vec(),
+        }
+    }
+
+    /// Parses a `MGet` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// MGET key [key ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<MGet> {
+        let mut keys = vec![parse.next_string()?];
+        keys.extend(parse.rest_strings()?);
+
+        Ok(MGet { keys })
+    }
+
+    /// Apply the `MGet` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let values = db
+            .mget(&self.keys)
+            .into_iter()
+            .map(|value| value.map(Frame::Bulk).unwrap_or(Frame::Null))
+            .collect();
+
+        let response = Frame::Array(values);
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("mget".as_bytes()));
+        for key in self.keys {
+            frame.push_bulk(Bytes::from(key.into_bytes()));
+        }
+        frame
+    }
+}
+
+impl MSet {
+    /// Create a new `MSet` command which writes `pairs`. If `nx` is set, nothing is written if
+    /// any of the keys already exists.
+    pub fn new(pairs: &[(String, Bytes)], nx: bool) -> MSet {
+        MSet {
+            pairs: pairs.to_vec(),
+            nx,
+        }
+    }
+
+    /// Parses a `MSet` instance from a received frame. `nx` is set when the frame was a MSETNX
+    /// command.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// MSET key value [key value ...]
+    /// MSETNX key value [key value ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse, nx: bool) -> crate::Result<MSet> {
+        let mut pairs = vec![(parse.next_string()?, parse.next_bytes()?)];
+
+        loop {
+            match parse.next_string() {
+                Ok(key) => pairs.push((key, parse.next_bytes()?)),
+                // `EndOfStream` indicates there is no further data to parse.
+                Err(ParseError::EndOfStream) => break,
+                // Bubble up all other errors, which are replied to the client.
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(MSet { pairs, nx })
+    }
+
+    /// Apply the `MSet` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let nx = self.nx;
+        let written = db.mset(self.pairs, nx);
+
+        let response = if nx {
+            Frame::Integer(written as i64)
+        } else {
+            Frame::Simple("OK".to_string())
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    /// Returns the command name.
+    pub(crate) fn get_name(&self) -> &str {
+        if self.nx {
+            "msetnx"
+        } else {
+            "mset"
+        }
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from(self.get_name().as_bytes().to_vec()));
+        for (key, value) in self.pairs {
+            frame.push_bulk(Bytes::from(key.into_bytes()));
+            frame.push_bulk(value);
+        }
+        frame
+    }
+}
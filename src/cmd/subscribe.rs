@@ -1,5 +1,6 @@
 use crate::cmd::{Parse, ParseError, Unknown};
-use crate::{Command, Connection, Db, Frame, Shutdown};
+use crate::db::Subscription;
+use crate::{Command, Connection, Db, Frame, LagPolicy, Shutdown};
 
 use async_stream::stream;
 use bytes::Bytes;
@@ -27,8 +28,40 @@ pub struct Unsubscribe {
     channels: Vec<String>,
 }
 
+/// Subscribes the client to the channels matching the specified glob-style patterns.
+///
+/// Messages published on a matching channel are received as `pmessage` frames, carrying the
+/// pattern along with the channel. As for SUBSCRIBE, the client enters the subscribed state.
+#[derive(Debug)]
+pub struct PSubscribe {
+    patterns: Vec<String>,
+}
+
+/// Unsubscribes the client from the given patterns, or from all of them if none is given.
+#[derive(Debug)]
+pub struct PUnsubscribe {
+    patterns: Vec<String>,
+}
+
+/// Event received through a subscription.
+enum Received<T> {
+    /// A published message.
+    Message(T),
+    /// The subscriber lagged behind and missed that many messages.
+    Lagged(u64),
+}
+
 /// Stream of messages.
-type Messages = Pin<Box<dyn Stream<Item = Bytes> + Send>>;
+type Messages = Pin<Box<dyn Stream<Item = Received<Bytes>> + Send>>;
+
+/// Stream of messages received through a pattern subscription, along with their channel.
+type PatternMessages = Pin<Box<dyn Stream<Item = Received<(String, Bytes)>> + Send>>;
+
+/// Subscriptions of a client in the subscribed state.
+struct Subscriptions {
+    channels: StreamMap<String, Messages>,
+    patterns: StreamMap<String, PatternMessages>,
+}
 
 impl Subscribe {
     /// Creates a `Subscribe` instance from a received frame.
@@ -62,7 +95,7 @@ impl Subscribe {
                 Ok(s) => channels.push(s),
                 // `EndOfStream` indicates there is no further data to parse.
                 Err(ParseError::EndOfStream) => break,
-                // Bubble up all other errors, resulting in the connection being terminated.
+                // Bubble up all other errors, which are replied to the client.
                 Err(err) => return Err(err.into()),
             }
         }
@@ -71,48 +104,12 @@ impl Subscribe {
     }
 
     pub(crate) async fn apply(
-        mut self,
+        self,
         db: &Db,
         dst: &mut Connection,
         shutdown: &mut Shutdown,
     ) -> crate::Result<()> {
-        // A client may subscribe to mutiple channels and may dynamically add and remove channels
-        // from its subscription list. To handle this, we use a `StreamMap` to track active
-        // subscription.
-        let mut subscriptions = StreamMap::new();
-
-        loop {
-            for channel_name in self.channels.drain(..) {
-                subscribe_to_channel(channel_name, &mut subscriptions, db, dst).await?;
-            }
-
-            tokio::select! {
-                // Received messages from one of the subscribed channels.
-                Some((channel_name, msg)) = subscriptions.next() => {
-                    dst.write_frame(&make_message_frame(channel_name, msg)).await?;
-                }
-                // Received a shutdown signal.
-                _ = shutdown.recv() => {
-                    return Ok(())
-                }
-                // Received a subscribe or a unsubscribe command from the client.
-                res = dst.read_frame() => {
-                    let frame = match res? {
-                        Some(frame) => frame,
-                        // happen if the remote client has disconnected.
-                        None => return Ok(()),
-                    };
-
-                    handle_command(
-                        frame,
-                        &mut self.channels,
-                        &mut subscriptions,
-                        dst,
-                    ).await?;
-
-                }
-            }
-        }
+        run_subscribed(self.channels, vec![], db, dst, shutdown).await
     }
 
     pub(crate) fn into_frame(self) -> Frame {
@@ -144,7 +141,7 @@ impl Unsubscribe {
                 Ok(s) => channels.push(s),
                 // No further data to parse.
                 Err(ParseError::EndOfStream) => break,
-                // Bubble up all other errors, resulting in the connection being terminated.
+                // Bubble up all other errors, which are replied to the client.
                 Err(err) => return Err(err),
             }
         }
@@ -164,47 +161,256 @@ impl Unsubscribe {
     }
 }
 
+impl PSubscribe {
+    pub(crate) fn new(patterns: &[String]) -> PSubscribe {
+        PSubscribe {
+            patterns: patterns.to_vec(),
+        }
+    }
+
+    /// Parses a `PSubscribe` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// PSUBSCRIBE pattern [pattern ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<PSubscribe> {
+        let mut patterns = vec![parse.next_string()?];
+        patterns.extend(parse.rest_strings()?);
+
+        Ok(PSubscribe { patterns })
+    }
+
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        shutdown: &mut Shutdown,
+    ) -> crate::Result<()> {
+        run_subscribed(vec![], self.patterns, db, dst, shutdown).await
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("psubscribe".as_bytes()));
+        for pattern in self.patterns {
+            frame.push_bulk(Bytes::from(pattern.into_bytes()));
+        }
+        frame
+    }
+}
+
+impl PUnsubscribe {
+    pub(crate) fn new(patterns: &[String]) -> PUnsubscribe {
+        PUnsubscribe {
+            patterns: patterns.to_vec(),
+        }
+    }
+
+    /// Parses a `PUnsubscribe` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// PUNSUBSCRIBE [pattern [pattern ...]]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<PUnsubscribe> {
+        Ok(PUnsubscribe {
+            patterns: parse.rest_strings()?,
+        })
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("punsubscribe".as_bytes()));
+        for pattern in self.patterns {
+            frame.push_bulk(Bytes::from(pattern.into_bytes()));
+        }
+        frame
+    }
+}
+
+/// Runs the subscribed state of a connection, entered with the subscriptions to `channels` and
+/// `patterns`.
+async fn run_subscribed(
+    mut channels: Vec<String>,
+    mut patterns: Vec<String>,
+    db: &Db,
+    dst: &mut Connection,
+    shutdown: &mut Shutdown,
+) -> crate::Result<()> {
+    // A client may subscribe to mutiple channels and may dynamically add and remove channels
+    // from its subscription list. To handle this, we use a `StreamMap` to track active
+    // subscription.
+    let mut subscriptions = Subscriptions {
+        channels: StreamMap::new(),
+        patterns: StreamMap::new(),
+    };
+
+    loop {
+        for channel_name in channels.drain(..) {
+            subscribe_to_channel(channel_name, &mut subscriptions, db, dst).await?;
+        }
+
+        for pattern in patterns.drain(..) {
+            subscribe_to_pattern(pattern, &mut subscriptions, db, dst).await?;
+        }
+
+        tokio::select! {
+            // Received messages from one of the subscribed channels.
+            Some((channel_name, received)) = subscriptions.channels.next() => match received {
+                Received::Message(msg) => {
+                    dst.write_frame(&make_message_frame(channel_name, msg)).await?;
+                }
+                Received::Lagged(skipped) => {
+                    handle_lag(db.lag_policy(), channel_name, skipped, dst).await?;
+                }
+            },
+            // Received messages from a channel matching one of the subscribed patterns.
+            Some((pattern, received)) = subscriptions.patterns.next() => match received {
+                Received::Message((channel_name, msg)) => {
+                    dst.write_frame(&make_pmessage_frame(pattern, channel_name, msg)).await?;
+                }
+                Received::Lagged(skipped) => {
+                    handle_lag(db.lag_policy(), pattern, skipped, dst).await?;
+                }
+            },
+            // Received a shutdown signal.
+            _ = shutdown.recv() => {
+                return Ok(())
+            }
+            // Received a subscribe or a unsubscribe command from the client.
+            res = dst.read_frame() => {
+                let frame = match res? {
+                    Some(frame) => frame,
+                    // happen if the remote client has disconnected.
+                    None => return Ok(()),
+                };
+
+                handle_command(
+                    frame,
+                    &mut channels,
+                    &mut patterns,
+                    &mut subscriptions,
+                    dst,
+                ).await?;
+            }
+        }
+    }
+}
+
 async fn subscribe_to_channel(
     channel_name: String,
-    subscriptions: &mut StreamMap<String, Messages>,
+    subscriptions: &mut Subscriptions,
     db: &Db,
     dst: &mut Connection,
 ) -> crate::Result<()> {
-    let mut rx = db.subscribe(channel_name.clone());
+    let rx = {
+        let _section = db.enter_section().await;
+        into_received(db.subscribe(channel_name.clone()), db.lag_policy())
+    };
 
-    let rx = Box::pin(stream! {
+    // Track subscription in client's subscription set.
+    subscriptions.channels.insert(channel_name.clone(), rx);
+
+    let response = make_subscription_frame("subscribe", channel_name, subscriptions.len());
+    dst.write_frame(&response).await?;
+
+    Ok(())
+}
+
+async fn subscribe_to_pattern(
+    pattern: String,
+    subscriptions: &mut Subscriptions,
+    db: &Db,
+    dst: &mut Connection,
+) -> crate::Result<()> {
+    let rx = {
+        let _section = db.enter_section().await;
+        into_received(db.psubscribe(pattern.clone()), db.lag_policy())
+    };
+
+    subscriptions.patterns.insert(pattern.clone(), rx);
+
+    let response = make_subscription_frame("psubscribe", pattern, subscriptions.len());
+    dst.write_frame(&response).await?;
+
+    Ok(())
+}
+
+/// Turns `rx` into a stream of received events. Lagging behind is only reported when
+/// `lag_policy` requires acting on it, otherwise the stream resumes with the oldest message still
+/// buffered.
+fn into_received<T>(
+    mut rx: Subscription<T>,
+    lag_policy: LagPolicy,
+) -> Pin<Box<dyn Stream<Item = Received<T>> + Send>>
+where
+    T: Clone + Send + 'static,
+{
+    Box::pin(stream! {
         loop {
             match rx.recv().await {
-                Ok(msg) => yield msg,
-                // If we lagged in consuming messages, just resume.
-                Err(broadcast::error::RecvError::Lagged(_)) => {},
+                Ok(msg) => yield Received::Message(msg),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    if lag_policy != LagPolicy::Drop {
+                        yield Received::Lagged(skipped);
+                    }
+                }
                 Err(_) => break,
             }
         }
-    });
-
-    // Track subscription in client's subscription set.
-    subscriptions.insert(channel_name.clone(), rx);
+    })
+}
 
-    let response = make_subscribe_frame(channel_name.clone(), subscriptions.len());
-    dst.write_frame(&response).await?;
+/// Acts on the subscription to `name` having missed `skipped` messages, according to
+/// `lag_policy`.
+///
+/// An error is returned to disconnect the client, as returning from the subscribed state would
+/// only let it issue regular commands again.
+async fn handle_lag(
+    lag_policy: LagPolicy,
+    name: String,
+    skipped: u64,
+    dst: &mut Connection,
+) -> crate::Result<()> {
+    match lag_policy {
+        LagPolicy::Disconnect => {
+            Err(format!("subscriber missed {} messages on `{}`", skipped, name).into())
+        }
+        LagPolicy::Notify => {
+            dst.write_frame(&make_lagged_frame(name, skipped)).await?;
+            Ok(())
+        }
+        LagPolicy::Drop => Ok(()),
+    }
+}
 
-    Ok(())
+impl Subscriptions {
+    /// Returns the number of channels and patterns subscribed to.
+    fn len(&self) -> usize {
+        self.channels.len() + self.patterns.len()
+    }
 }
 
-fn make_subscribe_frame(channel_name: String, num_subs: usize) -> Frame {
+/// Builds the reply to a subscription or unsubscription, `kind` being the name of the command:
+/// `subscribe`, `psubscribe`, `unsubscribe` or `punsubscribe`.
+fn make_subscription_frame(kind: &'static str, name: String, num_subs: usize) -> Frame {
     let mut response = Frame::array();
-    response.push_bulk(Bytes::from_static(b"subscribe"));
-    response.push_bulk(Bytes::from(channel_name));
-    response.push_int(num_subs as u64);
+    response.push_bulk(Bytes::from_static(kind.as_bytes()));
+    response.push_bulk(Bytes::from(name));
+    response.push_int(num_subs as i64);
     response
 }
 
-fn make_unsubscribe_frame(channel_name: String, num_subs: usize) -> Frame {
+/// Builds the notification sent to a subscriber which missed `skipped` messages published on
+/// the channel or pattern `name`.
+fn make_lagged_frame(name: String, skipped: u64) -> Frame {
     let mut response = Frame::array();
-    response.push_bulk(Bytes::from_static(b"unsubscribe"));
-    response.push_bulk(Bytes::from(channel_name));
-    response.push_int(num_subs as u64);
+    response.push_bulk(Bytes::from_static(b"lagged"));
+    response.push_bulk(Bytes::from(name));
+    response.push_int(skipped as i64);
     response
 }
 
@@ -216,30 +422,68 @@ fn make_message_frame(channel_name: String, msg: Bytes) -> Frame {
     response
 }
 
+fn make_pmessage_frame(pattern: String, channel_name: String, msg: Bytes) -> Frame {
+    let mut response = Frame::array();
+    response.push_bulk(Bytes::from_static(b"pmessage"));
+    response.push_bulk(Bytes::from(pattern));
+    response.push_bulk(Bytes::from(channel_name));
+    response.push_bulk(msg);
+    response
+}
+
 async fn handle_command(
     frame: Frame,
     channels: &mut Vec<String>,
-    subscriptions: &mut StreamMap<String, Messages>,
+    patterns: &mut Vec<String>,
+    subscriptions: &mut Subscriptions,
     dst: &mut Connection,
 ) -> crate::Result<()> {
-    // Only `SUBSCRIBE` and `UNSUBSCRIBE` commands are permitted.
-    match Command::from_frame(frame)? {
+    let cmd = match Command::from_frame(frame) {
+        Ok(cmd) => cmd,
+        Err(err) => {
+            dst.write_frame(&Frame::Error(format!("ERR {}", err)))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    // Only `SUBSCRIBE`, `UNSUBSCRIBE`, `PSUBSCRIBE` and `PUNSUBSCRIBE` commands are permitted.
+    match cmd {
         Command::Subscribe(subscribe) => {
-            channels.extend(subscribe.channels.into_iter());
+            channels.extend(subscribe.channels);
+        }
+        Command::PSubscribe(psubscribe) => {
+            patterns.extend(psubscribe.patterns);
         }
         Command::Unsubscribe(mut unsubscribe) => {
             // If no channels are specified, unsubscribing from all channels.
             if unsubscribe.channels.is_empty() {
                 unsubscribe.channels = subscriptions
+                    .channels
                     .keys()
                     .map(|channel_name| channel_name.to_owned())
                     .collect();
             }
 
             for channel_name in unsubscribe.channels {
-                subscriptions.remove(&channel_name);
+                subscriptions.channels.remove(&channel_name);
+
+                let response =
+                    make_subscription_frame("unsubscribe", channel_name, subscriptions.len());
+                dst.write_frame(&response).await?;
+            }
+        }
+        Command::PUnsubscribe(mut punsubscribe) => {
+            // If no patterns are specified, unsubscribing from all patterns.
+            if punsubscribe.patterns.is_empty() {
+                punsubscribe.patterns = subscriptions.patterns.keys().cloned().collect();
+            }
+
+            for pattern in punsubscribe.patterns {
+                subscriptions.patterns.remove(&pattern);
 
-                let response = make_unsubscribe_frame(channel_name, subscriptions.len());
+                let response =
+                    make_subscription_frame("punsubscribe", pattern, subscriptions.len());
                 dst.write_frame(&response).await?;
             }
         }
@@ -0,0 +1,368 @@
+use crate::cmd::{Command, Parse};
+use crate::db::WatchedKey;
+use crate::{Connection, Db, Frame};
+
+use bytes::Bytes;
+use std::mem;
+use tokio::task;
+use tracing::{debug, instrument};
+
+/// Marks the start of a transaction.
+///
+/// The following commands are queued instead of being applied, until `EXEC` applies all of them
+/// atomically or `DISCARD` drops them.
+#[derive(Debug, Default)]
+pub struct Multi {}
+
+/// Applies the commands queued since `MULTI` atomically and replies with an array of their
+/// replies.
+///
+/// If a command could not be queued, the transaction is discarded and an `EXECABORT` error is
+/// returned instead. If any of the keys watched with `WATCH` was modified, expired or deleted in
+/// the meantime, the transaction is discarded and `Null` is returned.
+#[derive(Debug, Default)]
+pub struct Exec {}
+
+/// Drops the commands queued since `MULTI` and ends the transaction.
+#[derive(Debug, Default)]
+pub struct Discard {}
+
+/// Watches the given keys, so that the next `EXEC` aborts the transaction if any of them is
+/// modified, expires or is deleted in the meantime.
+///
+/// Keys are watched until `EXEC`, `DISCARD` or `UNWATCH`.
+#[derive(Debug)]
+pub struct Watch {
+    keys: Vec<String>,
+}
+
+/// Forgets all the keys watched with `WATCH`.
+#[derive(Debug, Default)]
+pub struct Unwatch {}
+
+/// Transaction state of a connection.
+#[derive(Debug, Default)]
+pub(crate) struct Transaction {
+    /// Commands queued since `MULTI`, `None` outside of a transaction.
+    queued: Option<Vec<Command>>,
+    /// Set when a command could not be queued, in which case `EXEC` discards the transaction.
+    aborted: bool,
+    /// Keys watched with `WATCH`.
+    watched: Vec<WatchedKey>,
+}
+
+impl Multi {
+    /// Create a new `Multi` command.
+    pub fn new() -> Multi {
+        Multi {}
+    }
+
+    /// Parses a `Multi` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// MULTI
+    /// ```
+    pub(crate) fn parse_frames(_parse: &mut Parse) -> crate::Result<Multi> {
+        Ok(Multi {})
+    }
+
+    /// Starts a transaction on the connection and writes the response to `dst`.
+    #[instrument(skip(self, dst, transaction))]
+    pub(crate) async fn apply(
+        self,
+        dst: &mut Connection,
+        transaction: &mut Transaction,
+    ) -> crate::Result<()> {
+        let response = if transaction.is_active() {
+            Frame::Error("ERR MULTI calls can not be nested".to_string())
+        } else {
+            transaction.queued = Some(vec![]);
+            Frame::Simple("OK".to_string())
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("multi".as_bytes()));
+        frame
+    }
+}
+
+impl Exec {
+    /// Create a new `Exec` command.
+    pub fn new() -> Exec {
+        Exec {}
+    }
+
+    /// Parses an `Exec` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// EXEC
+    /// ```
+    pub(crate) fn parse_frames(_parse: &mut Parse) -> crate::Result<Exec> {
+        Ok(Exec {})
+    }
+
+    /// Applies the queued commands to the specified `Db` instance, without any other client
+    /// accessing it in between, and writes the response to `dst`.
+    ///
+    /// The commands are applied on a thread dedicated to blocking operations, as the `Db` stays
+    /// locked until all of them were applied.
+    #[instrument(skip(self, db, dst, transaction))]
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        transaction: &mut Transaction,
+    ) -> crate::Result<()> {
+        let Transaction {
+            queued,
+            aborted,
+            watched,
+        } = mem::take(transaction);
+
+        let response = match queued {
+            None => Frame::Error("ERR EXEC without MULTI".to_string()),
+            Some(_) if aborted => Frame::Error(
+                "EXECABORT Transaction discarded because of previous errors.".to_string(),
+            ),
+            Some(commands) => {
+                let db = db.clone();
+
+                task::spawn_blocking(move || {
+                    db.exclusive(|| {
+                        // Checked within the exclusive section, so that no other client may
+                        // modify the watched keys before the commands are applied.
+                        if watched.iter().any(WatchedKey::is_modified) {
+                            Frame::Null
+                        } else {
+                            let replies = commands.into_iter().map(|cmd| cmd.execute(&db));
+                            Frame::Array(replies.collect())
+                        }
+                    })
+                })
+                .await?
+            }
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("exec".as_bytes()));
+        frame
+    }
+}
+
+impl Discard {
+    /// Create a new `Discard` command.
+    pub fn new() -> Discard {
+        Discard {}
+    }
+
+    /// Parses a `Discard` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// DISCARD
+    /// ```
+    pub(crate) fn parse_frames(_parse: &mut Parse) -> crate::Result<Discard> {
+        Ok(Discard {})
+    }
+
+    /// Ends the transaction of the connection and writes the response to `dst`.
+    #[instrument(skip(self, dst, transaction))]
+    pub(crate) async fn apply(
+        self,
+        dst: &mut Connection,
+        transaction: &mut Transaction,
+    ) -> crate::Result<()> {
+        let response = if transaction.is_active() {
+            *transaction = Transaction::default();
+            Frame::Simple("OK".to_string())
+        } else {
+            Frame::Error("ERR DISCARD without MULTI".to_string())
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("discard".as_bytes()));
+        frame
+    }
+}
+
+impl Watch {
+    /// Create a new `Watch` command which watches `keys`.
+    pub fn new(keys: &[String]) -> Watch {
+        Watch {
+            keys: keys.to_vec(),
+        }
+    }
+
+    /// Parses a `Watch` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// WATCH key [key ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Watch> {
+        let mut keys = vec![parse.next_string()?];
+        keys.extend(parse.rest_strings()?);
+
+        Ok(Watch { keys })
+    }
+
+    /// Starts watching the keys in the specified `Db` instance and writes the response to `dst`.
+    #[instrument(skip(self, db, dst, transaction))]
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        transaction: &mut Transaction,
+    ) -> crate::Result<()> {
+        let response = if transaction.is_active() {
+            Frame::Error("ERR WATCH inside MULTI is not allowed".to_string())
+        } else {
+            let _section = db.enter_section().await;
+            for key in self.keys {
+                transaction.watched.push(db.watch(key));
+            }
+
+            Frame::Simple("OK".to_string())
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("watch".as_bytes()));
+        for key in self.keys {
+            frame.push_bulk(Bytes::from(key.into_bytes()));
+        }
+        frame
+    }
+}
+
+impl Unwatch {
+    /// Create a new `Unwatch` command.
+    pub fn new() -> Unwatch {
+        Unwatch {}
+    }
+
+    /// Parses an `Unwatch` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// UNWATCH
+    /// ```
+    pub(crate) fn parse_frames(_parse: &mut Parse) -> crate::Result<Unwatch> {
+        Ok(Unwatch {})
+    }
+
+    /// Forgets the keys watched by the connection and writes the response to `dst`.
+    #[instrument(skip(self, dst, transaction))]
+    pub(crate) async fn apply(
+        self,
+        dst: &mut Connection,
+        transaction: &mut Transaction,
+    ) -> crate::Result<()> {
+        transaction.watched.clear();
+
+        let response = self.execute();
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Returns the response of an `UNWATCH` queued in a transaction. `EXEC` has already
+    /// forgotten the watched keys by the time it is applied.
+    pub(crate) fn execute(self) -> Frame {
+        let response = Frame::Simple("OK".to_string());
+        debug!(?response);
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("unwatch".as_bytes()));
+        frame
+    }
+}
+
+impl Transaction {
+    /// Returns `true` between `MULTI` and `EXEC` or `DISCARD`.
+    pub(crate) fn is_active(&self) -> bool {
+        self.queued.is_some()
+    }
+
+    /// Marks the transaction as aborted, so that `EXEC` discards it. Does nothing outside of a
+    /// transaction.
+    pub(crate) fn abort(&mut self) {
+        if self.is_active() {
+            self.aborted = true;
+        }
+    }
+
+    /// Queues `cmd` to be applied by `EXEC` and writes the response to `dst`.
+    ///
+    /// Commands which cannot be applied within a transaction are rejected and abort the
+    /// transaction.
+    #[instrument(skip(self, dst))]
+    pub(crate) async fn queue(&mut self, cmd: Command, dst: &mut Connection) -> crate::Result<()> {
+        let response = match cmd {
+            Command::Unknown(cmd) => {
+                self.aborted = true;
+                cmd.execute()
+            }
+            Command::Subscribe(_)
+            | Command::PSubscribe(_)
+            | Command::Unsubscribe(_)
+            | Command::PUnsubscribe(_)
+            | Command::Custom(_) => {
+                self.aborted = true;
+                Frame::Error("ERR Command not allowed inside a transaction".to_string())
+            }
+            cmd => {
+                self.queued.get_or_insert_with(Vec::new).push(cmd);
+                Frame::Simple("QUEUED".to_string())
+            }
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
@@ -21,12 +21,17 @@ impl Unknown {
 
     #[instrument(skip(self, dst))]
     pub(crate) async fn apply(self, dst: &mut Connection) -> crate::Result<()> {
+        dst.write_frame(&self.execute()).await?;
+
+        Ok(())
+    }
+
+    /// Returns the error replied to the unknown command.
+    pub(crate) fn execute(self) -> Frame {
         let response = Frame::Error(format!("ERR unknown command '{}'", self.command_name));
 
         debug!(?response);
 
-        dst.write_frame(&response).await?;
-
-        Ok(())
+        response
     }
 }
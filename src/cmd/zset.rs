@@ -0,0 +1,797 @@
+use crate::db::parse_int;
+use crate::{Db, Frame, LexBound, Parse, ParseError, ScoreBound, ZAddOptions, ZRangeBy};
+
+use bytes::Bytes;
+use tracing::{debug, instrument};
+
+/// Add the specified members with their scores to the sorted set stored at key.
+///
+/// Members that already belong to the sorted set have their score updated. If key does not
+/// exist, a new sorted set is created. Returns the number of added members, or with the `INCR`
+/// option the new score of the single given member.
+#[derive(Debug)]
+pub struct ZAdd {
+    key: String,
+    members: Vec<(f64, Bytes)>,
+    options: ZAddOptions,
+    /// Increment the score of the member rather than setting it, like `ZINCRBY`.
+    incr: bool,
+}
+
+/// Returns the members of the sorted set stored at key selected by rank, score or lexicographical
+/// order.
+///
+/// Members are returned from the lowest to the highest score, or the other way around with the
+/// `REV` option, optionally along with their scores.
+#[derive(Debug)]
+pub struct ZRange {
+    key: String,
+    by: ZRangeBy,
+    rev: bool,
+    /// Number of selected members to skip and maximum number of members to return.
+    limit: Option<(u64, i64)>,
+    with_scores: bool,
+}
+
+/// Returns the rank of member in the sorted set stored at key, with the member having the lowest
+/// score at rank 0.
+#[derive(Debug)]
+pub struct ZRank {
+    key: String,
+    member: Bytes,
+}
+
+/// Returns the score of member in the sorted set stored at key.
+#[derive(Debug)]
+pub struct ZScore {
+    key: String,
+    member: Bytes,
+}
+
+/// Remove the specified members from the sorted set stored at key.
+///
+/// Members that are not part of the sorted set are ignored. The key is removed once the sorted
+/// set is empty. Returns the number of members that were removed.
+#[derive(Debug)]
+pub struct ZRem {
+    key: String,
+    members: Vec<Bytes>,
+}
+
+/// Returns the number of members of the sorted set stored at key with a score between min and
+/// max.
+#[derive(Debug)]
+pub struct ZCount {
+    key: String,
+    min: ScoreBound,
+    max: ScoreBound,
+}
+
+/// Increments the score of member in the sorted set stored at key by increment.
+///
+/// A missing member is added with increment as its score. Returns the new score.
+#[derive(Debug)]
+pub struct ZIncrBy {
+    key: String,
+    increment: f64,
+    member: Bytes,
+}
+
+/// Removes and returns the members with the lowest or the highest scores from the sorted set
+/// stored at key.
+///
+/// Received as ZPOPMIN or ZPOPMAX. Members are returned along with their scores, starting with
+/// the lowest score for `ZPOPMIN` and the highest score for `ZPOPMAX`.
+#[derive(Debug)]
+pub struct ZPop {
+    key: String,
+    count: Option<u64>,
+    /// Pop the members with the highest scores (`ZPOPMAX`) rather than the lowest (`ZPOPMIN`).
+    max: bool,
+}
+
+impl ZAdd {
+    /// Create a new `ZAdd` command which adds `members` with their scores to the sorted set
+    /// stored at `key`, subject to `options`.
+    pub fn new(key: impl ToString, members: Vec<(f64, Bytes)>, options: ZAddOptions) -> ZAdd {
+        ZAdd {
+            key: key.to_string(),
+            members,
+            options,
+            incr: false,
+        }
+    }
+
+    /// Create a new `ZAdd` command which increments the score of `member` in the sorted set
+    /// stored at `key` by `increment`, subject to `options`.
+    pub fn new_incr(
+        key: impl ToString,
+        increment: f64,
+        member: Bytes,
+        options: ZAddOptions,
+    ) -> ZAdd {
+        ZAdd {
+            key: key.to_string(),
+            members: vec![(increment, member)],
+            options,
+            incr: true,
+        }
+    }
+
+    /// Parses a `ZAdd` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// ZADD key [NX | XX] [GT | LT] [CH] [INCR] score member [score member ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZAdd> {
+        let key = parse.next_string()?;
+
+        let mut options = ZAddOptions::default();
+        let mut incr = false;
+
+        // Options come first, the first argument that is not an option is the first score.
+        let score = loop {
+            let arg = parse.next_string()?;
+
+            match &arg.to_uppercase()[..] {
+                "NX" => options.nx = true,
+                "XX" => options.xx = true,
+                "GT" => options.gt = true,
+                "LT" => options.lt = true,
+                "CH" => options.ch = true,
+                "INCR" => incr = true,
+                _ => break parse_score(&arg)?,
+            }
+        };
+
+        let mut members = vec![(score, parse.next_bytes()?)];
+
+        loop {
+            let score = match parse.next_string() {
+                Ok(score) => parse_score(&score)?,
+                Err(ParseError::EndOfStream) => break,
+                Err(err) => return Err(err.into()),
+            };
+
+            members.push((score, parse.next_bytes()?));
+        }
+
+        if options.nx && options.xx {
+            return Err(
+                "ZADD command error: `NX` and `XX` cannot be given at the same time".into(),
+            );
+        }
+
+        if [options.nx, options.gt, options.lt]
+            .iter()
+            .filter(|&&set| set)
+            .count()
+            > 1
+        {
+            return Err("ZADD command error: only one of `NX`, `GT` and `LT` can be given".into());
+        }
+
+        if incr && members.len() > 1 {
+            return Err(
+                "ZADD command error: `INCR` supports a single score and member pair".into(),
+            );
+        }
+
+        Ok(ZAdd {
+            key,
+            members,
+            options,
+            incr,
+        })
+    }
+
+    /// Apply the `ZAdd` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = if self.incr {
+            // `parse_frames` and `new_incr` ensure there is exactly one member.
+            let (increment, member) = self.members.into_iter().next().unwrap();
+
+            match db.zincr_by(&self.key, increment, member, self.options) {
+                Ok(Some(score)) => Frame::Double(score),
+                Ok(None) => Frame::Null,
+                Err(err) => Frame::Error(err.to_string()),
+            }
+        } else {
+            match db.zadd(&self.key, self.members, self.options) {
+                Ok(count) => Frame::Integer(count as i64),
+                Err(err) => Frame::Error(err.to_string()),
+            }
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zadd".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        let options = [
+            (self.options.nx, "nx"),
+            (self.options.xx, "xx"),
+            (self.options.gt, "gt"),
+            (self.options.lt, "lt"),
+            (self.options.ch, "ch"),
+            (self.incr, "incr"),
+        ];
+        for (_, option) in options.iter().filter(|(set, _)| *set) {
+            frame.push_bulk(Bytes::from(option.as_bytes()));
+        }
+        for (score, member) in self.members {
+            frame.push_bulk(Bytes::from(score.to_string()));
+            frame.push_bulk(member);
+        }
+        frame
+    }
+}
+
+impl ZRange {
+    /// Create a new `ZRange` command which reads the members of the sorted set stored at `key`
+    /// selected by `by`.
+    pub fn new(key: impl ToString, by: ZRangeBy) -> ZRange {
+        ZRange {
+            key: key.to_string(),
+            by,
+            rev: false,
+            limit: None,
+            with_scores: false,
+        }
+    }
+
+    /// Return the members from the highest to the lowest score. Ranks are then counted from the
+    /// highest score.
+    pub fn rev(mut self) -> ZRange {
+        self.rev = true;
+        self
+    }
+
+    /// Skip the first `offset` selected members and return at most `count` of the remaining
+    /// ones. A negative `count` returns all of them. Only supported for ranges by score or by
+    /// lexicographical order.
+    pub fn limit(mut self, offset: u64, count: i64) -> ZRange {
+        self.limit = Some((offset, count));
+        self
+    }
+
+    /// Return the score of each member along with it.
+    pub fn with_scores(mut self) -> ZRange {
+        self.with_scores = true;
+        self
+    }
+
+    /// Parses a `ZRange` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// ZRANGE key start stop [BYSCORE | BYLEX] [REV] [LIMIT offset count] [WITHSCORES]
+    /// ```
+    ///
+    /// With `REV`, start is the maximum and stop the minimum of score and lexicographical ranges.
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZRange> {
+        let key = parse.next_string()?;
+        let start = parse.next_bytes()?;
+        let stop = parse.next_bytes()?;
+
+        let mut by_score = false;
+        let mut by_lex = false;
+        let mut rev = false;
+        let mut limit = None;
+        let mut with_scores = false;
+
+        loop {
+            let option = match parse.next_string() {
+                Ok(s) => s.to_uppercase(),
+                Err(ParseError::EndOfStream) => break,
+                Err(err) => return Err(err.into()),
+            };
+
+            match &option[..] {
+                "BYSCORE" => by_score = true,
+                "BYLEX" => by_lex = true,
+                "REV" => rev = true,
+                "LIMIT" => limit = Some((parse.next_int()?, parse.next_signed_int()?)),
+                "WITHSCORES" => with_scores = true,
+                _ => {
+                    return Err(
+                        format!("ZRANGE command error: unsupported option {}", option).into(),
+                    )
+                }
+            }
+        }
+
+        // Score and lexicographical ranges are given from the maximum with `REV`.
+        let (min, max) = if rev {
+            (stop.clone(), start.clone())
+        } else {
+            (start.clone(), stop.clone())
+        };
+
+        let by = match (by_score, by_lex) {
+            (false, false) => {
+                if limit.is_some() {
+                    return Err(
+                        "ZRANGE command error: `LIMIT` requires `BYSCORE` or `BYLEX`".into(),
+                    );
+                }
+
+                ZRangeBy::Rank(parse_rank(&start)?, parse_rank(&stop)?)
+            }
+            (true, false) => ZRangeBy::Score(parse_score_bound(&min)?, parse_score_bound(&max)?),
+            (false, true) => {
+                if with_scores {
+                    return Err(
+                        "ZRANGE command error: `WITHSCORES` cannot be given with `BYLEX`".into(),
+                    );
+                }
+
+                ZRangeBy::Lex(parse_lex_bound(min)?, parse_lex_bound(max)?)
+            }
+            // Redis replies with a generic syntax error as well.
+            (true, true) => return Err("syntax error".into()),
+        };
+
+        Ok(ZRange {
+            key,
+            by,
+            rev,
+            limit,
+            with_scores,
+        })
+    }
+
+    /// Apply the `ZRange` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.zrange(&self.key, &self.by, self.rev, self.limit) {
+            Ok(members) => members_frame(members, self.with_scores),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zrange".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+
+        let (start, stop, by) = match self.by {
+            ZRangeBy::Rank(start, stop) => (
+                Bytes::from(start.to_string()),
+                Bytes::from(stop.to_string()),
+                None,
+            ),
+            ZRangeBy::Score(min, max) => (
+                score_bound_to_bytes(min),
+                score_bound_to_bytes(max),
+                Some("byscore"),
+            ),
+            ZRangeBy::Lex(min, max) => (
+                lex_bound_to_bytes(min),
+                lex_bound_to_bytes(max),
+                Some("bylex"),
+            ),
+        };
+
+        // Score and lexicographical ranges are given from the maximum with `REV`.
+        if self.rev && by.is_some() {
+            frame.push_bulk(stop);
+            frame.push_bulk(start);
+        } else {
+            frame.push_bulk(start);
+            frame.push_bulk(stop);
+        }
+
+        if let Some(by) = by {
+            frame.push_bulk(Bytes::from(by.as_bytes()));
+        }
+        if self.rev {
+            frame.push_bulk(Bytes::from("rev".as_bytes()));
+        }
+        if let Some((offset, count)) = self.limit {
+            frame.push_bulk(Bytes::from("limit".as_bytes()));
+            frame.push_int(offset as i64);
+            frame.push_int(count);
+        }
+        if self.with_scores {
+            frame.push_bulk(Bytes::from("withscores".as_bytes()));
+        }
+        frame
+    }
+}
+
+impl ZRank {
+    /// Create a new `ZRank` command which reads the rank of `member` in the sorted set stored at
+    /// `key`.
+    pub fn new(key: impl ToString, member: Bytes) -> ZRank {
+        ZRank {
+            key: key.to_string(),
+            member,
+        }
+    }
+
+    /// Parses a `ZRank` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// ZRANK key member
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZRank> {
+        let key = parse.next_string()?;
+        let member = parse.next_bytes()?;
+
+        Ok(ZRank { key, member })
+    }
+
+    /// Apply the `ZRank` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.zrank(&self.key, &self.member) {
+            Ok(Some(rank)) => Frame::Integer(rank as i64),
+            Ok(None) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zrank".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(self.member);
+        frame
+    }
+}
+
+impl ZScore {
+    /// Create a new `ZScore` command which reads the score of `member` in the sorted set stored
+    /// at `key`.
+    pub fn new(key: impl ToString, member: Bytes) -> ZScore {
+        ZScore {
+            key: key.to_string(),
+            member,
+        }
+    }
+
+    /// Parses a `ZScore` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// ZSCORE key member
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZScore> {
+        let key = parse.next_string()?;
+        let member = parse.next_bytes()?;
+
+        Ok(ZScore { key, member })
+    }
+
+    /// Apply the `ZScore` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.zscore(&self.key, &self.member) {
+            Ok(Some(score)) => Frame::Double(score),
+            Ok(None) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zscore".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(self.member);
+        frame
+    }
+}
+
+impl ZRem {
+    /// Create a new `ZRem` command which removes `members` from the sorted set stored at `key`.
+    pub fn new(key: impl ToString, members: Vec<Bytes>) -> ZRem {
+        ZRem {
+            key: key.to_string(),
+            members,
+        }
+    }
+
+    /// Parses a `ZRem` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// ZREM key member [member ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZRem> {
+        let key = parse.next_string()?;
+
+        let mut members = vec![parse.next_bytes()?];
+        members.extend(parse.rest_bytes()?);
+
+        Ok(ZRem { key, members })
+    }
+
+    /// Apply the `ZRem` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.zrem(&self.key, &self.members) {
+            Ok(removed) => Frame::Integer(removed as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zrem".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        for member in self.members {
+            frame.push_bulk(member);
+        }
+        frame
+    }
+}
+
+impl ZCount {
+    /// Create a new `ZCount` command which counts the members of the sorted set stored at `key`
+    /// with a score between `min` and `max`.
+    pub fn new(key: impl ToString, min: ScoreBound, max: ScoreBound) -> ZCount {
+        ZCount {
+            key: key.to_string(),
+            min,
+            max,
+        }
+    }
+
+    /// Parses a `ZCount` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// ZCOUNT key min max
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZCount> {
+        let key = parse.next_string()?;
+        let min = parse_score_bound(&parse.next_bytes()?)?;
+        let max = parse_score_bound(&parse.next_bytes()?)?;
+
+        Ok(ZCount { key, min, max })
+    }
+
+    /// Apply the `ZCount` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let response = match db.zcount(&self.key, self.min, self.max) {
+            Ok(count) => Frame::Integer(count as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zcount".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(score_bound_to_bytes(self.min));
+        frame.push_bulk(score_bound_to_bytes(self.max));
+        frame
+    }
+}
+
+impl ZIncrBy {
+    /// Create a new `ZIncrBy` command which increments the score of `member` in the sorted set
+    /// stored at `key` by `increment`.
+    pub fn new(key: impl ToString, increment: f64, member: Bytes) -> ZIncrBy {
+        ZIncrBy {
+            key: key.to_string(),
+            increment,
+            member,
+        }
+    }
+
+    /// Parses a `ZIncrBy` instance from a received frame.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// ZINCRBY key increment member
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ZIncrBy> {
+        let key = parse.next_string()?;
+        let increment = parse_score(&parse.next_string()?)?;
+        let member = parse.next_bytes()?;
+
+        Ok(ZIncrBy {
+            key,
+            increment,
+            member,
+        })
+    }
+
+    /// Apply the `ZIncrBy` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let options = ZAddOptions::default();
+
+        let response = match db.zincr_by(&self.key, self.increment, self.member, options) {
+            Ok(Some(score)) => Frame::Double(score),
+            // Without options, the member is always updated.
+            Ok(None) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zincrby".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.increment.to_string()));
+        frame.push_bulk(self.member);
+        frame
+    }
+}
+
+impl ZPop {
+    /// Create a new `ZPop` command which pops up to `count` members from the sorted set stored
+    /// at `key`, or a single member if `count` is `None`. The members with the highest scores
+    /// are popped if `max` is set, and the ones with the lowest scores otherwise.
+    pub fn new(key: impl ToString, count: Option<u64>, max: bool) -> ZPop {
+        ZPop {
+            key: key.to_string(),
+            count,
+            max,
+        }
+    }
+
+    /// Parses a `ZPop` instance from a received frame. `max` is set when the frame was
+    /// `ZPOPMAX`.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// ZPOPMIN key [count]
+    /// ZPOPMAX key [count]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse, max: bool) -> crate::Result<ZPop> {
+        let key = parse.next_string()?;
+
+        let count = match parse.next_int() {
+            Ok(count) => Some(count),
+            Err(ParseError::EndOfStream) => None,
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(ZPop { key, count, max })
+    }
+
+    /// Apply the `ZPop` command to the specified `Db` instance and return the response.
+    #[instrument(skip(self, db))]
+    pub(crate) fn execute(self, db: &Db) -> Frame {
+        let count = self.count.unwrap_or(1) as usize;
+
+        let response = match db.zpop(&self.key, count, self.max) {
+            Ok(members) => members_frame(members, true),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+
+        response
+    }
+
+    /// Returns the command name.
+    pub(crate) fn get_name(&self) -> &str {
+        if self.max {
+            "zpopmax"
+        } else {
+            "zpopmin"
+        }
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from(self.get_name().as_bytes().to_vec()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        if let Some(count) = self.count {
+            frame.push_int(count as i64);
+        }
+        frame
+    }
+}
+
+/// Encodes members as an array frame, each member followed by its score if `with_scores` is set.
+fn members_frame(members: Vec<(Bytes, f64)>, with_scores: bool) -> Frame {
+    let mut frame = Frame::array();
+    for (member, score) in members {
+        frame.push_bulk(member);
+        if with_scores {
+            frame.push_double(score);
+        }
+    }
+    frame
+}
+
+/// Parses a score, which may be `inf`, `+inf` or `-inf` but never NaN.
+fn parse_score(score: &str) -> crate::Result<f64> {
+    match score.parse::<f64>() {
+        Ok(score) if !score.is_nan() => Ok(score),
+        _ => Err("value is not a valid float".into()),
+    }
+}
+
+/// Parses a rank of a `ZRANGE` range by rank.
+fn parse_rank(rank: &[u8]) -> crate::Result<i64> {
+    parse_int(rank).ok_or_else(|| "value is not an integer or out of range".into())
+}
+
+/// Parses a score range bound, which is exclusive when prefixed with `(`.
+fn parse_score_bound(bound: &[u8]) -> crate::Result<ScoreBound> {
+    const MSG: &str = "min or max is not a float";
+
+    let bound = std::str::from_utf8(bound).map_err(|_| MSG)?;
+
+    match bound.strip_prefix('(') {
+        Some(score) => Ok(ScoreBound::Exclusive(parse_score(score).map_err(|_| MSG)?)),
+        None => Ok(ScoreBound::Inclusive(parse_score(bound).map_err(|_| MSG)?)),
+    }
+}
+
+/// Encodes a score range bound.
+fn score_bound_to_bytes(bound: ScoreBound) -> Bytes {
+    match bound {
+        ScoreBound::Inclusive(score) => Bytes::from(score.to_string()),
+        ScoreBound::Exclusive(score) => Bytes::from(format!("({}", score)),
+    }
+}
+
+/// Parses a lexicographical range bound, which is either `-`, `+`, or a member prefixed with `[`
+/// when inclusive or `(` when exclusive.
+fn parse_lex_bound(bound: Bytes) -> crate::Result<LexBound> {
+    match bound.first() {
+        Some(b'-') if bound.len() == 1 => Ok(LexBound::Min),
+        Some(b'+') if bound.len() == 1 => Ok(LexBound::Max),
+        Some(b'[') => Ok(LexBound::Inclusive(bound.slice(1..))),
+        Some(b'(') => Ok(LexBound::Exclusive(bound.slice(1..))),
+        _ => Err("min or max not valid string range item".into()),
+    }
+}
+
+/// Encodes a lexicographical range bound.
+fn lex_bound_to_bytes(bound: LexBound) -> Bytes {
+    match bound {
+        LexBound::Min => Bytes::from("-".as_bytes()),
+        LexBound::Max => Bytes::from("+".as_bytes()),
+        LexBound::Inclusive(member) => Bytes::from([&b"["[..], &member].concat()),
+        LexBound::Exclusive(member) => Bytes::from([&b"("[..], &member].concat()),
+    }
+}
@@ -1,6 +1,7 @@
 use crate::frame::{self, Frame};
 
 use bytes::{Buf, BytesMut};
+use std::future;
 use std::io::{self, Cursor};
 use tokio::io::{AsyncReadExt, AsyncWriteExt, BufWriter};
 use tokio::net::TcpStream;
@@ -12,17 +13,40 @@ pub struct Connection {
     stream: BufWriter<TcpStream>,
     /// The internal buffer for reading frames.
     buffer: BytesMut,
+    /// Maximum size of a received frame, in bytes.
+    max_frame_size: usize,
+    /// Maximum length of a received bulk string, in bytes.
+    max_bulk_size: usize,
 }
 
 impl Connection {
     /// Create a new `Connection`.
     pub fn new(socket: TcpStream) -> Connection {
+        Connection::with_capacity(socket, 4 * 1024)
+    }
+
+    /// Create a new `Connection`, with a read buffer initially holding `capacity` bytes.
+    pub fn with_capacity(socket: TcpStream, capacity: usize) -> Connection {
         Connection {
             stream: BufWriter::new(socket),
-            buffer: BytesMut::with_capacity(4 * 1024),
+            buffer: BytesMut::with_capacity(capacity),
+            max_frame_size: usize::MAX,
+            max_bulk_size: usize::MAX,
         }
     }
 
+    /// Limits the size of the received frames to `max` bytes, or lifts the limit if `max` is
+    /// `None`. Receiving a larger frame is a protocol error.
+    pub fn set_max_frame_size(&mut self, max: Option<usize>) {
+        self.max_frame_size = max.unwrap_or(usize::MAX);
+    }
+
+    /// Limits the length of the received bulk strings to `max` bytes, or lifts the limit if
+    /// `max` is `None`. Receiving a longer bulk string is a protocol error.
+    pub fn set_max_bulk_size(&mut self, max: Option<usize>) {
+        self.max_bulk_size = max.unwrap_or(usize::MAX);
+    }
+
     /// Tries to parse a frame from the buffer.
     ///
     /// # Returns
@@ -36,11 +60,15 @@ impl Connection {
         let mut buf = Cursor::new(&self.buffer[..]);
 
         // check if enough data has been buffered to parse a single frame.
-        match Frame::check(&mut buf) {
+        match Frame::check_limited(&mut buf, self.max_bulk_size) {
             Ok(_) => {
                 // remember the length of the frame.
                 let len = buf.position() as usize;
 
+                if len > self.max_frame_size {
+                    return Err("protocol error: frame too large".into());
+                }
+
                 // reset the position to zero.
                 buf.set_position(0);
 
@@ -52,6 +80,10 @@ impl Connection {
 
                 Ok(Some(frame))
             },
+            // The frame being received is already larger than allowed.
+            Err(Incomplete) if self.buffer.len() > self.max_frame_size => {
+                Err("protocol error: frame too large".into())
+            }
             // There is not enough data present in the read buffer to parse a single frame.
             Err(Incomplete) => Ok(None),
             // An error was encountered while parsing the frame.
@@ -83,38 +115,40 @@ impl Connection {
                     return Ok(None);
                 } else {
                     // `TcpStream` is closed unexpectedly.
-                    return Err("connection reset by peer".into());
+                    let err =
+                        io::Error::new(io::ErrorKind::ConnectionReset, "connection reset by peer");
+                    return Err(err.into());
                 }
             }
         }
     }
 
-    /// Write a single `Frame` value to the underlying stream.
-    pub async fn write_frame(&mut self, frame: &Frame) -> io::Result<()> {
-        match frame {
-            // Arrays are encoded by encoding each entry.
-            // Encoding recursive frame structures is not supported yet.
-            Frame::Array(val) => {
-                // encode the array frame prefix.
-                self.stream.write_u8(b'*').await?;
-
-                // encode the length of the aray.
-                self.write_decimal(val.len() as u64).await?;
-
-                // iterate and encode each entry in the array frame.
-                for entry in val {
-                   self.write_value(entry).await?; 
-                }
+    /// Waits until the peer closes the connection or the connection fails.
+    ///
+    /// Data received in the meantime is buffered, to be returned by the next `read_frame`. Once
+    /// more than a frame worth of data is buffered, this waits forever instead.
+    pub async fn closed(&mut self) {
+        while self.buffer.len() <= self.max_frame_size {
+            match self.stream.read_buf(&mut self.buffer).await {
+                // `0` indicates "end of stream".
+                Ok(0) | Err(_) => return,
+                Ok(_) => {}
             }
-            _ => self.write_value(frame).await?,
         }
 
+        future::pending().await
+    }
+
+    /// Write a single `Frame` value to the underlying stream.
+    pub async fn write_frame(&mut self, frame: &Frame) -> io::Result<()> {
+        self.write_value(frame).await?;
+
         // ensure the encoded frame is written to the socket.
         self.stream.flush().await
     }
 
     /// Write a decimal frame into the stream.
-    async fn write_decimal(&mut self, val: u64) -> io::Result<()> {
+    async fn write_decimal(&mut self, val: i64) -> io::Result<()> {
         use std::io::Write;
 
         let mut buf = [0u8; 20];
@@ -150,16 +184,34 @@ impl Connection {
                 let len = val.len();
 
                 self.stream.write_u8(b'$').await?;
-                self.write_decimal(len as u64).await?;
+                self.write_decimal(len as i64).await?;
                 self.stream.write_all(val).await?;
                 self.stream.write_all(b"\r\n").await?;
             }
             Frame::Null => {
                 self.stream.write_all(b"$-1\r\n").await?;
             }
-            // async fn do not support recursion in general.
-            Frame::Array(_val) => {
-                unreachable!()
+            Frame::Double(val) => {
+                let val = frame::format_double(*val);
+
+                self.stream.write_u8(b'$').await?;
+                self.write_decimal(val.len() as i64).await?;
+                self.stream.write_all(val.as_bytes()).await?;
+                self.stream.write_all(b"\r\n").await?;
+            }
+            // Arrays are encoded by encoding each entry, which may itself be an array.
+            Frame::Array(val) => {
+                // encode the array frame prefix.
+                self.stream.write_u8(b'*').await?;
+
+                // encode the length of the aray.
+                self.write_decimal(val.len() as i64).await?;
+
+                // iterate and encode each entry in the array frame. The recursive call must be
+                // boxed, as the size of the future would otherwise be infinite.
+                for entry in val {
+                    Box::pin(self.write_value(entry)).await?;
+                }
             }
         }
 
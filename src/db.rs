@@ -1,10 +1,35 @@
-use bytes::Bytes;
-use std::collections::{BTreeMap, HashMap};
-use std::sync::{Arc, Mutex};
-use tokio::sync::{broadcast, Notify};
+use bytes::{Bytes, BytesMut};
+use parking_lot::{ReentrantMutex, ReentrantMutexGuard};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::{broadcast, watch, Notify};
 use tokio::time::{self, Duration, Instant};
 
+mod config;
+pub use config::DbConfig;
+mod glob;
+mod hash;
+mod list;
+mod notify;
+use notify::KeyspaceEvent;
+pub use notify::KeyspaceEvents;
+mod rank_tree;
+mod script;
+pub(crate) use script::read_only;
+mod set;
+pub use set::SetOp;
+mod stream;
+pub use stream::{GroupEntry, PendingEntry, PendingSummary, StreamEntry, StreamId, XAddId};
+mod zset;
+pub use zset::{LexBound, ScoreBound, ZAddOptions, ZRangeBy};
+
 /// Server state shared across all connections.
+///
+/// `Db` may also be used in-process, without the network layer. Its methods implement the
+/// commands of the server, which applies them the same way, and take effect atomically. Cloning
+/// a `Db` returns a new handle to the same state.
 #[derive(Debug, Clone)]
 pub struct Db {
     shared: Arc<Shared>,
@@ -14,8 +39,24 @@ pub struct Db {
 struct Shared {
     /// Shared state guarded by a mutex.
     state: Mutex<State>,
+    /// Held by the thread running an exclusive section, see `Db::exclusive`. The state is only
+    /// locked along with it, so that no other thread accesses the state until the section ends.
+    section: ReentrantMutex<()>,
+    /// Signalled each time an exclusive section ends, see `Db::enter_section`. The receiver is
+    /// kept so that signalling never fails, and is cloned by the tasks waiting for a section to
+    /// end.
+    section_ended: (watch::Sender<()>, watch::Receiver<()>),
+    /// Lua interpreter running the scripts. Locked within exclusive sections only while a script
+    /// runs, and never while the state is locked.
+    scripts: Mutex<script::Scripts>,
     /// Notifies the background task handling entry expiration.
     background_task: Notify,
+    /// Broadcasts the keys of the streams entries are appended to, waking up blocked readers.
+    stream_appends: broadcast::Sender<String>,
+    /// Number of messages buffered for each pub/sub channel and pattern.
+    pubsub_capacity: usize,
+    /// How subscribers missing messages are dealt with.
+    lag_policy: LagPolicy,
 }
 
 #[derive(Debug)]
@@ -24,35 +65,227 @@ struct State {
     entries: HashMap<String, Entry>,
     /// The pub-sub key space.
     pub_sub: HashMap<String, broadcast::Sender<Bytes>>,
+    /// The pub-sub pattern space. Messages are sent along with the channel they were published
+    /// on.
+    pattern_sub: HashMap<String, broadcast::Sender<(String, Bytes)>>,
+    /// Keyspace notifications to publish.
+    keyspace_events: KeyspaceEvents,
     /// Tracks key TTLs.
     expirations: BTreeMap<(Instant, u64), String>,
-    /// Identifier to use for the next expiration.
+    /// Identifier to use for the next entry or blocked client.
     next_id: u64,
+    /// Number of times a stored value was modified, expired or deleted, see `State::touch`.
+    modifications: u64,
+    /// Keys watched with `WATCH`, see `Db::watch`. Unlike entries, they survive the deletion of
+    /// their key.
+    watched: HashMap<String, Watched>,
+    /// Clients blocked on list operations, by identifier.
+    waiters: HashMap<u64, list::Waiter>,
+    /// Identifiers of the clients blocked on each key, in the order they must be served.
+    blocked: HashMap<String, VecDeque<u64>>,
     /// True when the Db instance is shutting down.
     shutdown: bool,
 }
 
+/// The locked state, returned by `Shared::lock_state`.
+struct StateGuard<'a> {
+    state: MutexGuard<'a, State>,
+    /// Released after the state, once no other thread may access it in between.
+    _section: ReentrantMutexGuard<'a, ()>,
+}
+
+/// Receiver of the messages published on a pub/sub channel or pattern, returned by
+/// `Db::subscribe` and `Db::psubscribe`.
+///
+/// Dropping the last subscription to a channel or pattern removes its sender from the pub/sub
+/// key space, so that channels do not outlive their subscribers.
+#[derive(Debug)]
+pub struct Subscription<T> {
+    rx: broadcast::Receiver<T>,
+    db: Db,
+    key: SubscriptionKey,
+}
+
+/// How to deal with a subscriber lagging so far behind that the oldest messages buffered for it
+/// were dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LagPolicy {
+    /// Close the connection of the subscriber.
+    Disconnect,
+    /// Send a `lagged` frame with the number of skipped messages to the subscriber, then resume
+    /// with the oldest message still buffered.
+    Notify,
+    /// Resume with the oldest message still buffered without telling the subscriber.
+    #[default]
+    Drop,
+}
+
+/// Channel or pattern a `Subscription` receives the messages of.
+#[derive(Debug)]
+enum SubscriptionKey {
+    Channel(String),
+    Pattern(String),
+}
+
+/// Modifications of a key watched by at least one client.
+#[derive(Debug)]
+struct Watched {
+    /// Number of `WatchedKey` handles watching the key.
+    watchers: usize,
+    /// Number of times the value stored at the key was modified, expired or deleted since the
+    /// key is watched.
+    modifications: u64,
+}
+
+/// Exclusive section entered asynchronously, returned by `Db::enter_section`.
+///
+/// The section ends once the `Section` is dropped, which must happen before the task holding it
+/// awaits again.
+#[derive(Debug)]
+pub struct Section<'a> {
+    _guard: ReentrantMutexGuard<'a, ()>,
+}
+
+/// A key watched for modifications, returned by `Db::watch`.
+///
+/// The key stops being watched once the `WatchedKey` is dropped.
+#[derive(Debug)]
+pub struct WatchedKey {
+    db: Db,
+    key: String,
+    /// Modifications of the key when it started being watched.
+    modifications: u64,
+    /// Whether the key existed when it started being watched.
+    existed: bool,
+}
+
 #[derive(Debug)]
 struct Entry {
-    /// Unique identifier for this entry.
+    /// Unique identifier for this entry, distinguishing the expirations of entries expiring at
+    /// the same instant.
     id: u64,
     /// Stored data.
-    data: Bytes,
+    data: Value,
     /// Time to expire.
     expires_at: Option<Instant>,
 }
 
+/// Value stored at a key.
+#[derive(Debug, Clone)]
+enum Value {
+    /// A binary safe string.
+    String(Bytes),
+    /// A list of strings, sorted by insertion order.
+    List(VecDeque<Bytes>),
+    /// A map between string fields and string values.
+    Hash(HashMap<String, Bytes>),
+    /// An unordered collection of unique strings.
+    Set(HashSet<Bytes>),
+    /// A collection of unique strings ordered by score.
+    ZSet(zset::SortedSet),
+    /// An append-only log of entries made of field-value pairs.
+    Stream(stream::Stream),
+}
+
+/// Maximum length of a string value, matching the Redis limit of 512 MiB.
+const MAX_STRING_LEN: usize = 512 * 1024 * 1024;
+
+/// Condition under which `Db::set_if` writes a value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SetCondition {
+    /// Always write the value.
+    Always,
+    /// Only write the value if the key does not exist.
+    NotExists,
+    /// Only write the value if the key already exists.
+    Exists,
+}
+
+/// Expiration applied to an entry by `Db::set_if` and `Db::get_ex`.
+#[derive(Debug, Clone, Copy)]
+pub enum SetExpiration {
+    /// The value never expires.
+    Persist,
+    /// The value expires at the given instant.
+    At(Instant),
+    /// The entry keeps its current expiration.
+    Keep,
+}
+
+/// Error returned when an operation cannot be applied to the value stored at a key.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DbError {
+    /// The value is not an integer or out of the range of a signed 64 bit integer.
+    NotInteger,
+    /// The value is not a valid floating point number.
+    NotFloat,
+    /// The hash field value is not an integer.
+    HashNotInteger,
+    /// The integer operation would overflow.
+    Overflow,
+    /// The floating point operation would produce NaN or infinity.
+    NotFinite,
+    /// The resulting string would exceed the maximum string length.
+    TooLarge,
+    /// The key holds a value of a different type than the operation expects.
+    WrongType,
+    /// The key does not exist.
+    NoSuchKey,
+    /// The index is out of the range of the value.
+    OutOfRange,
+    /// The resulting sorted set score would be NaN.
+    ScoreNaN,
+    /// The ID of a new stream entry is 0-0.
+    StreamIdZero,
+    /// The ID of a new stream entry is not greater than the last ID of the stream.
+    StreamIdTooSmall,
+    /// The stream or its consumer group does not exist.
+    NoGroup,
+    /// The consumer group already exists.
+    BusyGroup,
+}
+
 impl Db {
-    pub(crate) fn new() -> Db {
+    /// Create a new `Db` with the given settings. Subscribers lagging behind the pub/sub
+    /// capacity miss the oldest messages and are dealt with according to the lag policy.
+    ///
+    /// The task purging expired keys is spawned on the current Tokio runtime. It stops once the
+    /// last handle to the `Db` is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pub/sub capacity is zero, or if called outside of a Tokio runtime.
+    pub fn new(config: DbConfig) -> Db {
+        let DbConfig {
+            pubsub_capacity,
+            lag_policy,
+            keyspace_events,
+            script_time_limit,
+        } = config;
+
+        assert!(pubsub_capacity > 0, "pub/sub capacity must be positive");
+
         let shared = Arc::new(Shared {
             state: Mutex::new(State {
                 entries: HashMap::new(),
                 pub_sub: HashMap::new(),
+                pattern_sub: HashMap::new(),
+                keyspace_events,
                 expirations: BTreeMap::new(),
                 next_id: 0,
+                modifications: 0,
+                watched: HashMap::new(),
+                waiters: HashMap::new(),
+                blocked: HashMap::new(),
                 shutdown: false,
             }),
+            section: ReentrantMutex::new(()),
+            section_ended: watch::channel(()),
+            scripts: Mutex::new(script::Scripts::new(script_time_limit)),
             background_task: Notify::new(),
+            stream_appends: broadcast::channel(1024).0,
+            pubsub_capacity,
+            lag_policy,
         });
 
         // start the background task.
@@ -61,89 +294,632 @@ impl Db {
         Db { shared }
     }
 
-    pub(crate) fn get(&self, key: &str) -> Option<Bytes> {
-        let state = self.shared.state.lock().unwrap();
-        state.entries.get(key).map(|entry| entry.data.clone())
+    /// Returns the string stored at `key`, or `None` if the key does not exist or holds another
+    /// type of value, which `try_get` tells apart.
+    pub fn get(&self, key: &str) -> Option<Bytes> {
+        self.try_get(key).ok().flatten()
     }
 
-    pub(crate) fn set(&self, key: String, value: Bytes, expire: Option<Duration>) {
-        let mut state = self.shared.state.lock().unwrap();
+    /// Returns the string stored at `key`, or `None` if the key does not exist.
+    pub fn try_get(&self, key: &str) -> Result<Option<Bytes>, DbError> {
+        let state = self.shared.lock_state();
+        Ok(state.get_string(key)?.cloned())
+    }
 
-        let id = state.next_id;
-        state.next_id += 1;
+    /// Stores `value` at `key`, overwriting any previous value regardless of its type. The key
+    /// expires after `expire` if given.
+    pub fn set(&self, key: String, value: Bytes, expire: Option<Duration>) {
+        // An expiration too far in the future to be represented is the same as no expiration.
+        let expiration = match expire.and_then(|duration| Instant::now().checked_add(duration)) {
+            Some(when) => SetExpiration::At(when),
+            None => SetExpiration::Persist,
+        };
 
-        let mut notify = false;
+        // Only fails when asked for the previous value.
+        let _ = self.set_if(key, value, expiration, SetCondition::Always, false);
+    }
 
-        let expires_at = expire.map(|duration| {
-            let when = Instant::now() + duration;
+    /// Stores `value` at `key` if `condition` holds for the current entry. The condition is
+    /// checked and the value written while holding the lock, so concurrent writers cannot
+    /// interleave.
+    ///
+    /// Any previous value is overwritten regardless of its type. If `get` is set, the previous
+    /// value is returned and must be a string, otherwise `DbError::WrongType` is returned and
+    /// nothing is written.
+    ///
+    /// Returns whether the value was written, along with the previous value if `get` is set.
+    pub fn set_if(
+        &self,
+        key: String,
+        value: Bytes,
+        expiration: SetExpiration,
+        condition: SetCondition,
+        get: bool,
+    ) -> Result<(bool, Option<Bytes>), DbError> {
+        let mut state = self.shared.lock_state();
 
-            notify = state
-                .next_expiration()
-                .map(|expiration| expiration > when)
-                .unwrap_or(true);
+        let prev_value = if get {
+            state.get_string(&key)?.cloned()
+        } else {
+            None
+        };
+        let prev = state.entries.get(&key);
 
-            // track the expiration.
-            state.expirations.insert((when, id), key.clone());
-            when
-        });
+        let write = match condition {
+            SetCondition::Always => true,
+            SetCondition::NotExists => prev.is_none(),
+            SetCondition::Exists => prev.is_some(),
+        };
 
-        // insert the entry into the hashmap.
-        let prev = state.entries.insert(
-            key,
-            Entry {
-                id,
-                data: value,
-                expires_at,
-            },
-        );
+        if !write {
+            return Ok((false, prev_value));
+        }
 
-        if let Some(prev) = prev {
-            if let Some(when) = prev.expires_at {
-                // clear expiration
-                state.expirations.remove(&(when, prev.id));
+        let expires_at = match expiration {
+            SetExpiration::Persist => None,
+            SetExpiration::At(when) => Some(when),
+            SetExpiration::Keep => prev.and_then(|entry| entry.expires_at),
+        };
+
+        // A deadline that already passed behaves as if the key was written and then expired
+        // right away.
+        if expires_at.is_some_and(|when| when <= Instant::now()) {
+            state.remove(&key);
+            state.touch(&key);
+            state.notify_keyspace_event(KeyspaceEvent::Set, &key);
+            state.notify_keyspace_event(KeyspaceEvent::Expired, &key);
+            return Ok((true, prev_value));
+        }
+
+        state.touch(&key);
+        state.notify_keyspace_event(KeyspaceEvent::Set, &key);
+        if let SetExpiration::At(_) = expiration {
+            state.notify_keyspace_event(KeyspaceEvent::Expire, &key);
+        }
+        let notify = state.insert(key, Value::String(value), expires_at);
+
+        drop(state);
+
+        if notify {
+            self.shared.background_task.notify_one();
+        }
+
+        Ok((true, prev_value))
+    }
+
+    /// Removes the given keys. Returns the number of keys that were removed.
+    pub fn del(&self, keys: &[String]) -> usize {
+        let mut state = self.shared.lock_state();
+
+        let mut removed = 0;
+        for key in keys {
+            if state.remove(key).is_some() {
+                state.touch(key);
+                state.notify_keyspace_event(KeyspaceEvent::Del, key);
+                removed += 1;
+            }
+        }
+
+        removed
+    }
+
+    /// Returns the number of the given keys that exist. A key mentioned multiple times is
+    /// counted multiple times.
+    pub fn exists(&self, keys: &[String]) -> usize {
+        let state = self.shared.lock_state();
+
+        keys.iter()
+            .filter(|key| state.entries.contains_key(key.as_str()))
+            .count()
+    }
+
+    /// Returns the name of the type of the value stored at `key`, or `None` if the key does not
+    /// exist.
+    pub fn key_type(&self, key: &str) -> Option<&'static str> {
+        let state = self.shared.lock_state();
+        state.entries.get(key).map(|entry| entry.data.type_name())
+    }
+
+    /// Renames `src` to `dst`, overwriting `dst` if it already exists unless `nx` is set. The
+    /// time to live of `src` is carried over to `dst`.
+    ///
+    /// Returns `None` if `src` does not exist, otherwise whether the key was renamed.
+    pub fn rename(&self, src: &str, dst: String, nx: bool) -> Option<bool> {
+        let mut state = self.shared.lock_state();
+
+        if !state.entries.contains_key(src) {
+            return None;
+        }
+
+        if nx && state.entries.contains_key(&dst) {
+            return Some(false);
+        }
+
+        let entry = state.remove(src)?;
+        let notify = state.insert(dst.clone(), entry.data, entry.expires_at);
+        state.touch(src);
+        state.touch(&dst);
+        state.notify_keyspace_event(KeyspaceEvent::RenameFrom, src);
+        state.notify_keyspace_event(KeyspaceEvent::RenameTo, &dst);
+        state.serve_blocked(&dst);
+
+        drop(state);
+
+        if notify {
+            self.shared.background_task.notify_one();
+        }
+
+        Some(true)
+    }
+
+    /// Copies the value and time to live of `src` to `dst`. An existing `dst` is only
+    /// overwritten if `replace` is set.
+    ///
+    /// Returns `true` if the value was copied.
+    pub fn copy(&self, src: &str, dst: String, replace: bool) -> bool {
+        let mut state = self.shared.lock_state();
+
+        let (data, expires_at) = match state.entries.get(src) {
+            Some(entry) => (entry.data.clone(), entry.expires_at),
+            None => return false,
+        };
+
+        if !replace && state.entries.contains_key(&dst) {
+            return false;
+        }
+
+        let notify = state.insert(dst.clone(), data, expires_at);
+        state.touch(&dst);
+        state.notify_keyspace_event(KeyspaceEvent::CopyTo, &dst);
+        state.serve_blocked(&dst);
+
+        drop(state);
+
+        if notify {
+            self.shared.background_task.notify_one();
+        }
+
+        true
+    }
+
+    /// Sets the expiration of the existing `key` to `when`, replacing any previous expiration.
+    /// A deadline that is not in the future removes the key right away.
+    ///
+    /// Returns `false` if the key does not exist.
+    pub fn expire(&self, key: &str, when: Instant) -> bool {
+        let mut state = self.shared.lock_state();
+
+        if !state.entries.contains_key(key) {
+            return false;
+        }
+
+        if when <= Instant::now() {
+            state.remove(key);
+            state.touch(key);
+            state.notify_keyspace_event(KeyspaceEvent::Del, key);
+            return true;
+        }
+
+        let notify = state.set_expiration(key, Some(when));
+        state.touch(key);
+        state.notify_keyspace_event(KeyspaceEvent::Expire, key);
+
+        drop(state);
+
+        if notify {
+            self.shared.background_task.notify_one();
+        }
+
+        true
+    }
+
+    /// Removes the expiration of `key` so that it is kept until it is deleted.
+    ///
+    /// Returns `false` if the key does not exist or has no expiration.
+    pub fn persist(&self, key: &str) -> bool {
+        let mut state = self.shared.lock_state();
+
+        match state.entries.get(key) {
+            Some(entry) if entry.expires_at.is_some() => {
+                state.set_expiration(key, None);
+                state.touch(key);
+                state.notify_keyspace_event(KeyspaceEvent::Persist, key);
+                true
             }
+            _ => false,
+        }
+    }
+
+    /// Returns the remaining time to live of `key`.
+    ///
+    /// The outer `Option` is `None` if the key does not exist, the inner one is `None` if the
+    /// key has no expiration.
+    pub fn ttl(&self, key: &str) -> Option<Option<Duration>> {
+        let state = self.shared.lock_state();
+        let now = Instant::now();
+
+        state.entries.get(key).map(|entry| {
+            entry
+                .expires_at
+                .map(|when| when.saturating_duration_since(now))
+        })
+    }
+
+    /// Adds `delta` to the integer stored at `key` and returns the new value. A missing key is
+    /// treated as `0`. The time to live of the key is retained.
+    pub fn incr_by(&self, key: &str, delta: i64) -> Result<i64, DbError> {
+        let mut state = self.shared.lock_state();
+
+        let current = match state.get_string(key)? {
+            Some(data) => parse_int(data).ok_or(DbError::NotInteger)?,
+            None => 0,
+        };
+
+        let value = current.checked_add(delta).ok_or(DbError::Overflow)?;
+        state.update(key, Value::String(Bytes::from(value.to_string())));
+        state.touch(key);
+        state.notify_keyspace_event(KeyspaceEvent::IncrBy, key);
+
+        Ok(value)
+    }
+
+    /// Adds `delta` to the floating point number stored at `key` and returns the new value. A
+    /// missing key is treated as `0`. The time to live of the key is retained.
+    pub fn incr_by_float(&self, key: &str, delta: f64) -> Result<f64, DbError> {
+        let mut state = self.shared.lock_state();
+
+        let current = match state.get_string(key)? {
+            Some(data) => parse_float(data).ok_or(DbError::NotFloat)?,
+            None => 0.0,
+        };
+
+        let value = current + delta;
+        if !value.is_finite() {
+            return Err(DbError::NotFinite);
+        }
+
+        state.update(key, Value::String(Bytes::from(value.to_string())));
+        state.touch(key);
+        state.notify_keyspace_event(KeyspaceEvent::IncrByFloat, key);
+
+        Ok(value)
+    }
+
+    /// Appends `value` to the string stored at `key`, creating the key if it does not exist.
+    /// Returns the length of the string after the append.
+    pub fn append(&self, key: &str, value: &[u8]) -> Result<usize, DbError> {
+        let mut state = self.shared.lock_state();
+
+        let mut data = match state.get_string(key)? {
+            Some(data) => BytesMut::from(&data[..]),
+            None => BytesMut::new(),
+        };
+
+        if data.len() + value.len() > MAX_STRING_LEN {
+            return Err(DbError::TooLarge);
+        }
+
+        data.extend_from_slice(value);
+        let len = data.len();
+        state.update(key, Value::String(data.freeze()));
+        state.touch(key);
+        state.notify_keyspace_event(KeyspaceEvent::Append, key);
+
+        Ok(len)
+    }
+
+    /// Returns the length of the string stored at `key`, or `0` if the key does not exist.
+    pub fn strlen(&self, key: &str) -> Result<usize, DbError> {
+        let state = self.shared.lock_state();
+        Ok(state.get_string(key)?.map_or(0, |data| data.len()))
+    }
+
+    /// Returns the substring of the string stored at `key` between the offsets `start` and `end`
+    /// (both inclusive). Negative offsets count from the end of the string.
+    pub fn get_range(&self, key: &str, start: i64, end: i64) -> Result<Bytes, DbError> {
+        let state = self.shared.lock_state();
+
+        let data = match state.get_string(key)? {
+            Some(data) => data,
+            None => return Ok(Bytes::new()),
+        };
+
+        match range_indices(data.len() as i64, start, end) {
+            Some((start, end)) => Ok(data.slice(start..=end)),
+            None => Ok(Bytes::new()),
+        }
+    }
+
+    /// Overwrites part of the string stored at `key`, starting at `offset`. The string is padded
+    /// with zero bytes if it is shorter than `offset`. Returns the length of the string after the
+    /// modification.
+    pub fn set_range(&self, key: &str, offset: usize, value: &[u8]) -> Result<usize, DbError> {
+        let mut state = self.shared.lock_state();
+
+        let current = state.get_string(key)?;
+
+        // An empty value does not create or modify the key.
+        if value.is_empty() {
+            return Ok(current.map_or(0, |data| data.len()));
+        }
+
+        let end = match offset.checked_add(value.len()) {
+            Some(end) if end <= MAX_STRING_LEN => end,
+            _ => return Err(DbError::TooLarge),
+        };
+
+        let mut data = match current {
+            Some(data) => BytesMut::from(&data[..]),
+            None => BytesMut::new(),
+        };
+
+        if data.len() < end {
+            data.resize(end, 0);
+        }
+        data[offset..end].copy_from_slice(value);
+
+        let len = data.len();
+        state.update(key, Value::String(data.freeze()));
+        state.touch(key);
+        state.notify_keyspace_event(KeyspaceEvent::SetRange, key);
+
+        Ok(len)
+    }
+
+    /// Removes `key` and returns the string it held.
+    pub fn get_del(&self, key: &str) -> Result<Option<Bytes>, DbError> {
+        let mut state = self.shared.lock_state();
+
+        let value = state.get_string(key)?.cloned();
+        if state.remove(key).is_some() {
+            state.touch(key);
+            state.notify_keyspace_event(KeyspaceEvent::Del, key);
         }
 
+        Ok(value)
+    }
+
+    /// Returns the string stored at `key` and updates its expiration.
+    pub fn get_ex(&self, key: &str, expiration: SetExpiration) -> Result<Option<Bytes>, DbError> {
+        let mut state = self.shared.lock_state();
+
+        let value = match state.get_string(key)? {
+            Some(value) => value.clone(),
+            None => return Ok(None),
+        };
+
+        let notify = match expiration {
+            SetExpiration::Keep => false,
+            SetExpiration::Persist => {
+                // Only a key with an expiration is modified.
+                if state.entries[key].expires_at.is_some() {
+                    state.set_expiration(key, None);
+                    state.touch(key);
+                    state.notify_keyspace_event(KeyspaceEvent::Persist, key);
+                }
+                false
+            }
+            SetExpiration::At(when) if when <= Instant::now() => {
+                state.remove(key);
+                state.touch(key);
+                state.notify_keyspace_event(KeyspaceEvent::Del, key);
+                false
+            }
+            SetExpiration::At(when) => {
+                state.touch(key);
+                state.notify_keyspace_event(KeyspaceEvent::Expire, key);
+                state.set_expiration(key, Some(when))
+            }
+        };
+
         drop(state);
 
         if notify {
             self.shared.background_task.notify_one();
         }
+
+        Ok(Some(value))
+    }
+
+    /// Returns the values of all the given keys, reading them under a single lock acquisition.
+    /// Keys that do not hold a string are reported as missing.
+    pub fn mget(&self, keys: &[String]) -> Vec<Option<Bytes>> {
+        let state = self.shared.lock_state();
+
+        keys.iter()
+            .map(|key| state.get_string(key).ok().flatten().cloned())
+            .collect()
+    }
+
+    /// Stores all the given key-value pairs at once, discarding the expiration of existing keys.
+    /// If `nx` is set, nothing is written if any of the keys already exists.
+    ///
+    /// Returns `true` if the values were written.
+    pub fn mset(&self, pairs: Vec<(String, Bytes)>, nx: bool) -> bool {
+        let mut state = self.shared.lock_state();
+
+        if nx && pairs.iter().any(|(key, _)| state.entries.contains_key(key)) {
+            return false;
+        }
+
+        for (key, value) in pairs {
+            state.touch(&key);
+            state.notify_keyspace_event(KeyspaceEvent::Set, &key);
+            state.insert(key, Value::String(value), None);
+        }
+
+        true
     }
 
     /// Publish a message to the channel. Returns the number of subscribers listening on the
-    /// channel.
-    pub(crate) fn publish(&self, key: &str, value: Bytes) -> usize {
-        let state = self.shared.state.lock().unwrap();
+    /// channel, including the subscribers to the patterns matching the channel.
+    pub fn publish(&self, key: &str, value: Bytes) -> usize {
+        let state = self.shared.lock_state();
+        state.publish(key, value)
+    }
 
-        state
-            .pub_sub
-            .get(key)
-            .map(
-                // `0` here indicates there are no receivers.
-                |tx| tx.send(value).unwrap_or(0),
-            )
-            // `0` here indicates there are no subscribers.
-            .unwrap_or(0)
+    /// Starts watching `key`, so that the returned `WatchedKey` tells whether the value stored
+    /// at `key` was modified, expired or deleted since. `EXEC` relies on it to abort
+    /// transactions on `WATCH`ed keys.
+    ///
+    /// To apply operations only if the watched keys were not modified, check them within the
+    /// same `exclusive` section as the operations.
+    pub fn watch(&self, key: String) -> WatchedKey {
+        let mut state = self.shared.lock_state();
+
+        let existed = state.is_live(&key);
+        let watched = state.watched.entry(key.clone()).or_insert(Watched {
+            watchers: 0,
+            modifications: 0,
+        });
+        watched.watchers += 1;
+
+        WatchedKey {
+            db: self.clone(),
+            modifications: watched.modifications,
+            key,
+            existed,
+        }
+    }
+
+    /// Runs `f` without any other client accessing the state in between, so that the operations
+    /// `f` applies to this `Db` take place atomically. Exclusive sections may be nested.
+    ///
+    /// Every other thread accessing this `Db` is blocked until `f` returns, while the clients
+    /// served by the server wait for the section to end without blocking the Tokio workers.
+    /// `f` must not wait for other tasks or threads using this `Db`, which would deadlock. From
+    /// async code, call `exclusive` from `tokio::task::spawn_blocking`, as `EXEC` does, rather
+    /// than from the task itself.
+    pub fn exclusive<R>(&self, f: impl FnOnce() -> R) -> R {
+        /// Wakes up the tasks waiting for the section to end, even if `f` panics.
+        struct Ended<'a>(&'a Shared);
+
+        impl Drop for Ended<'_> {
+            fn drop(&mut self) {
+                let _ = self.0.section_ended.0.send(());
+            }
+        }
+
+        // Declared first so that it is dropped after the section is released.
+        let _ended = Ended(&self.shared);
+        let _section = self.shared.section.lock();
+
+        f()
+    }
+
+    /// Enters an exclusive section, waiting asynchronously for the one run by another thread to
+    /// end, if any.
+    ///
+    /// Tasks call it before using the `Db`, so that they do not block the thread they run on
+    /// while a section lasts. The returned `Section` must be dropped before the task awaits
+    /// again, as other threads would otherwise be blocked meanwhile.
+    pub async fn enter_section(&self) -> Section<'_> {
+        Section {
+            _guard: self.shared.enter_section().await,
+        }
+    }
+
+    /// Returns how subscribers missing messages are dealt with.
+    pub(crate) fn lag_policy(&self) -> LagPolicy {
+        self.shared.lag_policy
     }
 
-    /// Returns a `Receiver` for the requested channel.
-    pub(crate) fn subscribe(&self, key: String) -> broadcast::Receiver<Bytes> {
+    /// Returns a `Subscription` to the requested channel.
+    pub fn subscribe(&self, key: String) -> Subscription<Bytes> {
         use std::collections::hash_map::Entry;
 
-        let mut state = self.shared.state.lock().unwrap();
+        let mut state = self.shared.lock_state();
 
         // If there is no entry for the requrest channel, then create a new broadcast channel and
         // associate it with the key. If one already exists, return an associated receiver.
-        match state.pub_sub.entry(key) {
+        let rx = match state.pub_sub.entry(key.clone()) {
+            Entry::Occupied(e) => e.get().subscribe(),
+            Entry::Vacant(e) => {
+                let (tx, rx) = broadcast::channel(self.shared.pubsub_capacity);
+                e.insert(tx);
+                rx
+            }
+        };
+
+        Subscription {
+            rx,
+            db: self.clone(),
+            key: SubscriptionKey::Channel(key),
+        }
+    }
+
+    /// Returns a `Subscription` to the messages published on the channels matching the
+    /// requested glob-style pattern, along with the channel they were published on.
+    pub fn psubscribe(&self, pattern: String) -> Subscription<(String, Bytes)> {
+        use std::collections::hash_map::Entry;
+
+        let mut state = self.shared.lock_state();
+
+        let rx = match state.pattern_sub.entry(pattern.clone()) {
             Entry::Occupied(e) => e.get().subscribe(),
             Entry::Vacant(e) => {
-                let (tx, rx) = broadcast::channel(1024);
+                let (tx, rx) = broadcast::channel(self.shared.pubsub_capacity);
                 e.insert(tx);
                 rx
             }
+        };
+
+        Subscription {
+            rx,
+            db: self.clone(),
+            key: SubscriptionKey::Pattern(pattern),
         }
     }
+
+    /// Returns the channels with at least one subscriber, only the ones matching the glob-style
+    /// `pattern` if given, in lexicographic order. Channels are removed along with their last
+    /// subscriber.
+    pub fn pubsub_channels(&self, pattern: Option<&str>) -> Vec<String> {
+        let state = self.shared.lock_state();
+
+        let mut channels: Vec<String> = state
+            .pub_sub
+            .keys()
+            .filter(|channel| {
+                pattern.is_none_or(|pattern| glob::matches(pattern.as_bytes(), channel.as_bytes()))
+            })
+            .cloned()
+            .collect();
+        channels.sort();
+
+        channels
+    }
+
+    /// Returns the number of subscribers of each of `channels`, not counting the subscribers to
+    /// patterns.
+    pub fn pubsub_numsub(&self, channels: &[String]) -> Vec<usize> {
+        let state = self.shared.lock_state();
+
+        channels
+            .iter()
+            .map(|channel| {
+                state
+                    .pub_sub
+                    .get(channel)
+                    .map_or(0, |tx| tx.receiver_count())
+            })
+            .collect()
+    }
+
+    /// Returns the number of patterns with at least one subscriber.
+    pub fn pubsub_numpat(&self) -> usize {
+        let state = self.shared.lock_state();
+
+        // Patterns are removed along with their last subscriber.
+        state.pattern_sub.len()
+    }
+}
+
+impl Default for Db {
+    /// Create a new `Db` with the default settings, as `Db::new` does.
+    fn default() -> Db {
+        Db::new(DbConfig::default())
+    }
 }
 
 impl Drop for Db {
@@ -151,6 +927,7 @@ impl Drop for Db {
         // If this is the last active `Db` instance, the background task must be
         // notified to shut down.
         if Arc::strong_count(&self.shared) == 2 {
+            // Only read by the background task, no need to wait for exclusive sections.
             let mut state = self.shared.state.lock().unwrap();
             state.shutdown = true;
 
@@ -160,39 +937,314 @@ impl Drop for Db {
     }
 }
 
-impl Shared {
-    fn purge_expired_keys(&self) -> Option<Instant> {
-        let mut state = self.state.lock().unwrap();
+impl std::str::FromStr for LagPolicy {
+    type Err = String;
 
-        if state.shutdown {
-            return None;
+    fn from_str(s: &str) -> Result<LagPolicy, String> {
+        match &s.to_lowercase()[..] {
+            "disconnect" => Ok(LagPolicy::Disconnect),
+            "notify" => Ok(LagPolicy::Notify),
+            "drop" => Ok(LagPolicy::Drop),
+            _ => Err(format!(
+                "invalid lag policy `{}`, expected disconnect, notify or drop",
+                s
+            )),
         }
+    }
+}
 
-        let state = &mut *state;
-
-        // find all key scheduled to expire before now.
-        let now = Instant::now();
+impl<T: Clone> Subscription<T> {
+    /// Receives the next message, as `broadcast::Receiver::recv` does. `RecvError::Lagged` is
+    /// returned when the subscriber fell so far behind that the oldest messages buffered for it
+    /// were dropped.
+    pub async fn recv(&mut self) -> Result<T, broadcast::error::RecvError> {
+        self.rx.recv().await
+    }
+}
 
-        while let Some((&(when, id), key)) = state.expirations.iter().next() {
-            if when > now {
-                // done purging.
-                return Some(when);
-            }
+impl<T> Drop for Subscription<T> {
+    fn drop(&mut self) {
+        // Releasing the sender does not need to wait for exclusive sections, which may not
+        // observe it anyway, so that subscriptions may be dropped from async code.
+        let mut state = self.db.shared.state.lock().unwrap();
 
-            // the key expired, remove it.
-            state.entries.remove(key);
-            state.expirations.remove(&(when, id));
+        match &self.key {
+            SubscriptionKey::Channel(channel) => release_sender(&mut state.pub_sub, channel),
+            SubscriptionKey::Pattern(pattern) => release_sender(&mut state.pattern_sub, pattern),
         }
-
-        None
     }
+}
+
+impl WatchedKey {
+    /// Returns `true` if the value stored at the key was modified, expired or deleted since the
+    /// key started being watched. A key which did not exist is only modified once it is created.
+    pub fn is_modified(&self) -> bool {
+        let state = self.db.shared.lock_state();
 
-    fn is_shutdown(&self) -> bool {
-        self.state.lock().unwrap().shutdown
+        // The key is watched until `self` is dropped, so it is always found.
+        let watched = &state.watched[&self.key];
+
+        // Keys which expired may not have been purged yet.
+        watched.modifications != self.modifications || (self.existed && !state.is_live(&self.key))
     }
 }
 
-impl State {
+impl Drop for WatchedKey {
+    fn drop(&mut self) {
+        // As for subscriptions, the watch count is released without waiting for exclusive
+        // sections. The sections checking the key hold it, so it stays watched meanwhile.
+        let mut state = self.db.shared.state.lock().unwrap();
+
+        if let Some(watched) = state.watched.get_mut(&self.key) {
+            watched.watchers -= 1;
+            if watched.watchers == 0 {
+                state.watched.remove(&self.key);
+            }
+        }
+    }
+}
+
+/// Removes the sender stored at `key` if the receiver of the subscription being dropped is the
+/// only one left.
+///
+/// The receiver is only dropped after `Subscription::drop` returns, so it is still counted. The
+/// state lock prevents any receiver from being created in between.
+fn release_sender<T>(senders: &mut HashMap<String, broadcast::Sender<T>>, key: &str) {
+    if senders.get(key).is_some_and(|tx| tx.receiver_count() <= 1) {
+        senders.remove(key);
+    }
+}
+
+impl Shared {
+    /// Locks the state, waiting for the exclusive section run by another thread to end, if any.
+    fn lock_state(&self) -> StateGuard<'_> {
+        let section = self.section.lock();
+
+        StateGuard {
+            state: self.state.lock().unwrap(),
+            _section: section,
+        }
+    }
+
+    /// Enters an exclusive section as `Db::enter_section` does.
+    async fn enter_section(&self) -> ReentrantMutexGuard<'_, ()> {
+        // Cloned before trying, so that a section ending in between is not missed.
+        let mut ended = self.section_ended.1.clone();
+
+        loop {
+            if let Some(section) = self.section.try_lock() {
+                return section;
+            }
+
+            // The sender is kept alongside, so this only returns once a section ended.
+            let _ = ended.changed().await;
+        }
+    }
+}
+
+impl Deref for StateGuard<'_> {
+    type Target = State;
+
+    fn deref(&self) -> &State {
+        &self.state
+    }
+}
+
+impl DerefMut for StateGuard<'_> {
+    fn deref_mut(&mut self) -> &mut State {
+        &mut self.state
+    }
+}
+
+impl State {
+    /// Removes the expired keys. Returns the instant the next key expires at, if any.
+    fn purge_expired_keys(&mut self) -> Option<Instant> {
+        // find all key scheduled to expire before now.
+        let now = Instant::now();
+
+        while let Some((&(when, id), key)) = self.expirations.iter().next() {
+            if when > now {
+                // done purging.
+                return Some(when);
+            }
+
+            // the key expired, remove it.
+            let key = key.clone();
+            self.entries.remove(&key);
+            self.expirations.remove(&(when, id));
+            self.touch(&key);
+            self.notify_keyspace_event(KeyspaceEvent::Expired, &key);
+        }
+
+        None
+    }
+
+    /// Publish a message to the channel, as `Db::publish` does, with the state already locked.
+    fn publish(&self, key: &str, value: Bytes) -> usize {
+        let channel_subscribers = self
+            .pub_sub
+            .get(key)
+            .map(
+                // `0` here indicates there are no receivers.
+                |tx| tx.send(value.clone()).unwrap_or(0),
+            )
+            // `0` here indicates there are no subscribers.
+            .unwrap_or(0);
+
+        let pattern_subscribers: usize = self
+            .pattern_sub
+            .iter()
+            .filter(|(pattern, _)| glob::matches(pattern.as_bytes(), key.as_bytes()))
+            .map(|(_, tx)| tx.send((key.to_string(), value.clone())).unwrap_or(0))
+            .sum();
+
+        channel_subscribers + pattern_subscribers
+    }
+
+    /// Stores `data` at `key`, replacing any previous entry and its expiration.
+    ///
+    /// Returns `true` if the new entry expires before any other key, in which case the
+    /// background task must be notified.
+    fn insert(&mut self, key: String, data: Value, expires_at: Option<Instant>) -> bool {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let mut notify = false;
+
+        if let Some(when) = expires_at {
+            notify = self
+                .next_expiration()
+                .map(|expiration| expiration > when)
+                .unwrap_or(true);
+
+            // track the expiration.
+            self.expirations.insert((when, id), key.clone());
+        }
+
+        // insert the entry into the hashmap.
+        let prev = self.entries.insert(
+            key,
+            Entry {
+                id,
+                data,
+                expires_at,
+            },
+        );
+
+        if let Some(prev) = prev {
+            if let Some(when) = prev.expires_at {
+                // clear expiration
+                self.expirations.remove(&(when, prev.id));
+            }
+        }
+
+        notify
+    }
+
+    /// Replaces the data of the entry stored at `key`, keeping its expiration. The entry is
+    /// created without expiration if it does not exist.
+    fn update(&mut self, key: &str, data: Value) {
+        match self.entries.get_mut(key) {
+            Some(entry) => entry.data = data,
+            None => {
+                self.insert(key.to_string(), data, None);
+            }
+        }
+    }
+
+    /// Replaces the expiration of the entry stored at `key`.
+    ///
+    /// Returns `true` if the new expiration is earlier than any other, in which case the
+    /// background task must be notified.
+    fn set_expiration(&mut self, key: &str, expires_at: Option<Instant>) -> bool {
+        let notify = expires_at.is_some_and(|when| {
+            self.next_expiration()
+                .map(|expiration| expiration > when)
+                .unwrap_or(true)
+        });
+
+        let entry = match self.entries.get_mut(key) {
+            Some(entry) => entry,
+            None => return false,
+        };
+
+        if let Some(prev) = entry.expires_at.take() {
+            self.expirations.remove(&(prev, entry.id));
+        }
+
+        if let Some(when) = expires_at {
+            entry.expires_at = Some(when);
+            self.expirations.insert((when, entry.id), key.to_string());
+        }
+
+        notify
+    }
+
+    /// Records that the value stored at `key` was modified, expired or deleted, so that the
+    /// clients watching the key, and the script running, notice it. Must only be called once the
+    /// value actually changed.
+    fn touch(&mut self, key: &str) {
+        self.modifications += 1;
+        if let Some(watched) = self.watched.get_mut(key) {
+            watched.modifications += 1;
+        }
+    }
+
+    /// Returns `true` if the entry stored at `key` exists and has not expired, even if it was
+    /// not purged yet.
+    fn is_live(&self, key: &str) -> bool {
+        let now = Instant::now();
+
+        self.entries
+            .get(key)
+            .is_some_and(|entry| entry.expires_at.is_none_or(|when| when > now))
+    }
+
+    /// Returns the string stored at `key`, or `None` if the key does not exist.
+    fn get_string(&self, key: &str) -> Result<Option<&Bytes>, DbError> {
+        match self.entries.get(key).map(|entry| &entry.data) {
+            Some(Value::String(data)) => Ok(Some(data)),
+            Some(_) => Err(DbError::WrongType),
+            None => Ok(None),
+        }
+    }
+
+    /// Removes the entry stored at `key` if it holds an empty collection. Redis never keeps
+    /// empty lists and other aggregate values around.
+    fn remove_if_empty(&mut self, key: &str) {
+        if self
+            .entries
+            .get(key)
+            .is_some_and(|entry| entry.data.is_empty())
+        {
+            self.remove(key);
+        }
+    }
+
+    /// Removes the entry stored at `key` if removing elements left it empty, notifying its
+    /// deletion. Called once the removal itself was notified.
+    fn remove_if_drained(&mut self, key: &str) {
+        if self
+            .entries
+            .get(key)
+            .is_some_and(|entry| entry.data.is_empty())
+        {
+            self.remove(key);
+            self.notify_keyspace_event(KeyspaceEvent::Del, key);
+        }
+    }
+
+    /// Removes the entry stored at `key` along with its expiration.
+    fn remove(&mut self, key: &str) -> Option<Entry> {
+        let entry = self.entries.remove(key)?;
+
+        if let Some(when) = entry.expires_at {
+            self.expirations.remove(&(when, entry.id));
+        }
+
+        Some(entry)
+    }
+
     fn next_expiration(&self) -> Option<Instant> {
         self.expirations
             .keys()
@@ -201,17 +1253,790 @@ impl State {
     }
 }
 
+impl Value {
+    /// Returns the name of the value's type, as reported by the TYPE command.
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::List(_) => "list",
+            Value::Hash(_) => "hash",
+            Value::Set(_) => "set",
+            Value::ZSet(_) => "zset",
+            Value::Stream(_) => "stream",
+        }
+    }
+
+    /// Returns `true` if the value is an empty collection. Strings are never considered empty.
+    fn is_empty(&self) -> bool {
+        match self {
+            Value::String(_) => false,
+            Value::List(list) => list.is_empty(),
+            Value::Hash(hash) => hash.is_empty(),
+            Value::Set(set) => set.is_empty(),
+            Value::ZSet(zset) => zset.is_empty(),
+            // Streams are kept even once all their entries are removed.
+            Value::Stream(_) => false,
+        }
+    }
+}
+
+/// Converts the inclusive range `start..=end` into indices of a sequence of length `len`.
+/// Negative offsets count from the end of the sequence and out of range offsets are clamped.
+///
+/// Returns `None` if the range is empty.
+fn range_indices(len: i64, start: i64, end: i64) -> Option<(usize, usize)> {
+    let start = if start < 0 { len + start } else { start }.max(0);
+    let end = if end < 0 { len + end } else { end }.min(len - 1);
+
+    if start > end || len == 0 {
+        return None;
+    }
+
+    Some((start as usize, end as usize))
+}
+
+/// Parses a stored value or an argument as a signed 64 bit integer. As in Redis, only an optional
+/// `-` followed by digits without leading zeros is accepted.
+pub(crate) fn parse_int(data: &[u8]) -> Option<i64> {
+    let digits = data.strip_prefix(b"-").unwrap_or(data);
+
+    let canonical = match digits {
+        // `-0` is not canonical.
+        [b'0'] => digits.len() == data.len(),
+        [b'1'..=b'9', rest @ ..] => rest.iter().all(u8::is_ascii_digit),
+        _ => false,
+    };
+
+    if !canonical {
+        return None;
+    }
+
+    std::str::from_utf8(data).ok()?.parse().ok()
+}
+
+/// Parses a stored value as a finite floating point number.
+fn parse_float(data: &[u8]) -> Option<f64> {
+    let value: f64 = std::str::from_utf8(data).ok()?.parse().ok()?;
+    Some(value).filter(|value| value.is_finite())
+}
+
+impl std::error::Error for DbError {}
+
+impl fmt::Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DbError::NotInteger => "ERR value is not an integer or out of range".fmt(f),
+            DbError::NotFloat => "ERR value is not a valid float".fmt(f),
+            DbError::HashNotInteger => "ERR hash value is not an integer".fmt(f),
+            DbError::Overflow => "ERR increment or decrement would overflow".fmt(f),
+            DbError::NotFinite => "ERR increment would produce NaN or Infinity".fmt(f),
+            DbError::TooLarge => "ERR string exceeds maximum allowed size".fmt(f),
+            DbError::WrongType => {
+                "WRONGTYPE Operation against a key holding the wrong kind of value".fmt(f)
+            }
+            DbError::NoSuchKey => "ERR no such key".fmt(f),
+            DbError::OutOfRange => "ERR index out of range".fmt(f),
+            DbError::ScoreNaN => "ERR resulting score is not a number (NaN)".fmt(f),
+            DbError::StreamIdZero => "ERR The ID specified in XADD must be greater than 0-0".fmt(f),
+            DbError::StreamIdTooSmall => {
+                "ERR The ID specified in XADD is equal or smaller than the target stream top item"
+                    .fmt(f)
+            }
+            DbError::NoGroup => "NOGROUP No such key or consumer group".fmt(f),
+            DbError::BusyGroup => "BUSYGROUP Consumer Group name already exists".fmt(f),
+        }
+    }
+}
+
 /// Routine executed by the background task.
 async fn purge_expired_tasks(shared: Arc<Shared>) {
-    while !shared.is_shutdown() {
-        if let Some(when) = shared.purge_expired_keys() {
-            tokio::select! {
-                _ = time::sleep_until(when) => {}
-                _ = shared.background_task.notified() => {}
+    loop {
+        let next = {
+            let _section = shared.enter_section().await;
+            let mut state = shared.lock_state();
+
+            if state.shutdown {
+                return;
             }
-        } else {
-            // there are no keys expiring in the future. Wait until the tasks is notified.
-            shared.background_task.notified().await;
+            state.purge_expired_keys()
+        };
+
+        match next {
+            Some(when) => {
+                tokio::select! {
+                    _ = time::sleep_until(when) => {}
+                    _ = shared.background_task.notified() => {}
+                }
+            }
+            None => {
+                // there are no keys expiring in the future. Wait until the tasks is notified.
+                shared.background_task.notified().await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn del_and_exists_count_keys() {
+        let db = Db::default();
+        db.set("a".to_string(), Bytes::from("1"), None);
+        db.set(
+            "b".to_string(),
+            Bytes::from("2"),
+            Some(Duration::from_secs(60)),
+        );
+
+        let keys = ["a".to_string(), "a".to_string(), "missing".to_string()];
+        assert_eq!(2, db.exists(&keys));
+
+        assert_eq!(
+            2,
+            db.del(&["a".to_string(), "b".to_string(), "c".to_string()])
+        );
+        assert_eq!(0, db.exists(&["a".to_string(), "b".to_string()]));
+        assert!(db.shared.lock_state().expirations.is_empty());
+    }
+
+    #[tokio::test]
+    async fn key_type_names_the_stored_value() {
+        let db = Db::default();
+        db.set("string".to_string(), Bytes::from("value"), None);
+        db.push("list", vec![Bytes::from("a")], false).unwrap();
+
+        assert_eq!(Some("string"), db.key_type("string"));
+        assert_eq!(Some("list"), db.key_type("list"));
+        assert_eq!(None, db.key_type("missing"));
+    }
+
+    #[tokio::test]
+    async fn rename_carries_the_expiration_over() {
+        let db = Db::default();
+        db.set(
+            "src".to_string(),
+            Bytes::from("1"),
+            Some(Duration::from_secs(60)),
+        );
+        db.set(
+            "dst".to_string(),
+            Bytes::from("2"),
+            Some(Duration::from_secs(30)),
+        );
+
+        assert_eq!(Some(true), db.rename("src", "dst".to_string(), false));
+        assert_eq!(None, db.get("src"));
+        assert_eq!(Some(Bytes::from("1")), db.get("dst"));
+        assert!(db.ttl("dst").unwrap().unwrap() > Duration::from_secs(30));
+
+        // Only the expiration of the renamed entry is left.
+        let state = db.shared.lock_state();
+        assert_eq!(1, state.expirations.len());
+        assert_eq!(Some(&"dst".to_string()), state.expirations.values().next());
+    }
+
+    #[tokio::test]
+    async fn renamenx_does_not_overwrite() {
+        let db = Db::default();
+        db.set("src".to_string(), Bytes::from("1"), None);
+        db.set("dst".to_string(), Bytes::from("2"), None);
+
+        assert_eq!(None, db.rename("missing", "dst".to_string(), true));
+        assert_eq!(Some(false), db.rename("src", "dst".to_string(), true));
+        assert_eq!(Some(Bytes::from("2")), db.get("dst"));
+    }
+
+    #[tokio::test]
+    async fn copy_only_replaces_when_asked() {
+        let db = Db::default();
+        db.set(
+            "src".to_string(),
+            Bytes::from("1"),
+            Some(Duration::from_secs(60)),
+        );
+        db.set("dst".to_string(), Bytes::from("2"), None);
+
+        assert!(!db.copy("src", "dst".to_string(), false));
+        assert_eq!(Some(Bytes::from("2")), db.get("dst"));
+
+        assert!(db.copy("src", "dst".to_string(), true));
+        assert_eq!(Some(Bytes::from("1")), db.get("dst"));
+        assert!(db.ttl("dst").unwrap().is_some());
+        assert_eq!(Some(Bytes::from("1")), db.get("src"));
+
+        assert!(!db.copy("missing", "other".to_string(), true));
+    }
+
+    #[tokio::test]
+    async fn expire_replaces_the_expiration_and_persist_removes_it() {
+        let db = Db::default();
+        db.set("key".to_string(), Bytes::from("value"), None);
+        assert_eq!(Some(None), db.ttl("key"));
+
+        assert!(db.expire("key", Instant::now() + Duration::from_secs(60)));
+        assert!(db.expire("key", Instant::now() + Duration::from_secs(30)));
+        assert!(db.ttl("key").unwrap().unwrap() <= Duration::from_secs(30));
+        assert_eq!(1, db.shared.lock_state().expirations.len());
+
+        assert!(db.persist("key"));
+        assert!(!db.persist("key"));
+        assert_eq!(Some(None), db.ttl("key"));
+        assert!(db.shared.lock_state().expirations.is_empty());
+
+        assert!(!db.expire("missing", Instant::now() + Duration::from_secs(60)));
+        assert_eq!(None, db.ttl("missing"));
+    }
+
+    #[tokio::test]
+    async fn expire_in_the_past_removes_the_key() {
+        let db = Db::default();
+        db.set("key".to_string(), Bytes::from("value"), None);
+
+        assert!(db.expire("key", Instant::now()));
+        assert_eq!(None, db.get("key"));
+    }
+
+    #[tokio::test]
+    async fn earlier_expiration_wakes_up_the_background_task() {
+        let db = Db::default();
+        db.set(
+            "later".to_string(),
+            Bytes::from("value"),
+            Some(Duration::from_secs(60)),
+        );
+        db.set("sooner".to_string(), Bytes::from("value"), None);
+
+        // Let the background task wait for the later key to expire.
+        time::sleep(Duration::from_millis(10)).await;
+
+        db.expire("sooner", Instant::now() + Duration::from_millis(10));
+        time::sleep(Duration::from_millis(50)).await;
+
+        let state = db.shared.lock_state();
+        assert!(!state.entries.contains_key("sooner"));
+        assert!(state.entries.contains_key("later"));
+    }
+
+    #[tokio::test]
+    async fn set_if_not_exists_has_a_single_winner() {
+        let db = Db::default();
+
+        let writers: Vec<_> = (0..8)
+            .map(|i| {
+                let db = db.clone();
+                std::thread::spawn(move || {
+                    let value = Bytes::from(i.to_string());
+                    let condition = SetCondition::NotExists;
+                    let res = db.set_if(
+                        "key".to_string(),
+                        value,
+                        SetExpiration::Persist,
+                        condition,
+                        true,
+                    );
+                    res.unwrap()
+                })
+            })
+            .collect();
+
+        let results: Vec<_> = writers.into_iter().map(|w| w.join().unwrap()).collect();
+        let winners: Vec<_> = results.iter().filter(|(written, _)| *written).collect();
+        assert_eq!(1, winners.len());
+        assert_eq!(None, winners[0].1);
+
+        // Every loser saw the value of the winner.
+        let value = db.get("key");
+        for (written, prev) in &results {
+            if !written {
+                assert_eq!(&value, prev);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn set_if_get_returns_the_previous_value() {
+        let db = Db::default();
+        db.push("list", vec![Bytes::from("a")], false).unwrap();
+
+        let set = |key: &str, condition| {
+            let value = Bytes::from("new");
+            db.set_if(
+                key.to_string(),
+                value,
+                SetExpiration::Persist,
+                condition,
+                true,
+            )
+        };
+
+        assert_eq!(Ok((false, None)), set("key", SetCondition::Exists));
+        assert_eq!(Ok((true, None)), set("key", SetCondition::Always));
+        assert_eq!(
+            Ok((false, Some(Bytes::from("new")))),
+            set("key", SetCondition::NotExists)
+        );
+
+        // Nothing is written when the previous value is not a string.
+        assert_eq!(Err(DbError::WrongType), set("list", SetCondition::Always));
+        assert_eq!(Some("list"), db.key_type("list"));
+    }
+
+    #[tokio::test]
+    async fn set_if_keeps_or_replaces_the_expiration() {
+        let db = Db::default();
+        db.set(
+            "key".to_string(),
+            Bytes::from("1"),
+            Some(Duration::from_secs(60)),
+        );
+
+        let set = |value: &'static str, expiration| {
+            let value = Bytes::from(value);
+            db.set_if(
+                "key".to_string(),
+                value,
+                expiration,
+                SetCondition::Always,
+                false,
+            )
+        };
+
+        set("2", SetExpiration::Keep).unwrap();
+        assert!(db.ttl("key").unwrap().is_some());
+
+        set("3", SetExpiration::Persist).unwrap();
+        assert_eq!(Some(None), db.ttl("key"));
+
+        // A deadline in the past writes the key and expires it right away.
+        assert_eq!(
+            Ok((true, None)),
+            set("4", SetExpiration::At(Instant::now()))
+        );
+        assert_eq!(None, db.get("key"));
+    }
+
+    #[tokio::test]
+    async fn incr_by_rejects_overflows_and_non_integers() {
+        let db = Db::default();
+
+        assert_eq!(Ok(-5), db.incr_by("counter", -5));
+        assert_eq!(Ok(i64::MAX - 5), db.incr_by("counter", i64::MAX));
+        assert_eq!(Ok(i64::MAX), db.incr_by("counter", 5));
+        assert_eq!(Err(DbError::Overflow), db.incr_by("counter", 1));
+        assert_eq!(Some(Bytes::from(i64::MAX.to_string())), db.get("counter"));
+
+        for value in ["+5", "007", "-0", " 1", "1.0", ""] {
+            db.set("key".to_string(), Bytes::from(value), None);
+            assert_eq!(
+                Err(DbError::NotInteger),
+                db.incr_by("key", 1),
+                "{:?}",
+                value
+            );
         }
     }
+
+    #[tokio::test]
+    async fn incr_by_float_formats_the_result() {
+        let db = Db::default();
+
+        assert_eq!(Ok(10.5), db.incr_by_float("key", 10.5));
+        db.incr_by_float("key", 0.1).unwrap();
+        assert_eq!(Some(Bytes::from("10.6")), db.get("key"));
+
+        db.incr_by_float("key", 4989.4).unwrap();
+        assert_eq!(Some(Bytes::from("5000")), db.get("key"));
+
+        // Large values are written out without exponent.
+        db.incr_by_float("key", 1e15).unwrap();
+        assert_eq!(Some(Bytes::from("1000000000005000")), db.get("key"));
+
+        assert_eq!(
+            Err(DbError::NotFinite),
+            db.incr_by_float("key", f64::INFINITY)
+        );
+        db.set("key".to_string(), Bytes::from("abc"), None);
+        assert_eq!(Err(DbError::NotFloat), db.incr_by_float("key", 1.0));
+    }
+
+    #[test]
+    fn parse_int_is_strict() {
+        assert_eq!(Some(0), parse_int(b"0"));
+        assert_eq!(Some(-12), parse_int(b"-12"));
+        assert_eq!(Some(i64::MIN), parse_int(i64::MIN.to_string().as_bytes()));
+
+        for data in ["", "-", "+1", "01", "-0", "1a", "9223372036854775808"] {
+            assert_eq!(None, parse_int(data.as_bytes()), "{:?}", data);
+        }
+    }
+
+    #[tokio::test]
+    async fn append_and_ranges_of_strings() {
+        let db = Db::default();
+
+        assert_eq!(Ok(5), db.append("key", b"Hello"));
+        assert_eq!(Ok(11), db.append("key", b" World"));
+        assert_eq!(Ok(11), db.strlen("key"));
+        assert_eq!(Ok(0), db.strlen("missing"));
+
+        assert_eq!(Ok(Bytes::from("Hello")), db.get_range("key", 0, 4));
+        assert_eq!(Ok(Bytes::from("World")), db.get_range("key", -5, -1));
+        assert_eq!(
+            Ok(Bytes::from("Hello World")),
+            db.get_range("key", -100, 100)
+        );
+        assert_eq!(Ok(Bytes::new()), db.get_range("key", 5, 3));
+
+        assert_eq!(Ok(11), db.set_range("key", 6, b"Redis"));
+        assert_eq!(Some(Bytes::from("Hello Redis")), db.get("key"));
+
+        // The string is padded with zero bytes up to the offset.
+        assert_eq!(Ok(5), db.set_range("padded", 3, b"ab"));
+        assert_eq!(Some(Bytes::from(&b"\0\0\0ab"[..])), db.get("padded"));
+
+        // An empty value does not create the key.
+        assert_eq!(Ok(0), db.set_range("empty", 10, b""));
+        assert_eq!(0, db.exists(&["empty".to_string()]));
+
+        assert_eq!(
+            Err(DbError::TooLarge),
+            db.set_range("key", MAX_STRING_LEN, b"a")
+        );
+    }
+
+    #[tokio::test]
+    async fn get_del_and_get_ex() {
+        let db = Db::default();
+        db.set("key".to_string(), Bytes::from("value"), None);
+
+        let in_a_minute = Instant::now() + Duration::from_secs(60);
+        assert_eq!(
+            Ok(Some(Bytes::from("value"))),
+            db.get_ex("key", SetExpiration::At(in_a_minute))
+        );
+        assert!(db.ttl("key").unwrap().is_some());
+
+        db.get_ex("key", SetExpiration::Persist).unwrap();
+        assert_eq!(Some(None), db.ttl("key"));
+
+        assert_eq!(Ok(Some(Bytes::from("value"))), db.get_del("key"));
+        assert_eq!(Ok(None), db.get_del("key"));
+        assert_eq!(Ok(None), db.get_ex("key", SetExpiration::Persist));
+    }
+
+    #[tokio::test]
+    async fn mget_and_mset() {
+        let db = Db::default();
+        db.push("list", vec![Bytes::from("a")], false).unwrap();
+
+        let pairs = vec![
+            ("a".to_string(), Bytes::from("1")),
+            ("b".to_string(), Bytes::from("2")),
+        ];
+        assert!(db.mset(pairs, false));
+
+        // Keys which do not hold a string are reported as missing.
+        let keys = ["a", "list", "missing", "b"].map(String::from);
+        let expected = vec![Some(Bytes::from("1")), None, None, Some(Bytes::from("2"))];
+        assert_eq!(expected, db.mget(&keys));
+
+        // Nothing is written if any of the keys exists.
+        let pairs = vec![
+            ("c".to_string(), Bytes::from("3")),
+            ("a".to_string(), Bytes::from("4")),
+        ];
+        assert!(!db.mset(pairs, true));
+        assert_eq!(
+            vec![None, Some(Bytes::from("1"))],
+            db.mget(&["c", "a"].map(String::from))
+        );
+    }
+
+    #[tokio::test]
+    async fn watched_key_is_modified_by_writes() {
+        let db = Db::default();
+        db.set("key".to_string(), Bytes::from("1"), None);
+
+        let watched = db.watch("key".to_string());
+        assert!(!watched.is_modified());
+
+        db.incr_by("key", 1).unwrap();
+        assert!(watched.is_modified());
+    }
+
+    #[tokio::test]
+    async fn get_tells_other_types_apart_only_when_tried() {
+        let db = Db::default();
+        db.push("list", vec![Bytes::from("a")], false).unwrap();
+
+        assert_eq!(None, db.get("list"));
+        assert_eq!(Err(DbError::WrongType), db.try_get("list"));
+        assert_eq!(Ok(None), db.try_get("missing"));
+    }
+
+    #[tokio::test]
+    async fn watched_key_is_not_modified_by_failed_or_no_op_writes() {
+        let db = Db::default();
+        db.set("string".to_string(), Bytes::from("value"), None);
+        db.push("list", vec![Bytes::from("a")], false).unwrap();
+
+        let string = db.watch("string".to_string());
+        let list = db.watch("list".to_string());
+
+        assert_eq!(Err(DbError::WrongType), db.push("string", vec![], false));
+        assert_eq!(
+            Err(DbError::WrongType),
+            db.sadd("string", vec![Bytes::from("a")])
+        );
+        assert_eq!(
+            Err(DbError::OutOfRange),
+            db.lset("list", 5, Bytes::from("b"))
+        );
+        assert_eq!(Ok(0), db.lrem("list", 0, b"missing"));
+        assert_eq!(Ok(()), db.ltrim("list", 0, -1));
+
+        assert!(!string.is_modified());
+        assert!(!list.is_modified());
+    }
+
+    #[tokio::test]
+    async fn watched_stream_is_not_modified_by_reads_or_failed_group_operations() {
+        let db = Db::default();
+        db.xgroup_create("stream", "group", None, true).unwrap();
+
+        let watched = db.watch("stream".to_string());
+
+        assert_eq!(
+            Err(DbError::BusyGroup),
+            db.xgroup_create("stream", "group", None, false)
+        );
+        let unknown = StreamId { ms: 1, seq: 0 };
+        assert_eq!(Ok(0), db.xack("stream", "group", &[unknown]));
+        db.xpending_summary("stream", "group").unwrap();
+
+        assert!(!watched.is_modified());
+    }
+
+    #[tokio::test]
+    async fn watched_key_created_and_deleted_is_modified() {
+        let db = Db::default();
+
+        let watched = db.watch("key".to_string());
+
+        db.set("key".to_string(), Bytes::from("value"), None);
+        db.del(&["key".to_string()]);
+
+        assert!(watched.is_modified());
+    }
+
+    #[tokio::test]
+    async fn watched_key_expiring_is_modified() {
+        let db = Db::default();
+        db.set(
+            "key".to_string(),
+            Bytes::from("value"),
+            Some(Duration::from_millis(10)),
+        );
+
+        let watched = db.watch("key".to_string());
+        time::sleep(Duration::from_millis(20)).await;
+
+        assert!(watched.is_modified());
+    }
+
+    #[tokio::test]
+    async fn watched_keys_are_forgotten_once_dropped() {
+        let db = Db::default();
+
+        let first = db.watch("key".to_string());
+        let second = db.watch("key".to_string());
+
+        drop(first);
+        db.set("key".to_string(), Bytes::from("value"), None);
+        assert!(second.is_modified());
+
+        drop(second);
+        assert!(db.shared.lock_state().watched.is_empty());
+    }
+
+    #[tokio::test]
+    async fn exclusive_section_is_not_interleaved() {
+        let db = Db::default();
+        let other = db.clone();
+
+        db.exclusive(|| {
+            let writer = std::thread::spawn(move || {
+                other.set("key".to_string(), Bytes::from("other"), None);
+            });
+
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            assert_eq!(None, db.get("key"));
+
+            // Nested sections and plain accesses from the same thread do not wait.
+            db.exclusive(|| db.set("key".to_string(), Bytes::from("mine"), None));
+            assert_eq!(Some(Bytes::from("mine")), db.get("key"));
+
+            writer
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(Some(Bytes::from("other")), db.get("key"));
+    }
+
+    #[tokio::test]
+    async fn set_with_unrepresentable_expiration_never_expires() {
+        let db = Db::default();
+        db.set("key".to_string(), Bytes::from("value"), Some(Duration::MAX));
+
+        assert_eq!(Some(Bytes::from("value")), db.get("key"));
+    }
+
+    #[tokio::test]
+    async fn blocking_xread_waits_for_appended_entries() {
+        let db = Db::default();
+        let streams = [("stream".to_string(), None)];
+
+        let timeout = Some(Duration::from_millis(10));
+        let read = db.blocking_xread(&streams, None, timeout, std::future::pending());
+        assert_eq!(Ok(vec![]), read.await);
+
+        let writer = db.clone();
+        tokio::spawn(async move {
+            time::sleep(Duration::from_millis(10)).await;
+            let fields = vec![(Bytes::from("field"), Bytes::from("value"))];
+            writer.xadd("stream", XAddId::Auto, fields, None, false)
+        });
+
+        let read = db
+            .blocking_xread(&streams, None, None, std::future::pending())
+            .await
+            .unwrap();
+        assert_eq!(1, read.len());
+        assert_eq!("stream", read[0].0);
+        assert_eq!(1, read[0].1.len());
+    }
+
+    #[tokio::test]
+    async fn keyspace_events_are_published_by_every_write() {
+        let events = "KEA".parse().unwrap();
+        let db = Db::new(DbConfig::new().keyspace_events(events));
+        let mut subscription = db.psubscribe("__keyevent@0__:*".to_string());
+
+        let in_a_minute = Instant::now() + Duration::from_secs(60);
+        db.incr_by("string", 1).unwrap();
+        db.append("string", b"0").unwrap();
+        db.set_range("string", 0, b"2").unwrap();
+        db.get_ex("string", SetExpiration::At(in_a_minute)).unwrap();
+        db.persist("string");
+        db.rename("string", "renamed".to_string(), false);
+        db.get_del("renamed").unwrap();
+        db.push("list", vec![Bytes::from("a")], true).unwrap();
+        db.pop("list", 1, false).unwrap();
+        db.hset("hash", vec![("field".to_string(), Bytes::from("1"))])
+            .unwrap();
+        db.sadd("set", vec![Bytes::from("a")]).unwrap();
+        let members = vec![(1.0, Bytes::from("a"))];
+        db.zadd("zset", members, ZAddOptions::default()).unwrap();
+        let fields = vec![(Bytes::from("field"), Bytes::from("value"))];
+        db.xadd("stream", XAddId::Auto, fields, None, false)
+            .unwrap();
+        db.expire("stream", in_a_minute);
+
+        let expected = [
+            ("incrby", "string"),
+            ("append", "string"),
+            ("setrange", "string"),
+            ("expire", "string"),
+            ("persist", "string"),
+            ("rename_from", "string"),
+            ("rename_to", "renamed"),
+            ("del", "renamed"),
+            ("lpush", "list"),
+            ("rpop", "list"),
+            ("del", "list"),
+            ("hset", "hash"),
+            ("sadd", "set"),
+            ("zadd", "zset"),
+            ("xadd", "stream"),
+            ("expire", "stream"),
+        ];
+
+        for (event, key) in expected {
+            let (channel, message) = subscription.recv().await.unwrap();
+            assert_eq!(format!("__keyevent@0__:{}", event), channel);
+            assert_eq!(key, message);
+        }
+    }
+
+    #[tokio::test]
+    async fn pubsub_introspection_counts_subscribers() {
+        let db = Db::default();
+
+        let _news = db.subscribe("news".to_string());
+        let _news_again = db.subscribe("news".to_string());
+        let _weather = db.subscribe("weather".to_string());
+        let _pattern = db.psubscribe("n*".to_string());
+        let _pattern_again = db.psubscribe("n*".to_string());
+
+        assert_eq!(vec!["news", "weather"], db.pubsub_channels(None));
+        assert_eq!(vec!["news"], db.pubsub_channels(Some("n*")));
+        assert!(db.pubsub_channels(Some("x*")).is_empty());
+
+        // Subscribers to patterns are not counted.
+        let channels = vec![
+            "news".to_string(),
+            "weather".to_string(),
+            "none".to_string(),
+        ];
+        assert_eq!(vec![2, 1, 0], db.pubsub_numsub(&channels));
+        assert_eq!(1, db.pubsub_numpat());
+
+        // Published messages reach the subscribers to the channel and to matching patterns.
+        assert_eq!(4, db.publish("news", Bytes::from("hello")));
+        assert_eq!(2, db.publish("nothing", Bytes::from("hello")));
+    }
+
+    #[tokio::test]
+    async fn idle_channels_are_reclaimed() {
+        let db = Db::default();
+
+        let first = db.subscribe("news".to_string());
+        let second = db.subscribe("news".to_string());
+        let pattern = db.psubscribe("n*".to_string());
+
+        drop(first);
+        assert_eq!(vec!["news"], db.pubsub_channels(None));
+
+        drop(second);
+        drop(pattern);
+        {
+            let state = db.shared.lock_state();
+            assert!(state.pub_sub.is_empty());
+            assert!(state.pattern_sub.is_empty());
+        }
+
+        // A channel subscribed to again is created anew.
+        let mut subscription = db.subscribe("news".to_string());
+        assert_eq!(1, db.publish("news", Bytes::from("hello")));
+        assert_eq!(Ok(Bytes::from("hello")), subscription.recv().await);
+    }
+
+    #[tokio::test]
+    async fn subscribers_lag_once_the_capacity_is_exceeded() {
+        let db = Db::new(DbConfig::new().pubsub_capacity(2));
+        let mut subscription = db.subscribe("news".to_string());
+
+        for n in 0..3 {
+            db.publish("news", Bytes::from(n.to_string()));
+        }
+
+        // The oldest message was dropped, the following ones are still buffered.
+        let lagged = broadcast::error::RecvError::Lagged(1);
+        assert_eq!(Err(lagged), subscription.recv().await);
+        assert_eq!(Ok(Bytes::from("1")), subscription.recv().await);
+        assert_eq!(Ok(Bytes::from("2")), subscription.recv().await);
+    }
 }
@@ -0,0 +1,66 @@
+use crate::{KeyspaceEvents, LagPolicy};
+
+use tokio::time::Duration;
+
+/// Settings of a `Db`, given to `Db::new`.
+///
+/// Each setting is changed by the method of the same name.
+#[derive(Debug, Clone)]
+pub struct DbConfig {
+    /// Number of messages buffered for each pub/sub channel and pattern. Defaults to
+    /// `DEFAULT_PUBSUB_CAPACITY`.
+    pub(crate) pubsub_capacity: usize,
+    /// How to deal with subscribers lagging further behind.
+    pub(crate) lag_policy: LagPolicy,
+    /// Keyspace notifications published along with the messages of the clients. None are
+    /// published by default.
+    pub(crate) keyspace_events: KeyspaceEvents,
+    /// Time after which a running Lua script is aborted. Defaults to
+    /// `DEFAULT_SCRIPT_TIME_LIMIT`.
+    pub(crate) script_time_limit: Duration,
+}
+
+impl DbConfig {
+    /// Create a new `DbConfig` with the default settings.
+    pub fn new() -> DbConfig {
+        DbConfig::default()
+    }
+
+    /// Sets the number of messages buffered for each pub/sub channel and pattern.
+    pub fn pubsub_capacity(mut self, capacity: usize) -> DbConfig {
+        self.pubsub_capacity = capacity;
+        self
+    }
+
+    /// Sets how to deal with subscribers lagging so far behind that the oldest messages
+    /// buffered for them were dropped.
+    pub fn lag_policy(mut self, policy: LagPolicy) -> DbConfig {
+        self.lag_policy = policy;
+        self
+    }
+
+    /// Sets the keyspace notifications published along with the messages of the clients.
+    pub fn keyspace_events(mut self, events: KeyspaceEvents) -> DbConfig {
+        self.keyspace_events = events;
+        self
+    }
+
+    /// Sets the time after which a running Lua script is aborted. Other clients wait for the
+    /// script in the meantime. Scripts which already modified the keyspace are left to complete,
+    /// so that they are never partially applied.
+    pub fn script_time_limit(mut self, limit: Duration) -> DbConfig {
+        self.script_time_limit = limit;
+        self
+    }
+}
+
+impl Default for DbConfig {
+    fn default() -> DbConfig {
+        DbConfig {
+            pubsub_capacity: crate::DEFAULT_PUBSUB_CAPACITY,
+            lag_policy: LagPolicy::default(),
+            keyspace_events: KeyspaceEvents::default(),
+            script_time_limit: crate::DEFAULT_SCRIPT_TIME_LIMIT,
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! Glob-style pattern matching, as used by pattern subscriptions.
+//!
+//! Patterns support `*` (any sequence of characters), `?` (any single character), `[...]`
+//! (any character of the class, or none of them if it starts with `^`, ranges such as `a-z`
+//! included) and `\` to escape the next character.
+
+/// Returns `true` if `string` matches the glob-style `pattern`.
+pub(crate) fn matches(pattern: &[u8], string: &[u8]) -> bool {
+    let (mut p, mut s) = (0, 0);
+
+    // Position in the pattern following the last `*`, and position in the string from which
+    // that `*` is retried on mismatch. Since every other token matches exactly one character,
+    // only the last `*` ever needs to be retried.
+    let mut retry = None;
+
+    while s < string.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                p += 1;
+                retry = Some((p, s));
+                continue;
+            }
+            Some(_) => {
+                if let Some(len) = match_one(&pattern[p..], string[s]) {
+                    p += len;
+                    s += 1;
+                    continue;
+                }
+            }
+            None => {}
+        }
+
+        // Mismatch, let the last `*` match one more character.
+        match retry {
+            Some((after_star, from)) => {
+                p = after_star;
+                s = from + 1;
+                retry = Some((after_star, s));
+            }
+            None => return false,
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+/// Matches the first token of `pattern`, which must not be `*`, against `c`.
+///
+/// Returns the length of the token if it matches.
+fn match_one(pattern: &[u8], c: u8) -> Option<usize> {
+    match pattern {
+        [b'?', ..] => Some(1),
+        [b'\\', escaped, ..] => (*escaped == c).then_some(2),
+        [b'[', class @ ..] => {
+            let (matched, len) = match_class(class, c);
+            matched.then_some(len + 1)
+        }
+        [literal, ..] => (*literal == c).then_some(1),
+        [] => None,
+    }
+}
+
+/// Matches the class following a `[` against `c`.
+///
+/// Returns whether `c` matches, along with the length of the class including the closing `]`.
+/// An unterminated class extends to the end of the pattern.
+fn match_class(class: &[u8], c: u8) -> (bool, usize) {
+    let negate = class.first() == Some(&b'^');
+    let mut i = negate as usize;
+    let mut matched = false;
+
+    while i < class.len() && class[i] != b']' {
+        match &class[i..] {
+            [b'\\', escaped, ..] => {
+                matched |= *escaped == c;
+                i += 2;
+            }
+            [start, b'-', end, ..] if *end != b']' => {
+                let (low, high) = if start <= end {
+                    (*start, *end)
+                } else {
+                    (*end, *start)
+                };
+                matched |= low <= c && c <= high;
+                i += 3;
+            }
+            [literal, ..] => {
+                matched |= *literal == c;
+                i += 1;
+            }
+            [] => unreachable!(),
+        }
+    }
+
+    // Skip the closing `]`, if any.
+    let len = (i + 1).min(class.len());
+
+    (matched != negate, len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::matches;
+
+    #[test]
+    fn star_backtracks() {
+        assert!(matches(b"*", b""));
+        assert!(matches(b"a*", b"a"));
+        assert!(matches(b"*a", b"aaa"));
+        assert!(matches(b"a*b*c", b"aXbYbZc"));
+        assert!(matches(b"*ab", b"aab"));
+        assert!(matches(b"news.*.sport*", b"news.eu.sports"));
+        assert!(!matches(b"a*b", b"aXbY"));
+        assert!(!matches(b"*a*b", b"bbba"));
+    }
+
+    #[test]
+    fn escape_matches_literally() {
+        assert!(matches(b"\\*", b"*"));
+        assert!(!matches(b"\\*", b"a"));
+        assert!(matches(b"a\\?", b"a?"));
+        assert!(!matches(b"a\\?", b"ab"));
+        assert!(matches(b"[\\]]", b"]"));
+
+        // A trailing backslash matches itself.
+        assert!(matches(b"a\\", b"a\\"));
+    }
+
+    #[test]
+    fn classes() {
+        assert!(matches(b"h[ae]llo", b"hallo"));
+        assert!(!matches(b"h[ae]llo", b"hillo"));
+        assert!(matches(b"h[^e]llo", b"hallo"));
+        assert!(!matches(b"h[^e]llo", b"hello"));
+        assert!(matches(b"h[a-b]llo", b"hbllo"));
+        assert!(matches(b"h[b-a]llo", b"hallo"));
+        assert!(matches(b"[a-]", b"-"));
+
+        // Empty classes match no character, and thus any once negated.
+        assert!(!matches(b"[]", b"a"));
+        assert!(matches(b"[^]", b"a"));
+
+        // An unterminated class extends to the end of the pattern.
+        assert!(matches(b"[ab", b"b"));
+        assert!(!matches(b"[ab", b"c"));
+    }
+}
@@ -0,0 +1,270 @@
+use crate::db::{parse_int, Db, DbError, KeyspaceEvent, State, Value};
+
+use bytes::Bytes;
+use std::collections::HashMap;
+
+impl Db {
+    /// Sets the given fields of the hash stored at `key` to their respective values. The hash is
+    /// created if the key does not exist.
+    ///
+    /// Returns the number of fields that were added, not counting updated ones.
+    pub fn hset(&self, key: &str, pairs: Vec<(String, Bytes)>) -> Result<usize, DbError> {
+        let mut state = self.shared.lock_state();
+
+        let hash = state.hash_entry(key)?;
+
+        let mut added = 0;
+        for (field, value) in pairs {
+            if hash.insert(field, value).is_none() {
+                added += 1;
+            }
+        }
+
+        state.touch(key);
+        state.notify_keyspace_event(KeyspaceEvent::HSet, key);
+
+        Ok(added)
+    }
+
+    /// Returns the value of `field` in the hash stored at `key`.
+    pub fn hget(&self, key: &str, field: &str) -> Result<Option<Bytes>, DbError> {
+        let state = self.shared.lock_state();
+        Ok(state
+            .get_hash(key)?
+            .and_then(|hash| hash.get(field).cloned()))
+    }
+
+    /// Returns the values of all the given fields in the hash stored at `key`.
+    pub fn hmget(&self, key: &str, fields: &[String]) -> Result<Vec<Option<Bytes>>, DbError> {
+        let state = self.shared.lock_state();
+
+        let hash = state.get_hash(key)?;
+
+        Ok(fields
+            .iter()
+            .map(|field| hash.and_then(|hash| hash.get(field).cloned()))
+            .collect())
+    }
+
+    /// Removes the given fields from the hash stored at `key`. The key is removed along with
+    /// the last field.
+    ///
+    /// Returns the number of fields that were removed.
+    pub fn hdel(&self, key: &str, fields: &[String]) -> Result<usize, DbError> {
+        let mut state = self.shared.lock_state();
+
+        let hash = match state.get_hash_mut(key)? {
+            Some(hash) => hash,
+            None => return Ok(0),
+        };
+
+        let removed = fields
+            .iter()
+            .filter(|field| hash.remove(field.as_str()).is_some())
+            .count();
+
+        if removed > 0 {
+            state.touch(key);
+            state.notify_keyspace_event(KeyspaceEvent::HDel, key);
+        }
+        state.remove_if_drained(key);
+
+        Ok(removed)
+    }
+
+    /// Returns all the fields and values of the hash stored at `key`.
+    pub fn hgetall(&self, key: &str) -> Result<Vec<(String, Bytes)>, DbError> {
+        let state = self.shared.lock_state();
+
+        Ok(state.get_hash(key)?.map_or_else(Vec::new, |hash| {
+            hash.iter()
+                .map(|(field, value)| (field.clone(), value.clone()))
+                .collect()
+        }))
+    }
+
+    /// Adds `delta` to the integer stored in `field` of the hash stored at `key` and returns the
+    /// new value. A missing field is treated as `0`.
+    pub fn hincr_by(&self, key: &str, field: &str, delta: i64) -> Result<i64, DbError> {
+        let mut state = self.shared.lock_state();
+
+        let hash = state.hash_entry(key)?;
+
+        let current = match hash.get(field) {
+            Some(data) => parse_int(data).ok_or(DbError::HashNotInteger),
+            None => Ok(0),
+        };
+
+        let value = current.and_then(|current| current.checked_add(delta).ok_or(DbError::Overflow));
+
+        if let Ok(value) = value {
+            hash.insert(field.to_string(), Bytes::from(value.to_string()));
+        }
+
+        // The hash may have been created for nothing if the increment failed.
+        state.remove_if_empty(key);
+        if value.is_ok() {
+            state.touch(key);
+            state.notify_keyspace_event(KeyspaceEvent::HIncrBy, key);
+        }
+
+        value
+    }
+
+    /// Returns `true` if `field` exists in the hash stored at `key`.
+    pub fn hexists(&self, key: &str, field: &str) -> Result<bool, DbError> {
+        let state = self.shared.lock_state();
+        Ok(state
+            .get_hash(key)?
+            .is_some_and(|hash| hash.contains_key(field)))
+    }
+
+    /// Returns all the field names of the hash stored at `key`.
+    pub fn hkeys(&self, key: &str) -> Result<Vec<String>, DbError> {
+        let state = self.shared.lock_state();
+        Ok(state
+            .get_hash(key)?
+            .map_or_else(Vec::new, |hash| hash.keys().cloned().collect()))
+    }
+
+    /// Returns all the values of the hash stored at `key`.
+    pub fn hvals(&self, key: &str) -> Result<Vec<Bytes>, DbError> {
+        let state = self.shared.lock_state();
+        Ok(state
+            .get_hash(key)?
+            .map_or_else(Vec::new, |hash| hash.values().cloned().collect()))
+    }
+
+    /// Returns the number of fields of the hash stored at `key`, or `0` if the key does not
+    /// exist.
+    pub fn hlen(&self, key: &str) -> Result<usize, DbError> {
+        let state = self.shared.lock_state();
+        Ok(state.get_hash(key)?.map_or(0, |hash| hash.len()))
+    }
+}
+
+impl State {
+    /// Returns the hash stored at `key`, or `None` if the key does not exist.
+    fn get_hash(&self, key: &str) -> Result<Option<&HashMap<String, Bytes>>, DbError> {
+        match self.entries.get(key).map(|entry| &entry.data) {
+            Some(Value::Hash(hash)) => Ok(Some(hash)),
+            Some(_) => Err(DbError::WrongType),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the hash stored at `key` for modification, or `None` if the key does not exist.
+    fn get_hash_mut(&mut self, key: &str) -> Result<Option<&mut HashMap<String, Bytes>>, DbError> {
+        match self.entries.get_mut(key).map(|entry| &mut entry.data) {
+            Some(Value::Hash(hash)) => Ok(Some(hash)),
+            Some(_) => Err(DbError::WrongType),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the hash stored at `key` for modification, creating an empty hash if the key does
+    /// not exist. Callers must follow up with `remove_if_empty` in case nothing was added.
+    fn hash_entry(&mut self, key: &str) -> Result<&mut HashMap<String, Bytes>, DbError> {
+        if !self.entries.contains_key(key) {
+            self.insert(key.to_string(), Value::Hash(HashMap::new()), None);
+        }
+
+        // `unwrap` is safe as the key was inserted above.
+        Ok(self.get_hash_mut(key)?.unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the given field names and values as pairs to store in a hash.
+    fn pairs(pairs: &[(&str, &'static str)]) -> Vec<(String, Bytes)> {
+        pairs
+            .iter()
+            .map(|(field, value)| (field.to_string(), Bytes::from(*value)))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn set_get_and_delete_fields() {
+        let db = Db::default();
+
+        assert_eq!(Ok(2), db.hset("hash", pairs(&[("a", "1"), ("b", "2")])));
+        // Updated fields are not counted.
+        assert_eq!(Ok(1), db.hset("hash", pairs(&[("b", "3"), ("c", "4")])));
+
+        assert_eq!(Ok(Some(Bytes::from("3"))), db.hget("hash", "b"));
+        assert_eq!(Ok(None), db.hget("hash", "missing"));
+        assert_eq!(Ok(None), db.hget("missing", "a"));
+
+        let fields = vec!["a".to_string(), "missing".to_string()];
+        assert_eq!(
+            Ok(vec![Some(Bytes::from("1")), None]),
+            db.hmget("hash", &fields)
+        );
+        assert_eq!(Ok(vec![None, None]), db.hmget("missing", &fields));
+
+        assert_eq!(Ok(3), db.hlen("hash"));
+        assert_eq!(Ok(true), db.hexists("hash", "a"));
+        assert_eq!(Ok(false), db.hexists("hash", "missing"));
+
+        let mut keys = db.hkeys("hash").unwrap();
+        keys.sort();
+        assert_eq!(vec!["a", "b", "c"], keys);
+
+        let mut all = db.hgetall("hash").unwrap();
+        all.sort();
+        assert_eq!(pairs(&[("a", "1"), ("b", "3"), ("c", "4")]), all);
+
+        assert_eq!(Ok(1), db.hdel("hash", &fields));
+        assert_eq!(Ok(0), db.hdel("missing", &fields));
+
+        // The key is removed along with the last field.
+        let rest = vec!["b".to_string(), "c".to_string()];
+        assert_eq!(Ok(2), db.hdel("hash", &rest));
+        assert_eq!(0, db.exists(&["hash".to_string()]));
+    }
+
+    #[tokio::test]
+    async fn hincr_by_rejects_overflows_and_non_integers() {
+        let db = Db::default();
+
+        assert_eq!(Ok(5), db.hincr_by("hash", "count", 5));
+        assert_eq!(Ok(2), db.hincr_by("hash", "count", -3));
+
+        db.hset("hash", pairs(&[("text", "a"), ("padded", "01")]))
+            .unwrap();
+        assert_eq!(Err(DbError::HashNotInteger), db.hincr_by("hash", "text", 1));
+        assert_eq!(
+            Err(DbError::HashNotInteger),
+            db.hincr_by("hash", "padded", 1)
+        );
+
+        assert_eq!(Ok(i64::MAX), db.hincr_by("hash", "max", i64::MAX));
+        assert_eq!(Err(DbError::Overflow), db.hincr_by("hash", "max", 1));
+        assert_eq!(
+            Ok(Some(Bytes::from(i64::MAX.to_string()))),
+            db.hget("hash", "max")
+        );
+    }
+
+    #[tokio::test]
+    async fn hash_operations_on_other_types_fail() {
+        let db = Db::default();
+
+        db.set("string".to_string(), Bytes::from("value"), None);
+
+        assert_eq!(
+            Err(DbError::WrongType),
+            db.hset("string", pairs(&[("a", "1")]))
+        );
+        assert_eq!(Err(DbError::WrongType), db.hget("string", "a"));
+        assert_eq!(Err(DbError::WrongType), db.hincr_by("string", "a", 1));
+        assert_eq!(Err(DbError::WrongType), db.hlen("string"));
+        assert_eq!(
+            Err(DbError::WrongType),
+            db.hdel("string", &["a".to_string()])
+        );
+    }
+}
@@ -0,0 +1,749 @@
+use crate::db::{range_indices, Db, DbError, KeyspaceEvent, State, Value};
+
+use bytes::Bytes;
+use std::collections::VecDeque;
+use std::future::{self, Future};
+use tokio::sync::oneshot;
+use tokio::time::{self, Duration, Instant};
+
+/// A client blocked until an element can be taken from one of `keys`.
+#[derive(Debug)]
+pub(super) struct Waiter {
+    /// Keys the client is blocked on.
+    keys: Vec<String>,
+    /// Operation to apply once one of the keys holds elements.
+    op: Take,
+    /// Delivers the taken element along with the key it was taken from.
+    tx: oneshot::Sender<Result<(String, Bytes), DbError>>,
+}
+
+/// Describes how an element is taken from a list by a blocking operation.
+#[derive(Debug, Clone)]
+struct Take {
+    /// Take the element from the head of the list rather than from its tail.
+    from_front: bool,
+    /// List the element is moved to, and whether it is pushed to its head.
+    to: Option<(String, bool)>,
+}
+
+impl Db {
+    /// Inserts `values` at the head of the list stored at `key` if `front` is set, or at its
+    /// tail otherwise. The list is created if the key does not exist.
+    ///
+    /// Returns the length of the list after the push.
+    pub fn push(&self, key: &str, values: Vec<Bytes>, front: bool) -> Result<usize, DbError> {
+        let mut state = self.shared.lock_state();
+
+        let list = state.list_entry(key)?;
+        let modified = !values.is_empty();
+
+        for value in values {
+            if front {
+                list.push_front(value);
+            } else {
+                list.push_back(value);
+            }
+        }
+
+        let len = list.len();
+        state.remove_if_empty(key);
+        if modified {
+            state.touch(key);
+            state.notify_keyspace_event(push_event(front), key);
+        }
+        state.serve_blocked(key);
+
+        Ok(len)
+    }
+
+    /// Removes and returns up to `count` elements from the head of the list stored at `key` if
+    /// `front` is set, or from its tail otherwise.
+    ///
+    /// Returns `None` if the key does not exist.
+    pub fn pop(&self, key: &str, count: usize, front: bool) -> Result<Option<Vec<Bytes>>, DbError> {
+        let mut state = self.shared.lock_state();
+
+        let list = match state.get_list_mut(key)? {
+            Some(list) => list,
+            None => return Ok(None),
+        };
+
+        let count = count.min(list.len());
+        let values = if front {
+            list.drain(..count).collect()
+        } else {
+            list.drain(list.len() - count..).rev().collect()
+        };
+
+        if count > 0 {
+            state.touch(key);
+            state.notify_keyspace_event(pop_event(front), key);
+        }
+        state.remove_if_drained(key);
+
+        Ok(Some(values))
+    }
+
+    /// Returns the elements of the list stored at `key` between the offsets `start` and `stop`
+    /// (both inclusive). Negative offsets count from the end of the list.
+    pub fn lrange(&self, key: &str, start: i64, stop: i64) -> Result<Vec<Bytes>, DbError> {
+        let state = self.shared.lock_state();
+
+        let list = match state.get_list(key)? {
+            Some(list) => list,
+            None => return Ok(vec![]),
+        };
+
+        match range_indices(list.len() as i64, start, stop) {
+            Some((start, stop)) => Ok(list.range(start..=stop).cloned().collect()),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Returns the length of the list stored at `key`, or `0` if the key does not exist.
+    pub fn llen(&self, key: &str) -> Result<usize, DbError> {
+        let state = self.shared.lock_state();
+        Ok(state.get_list(key)?.map_or(0, |list| list.len()))
+    }
+
+    /// Returns the element at `index` in the list stored at `key`. A negative index counts from
+    /// the end of the list.
+    pub fn lindex(&self, key: &str, index: i64) -> Result<Option<Bytes>, DbError> {
+        let state = self.shared.lock_state();
+
+        let list = match state.get_list(key)? {
+            Some(list) => list,
+            None => return Ok(None),
+        };
+
+        Ok(list_index(list.len(), index).map(|index| list[index].clone()))
+    }
+
+    /// Replaces the element at `index` in the list stored at `key`. A negative index counts from
+    /// the end of the list.
+    pub fn lset(&self, key: &str, index: i64, value: Bytes) -> Result<(), DbError> {
+        let mut state = self.shared.lock_state();
+
+        let list = state.get_list_mut(key)?.ok_or(DbError::NoSuchKey)?;
+        let index = list_index(list.len(), index).ok_or(DbError::OutOfRange)?;
+
+        list[index] = value;
+        state.touch(key);
+        state.notify_keyspace_event(KeyspaceEvent::LSet, key);
+
+        Ok(())
+    }
+
+    /// Removes elements equal to `value` from the list stored at `key`. A positive `count`
+    /// removes at most `count` elements starting from the head, a negative one starting from
+    /// the tail, and `0` removes all of them.
+    ///
+    /// Returns the number of removed elements.
+    pub fn lrem(&self, key: &str, count: i64, value: &[u8]) -> Result<usize, DbError> {
+        let mut state = self.shared.lock_state();
+
+        let list = match state.get_list_mut(key)? {
+            Some(list) => list,
+            None => return Ok(0),
+        };
+
+        let limit = if count == 0 {
+            usize::MAX
+        } else {
+            count.unsigned_abs() as usize
+        };
+
+        let mut matches: Vec<usize> = list
+            .iter()
+            .enumerate()
+            .filter(|(_, elem)| &elem[..] == value)
+            .map(|(index, _)| index)
+            .collect();
+
+        if count < 0 {
+            matches.reverse();
+        }
+        matches.truncate(limit);
+        // Remove from the back so the remaining indices stay valid.
+        matches.sort_unstable_by(|a, b| b.cmp(a));
+
+        for &index in &matches {
+            list.remove(index);
+        }
+
+        if !matches.is_empty() {
+            state.touch(key);
+            state.notify_keyspace_event(KeyspaceEvent::LRem, key);
+        }
+        state.remove_if_drained(key);
+
+        Ok(matches.len())
+    }
+
+    /// Trims the list stored at `key` so that it only contains the elements between the offsets
+    /// `start` and `stop` (both inclusive). Negative offsets count from the end of the list.
+    pub fn ltrim(&self, key: &str, start: i64, stop: i64) -> Result<(), DbError> {
+        let mut state = self.shared.lock_state();
+
+        let list = match state.get_list_mut(key)? {
+            Some(list) => list,
+            None => return Ok(()),
+        };
+        let len = list.len();
+
+        match range_indices(len as i64, start, stop) {
+            Some((start, stop)) => {
+                list.truncate(stop + 1);
+                list.drain(..start);
+            }
+            None => list.clear(),
+        }
+
+        if list.len() < len {
+            state.touch(key);
+            state.notify_keyspace_event(KeyspaceEvent::LTrim, key);
+        }
+        state.remove_if_drained(key);
+
+        Ok(())
+    }
+
+    /// Removes and returns an element from the first non-empty list among `keys`, taking it from
+    /// the head if `front` is set or from the tail otherwise. Returns `None` if all the lists are
+    /// empty.
+    pub fn pop_first(
+        &self,
+        keys: &[String],
+        front: bool,
+    ) -> Result<Option<(String, Bytes)>, DbError> {
+        let op = Take {
+            from_front: front,
+            to: None,
+        };
+
+        self.shared.lock_state().take_first(keys, &op)
+    }
+
+    /// Atomically moves an element from the list stored at `source` to the list stored at
+    /// `destination`, as `blocking_move` does without blocking. Returns `None` if `source` is
+    /// empty.
+    pub fn lmove(
+        &self,
+        source: String,
+        destination: String,
+        from_front: bool,
+        to_front: bool,
+    ) -> Result<Option<Bytes>, DbError> {
+        let op = Take {
+            from_front,
+            to: Some((destination, to_front)),
+        };
+
+        let moved = self.shared.lock_state().take_first(&[source], &op)?;
+
+        Ok(moved.map(|(_, value)| value))
+    }
+
+    /// Removes and returns an element from the first non-empty list among `keys`, taking it from
+    /// the head if `front` is set or from the tail otherwise.
+    ///
+    /// If all the lists are empty, the client blocks until an element is pushed to one of them.
+    /// Blocked clients are served in the order they started waiting. `None` is returned if no
+    /// element became available before `timeout` elapsed or `cancel` completed. A `timeout` of
+    /// `None` waits forever. An element handed over before `cancel` was noticed is still
+    /// returned.
+    pub async fn blocking_pop(
+        &self,
+        keys: Vec<String>,
+        front: bool,
+        timeout: Option<Duration>,
+        cancel: impl Future<Output = ()>,
+    ) -> Result<Option<(String, Bytes)>, DbError> {
+        let op = Take {
+            from_front: front,
+            to: None,
+        };
+
+        self.block(keys, op, timeout, cancel).await
+    }
+
+    /// Atomically moves an element from the list stored at `source` to the list stored at
+    /// `destination`, blocking until `source` holds elements. The element is taken from the head
+    /// of `source` if `from_front` is set and pushed to the head of `destination` if `to_front`
+    /// is set.
+    ///
+    /// Returns the moved element, or `None` under the same conditions as `blocking_pop`.
+    pub async fn blocking_move(
+        &self,
+        source: String,
+        destination: String,
+        from_front: bool,
+        to_front: bool,
+        timeout: Option<Duration>,
+        cancel: impl Future<Output = ()>,
+    ) -> Result<Option<Bytes>, DbError> {
+        let op = Take {
+            from_front,
+            to: Some((destination, to_front)),
+        };
+
+        let moved = self.block(vec![source], op, timeout, cancel).await?;
+
+        Ok(moved.map(|(_, value)| value))
+    }
+
+    /// Applies `op` to the first non-empty list among `keys`, waiting for an element to be
+    /// pushed if they are all empty.
+    async fn block(
+        &self,
+        keys: Vec<String>,
+        op: Take,
+        timeout: Option<Duration>,
+        cancel: impl Future<Output = ()>,
+    ) -> Result<Option<(String, Bytes)>, DbError> {
+        let (id, mut rx) = {
+            let _section = self.enter_section().await;
+            let mut state = self.shared.lock_state();
+
+            if let Some(taken) = state.take_first(&keys, &op)? {
+                return Ok(Some(taken));
+            }
+
+            // All the lists are empty, queue the client behind the ones already waiting.
+            let id = state.next_id;
+            state.next_id += 1;
+
+            for key in &keys {
+                state.blocked.entry(key.clone()).or_default().push_back(id);
+            }
+
+            let (tx, rx) = oneshot::channel();
+            state.waiters.insert(
+                id,
+                Waiter {
+                    keys,
+                    op: op.clone(),
+                    tx,
+                },
+            );
+
+            (id, rx)
+        };
+
+        // A deadline too far in the future to be represented is the same as no deadline.
+        let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+        let sleep = async {
+            match deadline {
+                Some(when) => time::sleep_until(when).await,
+                None => future::pending().await,
+            }
+        };
+
+        tokio::pin!(cancel);
+
+        let handed = tokio::select! {
+            res = &mut rx => res.ok(),
+            _ = sleep => None,
+            _ = &mut cancel => None,
+        };
+
+        {
+            let _section = self.enter_section().await;
+            self.shared.lock_state().unblock(id);
+        }
+
+        // An element may have been handed over right before the client was unblocked. The
+        // handover is final, even if the client was cancelled in the meantime: the element
+        // was taken, and moved, within the same critical section, so putting it back later
+        // could undo changes made by other clients since.
+        let handed = handed.or_else(|| rx.try_recv().ok());
+
+        handed.transpose()
+    }
+}
+
+impl State {
+    /// Applies `op` to the first non-empty list among `keys`, serving the clients blocked on the
+    /// destination list if any. Returns `None` if all the lists are empty.
+    fn take_first(
+        &mut self,
+        keys: &[String],
+        op: &Take,
+    ) -> Result<Option<(String, Bytes)>, DbError> {
+        for key in keys {
+            if let Some(value) = self.take(key, op)? {
+                if let Some((destination, _)) = &op.to {
+                    self.serve_blocked(destination);
+                }
+                return Ok(Some((key.clone(), value)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Hands the elements of the list stored at `key` over to the clients blocked on it, in the
+    /// order they started waiting. Lists receiving elements moved by `BLMOVE` are served in turn.
+    pub(super) fn serve_blocked(&mut self, key: &str) {
+        let mut ready = vec![key.to_string()];
+
+        while let Some(key) = ready.pop() {
+            while let Some(&id) = self.blocked.get(&key).and_then(VecDeque::front) {
+                // Clients keep waiting if the key was replaced by a value of another type.
+                if !matches!(self.get_list(&key), Ok(Some(_))) {
+                    break;
+                }
+
+                // A client which went away is skipped before anything is taken on its behalf.
+                if self.waiters[&id].tx.is_closed() {
+                    self.unblock(id);
+                    continue;
+                }
+
+                let op = self.waiters[&id].op.clone();
+                let res = match self.take(&key, &op) {
+                    Ok(Some(value)) => Ok((key.clone(), value)),
+                    // The list is drained, the remaining clients keep waiting.
+                    Ok(None) => break,
+                    // The destination of a move is not a list.
+                    Err(err) => Err(err),
+                };
+
+                // `unwrap` is safe as queued identifiers always refer to a waiter.
+                let waiter = self.unblock(id).unwrap();
+
+                match waiter.tx.send(res) {
+                    Ok(()) => {
+                        if let Some((destination, _)) = op.to {
+                            ready.push(destination);
+                        }
+                    }
+                    // The client went away in the meantime, put the element back where it was
+                    // taken from. Nothing else touched the lists since, the state being locked.
+                    Err(Ok((_, value))) => self.untake(&key, &op, value),
+                    Err(Err(_)) => {}
+                }
+            }
+        }
+    }
+
+    /// Removes the blocked client `id` from the queues of all the keys it waits on.
+    fn unblock(&mut self, id: u64) -> Option<Waiter> {
+        let waiter = self.waiters.remove(&id)?;
+
+        for key in &waiter.keys {
+            if let Some(queue) = self.blocked.get_mut(key) {
+                queue.retain(|&queued| queued != id);
+                if queue.is_empty() {
+                    self.blocked.remove(key);
+                }
+            }
+        }
+
+        Some(waiter)
+    }
+
+    /// Applies `op` to the list stored at `key`, returning the taken element or `None` if the
+    /// key does not exist.
+    fn take(&mut self, key: &str, op: &Take) -> Result<Option<Bytes>, DbError> {
+        if self.get_list(key)?.is_none() {
+            return Ok(None);
+        }
+
+        // The destination is checked before anything is taken from the source.
+        if let Some((destination, _)) = &op.to {
+            self.get_list(destination)?;
+        }
+
+        // `unwrap` is safe as the source list was found above.
+        let list = self.get_list_mut(key)?.unwrap();
+
+        let value = if op.from_front {
+            list.pop_front()
+        } else {
+            list.pop_back()
+        };
+
+        if value.is_some() {
+            self.touch(key);
+            self.notify_keyspace_event(pop_event(op.from_front), key);
+        }
+
+        // The element is pushed before the source is removed, which may also be the
+        // destination.
+        if let (Some(value), Some((destination, to_front))) = (&value, &op.to) {
+            let list = self.list_entry(destination)?;
+            if *to_front {
+                list.push_front(value.clone());
+            } else {
+                list.push_back(value.clone());
+            }
+            self.touch(destination);
+            self.notify_keyspace_event(push_event(*to_front), destination);
+        }
+
+        self.remove_if_drained(key);
+
+        Ok(value)
+    }
+
+    /// Reverts `op` after `value` was taken from the list stored at `key`.
+    fn untake(&mut self, key: &str, op: &Take, value: Bytes) {
+        if let Some((destination, to_front)) = &op.to {
+            if let Ok(Some(list)) = self.get_list_mut(destination) {
+                if *to_front {
+                    list.pop_front();
+                } else {
+                    list.pop_back();
+                }
+                self.touch(destination);
+                self.notify_keyspace_event(pop_event(*to_front), destination);
+            }
+            self.remove_if_drained(destination);
+        }
+
+        if let Ok(list) = self.list_entry(key) {
+            if op.from_front {
+                list.push_front(value);
+            } else {
+                list.push_back(value);
+            }
+            self.touch(key);
+            self.notify_keyspace_event(push_event(op.from_front), key);
+        }
+    }
+
+    /// Returns the list stored at `key`, or `None` if the key does not exist.
+    pub(super) fn get_list(&self, key: &str) -> Result<Option<&VecDeque<Bytes>>, DbError> {
+        match self.entries.get(key).map(|entry| &entry.data) {
+            Some(Value::List(list)) => Ok(Some(list)),
+            Some(_) => Err(DbError::WrongType),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the list stored at `key` for modification, or `None` if the key does not exist.
+    pub(super) fn get_list_mut(
+        &mut self,
+        key: &str,
+    ) -> Result<Option<&mut VecDeque<Bytes>>, DbError> {
+        match self.entries.get_mut(key).map(|entry| &mut entry.data) {
+            Some(Value::List(list)) => Ok(Some(list)),
+            Some(_) => Err(DbError::WrongType),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the list stored at `key` for modification, creating an empty list if the key does
+    /// not exist. Callers must follow up with `remove_if_empty` in case nothing was added.
+    pub(super) fn list_entry(&mut self, key: &str) -> Result<&mut VecDeque<Bytes>, DbError> {
+        if !self.entries.contains_key(key) {
+            self.insert(key.to_string(), Value::List(VecDeque::new()), None);
+        }
+
+        // `unwrap` is safe as the key was inserted above.
+        Ok(self.get_list_mut(key)?.unwrap())
+    }
+}
+
+/// Converts a possibly negative list index into a position in a list of length `len`.
+fn list_index(len: usize, index: i64) -> Option<usize> {
+    let index = if index < 0 { len as i64 + index } else { index };
+
+    if index < 0 || index >= len as i64 {
+        None
+    } else {
+        Some(index as usize)
+    }
+}
+
+/// Returns the event of pushing to the head of a list if `front` is set, or to its tail.
+fn push_event(front: bool) -> KeyspaceEvent {
+    if front {
+        KeyspaceEvent::LPush
+    } else {
+        KeyspaceEvent::RPush
+    }
+}
+
+/// Returns the event of popping from the head of a list if `front` is set, or from its tail.
+fn pop_event(front: bool) -> KeyspaceEvent {
+    if front {
+        KeyspaceEvent::LPop
+    } else {
+        KeyspaceEvent::RPop
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the elements of the list stored at `key` as strings.
+    fn elements(db: &Db, key: &str) -> Vec<String> {
+        let elements = db.lrange(key, 0, -1).unwrap();
+        elements
+            .iter()
+            .map(|elem| String::from_utf8(elem.to_vec()).unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn push_and_pop_at_both_ends() {
+        let db = Db::default();
+
+        let values = |values: &[&'static str]| values.iter().map(|v| Bytes::from(*v)).collect();
+        assert_eq!(Ok(2), db.push("list", values(&["b", "a"]), true));
+        assert_eq!(Ok(4), db.push("list", values(&["c", "d"]), false));
+        assert_eq!(vec!["a", "b", "c", "d"], elements(&db, "list"));
+
+        assert_eq!(Ok(Some(values(&["a"]))), db.pop("list", 1, true));
+        assert_eq!(Ok(Some(values(&["d", "c"]))), db.pop("list", 2, false));
+        assert_eq!(Ok(Some(values(&["b"]))), db.pop("list", 5, true));
+
+        // The drained list is removed.
+        assert_eq!(None, db.key_type("list"));
+        assert_eq!(Ok(None), db.pop("list", 1, true));
+    }
+
+    #[tokio::test]
+    async fn list_operations_on_other_types_fail() {
+        let db = Db::default();
+        db.set("string".to_string(), Bytes::from("value"), None);
+
+        let value = vec![Bytes::from("a")];
+        assert_eq!(Err(DbError::WrongType), db.push("string", value, true));
+        assert_eq!(Err(DbError::WrongType), db.pop("string", 1, true));
+        assert_eq!(Err(DbError::WrongType), db.lrange("string", 0, -1));
+        assert_eq!(Err(DbError::WrongType), db.llen("string"));
+    }
+
+    #[tokio::test]
+    async fn index_and_replace_elements() {
+        let db = Db::default();
+        let values = ["a", "b", "c"].iter().map(|v| Bytes::from(*v)).collect();
+        db.push("list", values, false).unwrap();
+
+        assert_eq!(Ok(Some(Bytes::from("a"))), db.lindex("list", 0));
+        assert_eq!(Ok(Some(Bytes::from("c"))), db.lindex("list", -1));
+        assert_eq!(Ok(None), db.lindex("list", 3));
+
+        assert_eq!(Ok(()), db.lset("list", -2, Bytes::from("B")));
+        assert_eq!(vec!["a", "B", "c"], elements(&db, "list"));
+        assert_eq!(
+            Err(DbError::NoSuchKey),
+            db.lset("missing", 0, Bytes::from("a"))
+        );
+    }
+
+    #[tokio::test]
+    async fn remove_and_trim_elements() {
+        let db = Db::default();
+        let values = ["a", "b", "a", "c", "a"];
+        let values = values.iter().map(|v| Bytes::from(*v)).collect();
+        db.push("list", values, false).unwrap();
+
+        // A negative count removes from the tail.
+        assert_eq!(Ok(1), db.lrem("list", -1, b"a"));
+        assert_eq!(vec!["a", "b", "a", "c"], elements(&db, "list"));
+
+        assert_eq!(Ok(1), db.lrem("list", 1, b"a"));
+        assert_eq!(vec!["b", "a", "c"], elements(&db, "list"));
+
+        assert_eq!(Ok(()), db.ltrim("list", 1, -1));
+        assert_eq!(vec!["a", "c"], elements(&db, "list"));
+
+        assert_eq!(Ok(()), db.ltrim("list", 5, 10));
+        assert_eq!(None, db.key_type("list"));
+    }
+
+    #[tokio::test]
+    async fn blocked_clients_are_served_in_order() {
+        let db = Db::default();
+
+        let mut clients = vec![];
+        for _ in 0..3 {
+            let db = db.clone();
+            let keys = vec!["other".to_string(), "list".to_string()];
+            clients.push(tokio::spawn(async move {
+                db.blocking_pop(keys, true, None, future::pending()).await
+            }));
+
+            // Let the client block before the next one.
+            time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let values = vec![Bytes::from("a"), Bytes::from("b"), Bytes::from("c")];
+        db.push("list", values, false).unwrap();
+
+        for expected in ["a", "b", "c"] {
+            let popped = clients.remove(0).await.unwrap().unwrap();
+            assert_eq!(Some(("list".to_string(), Bytes::from(expected))), popped);
+        }
+        assert_eq!(Ok(0), db.llen("list"));
+    }
+
+    #[tokio::test]
+    async fn cancelled_client_is_skipped() {
+        let db = Db::default();
+
+        let (cancel, cancelled) = oneshot::channel::<()>();
+        let first = {
+            let db = db.clone();
+            tokio::spawn(async move {
+                let cancelled = async {
+                    let _ = cancelled.await;
+                };
+                db.blocking_pop(vec!["list".to_string()], true, None, cancelled)
+                    .await
+            })
+        };
+        time::sleep(Duration::from_millis(10)).await;
+
+        let second = {
+            let db = db.clone();
+            tokio::spawn(async move {
+                let keys = vec!["list".to_string()];
+                db.blocking_pop(keys, true, None, future::pending()).await
+            })
+        };
+        time::sleep(Duration::from_millis(10)).await;
+
+        drop(cancel);
+        assert_eq!(Ok(None), first.await.unwrap());
+
+        db.push("list", vec![Bytes::from("a")], false).unwrap();
+        let popped = second.await.unwrap();
+        assert_eq!(Ok(Some(("list".to_string(), Bytes::from("a")))), popped);
+    }
+
+    #[tokio::test]
+    async fn cancelled_move_leaves_the_destination_alone() {
+        let db = Db::default();
+
+        let (cancel, cancelled) = oneshot::channel::<()>();
+        let waiter = {
+            let db = db.clone();
+            tokio::spawn(async move {
+                let cancelled = async {
+                    let _ = cancelled.await;
+                };
+                let (source, destination) = ("source".to_string(), "destination".to_string());
+                db.blocking_move(source, destination, true, false, None, cancelled)
+                    .await
+            })
+        };
+        time::sleep(Duration::from_millis(10)).await;
+
+        // The element is moved, then the waiter is cancelled and another client pushes to the
+        // destination, all before the waiter gets to run again.
+        db.push("source", vec![Bytes::from("a")], false).unwrap();
+        drop(cancel);
+        db.push("destination", vec![Bytes::from("b")], false)
+            .unwrap();
+
+        assert_eq!(Ok(Some(Bytes::from("a"))), waiter.await.unwrap());
+        assert_eq!(vec!["a", "b"], elements(&db, "destination"));
+        assert!(elements(&db, "source").is_empty());
+    }
+}
@@ -0,0 +1,267 @@
+use crate::db::State;
+
+use bytes::Bytes;
+use std::fmt;
+use std::str::FromStr;
+
+/// Keyspace notifications published on the `__keyspace@0__:<key>` and `__keyevent@0__:<event>`
+/// channels, configured as the Redis `notify-keyspace-events` setting.
+///
+/// Notifications are only published if at least one of `keyspace` and `keyevent` is set, along
+/// with the class of the event. Events are named after the Redis ones, e.g. `LPOP` and `BLPOP`
+/// both publish `lpop`, followed by `del` if the list is left empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct KeyspaceEvents {
+    /// Publish the name of the event on the `__keyspace@0__:<key>` channel (`K`).
+    pub keyspace: bool,
+    /// Publish the key on the `__keyevent@0__:<event>` channel (`E`).
+    pub keyevent: bool,
+    /// Commands not specific to a type, such as `DEL`, `EXPIRE` or `RENAME` (`g`).
+    pub generic: bool,
+    /// String commands, such as `SET` or `INCR` (`$`).
+    pub string: bool,
+    /// List commands (`l`).
+    pub list: bool,
+    /// Set commands (`s`).
+    pub set: bool,
+    /// Hash commands (`h`).
+    pub hash: bool,
+    /// Sorted set commands (`z`).
+    pub zset: bool,
+    /// Stream commands (`t`).
+    pub stream: bool,
+    /// Keys deleted when their timeout elapses (`x`).
+    pub expired: bool,
+    /// Keys evicted under memory pressure (`e`). Reserved: indb never evicts keys, so no event
+    /// of this class is published. Accepted so that Redis settings such as `Exe` may be reused.
+    pub evicted: bool,
+}
+
+/// Event happening to a key, notified according to the `KeyspaceEvents` configuration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(in crate::db) enum KeyspaceEvent {
+    Del,
+    Expire,
+    Persist,
+    RenameFrom,
+    RenameTo,
+    CopyTo,
+    Set,
+    SetRange,
+    Append,
+    IncrBy,
+    IncrByFloat,
+    LPush,
+    RPush,
+    LPop,
+    RPop,
+    LSet,
+    LRem,
+    LTrim,
+    SAdd,
+    SRem,
+    SInterStore,
+    SUnionStore,
+    SDiffStore,
+    HSet,
+    HDel,
+    HIncrBy,
+    ZAdd,
+    ZIncr,
+    ZRem,
+    ZPopMin,
+    ZPopMax,
+    XAdd,
+    XTrim,
+    XGroupCreate,
+    XGroupDestroy,
+    XGroupCreateConsumer,
+    XGroupDelConsumer,
+    XGroupSetId,
+    Expired,
+}
+
+impl KeyspaceEvents {
+    /// Returns `true` if notifications of `event` are published.
+    fn is_enabled(&self, event: KeyspaceEvent) -> bool {
+        use KeyspaceEvent::*;
+
+        let class = match event {
+            Del | Expire | Persist | RenameFrom | RenameTo | CopyTo => self.generic,
+            Set | SetRange | Append | IncrBy | IncrByFloat => self.string,
+            LPush | RPush | LPop | RPop | LSet | LRem | LTrim => self.list,
+            SAdd | SRem | SInterStore | SUnionStore | SDiffStore => self.set,
+            HSet | HDel | HIncrBy => self.hash,
+            ZAdd | ZIncr | ZRem | ZPopMin | ZPopMax => self.zset,
+            XAdd | XTrim | XGroupCreate | XGroupDestroy | XGroupCreateConsumer
+            | XGroupDelConsumer | XGroupSetId => self.stream,
+            Expired => self.expired,
+        };
+
+        class && (self.keyspace || self.keyevent)
+    }
+}
+
+impl KeyspaceEvent {
+    /// Returns the name of the event, as published in the notifications.
+    fn name(self) -> &'static str {
+        use KeyspaceEvent::*;
+
+        match self {
+            Del => "del",
+            Expire => "expire",
+            Persist => "persist",
+            RenameFrom => "rename_from",
+            RenameTo => "rename_to",
+            CopyTo => "copy_to",
+            Set => "set",
+            SetRange => "setrange",
+            Append => "append",
+            IncrBy => "incrby",
+            IncrByFloat => "incrbyfloat",
+            LPush => "lpush",
+            RPush => "rpush",
+            LPop => "lpop",
+            RPop => "rpop",
+            LSet => "lset",
+            LRem => "lrem",
+            LTrim => "ltrim",
+            SAdd => "sadd",
+            SRem => "srem",
+            SInterStore => "sinterstore",
+            SUnionStore => "sunionstore",
+            SDiffStore => "sdiffstore",
+            HSet => "hset",
+            HDel => "hdel",
+            HIncrBy => "hincrby",
+            ZAdd => "zadd",
+            ZIncr => "zincr",
+            ZRem => "zrem",
+            ZPopMin => "zpopmin",
+            ZPopMax => "zpopmax",
+            XAdd => "xadd",
+            XTrim => "xtrim",
+            XGroupCreate => "xgroup-create",
+            XGroupDestroy => "xgroup-destroy",
+            XGroupCreateConsumer => "xgroup-createconsumer",
+            XGroupDelConsumer => "xgroup-delconsumer",
+            XGroupSetId => "xgroup-setid",
+            Expired => "expired",
+        }
+    }
+}
+
+impl FromStr for KeyspaceEvents {
+    type Err = String;
+
+    /// Parses the characters of the classes of events to publish, `A` being an alias for all of
+    /// them. The empty string disables notifications.
+    fn from_str(s: &str) -> Result<KeyspaceEvents, String> {
+        let mut events = KeyspaceEvents::default();
+
+        for c in s.chars() {
+            match c {
+                'K' => events.keyspace = true,
+                'E' => events.keyevent = true,
+                'g' => events.generic = true,
+                '$' => events.string = true,
+                'l' => events.list = true,
+                's' => events.set = true,
+                'h' => events.hash = true,
+                'z' => events.zset = true,
+                't' => events.stream = true,
+                'x' => events.expired = true,
+                'e' => events.evicted = true,
+                'A' => {
+                    events.generic = true;
+                    events.string = true;
+                    events.list = true;
+                    events.set = true;
+                    events.hash = true;
+                    events.zset = true;
+                    events.stream = true;
+                    events.expired = true;
+                    events.evicted = true;
+                }
+                _ => return Err(format!("invalid keyspace event class `{}`", c)),
+            }
+        }
+
+        Ok(events)
+    }
+}
+
+impl fmt::Display for KeyspaceEvents {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let classes = [
+            (self.keyspace, "K"),
+            (self.keyevent, "E"),
+            (self.generic, "g"),
+            (self.string, "$"),
+            (self.list, "l"),
+            (self.set, "s"),
+            (self.hash, "h"),
+            (self.zset, "z"),
+            (self.stream, "t"),
+            (self.expired, "x"),
+            (self.evicted, "e"),
+        ];
+
+        for (enabled, class) in classes {
+            if enabled {
+                f.write_str(class)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl State {
+    /// Publishes the notifications of `event` happening to `key`, if enabled.
+    pub(in crate::db) fn notify_keyspace_event(&self, event: KeyspaceEvent, key: &str) {
+        if !self.keyspace_events.is_enabled(event) {
+            return;
+        }
+
+        if self.keyspace_events.keyspace {
+            let channel = format!("__keyspace@0__:{}", key);
+            self.publish(&channel, Bytes::from_static(event.name().as_bytes()));
+        }
+
+        if self.keyspace_events.keyevent {
+            let channel = format!("__keyevent@0__:{}", event.name());
+            self.publish(&channel, Bytes::copy_from_slice(key.as_bytes()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_display_classes() {
+        let events: KeyspaceEvents = "Kgx".parse().unwrap();
+        assert!(events.keyspace && events.generic && events.expired);
+        assert!(!events.keyevent && !events.string);
+        assert_eq!("Kgx", events.to_string());
+
+        let all: KeyspaceEvents = "EA".parse().unwrap();
+        assert_eq!("Eg$lshztxe", all.to_string());
+
+        assert_eq!(Ok(KeyspaceEvents::default()), "".parse());
+        assert!("Kq".parse::<KeyspaceEvents>().is_err());
+    }
+
+    #[test]
+    fn evicted_class_is_accepted() {
+        let events: KeyspaceEvents = "Exe".parse().unwrap();
+        assert!(events.keyevent && events.expired && events.evicted);
+        assert!(!events.generic);
+        assert_eq!("Exe", events.to_string());
+
+        let all: KeyspaceEvents = "KEA".parse().unwrap();
+        assert!(all.evicted);
+    }
+}
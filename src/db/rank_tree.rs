@@ -0,0 +1,219 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::ops::Range;
+
+/// An ordered collection of unique keys supporting rank queries.
+///
+/// Implemented as a treap where every node tracks the size of its subtree. Insertion, removal,
+/// computing the rank of a key and finding the key at a given rank all take `O(log n)` expected
+/// time.
+#[derive(Debug, Clone)]
+pub(super) struct RankTree<K> {
+    root: Link<K>,
+    /// State of the pseudo random generator used to pick node priorities.
+    seed: u64,
+}
+
+type Link<K> = Option<Box<Node<K>>>;
+
+#[derive(Debug, Clone)]
+struct Node<K> {
+    key: K,
+    /// Heap priority, keeping the tree balanced with high probability.
+    priority: u64,
+    /// Number of keys in the subtree rooted at this node.
+    size: usize,
+    left: Link<K>,
+    right: Link<K>,
+}
+
+impl<K: Ord> RankTree<K> {
+    pub(super) fn new() -> RankTree<K> {
+        // Seed the generator from the randomly keyed std hasher, so the shape of the tree cannot
+        // be predicted by clients.
+        let seed = RandomState::new().build_hasher().finish() | 1;
+
+        RankTree { root: None, seed }
+    }
+
+    /// Inserts `key`, which must not already be present in the tree.
+    pub(super) fn insert(&mut self, key: K) {
+        let node = Box::new(Node {
+            priority: self.next_priority(),
+            key,
+            size: 1,
+            left: None,
+            right: None,
+        });
+
+        let (left, right) = split(self.root.take(), &|other: &K| *other < node.key);
+        self.root = merge(merge(left, Some(node)), right);
+    }
+
+    /// Removes `key` from the tree. Returns `true` if the key was present.
+    pub(super) fn remove(&mut self, key: &K) -> bool {
+        let (left, rest) = split(self.root.take(), &|other: &K| other < key);
+        let (found, right) = split(rest, &|other: &K| other <= key);
+        self.root = merge(left, right);
+
+        found.is_some()
+    }
+
+    /// Returns the number of leading keys for which `pred` holds. `pred` must hold for all the
+    /// keys up to some point in the ordering and for none after it.
+    pub(super) fn count_while(&self, pred: impl Fn(&K) -> bool) -> usize {
+        let mut count = 0;
+        let mut link = &self.root;
+
+        while let Some(node) = link {
+            if pred(&node.key) {
+                count += size(&node.left) + 1;
+                link = &node.right;
+            } else {
+                link = &node.left;
+            }
+        }
+
+        count
+    }
+
+    /// Returns the keys with a rank within `range`, in order.
+    pub(super) fn range(&self, range: Range<usize>) -> Vec<&K> {
+        let mut keys = Vec::with_capacity(range.len());
+        collect(&self.root, 0, &range, &mut keys);
+        keys
+    }
+
+    /// Generates the priority of a new node using xorshift.
+    fn next_priority(&mut self) -> u64 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        self.seed
+    }
+}
+
+impl<K> Node<K> {
+    /// Recomputes the subtree size after one of the children changed.
+    fn update(&mut self) {
+        self.size = 1 + size(&self.left) + size(&self.right);
+    }
+}
+
+fn size<K>(link: &Link<K>) -> usize {
+    link.as_ref().map_or(0, |node| node.size)
+}
+
+/// Splits the tree into the keys for which `pred` holds and the remaining ones.
+fn split<K>(link: Link<K>, pred: &impl Fn(&K) -> bool) -> (Link<K>, Link<K>) {
+    match link {
+        None => (None, None),
+        Some(mut node) => {
+            if pred(&node.key) {
+                let (left, right) = split(node.right.take(), pred);
+                node.right = left;
+                node.update();
+                (Some(node), right)
+            } else {
+                let (left, right) = split(node.left.take(), pred);
+                node.left = right;
+                node.update();
+                (left, Some(node))
+            }
+        }
+    }
+}
+
+/// Joins two trees where all the keys of `left` are smaller than the keys of `right`.
+fn merge<K>(left: Link<K>, right: Link<K>) -> Link<K> {
+    match (left, right) {
+        (None, right) => right,
+        (left, None) => left,
+        (Some(mut left), Some(mut right)) => {
+            if left.priority > right.priority {
+                left.right = merge(left.right.take(), Some(right));
+                left.update();
+                Some(left)
+            } else {
+                right.left = merge(Some(left), right.left.take());
+                right.update();
+                Some(right)
+            }
+        }
+    }
+}
+
+/// Pushes the keys of the subtree at `link` with a rank within `range` to `keys`. `offset` is
+/// the rank of the first key of the subtree.
+fn collect<'a, K>(link: &'a Link<K>, offset: usize, range: &Range<usize>, keys: &mut Vec<&'a K>) {
+    let node = match link {
+        Some(node) if offset < range.end && offset + node.size > range.start => node,
+        _ => return,
+    };
+
+    let rank = offset + size(&node.left);
+
+    collect(&node.left, offset, range, keys);
+    if range.contains(&rank) {
+        keys.push(&node.key);
+    }
+    collect(&node.right, rank + 1, range, keys);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a tree of the keys `0..len`, inserted out of order.
+    fn tree(len: u64) -> RankTree<u64> {
+        let mut tree = RankTree::new();
+        // 7919 is prime, hence coprime with `len` as long as `len` is smaller.
+        for i in 0..len {
+            tree.insert(i * 7919 % len);
+        }
+        tree
+    }
+
+    #[test]
+    fn rank_counts_smaller_keys() {
+        let tree = tree(1000);
+
+        assert_eq!(1000, size(&tree.root));
+        for key in [0, 1, 499, 999, 1000] {
+            assert_eq!(key as usize, tree.count_while(|other| *other < key));
+        }
+        assert_eq!(1000, tree.count_while(|_| true));
+        assert_eq!(0, tree.count_while(|_| false));
+    }
+
+    #[test]
+    fn range_returns_keys_in_order() {
+        let tree = tree(1000);
+
+        let keys: Vec<u64> = tree.range(0..1000).into_iter().copied().collect();
+        assert_eq!((0..1000).collect::<Vec<_>>(), keys);
+
+        let keys: Vec<u64> = tree.range(10..15).into_iter().copied().collect();
+        assert_eq!(vec![10, 11, 12, 13, 14], keys);
+
+        // Ranks past the last key are ignored.
+        assert_eq!(vec![&998, &999], tree.range(998..2000));
+        assert!(tree.range(5..5).is_empty());
+        assert!(tree.range(1000..1010).is_empty());
+    }
+
+    #[test]
+    fn remove_updates_ranks() {
+        let mut tree = tree(100);
+
+        for key in (0..100).step_by(2) {
+            assert!(tree.remove(&key));
+        }
+        assert!(!tree.remove(&0));
+        assert!(!tree.remove(&100));
+
+        assert_eq!(50, size(&tree.root));
+        assert_eq!(5, tree.count_while(|other| *other < 10));
+        assert_eq!(vec![&11, &13, &15], tree.range(5..8));
+    }
+}
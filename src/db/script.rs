@@ -0,0 +1,247 @@
+use crate::db::Db;
+
+use bytes::Bytes;
+use mlua::{Function, HookTriggers, Lua, LuaOptions, RegistryKey, StdLib, Table};
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+/// Lua interpreter shared by all the scripts, along with the functions compiled from the cached
+/// scripts.
+#[derive(Debug)]
+pub(super) struct Scripts {
+    lua: Lua,
+    /// Functions compiled by `EVAL` and `SCRIPT LOAD`, by SHA1 digest.
+    functions: HashMap<String, RegistryKey>,
+    /// Time after which a running script is aborted.
+    time_limit: Duration,
+}
+
+/// Time limit of the script running, stored as app data of the interpreter.
+struct Deadline {
+    /// Time after which the script is aborted.
+    at: Instant,
+    /// Modifications of the `Db` when the script started, see `State::touch`.
+    modifications: u64,
+}
+
+/// Makes the globals and the libraries of the interpreter read-only, and returns the function
+/// doing so, which `read_only` calls.
+///
+/// The fields of a read-only table are moved to a hidden table it falls back to, so that
+/// assigning any of its fields raises an error. Its metatable is hidden as well. `rawset`,
+/// `setfenv` and `getfenv` are removed, as they would bypass the metatables, and the metatable of
+/// strings is hidden, as it holds the string library.
+const READ_ONLY: &str = r#"
+local pairs, setmetatable, error = pairs, setmetatable, error
+
+local function read_only(t, fallback)
+    local fields = setmetatable({}, { __index = fallback })
+    for k, v in pairs(t) do
+        fields[k] = v
+    end
+    for k in pairs(fields) do
+        t[k] = nil
+    end
+
+    return setmetatable(t, {
+        __index = fields,
+        __newindex = function()
+            error("Attempt to modify a readonly table", 2)
+        end,
+        __metatable = false,
+    })
+end
+
+rawset, setfenv, getfenv = nil, nil, nil
+getmetatable("").__metatable = false
+
+read_only(string)
+read_only(table)
+read_only(math)
+read_only(_G)
+
+return read_only
+"#;
+
+/// Name of the function returned by `READ_ONLY` in the registry of the interpreter.
+const READ_ONLY_KEY: &str = "read_only";
+
+/// Number of instructions a script runs between two checks of its time limit.
+const TIME_LIMIT_CHECK_INTERVAL: u32 = 10_000;
+
+impl Scripts {
+    /// Create a new interpreter aborting the scripts running for longer than `time_limit`.
+    ///
+    /// Only the base, table, string and math libraries are loaded, so that scripts may not access
+    /// the file system or the environment of the server. The globals and the libraries are made
+    /// read-only, so that scripts may not affect each other through them.
+    pub(super) fn new(time_limit: Duration) -> Scripts {
+        let libs = StdLib::TABLE | StdLib::STRING | StdLib::MATH;
+
+        // `unwrap` is safe as the libraries are all safe to load.
+        let lua = Lua::new_with(libs, LuaOptions::default()).unwrap();
+
+        // `unwrap` is safe as the chunk only uses the libraries loaded.
+        let read_only: Function = lua.load(READ_ONLY).eval().unwrap();
+        lua.set_named_registry_value(READ_ONLY_KEY, read_only)
+            .unwrap();
+
+        Scripts {
+            lua,
+            functions: HashMap::new(),
+            time_limit,
+        }
+    }
+}
+
+/// Makes `table` read-only as the globals of `lua` are, falling back to `fallback` for the fields
+/// it lacks.
+pub(crate) fn read_only<'lua>(
+    lua: &'lua Lua,
+    table: Table<'lua>,
+    fallback: Table<'lua>,
+) -> mlua::Result<Table<'lua>> {
+    let read_only: Function = lua.named_registry_value(READ_ONLY_KEY)?;
+    read_only.call((table, fallback))
+}
+
+impl Db {
+    /// Compiles `script` and caches it so that it can later be run by its SHA1 digest, which is
+    /// returned. Nothing is cached if the script does not compile.
+    pub fn script_load(&self, script: Bytes) -> mlua::Result<String> {
+        let sha1 = sha1_hex(&script);
+
+        let mut scripts = self.shared.scripts.lock().unwrap();
+
+        if !scripts.functions.contains_key(&sha1) {
+            let function = scripts
+                .lua
+                .load(&script[..])
+                .set_name("@user_script")
+                .into_function()?;
+            let function = scripts.lua.create_registry_value(function)?;
+            scripts.functions.insert(sha1.clone(), function);
+        }
+
+        Ok(sha1)
+    }
+
+    /// Calls `f` with the Lua interpreter and the function compiled from the cached script whose
+    /// SHA1 digest is `sha1`, so that it runs the script. Returns `None` if no such script is
+    /// cached.
+    ///
+    /// The script raises an error once it runs for longer than the time limit, unless it already
+    /// modified this `Db`: aborting it would then leave its modifications partially applied, so
+    /// it runs to completion instead. This `Db` is available to the functions the script calls as
+    /// app data of the interpreter.
+    pub(crate) fn run_script<R>(
+        &self,
+        sha1: &str,
+        f: impl FnOnce(&Lua, Function) -> mlua::Result<R>,
+    ) -> Option<mlua::Result<R>> {
+        let scripts = self.shared.scripts.lock().unwrap();
+        let lua = &scripts.lua;
+
+        let function = match scripts.functions.get(&sha1.to_lowercase()) {
+            Some(function) => lua.registry_value(function),
+            None => return None,
+        };
+
+        /// Removes the app data and the hook set for the script once it returns, even if `f`
+        /// panics. The interpreter would otherwise keep this `Db` alive.
+        struct Cleanup<'a>(&'a Lua);
+
+        impl Drop for Cleanup<'_> {
+            fn drop(&mut self) {
+                self.0.remove_app_data::<Db>();
+                self.0.remove_app_data::<Deadline>();
+                self.0.remove_hook();
+            }
+        }
+
+        lua.set_app_data(self.clone());
+        let _cleanup = Cleanup(lua);
+
+        // A time limit too far in the future to be represented is the same as no limit.
+        if let Some(at) = Instant::now().checked_add(scripts.time_limit) {
+            let modifications = self.shared.lock_state().modifications;
+            lua.set_app_data(Deadline { at, modifications });
+
+            let triggers = HookTriggers::new().every_nth_instruction(TIME_LIMIT_CHECK_INTERVAL);
+            lua.set_hook(triggers, |lua, _| {
+                // `unwrap` is safe as the hook is removed along with the app data.
+                lua.app_data_ref::<Db>().unwrap().check_time_limit(lua)
+            });
+        }
+
+        Some(function.and_then(|function| f(lua, function)))
+    }
+
+    /// Returns an error if the script run by `lua` exceeded its time limit without modifying
+    /// this `Db`, as checked periodically while it runs. Scripts must also check it before
+    /// applying a command, so that they do not start modifying the `Db` once past the limit.
+    pub(crate) fn check_time_limit(&self, lua: &Lua) -> mlua::Result<()> {
+        let deadline = match lua.app_data_ref::<Deadline>() {
+            Some(deadline) if Instant::now() > deadline.at => deadline,
+            _ => return Ok(()),
+        };
+
+        if self.shared.lock_state().modifications == deadline.modifications {
+            Err(mlua::Error::runtime("script exceeded the time limit"))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns whether each of the given SHA1 digests identifies a cached script.
+    pub fn script_exists(&self, sha1s: &[String]) -> Vec<bool> {
+        let scripts = self.shared.scripts.lock().unwrap();
+        sha1s
+            .iter()
+            .map(|sha1| scripts.functions.contains_key(&sha1.to_lowercase()))
+            .collect()
+    }
+
+    /// Removes all the cached scripts.
+    pub fn script_flush(&self) {
+        let mut scripts = self.shared.scripts.lock().unwrap();
+        scripts.functions.clear();
+        scripts.lua.expire_registry_values();
+    }
+}
+
+/// Returns the SHA1 digest of `script` as a lowercase hexadecimal string.
+fn sha1_hex(script: &[u8]) -> String {
+    Sha1::digest(script)
+        .iter()
+        .fold(String::with_capacity(40), |mut hex, byte| {
+            // Writing to a `String` cannot fail.
+            let _ = write!(hex, "{:02x}", byte);
+            hex
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn panicking_script_does_not_keep_the_db_alive() {
+        let db = Db::default();
+        let sha1 = db.script_load(Bytes::from("return 1")).unwrap();
+
+        let res = panic::catch_unwind(AssertUnwindSafe(|| {
+            db.run_script(&sha1, |_, _| -> mlua::Result<()> {
+                panic!("script panicked")
+            })
+        }));
+        assert!(res.is_err());
+
+        // Only this handle and the background task hold the state.
+        assert_eq!(2, Arc::strong_count(&db.shared));
+    }
+}
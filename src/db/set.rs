@@ -0,0 +1,293 @@
+use crate::db::{Db, DbError, KeyspaceEvent, State, Value};
+
+use bytes::Bytes;
+use std::collections::HashSet;
+
+/// Operation combining the sets stored at several keys.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SetOp {
+    /// Members present in all the sets.
+    Inter,
+    /// Members present in any of the sets.
+    Union,
+    /// Members of the first set that are not present in any of the following sets.
+    Diff,
+}
+
+impl Db {
+    /// Adds `members` to the set stored at `key`. The set is created if the key does not exist.
+    ///
+    /// Returns the number of members that were added, not counting the ones already present.
+    pub fn sadd(&self, key: &str, members: Vec<Bytes>) -> Result<usize, DbError> {
+        let mut state = self.shared.lock_state();
+
+        let set = state.set_entry(key)?;
+
+        let mut added = 0;
+        for member in members {
+            if set.insert(member) {
+                added += 1;
+            }
+        }
+
+        // The set may have been created for nothing if all the members were already present.
+        state.remove_if_empty(key);
+        if added > 0 {
+            state.touch(key);
+            state.notify_keyspace_event(KeyspaceEvent::SAdd, key);
+        }
+
+        Ok(added)
+    }
+
+    /// Removes `members` from the set stored at `key`. The key is removed along with the last
+    /// member.
+    ///
+    /// Returns the number of members that were removed.
+    pub fn srem(&self, key: &str, members: &[Bytes]) -> Result<usize, DbError> {
+        let mut state = self.shared.lock_state();
+
+        let set = match state.get_set_mut(key)? {
+            Some(set) => set,
+            None => return Ok(0),
+        };
+
+        let removed = members.iter().filter(|member| set.remove(*member)).count();
+
+        if removed > 0 {
+            state.touch(key);
+            state.notify_keyspace_event(KeyspaceEvent::SRem, key);
+        }
+        state.remove_if_drained(key);
+
+        Ok(removed)
+    }
+
+    /// Returns all the members of the set stored at `key`.
+    pub fn smembers(&self, key: &str) -> Result<Vec<Bytes>, DbError> {
+        let state = self.shared.lock_state();
+        Ok(state
+            .get_set(key)?
+            .map_or_else(Vec::new, |set| set.iter().cloned().collect()))
+    }
+
+    /// Returns `true` if `member` belongs to the set stored at `key`.
+    pub fn sismember(&self, key: &str, member: &[u8]) -> Result<bool, DbError> {
+        let state = self.shared.lock_state();
+        Ok(state.get_set(key)?.is_some_and(|set| set.contains(member)))
+    }
+
+    /// Returns the number of members of the set stored at `key`, or `0` if the key does not
+    /// exist.
+    pub fn scard(&self, key: &str) -> Result<usize, DbError> {
+        let state = self.shared.lock_state();
+        Ok(state.get_set(key)?.map_or(0, |set| set.len()))
+    }
+
+    /// Combines the sets stored at `keys` according to `op`. Missing keys are treated as empty
+    /// sets.
+    pub fn combine(&self, op: SetOp, keys: &[String]) -> Result<Vec<Bytes>, DbError> {
+        let state = self.shared.lock_state();
+        Ok(state.combine(op, keys)?.into_iter().collect())
+    }
+
+    /// Combines the sets stored at `keys` according to `op` and stores the result at
+    /// `destination`, overwriting any previous value. An empty result removes `destination`.
+    ///
+    /// Returns the number of members of the resulting set.
+    pub fn combine_store(
+        &self,
+        op: SetOp,
+        destination: String,
+        keys: &[String],
+    ) -> Result<usize, DbError> {
+        let mut state = self.shared.lock_state();
+
+        let set = state.combine(op, keys)?;
+        let len = set.len();
+
+        let event = match op {
+            SetOp::Inter => KeyspaceEvent::SInterStore,
+            SetOp::Union => KeyspaceEvent::SUnionStore,
+            SetOp::Diff => KeyspaceEvent::SDiffStore,
+        };
+
+        // An empty result only deletes the destination, as Redis does.
+        if set.is_empty() {
+            if state.remove(&destination).is_some() {
+                state.notify_keyspace_event(KeyspaceEvent::Del, &destination);
+            }
+        } else {
+            state.insert(destination.clone(), Value::Set(set), None);
+            state.notify_keyspace_event(event, &destination);
+        }
+        state.touch(&destination);
+
+        Ok(len)
+    }
+}
+
+impl State {
+    /// Returns the set stored at `key`, or `None` if the key does not exist.
+    fn get_set(&self, key: &str) -> Result<Option<&HashSet<Bytes>>, DbError> {
+        match self.entries.get(key).map(|entry| &entry.data) {
+            Some(Value::Set(set)) => Ok(Some(set)),
+            Some(_) => Err(DbError::WrongType),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the set stored at `key` for modification, or `None` if the key does not exist.
+    fn get_set_mut(&mut self, key: &str) -> Result<Option<&mut HashSet<Bytes>>, DbError> {
+        match self.entries.get_mut(key).map(|entry| &mut entry.data) {
+            Some(Value::Set(set)) => Ok(Some(set)),
+            Some(_) => Err(DbError::WrongType),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the set stored at `key` for modification, creating an empty set if the key does
+    /// not exist. Callers must follow up with `remove_if_empty` in case nothing was added.
+    fn set_entry(&mut self, key: &str) -> Result<&mut HashSet<Bytes>, DbError> {
+        if !self.entries.contains_key(key) {
+            self.insert(key.to_string(), Value::Set(HashSet::new()), None);
+        }
+
+        // `unwrap` is safe as the key was inserted above.
+        Ok(self.get_set_mut(key)?.unwrap())
+    }
+
+    /// Combines the sets stored at `keys` according to `op`. All keys are checked to hold sets,
+    /// even if the result is known to be empty early.
+    fn combine(&self, op: SetOp, keys: &[String]) -> Result<HashSet<Bytes>, DbError> {
+        let sets = keys
+            .iter()
+            .map(|key| self.get_set(key))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (first, rest) = match sets.split_first() {
+            Some((first, rest)) => (first.cloned().unwrap_or_default(), rest),
+            None => return Ok(HashSet::new()),
+        };
+
+        let combined = match op {
+            SetOp::Inter => first
+                .into_iter()
+                .filter(|member| {
+                    rest.iter()
+                        .all(|set| set.is_some_and(|set| set.contains(member)))
+                })
+                .collect(),
+            SetOp::Union => {
+                let mut combined = first;
+                for set in rest.iter().flatten() {
+                    combined.extend(set.iter().cloned());
+                }
+                combined
+            }
+            SetOp::Diff => first
+                .into_iter()
+                .filter(|member| !rest.iter().flatten().any(|set| set.contains(member)))
+                .collect(),
+        };
+
+        Ok(combined)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the given members as a set's members.
+    fn members(members: &[&'static str]) -> Vec<Bytes> {
+        members.iter().map(|member| Bytes::from(*member)).collect()
+    }
+
+    /// Returns `members` sorted, as sets are not ordered.
+    fn sorted(mut members: Vec<Bytes>) -> Vec<Bytes> {
+        members.sort();
+        members
+    }
+
+    #[tokio::test]
+    async fn add_and_remove_members() {
+        let db = Db::default();
+
+        assert_eq!(Ok(2), db.sadd("set", members(&["a", "b"])));
+        // Members already present are not counted.
+        assert_eq!(Ok(1), db.sadd("set", members(&["b", "c"])));
+
+        assert_eq!(Ok(3), db.scard("set"));
+        assert_eq!(Ok(0), db.scard("missing"));
+        assert_eq!(Ok(true), db.sismember("set", b"a"));
+        assert_eq!(Ok(false), db.sismember("set", b"d"));
+        assert_eq!(
+            members(&["a", "b", "c"]),
+            sorted(db.smembers("set").unwrap())
+        );
+
+        assert_eq!(Ok(1), db.srem("set", &members(&["a", "d"])));
+        assert_eq!(Ok(0), db.srem("missing", &members(&["a"])));
+
+        // The key is removed along with the last member.
+        assert_eq!(Ok(2), db.srem("set", &members(&["b", "c"])));
+        assert_eq!(0, db.exists(&["set".to_string()]));
+    }
+
+    #[tokio::test]
+    async fn combine_sets() {
+        let db = Db::default();
+
+        db.sadd("first", members(&["a", "b", "c"])).unwrap();
+        db.sadd("second", members(&["b", "c", "d"])).unwrap();
+        db.sadd("third", members(&["c"])).unwrap();
+
+        let keys = |keys: &[&str]| keys.iter().map(|key| key.to_string()).collect::<Vec<_>>();
+
+        let inter = db.combine(SetOp::Inter, &keys(&["first", "second", "third"]));
+        assert_eq!(members(&["c"]), sorted(inter.unwrap()));
+        let union = db.combine(SetOp::Union, &keys(&["first", "second"]));
+        assert_eq!(members(&["a", "b", "c", "d"]), sorted(union.unwrap()));
+        let diff = db.combine(SetOp::Diff, &keys(&["first", "second", "missing"]));
+        assert_eq!(members(&["a"]), sorted(diff.unwrap()));
+
+        // Missing keys are empty sets.
+        let inter = db.combine(SetOp::Inter, &keys(&["first", "missing"]));
+        assert_eq!(Ok(vec![]), inter);
+
+        let stored = db.combine_store(SetOp::Union, "dest".to_string(), &keys(&["first", "third"]));
+        assert_eq!(Ok(3), stored);
+        assert_eq!(
+            members(&["a", "b", "c"]),
+            sorted(db.smembers("dest").unwrap())
+        );
+
+        // An empty result removes the destination.
+        let stored = db.combine_store(SetOp::Inter, "dest".to_string(), &keys(&["missing"]));
+        assert_eq!(Ok(0), stored);
+        assert_eq!(0, db.exists(&["dest".to_string()]));
+    }
+
+    #[tokio::test]
+    async fn set_operations_on_other_types_fail() {
+        let db = Db::default();
+
+        db.set("string".to_string(), Bytes::from("value"), None);
+        db.sadd("set", members(&["a"])).unwrap();
+
+        assert_eq!(Err(DbError::WrongType), db.sadd("string", members(&["a"])));
+        assert_eq!(Err(DbError::WrongType), db.sismember("string", b"a"));
+        assert_eq!(Err(DbError::WrongType), db.scard("string"));
+
+        // Every key is checked, even once the result is known to be empty.
+        let keys = vec!["missing".to_string(), "string".to_string()];
+        assert_eq!(Err(DbError::WrongType), db.combine(SetOp::Inter, &keys));
+
+        // The destination is left alone when combining fails.
+        let keys = vec!["set".to_string(), "string".to_string()];
+        let stored = db.combine_store(SetOp::Union, "set".to_string(), &keys);
+        assert_eq!(Err(DbError::WrongType), stored);
+        assert_eq!(members(&["a"]), db.smembers("set").unwrap());
+    }
+}
@@ -0,0 +1,600 @@
+use crate::db::{Db, DbError, KeyspaceEvent, State, Value};
+
+mod group;
+use group::ConsumerGroup;
+pub use group::{PendingEntry, PendingSummary};
+
+use bytes::Bytes;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::future::{self, Future};
+use std::ops::Bound;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+use tokio::time::{self, Duration, Instant};
+
+/// An append-only log of entries, each identified by a unique and increasing ID.
+#[derive(Debug, Clone)]
+pub(super) struct Stream {
+    entries: BTreeMap<StreamId, Vec<(Bytes, Bytes)>>,
+    /// ID of the last entry ever added. New entries must have a greater ID, even if the last
+    /// entry was trimmed since.
+    last_id: StreamId,
+    /// Consumer groups of the stream, by name.
+    groups: HashMap<String, ConsumerGroup>,
+}
+
+/// Identifier of a stream entry, made of the creation time in milliseconds and a sequence
+/// number distinguishing entries created during the same millisecond.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct StreamId {
+    pub ms: u64,
+    pub seq: u64,
+}
+
+/// ID requested for an entry added by `XADD`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum XAddId {
+    /// Generate the ID from the current time.
+    Auto,
+    /// Use the given milliseconds and generate the sequence number.
+    Ms(u64),
+    /// Use the given ID, which must be greater than the last ID of the stream.
+    Explicit(StreamId),
+}
+
+/// A stream entry, made of its ID and its field-value pairs.
+pub type StreamEntry = (StreamId, Vec<(Bytes, Bytes)>);
+
+/// A stream entry read by a consumer, made of its ID and its field-value pairs, or `None` if the
+/// entry is pending but was removed from the stream since it was delivered.
+pub type GroupEntry = (StreamId, Option<Vec<(Bytes, Bytes)>>);
+
+impl Db {
+    /// Appends an entry made of `fields` to the stream stored at `key`, then trims the stream to
+    /// `max_len` entries if given. The stream is created if the key does not exist, unless
+    /// `no_mk_stream` is set.
+    ///
+    /// Returns the ID of the new entry, or `None` if the key does not exist and `no_mk_stream` is
+    /// set.
+    pub fn xadd(
+        &self,
+        key: &str,
+        id: XAddId,
+        fields: Vec<(Bytes, Bytes)>,
+        max_len: Option<usize>,
+        no_mk_stream: bool,
+    ) -> Result<Option<StreamId>, DbError> {
+        let mut state = self.shared.lock_state();
+
+        // The ID is checked before creating the stream, so that no empty stream is left behind.
+        let id = match state.get_stream(key)? {
+            Some(stream) => stream.next_id(id)?,
+            None if no_mk_stream => return Ok(None),
+            None => Stream::new().next_id(id)?,
+        };
+
+        let stream = state.stream_entry(key)?;
+
+        stream.entries.insert(id, fields);
+        stream.last_id = id;
+
+        let trimmed = max_len.map_or(0, |max_len| stream.trim(max_len));
+
+        state.touch(key);
+        state.notify_keyspace_event(KeyspaceEvent::XAdd, key);
+        if trimmed > 0 {
+            state.notify_keyspace_event(KeyspaceEvent::XTrim, key);
+        }
+
+        drop(state);
+
+        // Wake up the clients blocked in `XREAD`. There may be none, in which case the error is
+        // ignored.
+        let _ = self.shared.stream_appends.send(key.to_string());
+
+        Ok(Some(id))
+    }
+
+    /// Returns the entries of the stream stored at `key` with an ID between `start` and `end`,
+    /// in increasing order, or in decreasing order if `rev` is set. At most `count` entries are
+    /// returned if given.
+    pub fn xrange(
+        &self,
+        key: &str,
+        start: Bound<StreamId>,
+        end: Bound<StreamId>,
+        count: Option<usize>,
+        rev: bool,
+    ) -> Result<Vec<StreamEntry>, DbError> {
+        let state = self.shared.lock_state();
+
+        let stream = match state.get_stream(key)? {
+            Some(stream) => stream,
+            None => return Ok(vec![]),
+        };
+
+        Ok(stream.range(start, end, count.unwrap_or(usize::MAX), rev))
+    }
+
+    /// Returns the number of entries of the stream stored at `key`, or `0` if the key does not
+    /// exist.
+    pub fn xlen(&self, key: &str) -> Result<usize, DbError> {
+        let state = self.shared.lock_state();
+        Ok(state
+            .get_stream(key)?
+            .map_or(0, |stream| stream.entries.len()))
+    }
+
+    /// Removes the oldest entries of the stream stored at `key` until at most `max_len` remain.
+    ///
+    /// Returns the number of entries that were removed.
+    pub fn xtrim(&self, key: &str, max_len: usize) -> Result<usize, DbError> {
+        let mut state = self.shared.lock_state();
+
+        let trimmed = state
+            .get_stream_mut(key)?
+            .map_or(0, |stream| stream.trim(max_len));
+
+        if trimmed > 0 {
+            state.touch(key);
+            state.notify_keyspace_event(KeyspaceEvent::XTrim, key);
+        }
+
+        Ok(trimmed)
+    }
+
+    /// Returns the last ID of each of the streams stored at `keys`, or `0-0` for the keys that
+    /// do not exist.
+    pub fn xlast_ids(&self, keys: &[String]) -> Result<Vec<StreamId>, DbError> {
+        let state = self.shared.lock_state();
+
+        keys.iter()
+            .map(|key| {
+                Ok(state
+                    .get_stream(key)?
+                    .map_or_else(StreamId::default, |s| s.last_id))
+            })
+            .collect()
+    }
+
+    /// Returns up to `count` entries with an ID greater than the given one from each of the
+    /// given streams. Streams without such entries are omitted.
+    pub fn xread(
+        &self,
+        streams: &[(String, StreamId)],
+        count: Option<usize>,
+    ) -> Result<Vec<(String, Vec<StreamEntry>)>, DbError> {
+        let state = self.shared.lock_state();
+
+        let mut read = vec![];
+        for (key, id) in streams {
+            let stream = match state.get_stream(key)? {
+                Some(stream) => stream,
+                None => continue,
+            };
+
+            let count = count.unwrap_or(usize::MAX);
+            let entries = stream.range(Bound::Excluded(*id), Bound::Unbounded, count, false);
+
+            if !entries.is_empty() {
+                read.push((key.clone(), entries));
+            }
+        }
+
+        Ok(read)
+    }
+
+    /// Reads entries from the given streams as `xread` does, a `None` ID standing for the last
+    /// ID of its stream, so that only the entries appended from now on are read.
+    ///
+    /// If none of the streams holds such entries, waits until entries are appended to one of
+    /// them. No streams are returned if none were appended before `timeout` elapsed or `cancel`
+    /// completed. A `timeout` of `None` waits forever.
+    pub async fn blocking_xread(
+        &self,
+        streams: &[(String, Option<StreamId>)],
+        count: Option<usize>,
+        timeout: Option<Duration>,
+        cancel: impl Future<Output = ()>,
+    ) -> Result<Vec<(String, Vec<StreamEntry>)>, DbError> {
+        // Subscribe before reading the last IDs, so that no entry added in between is missed.
+        let appends = self.stream_appends();
+
+        let keys: Vec<String> = streams.iter().map(|(key, _)| key.clone()).collect();
+        let last_ids = {
+            let _section = self.enter_section().await;
+            self.xlast_ids(&keys)?
+        };
+
+        let streams: Vec<(String, StreamId)> = streams
+            .iter()
+            .zip(last_ids)
+            .map(|((key, id), last_id)| (key.clone(), id.unwrap_or(last_id)))
+            .collect();
+
+        let read = || self.xread(&streams, count);
+        block_on_streams(self, appends, &keys, timeout, cancel, read).await
+    }
+
+    /// Reads entries from the given streams on behalf of a consumer as `xread_group` does,
+    /// waiting for entries to be appended as `blocking_xread` does if no entries were delivered.
+    ///
+    /// Reading pending entries always returns the streams, and thus never waits.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn blocking_xread_group(
+        &self,
+        group: &str,
+        consumer: &str,
+        streams: &[(String, Option<StreamId>)],
+        count: Option<usize>,
+        no_ack: bool,
+        timeout: Option<Duration>,
+        cancel: impl Future<Output = ()>,
+    ) -> Result<Vec<(String, Vec<GroupEntry>)>, DbError> {
+        // Subscribe before reading, so that no entry added in between is missed.
+        let appends = self.stream_appends();
+
+        let keys: Vec<String> = streams.iter().map(|(key, _)| key.clone()).collect();
+
+        let read = || self.xread_group(group, consumer, streams, count, no_ack);
+        block_on_streams(self, appends, &keys, timeout, cancel, read).await
+    }
+
+    /// Returns a `Receiver` for the keys of the streams entries are appended to.
+    ///
+    /// The receiver must be created before reading the streams, so that no append is missed in
+    /// between. Appends are dropped for receivers lagging too far behind, which receive
+    /// `RecvError::Lagged` instead.
+    pub fn stream_appends(&self) -> broadcast::Receiver<String> {
+        self.shared.stream_appends.subscribe()
+    }
+}
+
+/// Calls `read` until it returns entries, waiting for entries to be appended to the streams
+/// stored at `keys` in between. `appends` must be created before the first call to `read`, which
+/// is made within an exclusive section of `db` entered asynchronously.
+///
+/// Returns no streams if none were read before `timeout` elapsed or `cancel` completed.
+async fn block_on_streams<T>(
+    db: &Db,
+    mut appends: broadcast::Receiver<String>,
+    keys: &[String],
+    timeout: Option<Duration>,
+    cancel: impl Future<Output = ()>,
+    mut read: impl FnMut() -> Result<Vec<(String, Vec<T>)>, DbError>,
+) -> Result<Vec<(String, Vec<T>)>, DbError> {
+    // A deadline too far in the future to be represented is the same as no deadline.
+    let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+    let sleep = async {
+        match deadline {
+            Some(when) => time::sleep_until(when).await,
+            None => future::pending().await,
+        }
+    };
+
+    tokio::pin!(sleep);
+    tokio::pin!(cancel);
+
+    loop {
+        let entries = {
+            let _section = db.enter_section().await;
+            read()?
+        };
+        if !entries.is_empty() {
+            return Ok(entries);
+        }
+
+        // Wait for an entry to be added to one of the streams, then read them again. They are
+        // read one last time once the timeout elapses, as entries may have been added right
+        // before.
+        loop {
+            tokio::select! {
+                res = appends.recv() => match res {
+                    Ok(key) if !keys.contains(&key) => continue,
+                    // Appends may have been missed when lagging, so read the streams again.
+                    _ => break,
+                },
+                _ = &mut sleep => {
+                    let _section = db.enter_section().await;
+                    return read();
+                }
+                _ = &mut cancel => return Ok(vec![]),
+            }
+        }
+    }
+}
+
+impl Stream {
+    pub(super) fn new() -> Stream {
+        Stream {
+            entries: BTreeMap::new(),
+            last_id: StreamId::default(),
+            groups: HashMap::new(),
+        }
+    }
+
+    /// Computes the ID of a new entry as requested by `id`.
+    fn next_id(&self, id: XAddId) -> Result<StreamId, DbError> {
+        let last = self.last_id;
+
+        let id = match id {
+            XAddId::Auto => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |now| now.as_millis() as u64);
+
+                // The clock may have gone backwards, IDs must keep increasing regardless.
+                if now > last.ms {
+                    Some(StreamId { ms: now, seq: 0 })
+                } else {
+                    last.successor()
+                }
+            }
+            XAddId::Ms(ms) if ms == last.ms => last.successor(),
+            XAddId::Ms(ms) => Some(StreamId { ms, seq: 0 }),
+            XAddId::Explicit(id) => Some(id),
+        };
+
+        match id {
+            Some(id) if id == StreamId::default() => Err(DbError::StreamIdZero),
+            Some(id) if id > last => Ok(id),
+            _ => Err(DbError::StreamIdTooSmall),
+        }
+    }
+
+    /// Returns up to `count` entries with an ID between `start` and `end`.
+    fn range(
+        &self,
+        start: Bound<StreamId>,
+        end: Bound<StreamId>,
+        count: usize,
+        rev: bool,
+    ) -> Vec<StreamEntry> {
+        if is_empty_range(start, end) {
+            return vec![];
+        }
+
+        let range = self.entries.range((start, end));
+        let entries: Box<dyn Iterator<Item = _>> = if rev {
+            Box::new(range.rev())
+        } else {
+            Box::new(range)
+        };
+
+        entries
+            .take(count)
+            .map(|(id, fields)| (*id, fields.clone()))
+            .collect()
+    }
+
+    /// Removes the oldest entries until at most `max_len` remain. Returns the number of entries
+    /// that were removed.
+    fn trim(&mut self, max_len: usize) -> usize {
+        let excess = self.entries.len().saturating_sub(max_len);
+
+        for _ in 0..excess {
+            self.entries.pop_first();
+        }
+
+        excess
+    }
+}
+
+/// Returns `true` if no ID lies between `start` and `end`.
+///
+/// `BTreeMap::range` panics on ranges ending before they start, which must be checked first.
+fn is_empty_range(start: Bound<StreamId>, end: Bound<StreamId>) -> bool {
+    match (start, end) {
+        (Bound::Included(start), Bound::Included(end)) => start > end,
+        (Bound::Included(start), Bound::Excluded(end))
+        | (Bound::Excluded(start), Bound::Included(end))
+        | (Bound::Excluded(start), Bound::Excluded(end)) => start >= end,
+        _ => false,
+    }
+}
+
+impl StreamId {
+    /// Returns the smallest ID greater than `self`, if any.
+    fn successor(self) -> Option<StreamId> {
+        match self.seq.checked_add(1) {
+            Some(seq) => Some(StreamId { ms: self.ms, seq }),
+            None => Some(StreamId {
+                ms: self.ms.checked_add(1)?,
+                seq: 0,
+            }),
+        }
+    }
+}
+
+impl fmt::Display for StreamId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.ms, self.seq)
+    }
+}
+
+impl State {
+    /// Returns the stream stored at `key`, or `None` if the key does not exist.
+    fn get_stream(&self, key: &str) -> Result<Option<&Stream>, DbError> {
+        match self.entries.get(key).map(|entry| &entry.data) {
+            Some(Value::Stream(stream)) => Ok(Some(stream)),
+            Some(_) => Err(DbError::WrongType),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the stream stored at `key` for modification, or `None` if the key does not
+    /// exist.
+    fn get_stream_mut(&mut self, key: &str) -> Result<Option<&mut Stream>, DbError> {
+        match self.entries.get_mut(key).map(|entry| &mut entry.data) {
+            Some(Value::Stream(stream)) => Ok(Some(stream)),
+            Some(_) => Err(DbError::WrongType),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the stream stored at `key` for modification, creating an empty stream if the key
+    /// does not exist. Unlike other aggregate values, empty streams are kept.
+    fn stream_entry(&mut self, key: &str) -> Result<&mut Stream, DbError> {
+        if !self.entries.contains_key(key) {
+            self.insert(key.to_string(), Value::Stream(Stream::new()), None);
+        }
+
+        // `unwrap` is safe as the key was inserted above.
+        Ok(self.get_stream_mut(key)?.unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a single field-value pair to add as an entry.
+    fn fields(value: &'static str) -> Vec<(Bytes, Bytes)> {
+        vec![(Bytes::from("field"), Bytes::from(value))]
+    }
+
+    /// Returns the IDs of `entries`.
+    fn ids(entries: &[StreamEntry]) -> Vec<StreamId> {
+        entries.iter().map(|(id, _)| *id).collect()
+    }
+
+    fn id(ms: u64, seq: u64) -> StreamId {
+        StreamId { ms, seq }
+    }
+
+    #[tokio::test]
+    async fn ids_are_generated_in_increasing_order() {
+        let db = Db::default();
+
+        let add = |id| db.xadd("stream", id, fields("v"), None, false);
+
+        assert_eq!(Ok(Some(id(5, 0))), add(XAddId::Ms(5)));
+        assert_eq!(Ok(Some(id(5, 1))), add(XAddId::Ms(5)));
+        assert_eq!(Ok(Some(id(5, 3))), add(XAddId::Explicit(id(5, 3))));
+        assert_eq!(Ok(Some(id(7, 0))), add(XAddId::Ms(7)));
+
+        assert_eq!(
+            Err(DbError::StreamIdTooSmall),
+            add(XAddId::Explicit(id(7, 0)))
+        );
+        assert_eq!(Err(DbError::StreamIdTooSmall), add(XAddId::Ms(6)));
+
+        // Automatic IDs follow the clock, which is far ahead of the explicit IDs.
+        let auto = add(XAddId::Auto).unwrap().unwrap();
+        assert!(auto > id(7, 0));
+        let next = add(XAddId::Auto).unwrap().unwrap();
+        assert!(next > auto);
+
+        // IDs beyond the clock are still followed by greater automatic IDs.
+        let ahead = id(u64::MAX - 1, u64::MAX);
+        assert_eq!(Ok(Some(ahead)), add(XAddId::Explicit(ahead)));
+        assert_eq!(Ok(Some(id(u64::MAX, 0))), add(XAddId::Auto));
+
+        assert_eq!(Ok(8), db.xlen("stream"));
+    }
+
+    #[tokio::test]
+    async fn zero_id_is_rejected_without_creating_the_stream() {
+        let db = Db::default();
+
+        let added = db.xadd(
+            "stream",
+            XAddId::Explicit(id(0, 0)),
+            fields("v"),
+            None,
+            false,
+        );
+        assert_eq!(Err(DbError::StreamIdZero), added);
+        assert_eq!(None, db.key_type("stream"));
+
+        // `NOMKSTREAM` does not create the stream either.
+        let added = db.xadd("stream", XAddId::Auto, fields("v"), None, true);
+        assert_eq!(Ok(None), added);
+        assert_eq!(None, db.key_type("stream"));
+    }
+
+    #[tokio::test]
+    async fn range_and_read_entries() {
+        let db = Db::default();
+
+        for ms in 1..=5 {
+            db.xadd("stream", XAddId::Ms(ms), fields("v"), None, false)
+                .unwrap();
+        }
+
+        let range = |start, end, count, rev| db.xrange("stream", start, end, count, rev).unwrap();
+
+        let all = range(Bound::Unbounded, Bound::Unbounded, None, false);
+        assert_eq!(
+            vec![id(1, 0), id(2, 0), id(3, 0), id(4, 0), id(5, 0)],
+            ids(&all)
+        );
+        assert_eq!(fields("v"), all[0].1);
+
+        let some = range(
+            Bound::Included(id(2, 0)),
+            Bound::Excluded(id(5, 0)),
+            None,
+            false,
+        );
+        assert_eq!(vec![id(2, 0), id(3, 0), id(4, 0)], ids(&some));
+
+        let rev = range(Bound::Unbounded, Bound::Included(id(4, 0)), Some(2), true);
+        assert_eq!(vec![id(4, 0), id(3, 0)], ids(&rev));
+
+        // Ranges ending before they start are empty.
+        let empty = range(
+            Bound::Included(id(4, 0)),
+            Bound::Included(id(2, 0)),
+            None,
+            false,
+        );
+        assert!(empty.is_empty());
+        let empty = range(
+            Bound::Excluded(id(3, 0)),
+            Bound::Excluded(id(3, 0)),
+            None,
+            false,
+        );
+        assert!(empty.is_empty());
+
+        let streams = vec![
+            ("stream".to_string(), id(3, 0)),
+            ("missing".to_string(), id(0, 0)),
+        ];
+        let read = db.xread(&streams, Some(1)).unwrap();
+        assert_eq!(1, read.len());
+        assert_eq!("stream", read[0].0);
+        assert_eq!(vec![id(4, 0)], ids(&read[0].1));
+
+        // Nothing is read past the last entry.
+        let streams = vec![("stream".to_string(), id(5, 0))];
+        assert_eq!(Ok(vec![]), db.xread(&streams, None));
+    }
+
+    #[tokio::test]
+    async fn trimming_keeps_ids_increasing() {
+        let db = Db::default();
+
+        for ms in 1..=5 {
+            db.xadd("stream", XAddId::Ms(ms), fields("v"), Some(3), false)
+                .unwrap();
+        }
+
+        let all = db
+            .xrange("stream", Bound::Unbounded, Bound::Unbounded, None, false)
+            .unwrap();
+        assert_eq!(vec![id(3, 0), id(4, 0), id(5, 0)], ids(&all));
+
+        assert_eq!(Ok(3), db.xtrim("stream", 0));
+        assert_eq!(Ok(0), db.xtrim("stream", 0));
+        assert_eq!(Ok(0), db.xlen("stream"));
+
+        // The emptied stream is kept, along with its last ID.
+        assert_eq!(Some("stream"), db.key_type("stream"));
+        assert_eq!(Ok(vec![id(5, 0)]), db.xlast_ids(&["stream".to_string()]));
+        let added = db.xadd("stream", XAddId::Ms(4), fields("v"), None, false);
+        assert_eq!(Err(DbError::StreamIdTooSmall), added);
+    }
+}
@@ -0,0 +1,827 @@
+use crate::db::stream::{is_empty_range, GroupEntry, Stream, StreamEntry, StreamId};
+use crate::db::{Db, DbError, KeyspaceEvent, State};
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::Bound;
+use tokio::time::{Duration, Instant};
+
+/// A named group of consumers sharing the entries of a stream, so that each entry is delivered
+/// to a single consumer of the group.
+#[derive(Debug, Clone)]
+pub(in crate::db) struct ConsumerGroup {
+    /// ID of the last entry delivered to the group. `XREADGROUP` with the `>` ID delivers the
+    /// entries following it.
+    last_delivered: StreamId,
+    /// Entries delivered to a consumer but not acknowledged yet, by ID.
+    pending: BTreeMap<StreamId, Delivery>,
+    consumers: HashMap<String, Consumer>,
+}
+
+/// Delivery of a pending entry.
+#[derive(Debug, Clone)]
+struct Delivery {
+    /// Name of the consumer owning the entry.
+    consumer: String,
+    /// Last time the entry was delivered, from which its idle time is computed.
+    delivered_at: Instant,
+    /// Number of times the entry was delivered.
+    delivery_count: u64,
+}
+
+#[derive(Debug, Clone)]
+struct Consumer {
+    /// IDs of the pending entries owned by the consumer.
+    pending: BTreeSet<StreamId>,
+}
+
+/// Summary of the pending entries of a consumer group, as returned by `XPENDING`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingSummary {
+    /// Number of pending entries.
+    pub count: usize,
+    /// Smallest and greatest IDs of the pending entries, if any.
+    pub range: Option<(StreamId, StreamId)>,
+    /// Number of pending entries of each consumer owning some, ordered by consumer name.
+    pub consumers: Vec<(String, usize)>,
+}
+
+/// A pending entry of a consumer group, as returned by `XPENDING`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingEntry {
+    pub id: StreamId,
+    /// Name of the consumer owning the entry.
+    pub consumer: String,
+    /// Time elapsed since the entry was last delivered.
+    pub idle: Duration,
+    /// Number of times the entry was delivered.
+    pub delivery_count: u64,
+}
+
+impl Db {
+    /// Creates the consumer group `group` for the stream stored at `key`, delivering the entries
+    /// following `id`, or only the entries added from now on if `id` is `None`. A missing
+    /// stream is created empty if `mk_stream` is set.
+    pub fn xgroup_create(
+        &self,
+        key: &str,
+        group: &str,
+        id: Option<StreamId>,
+        mk_stream: bool,
+    ) -> Result<(), DbError> {
+        let mut state = self.shared.lock_state();
+
+        let stream = match state.get_stream_mut(key)? {
+            Some(stream) => stream,
+            None if mk_stream => state.stream_entry(key)?,
+            None => return Err(DbError::NoSuchKey),
+        };
+
+        if stream.groups.contains_key(group) {
+            return Err(DbError::BusyGroup);
+        }
+
+        let group_state = ConsumerGroup {
+            last_delivered: id.unwrap_or(stream.last_id),
+            pending: BTreeMap::new(),
+            consumers: HashMap::new(),
+        };
+        stream.groups.insert(group.to_string(), group_state);
+        state.touch(key);
+        state.notify_keyspace_event(KeyspaceEvent::XGroupCreate, key);
+
+        Ok(())
+    }
+
+    /// Destroys the consumer group `group` of the stream stored at `key`, along with its
+    /// pending entries.
+    ///
+    /// Returns `true` if the group existed.
+    pub fn xgroup_destroy(&self, key: &str, group: &str) -> Result<bool, DbError> {
+        let mut state = self.shared.lock_state();
+
+        let destroyed = match state.get_stream_mut(key)? {
+            Some(stream) => stream.groups.remove(group).is_some(),
+            None => return Err(DbError::NoSuchKey),
+        };
+
+        if destroyed {
+            state.touch(key);
+            state.notify_keyspace_event(KeyspaceEvent::XGroupDestroy, key);
+        }
+
+        Ok(destroyed)
+    }
+
+    /// Creates the consumer `consumer` in the consumer group `group` of the stream stored at
+    /// `key`.
+    ///
+    /// Returns `true` if the consumer was created, or `false` if it already existed.
+    pub fn xgroup_create_consumer(
+        &self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+    ) -> Result<bool, DbError> {
+        let mut state = self.shared.lock_state();
+
+        let (_, group) = state.get_group_mut(key, group)?;
+
+        let created = group.create_consumer(consumer);
+        if created {
+            state.touch(key);
+            state.notify_keyspace_event(KeyspaceEvent::XGroupCreateConsumer, key);
+        }
+
+        Ok(created)
+    }
+
+    /// Deletes the consumer `consumer` from the consumer group `group` of the stream stored at
+    /// `key`. The pending entries of the consumer are dropped from the group.
+    ///
+    /// Returns the number of pending entries the consumer owned.
+    pub fn xgroup_del_consumer(
+        &self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+    ) -> Result<usize, DbError> {
+        let mut state = self.shared.lock_state();
+
+        let (_, group) = state.get_group_mut(key, group)?;
+
+        let consumer = match group.consumers.remove(consumer) {
+            Some(consumer) => consumer,
+            None => return Ok(0),
+        };
+
+        for id in &consumer.pending {
+            group.pending.remove(id);
+        }
+
+        state.touch(key);
+        state.notify_keyspace_event(KeyspaceEvent::XGroupDelConsumer, key);
+
+        Ok(consumer.pending.len())
+    }
+
+    /// Sets the last delivered ID of the consumer group `group` of the stream stored at `key` to
+    /// `id`, or to the last ID of the stream if `id` is `None`.
+    pub fn xgroup_set_id(
+        &self,
+        key: &str,
+        group: &str,
+        id: Option<StreamId>,
+    ) -> Result<(), DbError> {
+        let mut state = self.shared.lock_state();
+
+        let (stream_last_id, group) = state.get_group_mut(key, group)?;
+        group.last_delivered = id.unwrap_or(stream_last_id);
+        state.touch(key);
+        state.notify_keyspace_event(KeyspaceEvent::XGroupSetId, key);
+
+        Ok(())
+    }
+
+    /// Reads entries from the given streams on behalf of `consumer` of the consumer group
+    /// `group`, at most `count` per stream if given.
+    ///
+    /// A `None` ID delivers the entries never delivered to the group, which become pending for
+    /// the consumer unless `no_ack` is set. Streams without such entries are omitted. Other IDs
+    /// return the pending entries of the consumer following the ID. Entries removed from the
+    /// stream since they were delivered are returned without fields, and stay pending until
+    /// acknowledged.
+    pub fn xread_group(
+        &self,
+        group: &str,
+        consumer: &str,
+        streams: &[(String, Option<StreamId>)],
+        count: Option<usize>,
+        no_ack: bool,
+    ) -> Result<Vec<(String, Vec<GroupEntry>)>, DbError> {
+        let mut state = self.shared.lock_state();
+
+        // Check all the groups exist before delivering anything.
+        for (key, _) in streams {
+            state.get_group(key, group)?;
+        }
+
+        let now = Instant::now();
+        let count = count.unwrap_or(usize::MAX);
+
+        let mut read = vec![];
+        for (key, id) in streams {
+            // `unwrap` is safe as the stream was checked above.
+            let stream = state.get_stream_mut(key)?.unwrap();
+            let Stream {
+                entries, groups, ..
+            } = stream;
+            let group = groups.get_mut(group).unwrap();
+
+            let created = group.create_consumer(consumer);
+
+            match id {
+                None => {
+                    let start = Bound::Excluded(group.last_delivered);
+                    let delivered: Vec<GroupEntry> = entries
+                        .range((start, Bound::Unbounded))
+                        .take(count)
+                        .map(|(id, fields)| (*id, Some(fields.clone())))
+                        .collect();
+
+                    for (id, _) in &delivered {
+                        group.last_delivered = *id;
+                        if !no_ack {
+                            group.deliver(*id, consumer, now, true);
+                        }
+                    }
+
+                    if !delivered.is_empty() {
+                        read.push((key.clone(), delivered));
+                        state.touch(key);
+                    }
+                }
+                Some(id) => {
+                    let history = group.consumers[consumer]
+                        .pending
+                        .range((Bound::Excluded(*id), Bound::Unbounded))
+                        .take(count)
+                        .map(|id| (*id, entries.get(id).cloned()))
+                        .collect();
+
+                    read.push((key.clone(), history));
+                }
+            }
+
+            if created {
+                state.touch(key);
+                state.notify_keyspace_event(KeyspaceEvent::XGroupCreateConsumer, key);
+            }
+        }
+
+        Ok(read)
+    }
+
+    /// Acknowledges the pending entries `ids` of the consumer group `group` of the stream stored
+    /// at `key`, removing them from the pending entries.
+    ///
+    /// Returns the number of entries that were pending.
+    pub fn xack(&self, key: &str, group: &str, ids: &[StreamId]) -> Result<usize, DbError> {
+        let mut state = self.shared.lock_state();
+
+        let group = match state.get_group_mut(key, group) {
+            Ok((_, group)) => group,
+            Err(DbError::NoGroup) => return Ok(0),
+            Err(err) => return Err(err),
+        };
+
+        let acknowledged = ids.iter().filter(|id| group.acknowledge(**id)).count();
+
+        if acknowledged > 0 {
+            state.touch(key);
+        }
+
+        Ok(acknowledged)
+    }
+
+    /// Returns a summary of the pending entries of the consumer group `group` of the stream
+    /// stored at `key`.
+    pub fn xpending_summary(&self, key: &str, group: &str) -> Result<PendingSummary, DbError> {
+        let state = self.shared.lock_state();
+
+        let group = state.get_group(key, group)?;
+
+        let range = match (
+            group.pending.keys().next(),
+            group.pending.keys().next_back(),
+        ) {
+            (Some(min), Some(max)) => Some((*min, *max)),
+            _ => None,
+        };
+
+        let mut consumers: Vec<_> = group
+            .consumers
+            .iter()
+            .filter(|(_, consumer)| !consumer.pending.is_empty())
+            .map(|(name, consumer)| (name.clone(), consumer.pending.len()))
+            .collect();
+        consumers.sort();
+
+        Ok(PendingSummary {
+            count: group.pending.len(),
+            range,
+            consumers,
+        })
+    }
+
+    /// Returns up to `count` pending entries of the consumer group `group` of the stream stored
+    /// at `key` with an ID between `start` and `end`. Only the entries owned by `consumer` are
+    /// returned if given, and only the entries idle for at least `min_idle` if given.
+    #[allow(clippy::too_many_arguments)]
+    pub fn xpending(
+        &self,
+        key: &str,
+        group: &str,
+        start: Bound<StreamId>,
+        end: Bound<StreamId>,
+        count: usize,
+        consumer: Option<&str>,
+        min_idle: Option<Duration>,
+    ) -> Result<Vec<PendingEntry>, DbError> {
+        let state = self.shared.lock_state();
+
+        let group = state.get_group(key, group)?;
+
+        if is_empty_range(start, end) {
+            return Ok(vec![]);
+        }
+
+        let now = Instant::now();
+
+        Ok(group
+            .pending
+            .range((start, end))
+            .filter(|(_, delivery)| consumer.is_none_or(|name| delivery.consumer == name))
+            .map(|(id, delivery)| PendingEntry {
+                id: *id,
+                consumer: delivery.consumer.clone(),
+                idle: now.saturating_duration_since(delivery.delivered_at),
+                delivery_count: delivery.delivery_count,
+            })
+            .filter(|entry| min_idle.is_none_or(|min_idle| entry.idle >= min_idle))
+            .take(count)
+            .collect())
+    }
+
+    /// Transfers the ownership of the pending entries `ids` of the consumer group `group` of the
+    /// stream stored at `key` to `consumer`, if they have been idle for at least `min_idle`.
+    /// Their delivery count is incremented unless `just_id` is set.
+    ///
+    /// Returns the claimed entries. Pending entries that were removed from the stream are
+    /// dropped from the group instead of being claimed.
+    pub fn xclaim(
+        &self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+        min_idle: Duration,
+        ids: &[StreamId],
+        just_id: bool,
+    ) -> Result<Vec<StreamEntry>, DbError> {
+        let mut state = self.shared.lock_state();
+
+        // Fails if the group does not exist.
+        state.get_group(key, group)?;
+
+        // `unwrap` is safe as the stream was checked above.
+        let stream = state.get_stream_mut(key)?.unwrap();
+        let Stream {
+            entries, groups, ..
+        } = stream;
+        let group = groups.get_mut(group).unwrap();
+
+        let now = Instant::now();
+        let created = group.create_consumer(consumer);
+
+        let mut claimed = vec![];
+        let mut modified = created;
+
+        for id in ids {
+            if !group.is_idle(*id, min_idle, now) {
+                continue;
+            }
+
+            match entries.get(id) {
+                Some(fields) => {
+                    group.deliver(*id, consumer, now, !just_id);
+                    claimed.push((*id, fields.clone()));
+                }
+                None => {
+                    group.acknowledge(*id);
+                }
+            }
+            modified = true;
+        }
+
+        if modified {
+            state.touch(key);
+        }
+        if created {
+            state.notify_keyspace_event(KeyspaceEvent::XGroupCreateConsumer, key);
+        }
+
+        Ok(claimed)
+    }
+
+    /// Claims up to `count` pending entries of the consumer group `group` of the stream stored
+    /// at `key` that have been idle for at least `min_idle`, scanning the pending entries from
+    /// `start`, as `xclaim` does.
+    ///
+    /// Returns the ID to resume the scan from, `0-0` once all the pending entries were scanned,
+    /// along with the claimed entries and the IDs of the pending entries that were dropped from
+    /// the group as they were removed from the stream.
+    #[allow(clippy::too_many_arguments)]
+    pub fn xautoclaim(
+        &self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+        min_idle: Duration,
+        start: StreamId,
+        count: usize,
+        just_id: bool,
+    ) -> Result<(StreamId, Vec<StreamEntry>, Vec<StreamId>), DbError> {
+        let mut state = self.shared.lock_state();
+
+        // Fails if the group does not exist.
+        state.get_group(key, group)?;
+
+        // `unwrap` is safe as the stream was checked above.
+        let stream = state.get_stream_mut(key)?.unwrap();
+        let Stream {
+            entries, groups, ..
+        } = stream;
+        let group = groups.get_mut(group).unwrap();
+
+        let now = Instant::now();
+        let created = group.create_consumer(consumer);
+
+        // Bound the work done under the lock, like Redis does, by scanning at most ten times as
+        // many entries as requested.
+        let scanned: Vec<StreamId> = group
+            .pending
+            .range(start..)
+            .map(|(id, _)| *id)
+            .take(count.saturating_mul(10))
+            .collect();
+
+        let mut claimed = vec![];
+        let mut deleted = vec![];
+        let mut next = None;
+
+        for id in &scanned {
+            if claimed.len() == count {
+                next = Some(*id);
+                break;
+            }
+
+            if !group.is_idle(*id, min_idle, now) {
+                continue;
+            }
+
+            match entries.get(id) {
+                Some(fields) => {
+                    group.deliver(*id, consumer, now, !just_id);
+                    claimed.push((*id, fields.clone()));
+                }
+                None => {
+                    group.acknowledge(*id);
+                    deleted.push(*id);
+                }
+            }
+        }
+
+        // The scan may have stopped before the end of the pending entries.
+        let next = next.or_else(|| {
+            let last = *scanned.last()?;
+            let mut rest = group
+                .pending
+                .range((Bound::Excluded(last), Bound::Unbounded));
+            rest.next().map(|(id, _)| *id)
+        });
+
+        if created || !claimed.is_empty() || !deleted.is_empty() {
+            state.touch(key);
+        }
+        if created {
+            state.notify_keyspace_event(KeyspaceEvent::XGroupCreateConsumer, key);
+        }
+
+        Ok((next.unwrap_or_default(), claimed, deleted))
+    }
+}
+
+impl ConsumerGroup {
+    /// Creates the consumer named `name` if it does not exist. Returns `true` if it was created.
+    fn create_consumer(&mut self, name: &str) -> bool {
+        if self.consumers.contains_key(name) {
+            return false;
+        }
+
+        self.consumer_mut(name);
+        true
+    }
+
+    /// Returns the consumer named `name`, creating it if needed.
+    fn consumer_mut(&mut self, name: &str) -> &mut Consumer {
+        self.consumers
+            .entry(name.to_string())
+            .or_insert_with(|| Consumer {
+                pending: BTreeSet::new(),
+            })
+    }
+
+    /// Delivers the entry `id` to `consumer` at `now`, transferring its ownership if it was
+    /// already pending. The delivery count is incremented if `count` is set.
+    fn deliver(&mut self, id: StreamId, consumer: &str, now: Instant, count: bool) {
+        let delivery = self.pending.entry(id).or_insert_with(|| Delivery {
+            consumer: consumer.to_string(),
+            delivered_at: now,
+            delivery_count: 0,
+        });
+
+        if delivery.consumer != consumer {
+            if let Some(previous) = self.consumers.get_mut(&delivery.consumer) {
+                previous.pending.remove(&id);
+            }
+            delivery.consumer = consumer.to_string();
+        }
+
+        delivery.delivered_at = now;
+        if count {
+            delivery.delivery_count += 1;
+        }
+
+        self.consumer_mut(consumer).pending.insert(id);
+    }
+
+    /// Removes the entry `id` from the pending entries. Returns `true` if it was pending.
+    fn acknowledge(&mut self, id: StreamId) -> bool {
+        let delivery = match self.pending.remove(&id) {
+            Some(delivery) => delivery,
+            None => return false,
+        };
+
+        if let Some(consumer) = self.consumers.get_mut(&delivery.consumer) {
+            consumer.pending.remove(&id);
+        }
+
+        true
+    }
+
+    /// Returns `true` if the entry `id` is pending and was delivered at least `min_idle` ago.
+    fn is_idle(&self, id: StreamId, min_idle: Duration, now: Instant) -> bool {
+        self.pending.get(&id).is_some_and(|delivery| {
+            now.saturating_duration_since(delivery.delivered_at) >= min_idle
+        })
+    }
+}
+
+impl State {
+    /// Returns the consumer group `group` of the stream stored at `key`.
+    fn get_group(&self, key: &str, group: &str) -> Result<&ConsumerGroup, DbError> {
+        let stream = self.get_stream(key)?.ok_or(DbError::NoGroup)?;
+        stream.groups.get(group).ok_or(DbError::NoGroup)
+    }
+
+    /// Returns the consumer group `group` of the stream stored at `key` for modification, along
+    /// with the last ID of the stream.
+    fn get_group_mut(
+        &mut self,
+        key: &str,
+        group: &str,
+    ) -> Result<(StreamId, &mut ConsumerGroup), DbError> {
+        let stream = self.get_stream_mut(key)?.ok_or(DbError::NoGroup)?;
+        let last_id = stream.last_id;
+
+        match stream.groups.get_mut(group) {
+            Some(group) => Ok((last_id, group)),
+            None => Err(DbError::NoGroup),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::stream::XAddId;
+    use crate::db::{Db, DbError, StreamId};
+
+    use bytes::Bytes;
+    use std::ops::Bound;
+    use tokio::time::Duration;
+
+    fn id(ms: u64, seq: u64) -> StreamId {
+        StreamId { ms, seq }
+    }
+
+    /// Returns a database holding a stream with the entries `1-0` to `count-0`, and a consumer
+    /// group `group` which was delivered none of them.
+    fn stream_with_group(count: u64) -> Db {
+        let db = Db::default();
+
+        for ms in 1..=count {
+            let fields = vec![(Bytes::from("n"), Bytes::from(ms.to_string()))];
+            db.xadd("stream", XAddId::Ms(ms), fields, None, false)
+                .unwrap();
+        }
+        db.xgroup_create("stream", "group", Some(id(0, 0)), false)
+            .unwrap();
+
+        db
+    }
+
+    /// Reads the entries never delivered to the group on behalf of `consumer` and returns their
+    /// IDs.
+    fn read_new(db: &Db, consumer: &str, count: usize) -> Vec<StreamId> {
+        let streams = vec![("stream".to_string(), None)];
+        let read = db
+            .xread_group("group", consumer, &streams, Some(count), false)
+            .unwrap();
+        read.into_iter()
+            .flat_map(|(_, entries)| entries)
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn entries_are_delivered_once_and_pending_until_acknowledged() {
+        let db = stream_with_group(3);
+
+        assert_eq!(vec![id(1, 0), id(2, 0)], read_new(&db, "alice", 2));
+        assert_eq!(vec![id(3, 0)], read_new(&db, "bob", 2));
+        assert!(read_new(&db, "alice", 2).is_empty());
+
+        let summary = db.xpending_summary("stream", "group").unwrap();
+        assert_eq!(3, summary.count);
+        assert_eq!(Some((id(1, 0), id(3, 0))), summary.range);
+        let consumers = vec![("alice".to_string(), 2), ("bob".to_string(), 1)];
+        assert_eq!(consumers, summary.consumers);
+
+        let pending = db
+            .xpending(
+                "stream",
+                "group",
+                Bound::Unbounded,
+                Bound::Unbounded,
+                10,
+                Some("alice"),
+                None,
+            )
+            .unwrap();
+        let ids: Vec<_> = pending.iter().map(|entry| entry.id).collect();
+        assert_eq!(vec![id(1, 0), id(2, 0)], ids);
+        assert!(pending.iter().all(|entry| entry.delivery_count == 1));
+
+        // Only pending entries are acknowledged.
+        assert_eq!(Ok(1), db.xack("stream", "group", &[id(1, 0), id(9, 0)]));
+        assert_eq!(Ok(0), db.xack("stream", "group", &[id(1, 0)]));
+        assert_eq!(2, db.xpending_summary("stream", "group").unwrap().count);
+
+        // The history of a consumer is made of its pending entries.
+        let streams = vec![("stream".to_string(), Some(id(0, 0)))];
+        let read = db
+            .xread_group("group", "alice", &streams, None, false)
+            .unwrap();
+        let ids: Vec<_> = read[0].1.iter().map(|(id, _)| *id).collect();
+        assert_eq!(vec![id(2, 0)], ids);
+    }
+
+    #[tokio::test]
+    async fn no_ack_does_not_add_pending_entries() {
+        let db = stream_with_group(2);
+
+        let streams = vec![("stream".to_string(), None)];
+        db.xread_group("group", "alice", &streams, None, true)
+            .unwrap();
+
+        assert_eq!(0, db.xpending_summary("stream", "group").unwrap().count);
+        assert!(read_new(&db, "alice", 10).is_empty());
+    }
+
+    #[tokio::test]
+    async fn deleted_pending_entries_are_read_without_fields() {
+        let db = stream_with_group(3);
+
+        read_new(&db, "alice", 3);
+        db.xtrim("stream", 1).unwrap();
+
+        let streams = vec![("stream".to_string(), Some(id(0, 0)))];
+        let read = db
+            .xread_group("group", "alice", &streams, None, false)
+            .unwrap();
+
+        let fields = vec![(Bytes::from("n"), Bytes::from("3"))];
+        let history = vec![(id(1, 0), None), (id(2, 0), None), (id(3, 0), Some(fields))];
+        assert_eq!(vec![("stream".to_string(), history)], read);
+
+        // The deleted entries stay pending until acknowledged.
+        assert_eq!(3, db.xpending_summary("stream", "group").unwrap().count);
+    }
+
+    #[tokio::test]
+    async fn claiming_transfers_idle_entries() {
+        let db = stream_with_group(3);
+
+        read_new(&db, "alice", 3);
+
+        // The entries were just delivered and are not idle enough.
+        let minute = Duration::from_secs(60);
+        let claimed = db.xclaim("stream", "group", "bob", minute, &[id(1, 0)], false);
+        assert_eq!(Ok(vec![]), claimed);
+
+        let ids = [id(1, 0), id(2, 0), id(9, 0)];
+        let claimed = db
+            .xclaim("stream", "group", "bob", Duration::ZERO, &ids, false)
+            .unwrap();
+        let claimed: Vec<_> = claimed.into_iter().map(|(id, _)| id).collect();
+        assert_eq!(vec![id(1, 0), id(2, 0)], claimed);
+
+        let summary = db.xpending_summary("stream", "group").unwrap();
+        let consumers = vec![("alice".to_string(), 1), ("bob".to_string(), 2)];
+        assert_eq!(consumers, summary.consumers);
+
+        let pending = db
+            .xpending(
+                "stream",
+                "group",
+                Bound::Included(id(1, 0)),
+                Bound::Included(id(1, 0)),
+                10,
+                None,
+                None,
+            )
+            .unwrap();
+        assert_eq!("bob", pending[0].consumer);
+        assert_eq!(2, pending[0].delivery_count);
+
+        // `JUSTID` leaves the delivery count alone.
+        db.xclaim(
+            "stream",
+            "group",
+            "alice",
+            Duration::ZERO,
+            &[id(1, 0)],
+            true,
+        )
+        .unwrap();
+        let pending = db
+            .xpending(
+                "stream",
+                "group",
+                Bound::Unbounded,
+                Bound::Unbounded,
+                1,
+                None,
+                None,
+            )
+            .unwrap();
+        assert_eq!("alice", pending[0].consumer);
+        assert_eq!(2, pending[0].delivery_count);
+
+        // Entries removed from the stream are dropped from the group instead of being claimed.
+        db.xtrim("stream", 1).unwrap();
+        let claimed = db.xclaim("stream", "group", "bob", Duration::ZERO, &[id(2, 0)], false);
+        assert_eq!(Ok(vec![]), claimed);
+        assert_eq!(2, db.xpending_summary("stream", "group").unwrap().count);
+    }
+
+    #[tokio::test]
+    async fn autoclaim_scans_the_pending_entries() {
+        let db = stream_with_group(5);
+
+        read_new(&db, "alice", 5);
+        db.xack("stream", "group", &[id(4, 0)]).unwrap();
+        // Removes the entries `1-0` and `2-0`.
+        db.xtrim("stream", 3).unwrap();
+
+        let (next, claimed, deleted) = db
+            .xautoclaim("stream", "group", "bob", Duration::ZERO, id(0, 0), 1, false)
+            .unwrap();
+        assert_eq!(id(5, 0), next);
+        assert_eq!(
+            vec![id(3, 0)],
+            claimed.iter().map(|(id, _)| *id).collect::<Vec<_>>()
+        );
+        assert_eq!(vec![id(1, 0), id(2, 0)], deleted);
+
+        let (next, claimed, deleted) = db
+            .xautoclaim("stream", "group", "bob", Duration::ZERO, next, 1, false)
+            .unwrap();
+        assert_eq!(id(0, 0), next);
+        assert_eq!(
+            vec![id(5, 0)],
+            claimed.iter().map(|(id, _)| *id).collect::<Vec<_>>()
+        );
+        assert!(deleted.is_empty());
+
+        let summary = db.xpending_summary("stream", "group").unwrap();
+        assert_eq!(vec![("bob".to_string(), 2)], summary.consumers);
+    }
+
+    #[tokio::test]
+    async fn group_operations_require_the_group() {
+        let db = stream_with_group(1);
+
+        let created = db.xgroup_create("stream", "group", None, false);
+        assert_eq!(Err(DbError::BusyGroup), created);
+        let created = db.xgroup_create("missing", "group", None, false);
+        assert_eq!(Err(DbError::NoSuchKey), created);
+
+        let streams = vec![("stream".to_string(), None)];
+        let read = db.xread_group("other", "alice", &streams, None, false);
+        assert_eq!(Err(DbError::NoGroup), read);
+        assert_eq!(Ok(0), db.xack("stream", "other", &[id(1, 0)]));
+
+        assert_eq!(Ok(true), db.xgroup_destroy("stream", "group"));
+        assert_eq!(Ok(false), db.xgroup_destroy("stream", "group"));
+    }
+}
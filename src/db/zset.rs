@@ -0,0 +1,499 @@
+use crate::db::rank_tree::RankTree;
+use crate::db::{range_indices, Db, DbError, KeyspaceEvent, State, Value};
+
+use bytes::Bytes;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+/// A collection of unique members ordered by score, and by member for equal scores.
+#[derive(Debug, Clone)]
+pub(super) struct SortedSet {
+    /// Score of every member.
+    scores: HashMap<Bytes, f64>,
+    /// Members ordered by score, used for rank and range queries.
+    ranks: RankTree<(Score, Bytes)>,
+}
+
+/// A score with a total ordering, so that it can be used as a tree key. Scores are never NaN.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Score(f64);
+
+/// Conditions applied by `ZADD` to each of its members.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ZAddOptions {
+    /// Only add new members, never update existing ones.
+    pub nx: bool,
+    /// Only update existing members, never add new ones.
+    pub xx: bool,
+    /// Only update existing members if the new score is greater than the current one.
+    pub gt: bool,
+    /// Only update existing members if the new score is less than the current one.
+    pub lt: bool,
+    /// Count the members whose score changed in addition to the added ones.
+    pub ch: bool,
+}
+
+/// Lower or upper bound of a score range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScoreBound {
+    /// The bound itself is part of the range.
+    Inclusive(f64),
+    /// The bound itself is not part of the range.
+    Exclusive(f64),
+}
+
+/// Lower or upper bound of a lexicographical range of members.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexBound {
+    /// Smaller than any member.
+    Min,
+    /// Greater than any member.
+    Max,
+    /// The bound itself is part of the range.
+    Inclusive(Bytes),
+    /// The bound itself is not part of the range.
+    Exclusive(Bytes),
+}
+
+/// Selects the members of a sorted set returned by `ZRANGE`.
+#[derive(Debug, Clone)]
+pub enum ZRangeBy {
+    /// Members between two ranks, both inclusive. Negative ranks count from the end.
+    Rank(i64, i64),
+    /// Members with a score between a minimum and a maximum.
+    Score(ScoreBound, ScoreBound),
+    /// Members between a minimum and a maximum, assuming all of them have the same score.
+    Lex(LexBound, LexBound),
+}
+
+impl Db {
+    /// Adds `members` to the sorted set stored at `key`, or updates their scores if they already
+    /// belong to it, subject to `options`. The sorted set is created if the key does not exist.
+    ///
+    /// Returns the number of added members, plus the number of updated members if `options.ch`
+    /// is set. Nothing is added if any of the scores is NaN.
+    pub fn zadd(
+        &self,
+        key: &str,
+        members: Vec<(f64, Bytes)>,
+        options: ZAddOptions,
+    ) -> Result<usize, DbError> {
+        // Checked before adding any member, so that none is added when one of them fails.
+        if members.iter().any(|(score, _)| score.is_nan()) {
+            return Err(DbError::ScoreNaN);
+        }
+
+        let mut state = self.shared.lock_state();
+
+        let zset = state.zset_entry(key)?;
+        let mut modified = false;
+
+        let count = members.into_iter().try_fold(0, |count, (score, member)| {
+            let added = zset.add(member, score, false, options)?;
+            modified |= matches!(added, Some(Added::New) | Some(Added::Changed));
+
+            match added {
+                Some(Added::New) => Ok(count + 1),
+                Some(Added::Changed) if options.ch => Ok(count + 1),
+                _ => Ok(count),
+            }
+        });
+
+        state.remove_if_empty(key);
+        if modified {
+            state.touch(key);
+            state.notify_keyspace_event(KeyspaceEvent::ZAdd, key);
+        }
+
+        count
+    }
+
+    /// Adds `increment` to the score of `member` in the sorted set stored at `key`, subject to
+    /// `options`. A missing member is added with `increment` as its score.
+    ///
+    /// Returns the new score, or `None` if the conditions of `options` prevented the update.
+    pub fn zincr_by(
+        &self,
+        key: &str,
+        increment: f64,
+        member: Bytes,
+        options: ZAddOptions,
+    ) -> Result<Option<f64>, DbError> {
+        let mut state = self.shared.lock_state();
+
+        let zset = state.zset_entry(key)?;
+
+        let score = zset
+            .add(member.clone(), increment, true, options)
+            .map(|added| added.and_then(|_| zset.score(&member)));
+
+        state.remove_if_empty(key);
+        if let Ok(Some(_)) = score {
+            state.touch(key);
+            state.notify_keyspace_event(KeyspaceEvent::ZIncr, key);
+        }
+
+        score
+    }
+
+    /// Returns the members of the sorted set stored at `key` selected by `by`, along with their
+    /// scores. Members are ordered from the lowest to the highest score, or the other way around
+    /// if `rev` is set.
+    ///
+    /// `limit` skips the given number of selected members and returns at most the given count of
+    /// the remaining ones. A negative count returns all of them.
+    pub fn zrange(
+        &self,
+        key: &str,
+        by: &ZRangeBy,
+        rev: bool,
+        limit: Option<(u64, i64)>,
+    ) -> Result<Vec<(Bytes, f64)>, DbError> {
+        let state = self.shared.lock_state();
+
+        let zset = match state.get_zset(key)? {
+            Some(zset) => zset,
+            None => return Ok(vec![]),
+        };
+
+        let (mut start, mut end) = zset.bounds(by, rev);
+
+        if let Some((offset, count)) = limit {
+            let offset = offset.min(end.saturating_sub(start) as u64) as usize;
+            let count = usize::try_from(count).unwrap_or(usize::MAX);
+
+            if rev {
+                end -= offset;
+                start = start.max(end.saturating_sub(count));
+            } else {
+                start += offset;
+                end = end.min(start.saturating_add(count));
+            }
+        }
+
+        let mut members: Vec<_> = zset
+            .ranks
+            .range(start..end)
+            .into_iter()
+            .map(|(score, member)| (member.clone(), score.0))
+            .collect();
+
+        if rev {
+            members.reverse();
+        }
+
+        Ok(members)
+    }
+
+    /// Returns the rank of `member` in the sorted set stored at `key`, counting from the lowest
+    /// score.
+    pub fn zrank(&self, key: &str, member: &[u8]) -> Result<Option<usize>, DbError> {
+        let state = self.shared.lock_state();
+        Ok(state.get_zset(key)?.and_then(|zset| zset.rank(member)))
+    }
+
+    /// Returns the score of `member` in the sorted set stored at `key`.
+    pub fn zscore(&self, key: &str, member: &[u8]) -> Result<Option<f64>, DbError> {
+        let state = self.shared.lock_state();
+        Ok(state.get_zset(key)?.and_then(|zset| zset.score(member)))
+    }
+
+    /// Removes `members` from the sorted set stored at `key`. The key is removed along with the
+    /// last member.
+    ///
+    /// Returns the number of members that were removed.
+    pub fn zrem(&self, key: &str, members: &[Bytes]) -> Result<usize, DbError> {
+        let mut state = self.shared.lock_state();
+
+        let zset = match state.get_zset_mut(key)? {
+            Some(zset) => zset,
+            None => return Ok(0),
+        };
+
+        let removed = members.iter().filter(|member| zset.remove(member)).count();
+
+        if removed > 0 {
+            state.touch(key);
+            state.notify_keyspace_event(KeyspaceEvent::ZRem, key);
+        }
+        state.remove_if_drained(key);
+
+        Ok(removed)
+    }
+
+    /// Returns the number of members of the sorted set stored at `key` with a score between
+    /// `min` and `max`.
+    pub fn zcount(&self, key: &str, min: ScoreBound, max: ScoreBound) -> Result<usize, DbError> {
+        let state = self.shared.lock_state();
+
+        Ok(state.get_zset(key)?.map_or(0, |zset| {
+            let (start, end) = zset.bounds(&ZRangeBy::Score(min, max), false);
+            end - start
+        }))
+    }
+
+    /// Removes and returns up to `count` members with the lowest scores from the sorted set
+    /// stored at `key`, or with the highest scores if `max` is set.
+    pub fn zpop(&self, key: &str, count: usize, max: bool) -> Result<Vec<(Bytes, f64)>, DbError> {
+        let mut state = self.shared.lock_state();
+
+        let zset = match state.get_zset_mut(key)? {
+            Some(zset) => zset,
+            None => return Ok(vec![]),
+        };
+
+        let len = zset.len();
+        let count = count.min(len);
+        let range = if max { len - count..len } else { 0..count };
+
+        let mut members: Vec<_> = zset
+            .ranks
+            .range(range)
+            .into_iter()
+            .map(|(score, member)| (member.clone(), score.0))
+            .collect();
+
+        if max {
+            members.reverse();
+        }
+
+        for (member, _) in &members {
+            zset.remove(member);
+        }
+
+        if !members.is_empty() {
+            let event = if max {
+                KeyspaceEvent::ZPopMax
+            } else {
+                KeyspaceEvent::ZPopMin
+            };
+
+            state.touch(key);
+            state.notify_keyspace_event(event, key);
+        }
+        state.remove_if_drained(key);
+
+        Ok(members)
+    }
+}
+
+/// Outcome of adding a member to a sorted set.
+enum Added {
+    /// The member was not part of the set.
+    New,
+    /// The score of an existing member was updated.
+    Changed,
+    /// The score of an existing member was updated to its current value.
+    Unchanged,
+}
+
+impl SortedSet {
+    pub(super) fn new() -> SortedSet {
+        SortedSet {
+            scores: HashMap::new(),
+            ranks: RankTree::new(),
+        }
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    fn score(&self, member: &[u8]) -> Option<f64> {
+        self.scores.get(member).copied()
+    }
+
+    fn rank(&self, member: &[u8]) -> Option<usize> {
+        let score = Score(self.score(member)?);
+        Some(
+            self.ranks
+                .count_while(|(other_score, other)| (*other_score, &other[..]) < (score, member)),
+        )
+    }
+
+    /// Sets the score of `member` to `score`, or adds `score` to its current score if
+    /// `increment` is set, subject to `options`.
+    ///
+    /// Returns `None` if the conditions of `options` prevented the update.
+    fn add(
+        &mut self,
+        member: Bytes,
+        score: f64,
+        increment: bool,
+        options: ZAddOptions,
+    ) -> Result<Option<Added>, DbError> {
+        let current = self.score(&member);
+
+        if (options.nx && current.is_some()) || (options.xx && current.is_none()) {
+            return Ok(None);
+        }
+
+        let score = match current {
+            Some(current) if increment => current + score,
+            _ => score,
+        };
+
+        if score.is_nan() {
+            return Err(DbError::ScoreNaN);
+        }
+
+        // Store negative zero as zero, so that both sort the same way.
+        let score = score + 0.0;
+
+        let current = match current {
+            Some(current) => current,
+            None => {
+                self.scores.insert(member.clone(), score);
+                self.ranks.insert((Score(score), member));
+                return Ok(Some(Added::New));
+            }
+        };
+
+        if (options.gt && score <= current) || (options.lt && score >= current) {
+            return Ok(None);
+        }
+
+        if score != current {
+            self.ranks.remove(&(Score(current), member.clone()));
+            self.ranks.insert((Score(score), member.clone()));
+            self.scores.insert(member, score);
+            Ok(Some(Added::Changed))
+        } else {
+            // Redis does not count members updated to their current score as changed.
+            Ok(Some(Added::Unchanged))
+        }
+    }
+
+    fn remove(&mut self, member: &[u8]) -> bool {
+        match self.scores.remove_entry(member) {
+            Some((member, score)) => self.ranks.remove(&(Score(score), member)),
+            None => false,
+        }
+    }
+
+    /// Returns the range of ranks selected by `by`. For ranges by rank, `rev` counts ranks from
+    /// the highest score.
+    fn bounds(&self, by: &ZRangeBy, rev: bool) -> (usize, usize) {
+        let len = self.len();
+
+        let (start, end) = match by {
+            ZRangeBy::Rank(start, stop) => match range_indices(len as i64, *start, *stop) {
+                Some((start, stop)) if rev => (len - 1 - stop, len - start),
+                Some((start, stop)) => (start, stop + 1),
+                None => (0, 0),
+            },
+            ZRangeBy::Score(min, max) => {
+                let start = match *min {
+                    ScoreBound::Inclusive(min) => self.ranks.count_while(|(s, _)| s.0 < min),
+                    ScoreBound::Exclusive(min) => self.ranks.count_while(|(s, _)| s.0 <= min),
+                };
+                let end = match *max {
+                    ScoreBound::Inclusive(max) => self.ranks.count_while(|(s, _)| s.0 <= max),
+                    ScoreBound::Exclusive(max) => self.ranks.count_while(|(s, _)| s.0 < max),
+                };
+                (start, end)
+            }
+            ZRangeBy::Lex(min, max) => {
+                let start = match min {
+                    LexBound::Min => 0,
+                    LexBound::Max => len,
+                    LexBound::Inclusive(min) => self.ranks.count_while(|(_, m)| m < min),
+                    LexBound::Exclusive(min) => self.ranks.count_while(|(_, m)| m <= min),
+                };
+                let end = match max {
+                    LexBound::Min => 0,
+                    LexBound::Max => len,
+                    LexBound::Inclusive(max) => self.ranks.count_while(|(_, m)| m <= max),
+                    LexBound::Exclusive(max) => self.ranks.count_while(|(_, m)| m < max),
+                };
+                (start, end)
+            }
+        };
+
+        // An empty range may be reported with its end before its start.
+        (start, end.max(start))
+    }
+}
+
+impl Eq for Score {}
+
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Score) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Score {
+    fn cmp(&self, other: &Score) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl State {
+    /// Returns the sorted set stored at `key`, or `None` if the key does not exist.
+    fn get_zset(&self, key: &str) -> Result<Option<&SortedSet>, DbError> {
+        match self.entries.get(key).map(|entry| &entry.data) {
+            Some(Value::ZSet(zset)) => Ok(Some(zset)),
+            Some(_) => Err(DbError::WrongType),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the sorted set stored at `key` for modification, or `None` if the key does not
+    /// exist.
+    fn get_zset_mut(&mut self, key: &str) -> Result<Option<&mut SortedSet>, DbError> {
+        match self.entries.get_mut(key).map(|entry| &mut entry.data) {
+            Some(Value::ZSet(zset)) => Ok(Some(zset)),
+            Some(_) => Err(DbError::WrongType),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the sorted set stored at `key` for modification, creating an empty sorted set if
+    /// the key does not exist. Callers must follow up with `remove_if_empty` in case nothing was
+    /// added.
+    fn zset_entry(&mut self, key: &str) -> Result<&mut SortedSet, DbError> {
+        if !self.entries.contains_key(key) {
+            self.insert(key.to_string(), Value::ZSet(SortedSet::new()), None);
+        }
+
+        // `unwrap` is safe as the key was inserted above.
+        Ok(self.get_zset_mut(key)?.unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn zadd_with_a_nan_score_adds_no_member() {
+        let db = Db::default();
+        db.zadd(
+            "zset",
+            vec![(1.0, Bytes::from("a"))],
+            ZAddOptions::default(),
+        )
+        .unwrap();
+
+        let members = vec![(2.0, Bytes::from("b")), (f64::NAN, Bytes::from("c"))];
+        let res = db.zadd("zset", members, ZAddOptions::default());
+        assert_eq!(Err(DbError::ScoreNaN), res);
+
+        assert_eq!(Ok(None), db.zscore("zset", b"b"));
+        assert_eq!(
+            Err(DbError::ScoreNaN),
+            db.zadd(
+                "other",
+                vec![(f64::NAN, Bytes::from("a"))],
+                ZAddOptions::default()
+            )
+        );
+        assert_eq!(Ok(None), db.zscore("other", b"a"));
+    }
+}
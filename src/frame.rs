@@ -14,10 +14,15 @@ use std::string::FromUtf8Error;
 pub enum Frame {
     Simple(String),
     Error(String),
-    Integer(u64),
+    Integer(i64),
     Bulk(Bytes),
     Null,
     Array(Vec<Frame>),
+    /// A floating point number, such as a sorted set score.
+    ///
+    /// RESP2 has no dedicated double type, so doubles are written as bulk strings. RESP3 double
+    /// frames are accepted when reading.
+    Double(f64),
 }
 
 #[derive(Debug)]
@@ -51,7 +56,7 @@ impl Frame {
     /// # Panics
     ///
     /// Panics if `self` is not an array.
-    pub(crate) fn push_int(&mut self, value: u64) {
+    pub(crate) fn push_int(&mut self, value: i64) {
         match self {
             Frame::Array(vec) => {
                 vec.push(Frame::Integer(value));
@@ -60,8 +65,29 @@ impl Frame {
         }
     }
 
+    /// Pushes a "double" frame into the array.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self` is not an array.
+    pub(crate) fn push_double(&mut self, value: f64) {
+        match self {
+            Frame::Array(vec) => vec.push(Frame::Double(value)),
+            _ => panic!("not an array frame"),
+        }
+    }
+
     /// Checks if an entire message can be decoded from `src`.
     pub fn check(src: &mut Cursor<&[u8]>) -> Result<(), Error> {
+        Frame::check_limited(src, usize::MAX)
+    }
+
+    /// Checks if an entire message can be decoded from `src` as `check` does, rejecting bulk
+    /// strings longer than `max_bulk_size` bytes before they are received.
+    pub(crate) fn check_limited(
+        src: &mut Cursor<&[u8]>,
+        max_bulk_size: usize,
+    ) -> Result<(), Error> {
         match eat_u8(src)? {
             // check simple frame
             //
@@ -81,7 +107,14 @@ impl Frame {
             //
             // ":1000\r\n"
             b':' => {
-                eat_decimal(src)?;
+                eat_signed_decimal(src)?;
+                Ok(())
+            }
+            // check double frame
+            //
+            // ",1.5\r\n"
+            b',' => {
+                eat_line(src)?;
                 Ok(())
             }
             // check bulk frame
@@ -96,6 +129,10 @@ impl Frame {
                     // Read the bulk string
                     let len: usize = eat_decimal(src)?.try_into()?;
 
+                    if len > max_bulk_size {
+                        return Err("protocol error: invalid bulk length".into());
+                    }
+
                     // skip the number of bytes + 2 (\r\n)
                     skip(src, len + 2)
                 }
@@ -113,7 +150,7 @@ impl Frame {
                 let len = eat_decimal(src)?;
 
                 for _ in 0..len {
-                    Frame::check(src)?;
+                    Frame::check_limited(src, max_bulk_size)?;
                 }
 
                 Ok(())
@@ -149,9 +186,16 @@ impl Frame {
             //
             // ":1000\r\n"
             b':' => {
-                let int = eat_decimal(src)?;
+                let int = eat_signed_decimal(src)?;
                 Ok(Frame::Integer(int))
             }
+            // parse double frame
+            //
+            // ",1.5\r\n"
+            b',' => {
+                let double = eat_double(src)?;
+                Ok(Frame::Double(double))
+            }
             // parse bulk frame
             //
             // "$-1\r\n" (Null)
@@ -269,6 +313,49 @@ fn eat_decimal(src: &mut Cursor<&[u8]>) -> Result<u64, Error> {
     atoi::<u64>(line).ok_or_else(|| ERROR_INVALID_FRAME.into())
 }
 
+fn eat_signed_decimal(src: &mut Cursor<&[u8]>) -> Result<i64, Error> {
+    use atoi::atoi;
+
+    let line = eat_line(src)?;
+
+    atoi::<i64>(line).ok_or_else(|| ERROR_INVALID_FRAME.into())
+}
+
+fn eat_double(src: &mut Cursor<&[u8]>) -> Result<f64, Error> {
+    let line = eat_line(src)?;
+
+    std::str::from_utf8(line)
+        .ok()
+        .and_then(|line| line.parse().ok())
+        .ok_or_else(|| ERROR_INVALID_FRAME.into())
+}
+
+/// Formats `value` the way Redis replies with doubles: the shortest representation which
+/// round-trips, in the style of `%.17g`, with `inf` and `-inf` for infinities.
+pub(crate) fn format_double(value: f64) -> String {
+    if value.is_nan() {
+        return "nan".to_string();
+    }
+
+    if value.is_infinite() {
+        let inf = if value > 0.0 { "inf" } else { "-inf" };
+        return inf.to_string();
+    }
+
+    // `{:e}` yields the shortest digits followed by the exponent, e.g. `1.5e-7`.
+    let scientific = format!("{:e}", value);
+    // `unwrap` is safe as finite values are always written with an exponent.
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+
+    if (-4..17).contains(&exponent) {
+        value.to_string()
+    } else {
+        let sign = if exponent < 0 { '-' } else { '+' };
+        format!("{}e{}{:02}", mantissa, sign, exponent.abs())
+    }
+}
+
 impl std::error::Error for Error {}
 
 impl fmt::Display for Error {
@@ -317,12 +404,13 @@ impl fmt::Display for Frame {
                 Err(_) => write!(fmt, "{:?}", msg),
             },
             Frame::Null => "(nil)".fmt(fmt),
+            Frame::Double(num) => format_double(*num).fmt(fmt),
             Frame::Array(parts) => {
                 for (i, part) in parts.iter().enumerate() {
                     if i > 0 {
                         write!(fmt, " ")?;
-                        part.fmt(fmt)?;
                     }
+                    part.fmt(fmt)?;
                 }
 
                 Ok(())
@@ -330,3 +418,24 @@ impl fmt::Display for Frame {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn doubles_are_formatted_like_redis() {
+        assert_eq!("1.5", format_double(1.5));
+        assert_eq!("3", format_double(3.0));
+        assert_eq!("-0", format_double(-0.0));
+        assert_eq!("0.0001", format_double(0.0001));
+        assert_eq!("1e-05", format_double(0.00001));
+        assert_eq!("1.25e-05", format_double(1.25e-5));
+        assert_eq!("10000000000000000", format_double(1e16));
+        assert_eq!("1e+17", format_double(1e17));
+        assert_eq!("-1.7976931348623157e+308", format_double(f64::MIN));
+        assert_eq!("5e-324", format_double(5e-324));
+        assert_eq!("inf", format_double(f64::INFINITY));
+        assert_eq!("-inf", format_double(f64::NEG_INFINITY));
+    }
+}
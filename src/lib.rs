@@ -1,7 +1,15 @@
 //! A dead simple and very incomplete implementation of a Redis server and client.
+//!
+//! The server state, `Db`, may also be embedded in an application and used in-process, without
+//! the network layer. Commands are then either applied through its methods, or parsed into a
+//! `Command` and applied with `Command::execute`.
 
 mod db;
-pub use db::Db;
+pub use db::{
+    Db, DbConfig, DbError, GroupEntry, KeyspaceEvents, LagPolicy, LexBound, PendingEntry,
+    PendingSummary, ScoreBound, Section, SetCondition, SetExpiration, SetOp, StreamEntry, StreamId,
+    Subscription, WatchedKey, XAddId, ZAddOptions, ZRangeBy,
+};
 
 mod frame;
 pub use frame::Frame;
@@ -13,17 +21,24 @@ mod shutdown;
 pub use shutdown::Shutdown;
 
 mod parse;
-use parse::{Parse, ParseError};
+pub use parse::{Parse, ParseError};
 
 pub mod server;
 pub mod client;
 
 pub mod cmd;
-pub use cmd::Command;
+pub use cmd::{Command, CommandHandler, CommandRegistry};
 
 /// Default port that a redis server listens on.
 pub const DEFAULT_PORT: &str = "6379";
 
+/// Default number of messages buffered for each pub/sub channel and pattern.
+pub const DEFAULT_PUBSUB_CAPACITY: usize = 1024;
+
+/// Default time after which a running Lua script is aborted, matching the Redis
+/// `lua-time-limit`.
+pub const DEFAULT_SCRIPT_TIME_LIMIT: std::time::Duration = std::time::Duration::from_secs(5);
+
 /// Error returned by most functions.
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
 
@@ -1,22 +1,27 @@
+use crate::db::parse_int;
 use crate::Frame;
 
 use bytes::Bytes;
+use std::convert::TryFrom;
 use std::{fmt, str, vec};
 
+/// Error reported when an argument is not an integer.
+const MSG_NOT_INTEGER: &str = "value is not an integer or out of range";
+
 /// Utility for parsing a command.
 ///
-/// Commands are represented as array frames. Each entry in the frame is a "token".
+/// Commands are represented as array frames. Each entry in the frame is a "token". Custom
+/// commands are handed a `Parse` positioned after the command name, see `CommandHandler`.
 #[derive(Debug)]
-pub(crate) struct Parse {
+pub struct Parse {
     parts: vec::IntoIter<Frame>,
 }
 
 /// Error encountered while parsing a frame.
 ///
-/// Currently only `EndOfStream` errors are handled at runtime. All other errors result in the
-/// connection being terminated.
+/// A command failing to parse is replied with an error, and the connection is kept open.
 #[derive(Debug)]
-pub(crate) enum ParseError {
+pub enum ParseError {
     /// Failed to extract a value due to the frame being fully consumed.
     EndOfStream,
 
@@ -45,7 +50,7 @@ impl Parse {
     ///
     /// Only `Simple` and `Bulk` frames can be represented as strings. While errors are stored as
     /// strings, they are considered separate types.
-    pub(crate) fn next_string(&mut self) -> Result<String, ParseError> {
+    pub fn next_string(&mut self) -> Result<String, ParseError> {
         match self.next()? {
             Frame::Simple(s) => Ok(s),
             Frame::Bulk(data) => str::from_utf8(&data[..])
@@ -63,7 +68,7 @@ impl Parse {
     ///
     /// Only `Simple` and `Bulk` frames can be represented as raw bytes. Although errors are stored
     /// as strings and could be represented as raw bytes, they are considered separate types.
-    pub(crate) fn next_bytes(&mut self) -> Result<Bytes, ParseError> {
+    pub fn next_bytes(&mut self) -> Result<Bytes, ParseError> {
         match self.next()? {
             Frame::Simple(s) => Ok(Bytes::from(s.into_bytes())),
             Frame::Bulk(data) => Ok(data),
@@ -77,20 +82,70 @@ impl Parse {
 
     /// Returns the next frame as an integer.
     ///
-    /// This includes `Simple`, `Bulk` and `Integer` frames. `Simple` and `Bulk` frames are parsed.
-    pub(crate) fn next_int(&mut self) -> Result<u64, ParseError> {
-        use atoi::atoi;
-
-        const MSG: &str = "protocol error: invalid number";
+    /// This includes `Simple`, `Bulk` and `Integer` frames. `Simple` and `Bulk` frames are parsed
+    /// as Redis does, see `next_signed_int`.
+    pub fn next_int(&mut self) -> Result<u64, ParseError> {
+        u64::try_from(self.next_signed_int()?).map_err(|_| MSG_NOT_INTEGER.into())
+    }
 
+    /// Returns the next frame as a signed integer.
+    ///
+    /// This includes `Simple`, `Bulk` and `Integer` frames. `Simple` and `Bulk` frames are parsed
+    /// as Redis does: an optional `-` followed by digits, without leading zeros.
+    pub fn next_signed_int(&mut self) -> Result<i64, ParseError> {
         match self.next()? {
             Frame::Integer(v) => Ok(v),
-            Frame::Simple(s) => atoi::<u64>(s.as_bytes()).ok_or_else(|| MSG.into()),
-            Frame::Bulk(data) => atoi::<u64>(&data).ok_or_else(|| MSG.into()),
+            Frame::Simple(s) => parse_int(s.as_bytes()).ok_or_else(|| MSG_NOT_INTEGER.into()),
+            Frame::Bulk(data) => parse_int(&data).ok_or_else(|| MSG_NOT_INTEGER.into()),
             other => Err(format!("protocol error: expected integer frame but got {:?}", other).into()),
         }
     }
 
+    /// Returns the next frame as a floating point number.
+    ///
+    /// This includes `Simple`, `Bulk` and `Integer` frames. `Simple` and `Bulk` frames are parsed.
+    pub fn next_float(&mut self) -> Result<f64, ParseError> {
+        const MSG: &str = "value is not a valid float";
+
+        match self.next()? {
+            Frame::Integer(v) => Ok(v as f64),
+            Frame::Simple(s) => s.parse().map_err(|_| MSG.into()),
+            Frame::Bulk(data) => str::from_utf8(&data)
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .ok_or_else(|| MSG.into()),
+            other => Err(format!("protocol error: expected float frame but got {:?}", other).into()),
+        }
+    }
+
+    /// Returns all remaining frames as strings.
+    pub fn rest_strings(&mut self) -> Result<Vec<String>, ParseError> {
+        let mut strings = vec![];
+
+        loop {
+            match self.next_string() {
+                Ok(s) => strings.push(s),
+                // No further data to parse.
+                Err(ParseError::EndOfStream) => return Ok(strings),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Returns all remaining frames as raw bytes.
+    pub fn rest_bytes(&mut self) -> Result<Vec<Bytes>, ParseError> {
+        let mut values = vec![];
+
+        loop {
+            match self.next_bytes() {
+                Ok(value) => values.push(value),
+                // No further data to parse.
+                Err(ParseError::EndOfStream) => return Ok(values),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
     /// Ensure there are no more entries in the array.
     pub(crate) fn finish(&mut self) -> Result<(), ParseError> {
         if self.parts.next().is_none() {
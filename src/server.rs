@@ -1,7 +1,11 @@
 //! Server implementation.
-use crate::{Command, Connection, Db, Shutdown};
+use crate::cmd::Transaction;
+use crate::{Command, Connection, Db, Frame, Shutdown};
 
-use std::future::Future;
+mod config;
+pub use config::ServerConfig;
+
+use std::future::{self, Future};
 use std::sync::Arc;
 use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::{broadcast, mpsc, Semaphore};
@@ -17,6 +21,9 @@ struct Listener {
     /// passed into the per connection state.
     db: Db,
 
+    /// Settings of the server, shared with all the connections.
+    config: Arc<ServerConfig>,
+
     /// TCP listener.
     listener: TcpListener,
 
@@ -38,9 +45,15 @@ struct Handler {
     /// Shared database handle.
     db: Db,
 
+    /// Settings of the server, including the custom commands the client may apply.
+    config: Arc<ServerConfig>,
+
     /// The TCP connection.
     connection: Connection,
 
+    /// Commands queued by the client since `MULTI`.
+    transaction: Transaction,
+
     /// Max connection semaphore.
     ///
     /// When the handler is dropped, a permit is returned to this semaphore.
@@ -53,23 +66,29 @@ struct Handler {
     _shutdown_complete: mpsc::Sender<()>,
 }
 
-/// Maximum number of concurrent connections the redis server will accept.
-const MAX_CONNECTIONS: usize = 250;
-
 /// Run the server.
 ///
-/// Accepts connections from the supplied listener. For each inbound connection,
-/// a task in spawned to handle that connection. The server runs until the `shutdown`
-/// future completes, at which point the server shuts down gracefully.
-pub async fn run(listener: TcpListener, shutdown: impl Future) -> crate::Result<()> {
+/// Accepts connections from the supplied listener, with the settings of `config`. For each
+/// inbound connection, a task in spawned to handle that connection. The server runs until the
+/// `shutdown` future completes, at which point the server shuts down gracefully.
+pub async fn run(
+    listener: TcpListener,
+    config: ServerConfig,
+    shutdown: impl Future,
+) -> crate::Result<()> {
+    config.validate()?;
+
+    let db = Db::new(config.db.clone());
+
     let (notify_shutdown, _) = broadcast::channel(1);
     let (shutdown_complete_tx, shutdown_complete_rx) = mpsc::channel(1);
 
     // Initialize the listener.
     let mut server = Listener {
         listener,
-        db: Db::new(),
-        limit_connections: Arc::new(Semaphore::new(MAX_CONNECTIONS)),
+        db,
+        limit_connections: Arc::new(Semaphore::new(config.max_connections)),
+        config: Arc::new(config),
         notify_shutdown,
         shutdown_complete_tx,
         shutdown_complete_rx,
@@ -122,9 +141,22 @@ impl Listener {
             // Accept a new socket.
             let socket = self.accept().await?;
 
+            if let Err(err) = self.config.configure(&socket) {
+                // Only this connection is dropped, the server keeps accepting the others.
+                error!(cause = %err, "failed to configure socket");
+                self.limit_connections.add_permits(1);
+                continue;
+            }
+
+            let mut connection = Connection::with_capacity(socket, self.config.read_buffer_size);
+            connection.set_max_frame_size(self.config.max_frame_size);
+            connection.set_max_bulk_size(self.config.max_bulk_size);
+
             let mut handler = Handler {
                 db: self.db.clone(),
-                connection: Connection::new(socket),
+                config: self.config.clone(),
+                connection,
+                transaction: Transaction::default(),
                 limit_connections: self.limit_connections.clone(),
                 shutdown: Shutdown::new(self.notify_shutdown.subscribe()),
                 _shutdown_complete: self.shutdown_complete_tx.clone(),
@@ -143,17 +175,18 @@ impl Listener {
     ///
     /// Errors are handled by a exponential backoff strategy. First failed task
     /// waits for 1 second, and second failure waits for 2 seconds. Each subsequent
-    /// failure doubles the wait time. If accepting fails on the 6th try after
-    /// waiting for 64 seconds, then this function returns with an error.
+    /// failure doubles the wait time. If accepting fails again once the wait time would
+    /// exceed the maximum backoff of the configuration, 64 seconds by default, then this
+    /// function returns with an error.
     async fn accept(&mut self) -> crate::Result<TcpStream> {
-        let mut backoff = 1;
+        let mut backoff = Duration::from_secs(1);
 
         // try to accept a few times.
         loop {
             match self.listener.accept().await {
                 Ok((socket, _)) => return Ok(socket),
                 Err(err) => {
-                    if backoff > 64 {
+                    if backoff > self.config.max_accept_backoff {
                         // failed too many times. Return the error.
                         return Err(err.into());
                     }
@@ -161,7 +194,7 @@ impl Listener {
             }
 
             // Pause execution until the backoff period elapses.
-            time::sleep(Duration::from_secs(backoff)).await;
+            time::sleep(backoff).await;
 
             // Double the backoff.
             backoff *= 2;
@@ -175,11 +208,24 @@ impl Handler {
     async fn run(&mut self) -> crate::Result<()> {
         // Read new request frames until the shutdown signal has been received.
         while !self.shutdown.is_shutdown() {
+            // Completes once the client has been idle for too long, if a timeout is configured.
+            let idle_timeout = self.config.idle_timeout;
+            let idle = async move {
+                match idle_timeout {
+                    Some(timeout) => time::sleep(timeout).await,
+                    None => future::pending().await,
+                }
+            };
+
             let maybe_frame = tokio::select! {
                 res = self.connection.read_frame() => res?,
                 _ = self.shutdown.recv() => {
                     return Ok(())
                 }
+                _ = idle => {
+                    debug!("closing idle connection");
+                    return Ok(())
+                }
             };
 
             // If `None` is returned then the peer has closed the socket.
@@ -189,13 +235,29 @@ impl Handler {
                 None => return Ok(()),
             };
 
-            // Convert the frame into a command.
-            let cmd = Command::from_frame(frame)?;
+            // Convert the frame into a command. A frame which is not a valid command is replied
+            // with an error, and the connection is kept open. Within a transaction, it could
+            // not be queued, so the transaction is aborted.
+            let cmd = match Command::from_frame_with(frame, &self.config.commands) {
+                Ok(cmd) => cmd,
+                Err(err) => {
+                    self.transaction.abort();
+                    let response = Frame::Error(format!("ERR {}", err));
+                    debug!(?response);
+                    self.connection.write_frame(&response).await?;
+                    continue;
+                }
+            };
 
             debug!(?cmd);
 
-            cmd.apply(&self.db, &mut self.connection, &mut self.shutdown)
-                .await?;
+            cmd.apply(
+                &self.db,
+                &mut self.connection,
+                &mut self.shutdown,
+                &mut self.transaction,
+            )
+            .await?;
         }
 
         Ok(())
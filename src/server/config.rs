@@ -0,0 +1,308 @@
+use crate::{CommandRegistry, DbConfig, KeyspaceEvents, LagPolicy};
+
+use socket2::{SockRef, TcpKeepalive};
+use std::io;
+use tokio::net::TcpStream;
+use tokio::time::Duration;
+
+/// Bounds of the maximum time to wait before retrying to accept a connection.
+const MIN_ACCEPT_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_ACCEPT_BACKOFF: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Settings of the server, given to `server::run`.
+///
+/// Each setting is changed by the method of the same name. `server::run` checks that they are
+/// valid before accepting any connection.
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// Maximum number of concurrent connections. Further clients wait to be accepted until
+    /// another one disconnects. Defaults to 250.
+    pub(crate) max_connections: usize,
+    /// Initial capacity of the read buffer of each connection, in bytes. Defaults to 4 KiB.
+    pub(crate) read_buffer_size: usize,
+    /// Maximum size of a frame received from a client, in bytes. Unlimited by default.
+    pub(crate) max_frame_size: Option<usize>,
+    /// Maximum length of a bulk string received from a client, in bytes. Unlimited by default.
+    pub(crate) max_bulk_size: Option<usize>,
+    /// Time after which a client which did not send any command is disconnected. Clients are
+    /// never disconnected by default.
+    pub(crate) idle_timeout: Option<Duration>,
+    /// Time after which TCP keepalive probes are sent over an idle connection. Disabled by
+    /// default.
+    pub(crate) tcp_keepalive: Option<Duration>,
+    /// Disables Nagle's algorithm on the connections. Not set by default.
+    pub(crate) tcp_nodelay: bool,
+    /// Maximum time to wait before retrying to accept a connection. Accepting doubles the wait
+    /// after each failure, starting from 1 second, and the server stops once it would exceed
+    /// this. Defaults to 64 seconds.
+    pub(crate) max_accept_backoff: Duration,
+    /// Settings of the `Db` shared by the connections.
+    pub(crate) db: DbConfig,
+    /// Custom commands applied along with the commands indb implements.
+    pub(crate) commands: CommandRegistry,
+}
+
+impl ServerConfig {
+    /// Create a new `ServerConfig` with the default settings.
+    pub fn new() -> ServerConfig {
+        ServerConfig::default()
+    }
+
+    /// Sets the maximum number of concurrent connections.
+    pub fn max_connections(mut self, max: usize) -> ServerConfig {
+        self.max_connections = max;
+        self
+    }
+
+    /// Sets the initial capacity of the read buffer of each connection, in bytes.
+    pub fn read_buffer_size(mut self, size: usize) -> ServerConfig {
+        self.read_buffer_size = size;
+        self
+    }
+
+    /// Sets the maximum size of a frame received from a client, in bytes, or lifts the limit if
+    /// `max` is `None`. Clients sending larger frames are disconnected.
+    pub fn max_frame_size(mut self, max: Option<usize>) -> ServerConfig {
+        self.max_frame_size = max;
+        self
+    }
+
+    /// Sets the maximum length of a bulk string received from a client, in bytes, or lifts the
+    /// limit if `max` is `None`. Clients sending longer bulk strings are disconnected.
+    pub fn max_bulk_size(mut self, max: Option<usize>) -> ServerConfig {
+        self.max_bulk_size = max;
+        self
+    }
+
+    /// Sets the time after which a client which did not send any command is disconnected, or
+    /// never disconnects them if `timeout` is `None`.
+    ///
+    /// Subscribers and clients blocked by a command are never disconnected.
+    pub fn idle_timeout(mut self, timeout: Option<Duration>) -> ServerConfig {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// Sets the time after which TCP keepalive probes are sent over an idle connection, or
+    /// disables them if `time` is `None`.
+    pub fn tcp_keepalive(mut self, time: Option<Duration>) -> ServerConfig {
+        self.tcp_keepalive = time;
+        self
+    }
+
+    /// Sets whether Nagle's algorithm is disabled on the connections.
+    pub fn tcp_nodelay(mut self, nodelay: bool) -> ServerConfig {
+        self.tcp_nodelay = nodelay;
+        self
+    }
+
+    /// Sets the maximum time to wait before retrying to accept a connection, between 1 second
+    /// and 1 day.
+    pub fn max_accept_backoff(mut self, max: Duration) -> ServerConfig {
+        self.max_accept_backoff = max;
+        self
+    }
+
+    /// Sets the number of messages buffered for each pub/sub channel and pattern.
+    pub fn pubsub_capacity(mut self, capacity: usize) -> ServerConfig {
+        self.db = self.db.pubsub_capacity(capacity);
+        self
+    }
+
+    /// Sets how to deal with subscribers lagging so far behind that the oldest messages
+    /// buffered for them were dropped.
+    pub fn lag_policy(mut self, policy: LagPolicy) -> ServerConfig {
+        self.db = self.db.lag_policy(policy);
+        self
+    }
+
+    /// Sets the keyspace notifications published along with the messages of the clients.
+    pub fn keyspace_events(mut self, events: KeyspaceEvents) -> ServerConfig {
+        self.db = self.db.keyspace_events(events);
+        self
+    }
+
+    /// Sets the time after which a running Lua script is aborted. Other clients wait for the
+    /// script in the meantime. Scripts which already modified the keyspace are left to complete,
+    /// so that they are never partially applied.
+    pub fn script_time_limit(mut self, limit: Duration) -> ServerConfig {
+        self.db = self.db.script_time_limit(limit);
+        self
+    }
+
+    /// Sets the custom commands applied along with the commands indb implements. Custom commands
+    /// are not allowed within transactions nor scripts.
+    pub fn commands(mut self, commands: CommandRegistry) -> ServerConfig {
+        self.commands = commands;
+        self
+    }
+
+    /// Checks that the settings are valid.
+    pub fn validate(&self) -> crate::Result<()> {
+        // Semaphores panic when created with more permits.
+        if self.max_connections == 0 || self.max_connections > usize::MAX >> 3 {
+            return Err("max connections must be positive and at most usize::MAX >> 3".into());
+        }
+
+        if self.read_buffer_size == 0 {
+            return Err("read buffer size must be positive".into());
+        }
+
+        if self.max_frame_size == Some(0) {
+            return Err("max frame size must be positive".into());
+        }
+
+        if self.max_bulk_size == Some(0) {
+            return Err("max bulk size must be positive".into());
+        }
+
+        if self.idle_timeout == Some(Duration::ZERO) {
+            return Err("idle timeout must be positive".into());
+        }
+
+        if self.tcp_keepalive == Some(Duration::ZERO) {
+            return Err("TCP keepalive time must be positive".into());
+        }
+
+        // Accepting waits 1 second after the first failure, and the doubled wait must remain
+        // representable as a deadline.
+        if self.max_accept_backoff < MIN_ACCEPT_BACKOFF
+            || self.max_accept_backoff > MAX_ACCEPT_BACKOFF
+        {
+            return Err("max accept backoff must be between 1 second and 1 day".into());
+        }
+
+        if self.db.pubsub_capacity == 0 {
+            return Err("pub/sub capacity must be positive".into());
+        }
+
+        if self.db.script_time_limit == Duration::ZERO {
+            return Err("script time limit must be positive".into());
+        }
+
+        Ok(())
+    }
+
+    /// Applies the TCP settings to an accepted `socket`.
+    pub(crate) fn configure(&self, socket: &TcpStream) -> io::Result<()> {
+        socket.set_nodelay(self.tcp_nodelay)?;
+
+        if let Some(time) = self.tcp_keepalive {
+            let keepalive = TcpKeepalive::new().with_time(time);
+            SockRef::from(socket).set_tcp_keepalive(&keepalive)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> ServerConfig {
+        ServerConfig {
+            max_connections: 250,
+            read_buffer_size: 4 * 1024,
+            max_frame_size: None,
+            max_bulk_size: None,
+            idle_timeout: None,
+            tcp_keepalive: None,
+            tcp_nodelay: false,
+            max_accept_backoff: Duration::from_secs(64),
+            db: DbConfig::default(),
+            commands: CommandRegistry::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the error `config` fails to validate with.
+    fn error(config: ServerConfig) -> String {
+        config.validate().unwrap_err().to_string()
+    }
+
+    #[test]
+    fn default_settings_are_valid() {
+        assert!(ServerConfig::new().validate().is_ok());
+    }
+
+    #[test]
+    fn zero_limits_are_rejected() {
+        let max_connections = "max connections must be positive and at most usize::MAX >> 3";
+        assert_eq!(
+            max_connections,
+            error(ServerConfig::new().max_connections(0))
+        );
+        assert_eq!(
+            max_connections,
+            error(ServerConfig::new().max_connections(usize::MAX))
+        );
+        assert_eq!(
+            "read buffer size must be positive",
+            error(ServerConfig::new().read_buffer_size(0))
+        );
+        assert_eq!(
+            "max frame size must be positive",
+            error(ServerConfig::new().max_frame_size(Some(0)))
+        );
+        assert_eq!(
+            "max bulk size must be positive",
+            error(ServerConfig::new().max_bulk_size(Some(0)))
+        );
+        assert_eq!(
+            "pub/sub capacity must be positive",
+            error(ServerConfig::new().pubsub_capacity(0))
+        );
+    }
+
+    #[test]
+    fn zero_durations_are_rejected() {
+        assert_eq!(
+            "idle timeout must be positive",
+            error(ServerConfig::new().idle_timeout(Some(Duration::ZERO)))
+        );
+        assert_eq!(
+            "TCP keepalive time must be positive",
+            error(ServerConfig::new().tcp_keepalive(Some(Duration::ZERO)))
+        );
+        assert_eq!(
+            "script time limit must be positive",
+            error(ServerConfig::new().script_time_limit(Duration::ZERO))
+        );
+    }
+
+    #[test]
+    fn max_accept_backoff_is_bounded() {
+        let expected = "max accept backoff must be between 1 second and 1 day";
+        let below = MIN_ACCEPT_BACKOFF - Duration::from_millis(1);
+        let above = MAX_ACCEPT_BACKOFF + Duration::from_secs(1);
+
+        assert_eq!(
+            expected,
+            error(ServerConfig::new().max_accept_backoff(below))
+        );
+        assert_eq!(
+            expected,
+            error(ServerConfig::new().max_accept_backoff(above))
+        );
+
+        for max in &[MIN_ACCEPT_BACKOFF, MAX_ACCEPT_BACKOFF] {
+            assert!(ServerConfig::new()
+                .max_accept_backoff(*max)
+                .validate()
+                .is_ok());
+        }
+    }
+
+    #[test]
+    fn unlimited_and_disabled_settings_are_valid() {
+        let config = ServerConfig::new()
+            .max_frame_size(None)
+            .max_bulk_size(None)
+            .idle_timeout(None)
+            .tcp_keepalive(None);
+
+        assert!(config.validate().is_ok());
+    }
+}
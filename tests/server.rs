@@ -0,0 +1,721 @@
+use indb::server::{self, ServerConfig};
+use indb::{CommandHandler, CommandRegistry, Connection, Db, Frame, LagPolicy, Parse};
+
+use async_trait::async_trait;
+use std::future;
+use std::net::SocketAddr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::{self, Duration};
+
+/// A client disconnecting while blocked on a list must not swallow the next pushed element.
+#[tokio::test]
+async fn blocked_client_disconnecting_does_not_lose_elements() {
+    let addr = start_server(ServerConfig::new()).await;
+
+    let mut blocked = TcpStream::connect(addr).await.unwrap();
+    blocked
+        .write_all(b"*3\r\n$5\r\nBLPOP\r\n$5\r\nqueue\r\n$1\r\n0\r\n")
+        .await
+        .unwrap();
+
+    // Wait for the client to block, then go away.
+    time::sleep(Duration::from_millis(50)).await;
+    drop(blocked);
+    time::sleep(Duration::from_millis(50)).await;
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    stream
+        .write_all(b"*3\r\n$5\r\nLPUSH\r\n$5\r\nqueue\r\n$3\r\njob\r\n")
+        .await
+        .unwrap();
+    assert_reply(&mut stream, b":1\r\n").await;
+
+    stream
+        .write_all(b"*2\r\n$4\r\nLPOP\r\n$5\r\nqueue\r\n")
+        .await
+        .unwrap();
+    assert_reply(&mut stream, b"$3\r\njob\r\n").await;
+}
+
+/// `TTL` and `PTTL` reply `-2` for missing keys and `-1` for keys without expiration.
+#[tokio::test]
+async fn ttl_reports_missing_and_persistent_keys() {
+    let addr = start_server(ServerConfig::new()).await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    write_command(&mut stream, &["TTL", "key"]).await;
+    assert_reply(&mut stream, b":-2\r\n").await;
+
+    write_command(&mut stream, &["SET", "key", "value"]).await;
+    assert_reply(&mut stream, b"+OK\r\n").await;
+
+    write_command(&mut stream, &["PTTL", "key"]).await;
+    assert_reply(&mut stream, b":-1\r\n").await;
+
+    write_command(&mut stream, &["EXPIRE", "key", "100"]).await;
+    assert_reply(&mut stream, b":1\r\n").await;
+
+    write_command(&mut stream, &["TTL", "key"]).await;
+    assert_reply(&mut stream, b":100\r\n").await;
+
+    write_command(&mut stream, &["PERSIST", "key"]).await;
+    assert_reply(&mut stream, b":1\r\n").await;
+
+    write_command(&mut stream, &["TTL", "key"]).await;
+    assert_reply(&mut stream, b":-1\r\n").await;
+
+    write_command(&mut stream, &["PEXPIREAT", "key", "1"]).await;
+    assert_reply(&mut stream, b":1\r\n").await;
+
+    write_command(&mut stream, &["EXISTS", "key"]).await;
+    assert_reply(&mut stream, b":0\r\n").await;
+}
+
+/// `SET` options are accepted in any order, and `GET` replies with the previous value whether or
+/// not the condition held.
+#[tokio::test]
+async fn set_accepts_options_in_any_order() {
+    let addr = start_server(ServerConfig::new()).await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    write_command(&mut stream, &["SET", "key", "1", "GET", "NX", "EX", "100"]).await;
+    assert_reply(&mut stream, b"$-1\r\n").await;
+
+    write_command(&mut stream, &["SET", "key", "2", "NX", "GET"]).await;
+    assert_reply(&mut stream, b"$1\r\n1\r\n").await;
+
+    write_command(&mut stream, &["SET", "key", "3", "KEEPTTL", "XX"]).await;
+    assert_reply(&mut stream, b"+OK\r\n").await;
+
+    write_command(&mut stream, &["TTL", "key"]).await;
+    assert_reply(&mut stream, b":100\r\n").await;
+
+    write_command(&mut stream, &["SET", "key", "4", "PXAT", "1"]).await;
+    assert_reply(&mut stream, b"+OK\r\n").await;
+
+    write_command(&mut stream, &["GET", "key"]).await;
+    assert_reply(&mut stream, b"$-1\r\n").await;
+}
+
+/// Arguments which are not canonical integers are replied with an error, and the connection
+/// stays open.
+#[tokio::test]
+async fn invalid_integer_arguments_are_replied_with_an_error() {
+    let addr = start_server(ServerConfig::new()).await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    let not_integer = b"-ERR value is not an integer or out of range\r\n";
+
+    for increment in ["+5", "007", "5x"] {
+        write_command(&mut stream, &["INCRBY", "counter", increment]).await;
+        assert_reply(&mut stream, not_integer).await;
+    }
+
+    write_command(&mut stream, &["SET", "key", "value", "EX", "-1"]).await;
+    assert_reply(&mut stream, not_integer).await;
+
+    write_command(&mut stream, &["BLPOP", "list", "soon"]).await;
+    assert_reply(
+        &mut stream,
+        b"-ERR timeout is not a float or out of range\r\n",
+    )
+    .await;
+
+    write_command(&mut stream, &["BLPOP", "list", "-1"]).await;
+    assert_reply(&mut stream, b"-ERR timeout is negative\r\n").await;
+
+    write_command(&mut stream, &["INCRBY", "counter", "-5"]).await;
+    assert_reply(&mut stream, b":-5\r\n").await;
+
+    write_command(&mut stream, &["INCRBY", "counter", "9223372036854775807"]).await;
+    assert_reply(&mut stream, b":9223372036854775802\r\n").await;
+
+    write_command(&mut stream, &["INCRBY", "counter", "6"]).await;
+    assert_reply(
+        &mut stream,
+        b"-ERR increment or decrement would overflow\r\n",
+    )
+    .await;
+
+    write_command(&mut stream, &["INCRBYFLOAT", "float", "10.5"]).await;
+    assert_reply(&mut stream, b"$4\r\n10.5\r\n").await;
+
+    write_command(&mut stream, &["INCRBYFLOAT", "float", "5.0e3"]).await;
+    assert_reply(&mut stream, b"$6\r\n5010.5\r\n").await;
+}
+
+/// A watched key created and deleted by another client before `EXEC` aborts the transaction,
+/// although it is missing in both cases.
+#[tokio::test]
+async fn exec_aborts_when_watched_key_is_created_and_deleted() {
+    let addr = start_server(ServerConfig::new()).await;
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let mut other = TcpStream::connect(addr).await.unwrap();
+
+    stream
+        .write_all(b"*2\r\n$5\r\nWATCH\r\n$3\r\nkey\r\n")
+        .await
+        .unwrap();
+    assert_reply(&mut stream, b"+OK\r\n").await;
+
+    other
+        .write_all(b"*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$5\r\nvalue\r\n")
+        .await
+        .unwrap();
+    assert_reply(&mut other, b"+OK\r\n").await;
+
+    other
+        .write_all(b"*2\r\n$3\r\nDEL\r\n$3\r\nkey\r\n")
+        .await
+        .unwrap();
+    assert_reply(&mut other, b":1\r\n").await;
+
+    stream.write_all(b"*1\r\n$5\r\nMULTI\r\n").await.unwrap();
+    assert_reply(&mut stream, b"+OK\r\n").await;
+
+    stream
+        .write_all(b"*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$4\r\nmine\r\n")
+        .await
+        .unwrap();
+    assert_reply(&mut stream, b"+QUEUED\r\n").await;
+
+    stream.write_all(b"*1\r\n$4\r\nEXEC\r\n").await.unwrap();
+    assert_reply(&mut stream, b"$-1\r\n").await;
+}
+
+/// `EXEC` applies the queued commands and replies with their replies.
+#[tokio::test]
+async fn exec_applies_queued_commands() {
+    let addr = start_server(ServerConfig::new()).await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    write_command(&mut stream, &["MULTI"]).await;
+    assert_reply(&mut stream, b"+OK\r\n").await;
+
+    write_command(&mut stream, &["SET", "key", "1"]).await;
+    assert_reply(&mut stream, b"+QUEUED\r\n").await;
+
+    write_command(&mut stream, &["INCR", "key"]).await;
+    assert_reply(&mut stream, b"+QUEUED\r\n").await;
+
+    write_command(&mut stream, &["LPUSH", "key", "a"]).await;
+    assert_reply(&mut stream, b"+QUEUED\r\n").await;
+
+    write_command(&mut stream, &["EXEC"]).await;
+    let wrong_type = "-WRONGTYPE Operation against a key holding the wrong kind of value\r\n";
+    let replies = format!("*3\r\n+OK\r\n:2\r\n{}", wrong_type);
+    assert_reply(&mut stream, replies.as_bytes()).await;
+
+    write_command(&mut stream, &["EXEC"]).await;
+    assert_reply(&mut stream, b"-ERR EXEC without MULTI\r\n").await;
+}
+
+/// XAUTOCLAIM must be asked for at least one entry.
+#[tokio::test]
+async fn xautoclaim_rejects_a_zero_count() {
+    let addr = start_server(ServerConfig::new()).await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    let args = ["XGROUP", "CREATE", "stream", "group", "$", "MKSTREAM"];
+    write_command(&mut stream, &args).await;
+    assert_reply(&mut stream, b"+OK\r\n").await;
+
+    let args = [
+        "XAUTOCLAIM",
+        "stream",
+        "group",
+        "alice",
+        "0",
+        "-",
+        "COUNT",
+        "0",
+    ];
+    write_command(&mut stream, &args).await;
+    assert_reply(&mut stream, b"-ERR COUNT must be > 0\r\n").await;
+}
+
+/// Score and lexicographical ranges cannot be combined.
+#[tokio::test]
+async fn zrange_rejects_byscore_with_bylex() {
+    let addr = start_server(ServerConfig::new()).await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    write_command(&mut stream, &["ZADD", "zset", "1", "a"]).await;
+    assert_reply(&mut stream, b":1\r\n").await;
+
+    let args = ["ZRANGE", "zset", "[a", "[z", "BYSCORE", "BYLEX"];
+    write_command(&mut stream, &args).await;
+    assert_reply(&mut stream, b"-ERR syntax error\r\n").await;
+}
+
+/// A command which cannot be queued discards the whole transaction.
+#[tokio::test]
+async fn exec_aborts_after_queueing_error() {
+    let addr = start_server(ServerConfig::new()).await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    write_command(&mut stream, &["MULTI"]).await;
+    assert_reply(&mut stream, b"+OK\r\n").await;
+
+    write_command(&mut stream, &["SET", "key", "1"]).await;
+    assert_reply(&mut stream, b"+QUEUED\r\n").await;
+
+    write_command(&mut stream, &["nosuchcommand"]).await;
+    assert_reply(&mut stream, b"-ERR unknown command 'nosuchcommand'\r\n").await;
+
+    write_command(&mut stream, &["EXEC"]).await;
+    assert_reply(
+        &mut stream,
+        b"-EXECABORT Transaction discarded because of previous errors.\r\n",
+    )
+    .await;
+
+    write_command(&mut stream, &["GET", "key"]).await;
+    assert_reply(&mut stream, b"$-1\r\n").await;
+}
+
+/// Pending entries removed from the stream are read back by their consumer with nil fields.
+#[tokio::test]
+async fn xreadgroup_history_includes_deleted_entries() {
+    let addr = start_server(ServerConfig::new()).await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    write_command(&mut stream, &["XADD", "s", "1-0", "f", "a"]).await;
+    assert_reply(&mut stream, b"$3\r\n1-0\r\n").await;
+    write_command(&mut stream, &["XADD", "s", "2-0", "f", "b"]).await;
+    assert_reply(&mut stream, b"$3\r\n2-0\r\n").await;
+    write_command(&mut stream, &["XGROUP", "CREATE", "s", "g", "0"]).await;
+    assert_reply(&mut stream, b"+OK\r\n").await;
+
+    let read = ["XREADGROUP", "GROUP", "g", "c", "STREAMS", "s", ">"];
+    write_command(&mut stream, &read).await;
+    let expected = "*1\r\n*2\r\n$1\r\ns\r\n*2\r\n\
+        *2\r\n$3\r\n1-0\r\n*2\r\n$1\r\nf\r\n$1\r\na\r\n\
+        *2\r\n$3\r\n2-0\r\n*2\r\n$1\r\nf\r\n$1\r\nb\r\n";
+    assert_reply(&mut stream, expected.as_bytes()).await;
+
+    write_command(&mut stream, &["XTRIM", "s", "MAXLEN", "1"]).await;
+    assert_reply(&mut stream, b":1\r\n").await;
+
+    let read = ["XREADGROUP", "GROUP", "g", "c", "STREAMS", "s", "0"];
+    write_command(&mut stream, &read).await;
+    let expected = "*1\r\n*2\r\n$1\r\ns\r\n*2\r\n\
+        *2\r\n$3\r\n1-0\r\n$-1\r\n\
+        *2\r\n$3\r\n2-0\r\n*2\r\n$1\r\nf\r\n$1\r\nb\r\n";
+    assert_reply(&mut stream, expected.as_bytes()).await;
+}
+
+/// `PUBSUB` reports the channels of connected subscribers, which are forgotten once they
+/// disconnect.
+#[tokio::test]
+async fn pubsub_reports_active_channels() {
+    let addr = start_server(ServerConfig::new()).await;
+
+    let mut subscriber = TcpStream::connect(addr).await.unwrap();
+    write_command(&mut subscriber, &["SUBSCRIBE", "news"]).await;
+    assert_reply(
+        &mut subscriber,
+        b"*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n",
+    )
+    .await;
+    write_command(&mut subscriber, &["PSUBSCRIBE", "n*"]).await;
+    assert_reply(
+        &mut subscriber,
+        b"*3\r\n$10\r\npsubscribe\r\n$2\r\nn*\r\n:2\r\n",
+    )
+    .await;
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    write_command(&mut stream, &["PUBSUB", "CHANNELS"]).await;
+    assert_reply(&mut stream, b"*1\r\n$4\r\nnews\r\n").await;
+    write_command(&mut stream, &["PUBSUB", "NUMSUB", "news", "none"]).await;
+    let expected = b"*4\r\n$4\r\nnews\r\n:1\r\n$4\r\nnone\r\n:0\r\n";
+    assert_reply(&mut stream, expected).await;
+    write_command(&mut stream, &["PUBSUB", "NUMPAT"]).await;
+    assert_reply(&mut stream, b":1\r\n").await;
+
+    drop(subscriber);
+    time::sleep(Duration::from_millis(50)).await;
+
+    write_command(&mut stream, &["PUBSUB", "CHANNELS"]).await;
+    assert_reply(&mut stream, b"*0\r\n").await;
+    write_command(&mut stream, &["PUBSUB", "NUMPAT"]).await;
+    assert_reply(&mut stream, b":0\r\n").await;
+}
+
+/// Subscribes to `news` on a server buffering a single message per channel, then publishes
+/// `a`, `b` and `c` at once so that the subscriber misses the first two. Returns the
+/// subscriber's stream.
+async fn lagging_subscriber(lag_policy: LagPolicy) -> TcpStream {
+    let config = ServerConfig::new()
+        .pubsub_capacity(1)
+        .lag_policy(lag_policy);
+    let addr = start_server(config).await;
+
+    let mut subscriber = TcpStream::connect(addr).await.unwrap();
+    write_command(&mut subscriber, &["SUBSCRIBE", "news"]).await;
+    assert_reply(
+        &mut subscriber,
+        b"*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n",
+    )
+    .await;
+
+    // The commands are pipelined, so that the server publishes all the messages before the
+    // subscriber's task gets to receive any, as the test runtime has a single thread.
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let publish = "*3\r\n$7\r\nPUBLISH\r\n$4\r\nnews\r\n$1\r\n";
+    let pipeline = format!("{0}a\r\n{0}b\r\n{0}c\r\n", publish);
+    stream.write_all(pipeline.as_bytes()).await.unwrap();
+    assert_reply(&mut stream, b":1\r\n:1\r\n:1\r\n").await;
+
+    subscriber
+}
+
+/// A lagging subscriber is disconnected under `LagPolicy::Disconnect`.
+#[tokio::test]
+async fn lagging_subscriber_is_disconnected() {
+    let mut subscriber = lagging_subscriber(LagPolicy::Disconnect).await;
+
+    let mut buf = [0; 64];
+    let n = time::timeout(Duration::from_secs(1), subscriber.read(&mut buf))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(0, n);
+}
+
+/// A lagging subscriber is told how many messages it missed under `LagPolicy::Notify`.
+#[tokio::test]
+async fn lagging_subscriber_is_notified() {
+    let mut subscriber = lagging_subscriber(LagPolicy::Notify).await;
+
+    let expected = b"*3\r\n$6\r\nlagged\r\n$4\r\nnews\r\n:2\r\n\
+        *3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$1\r\nc\r\n";
+    assert_reply(&mut subscriber, expected).await;
+}
+
+/// A lagging subscriber silently resumes with the oldest buffered message under
+/// `LagPolicy::Drop`.
+#[tokio::test]
+async fn lagging_subscriber_drops_messages() {
+    let mut subscriber = lagging_subscriber(LagPolicy::Drop).await;
+
+    let expected = b"*3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$1\r\nc\r\n";
+    assert_reply(&mut subscriber, expected).await;
+}
+
+/// A script which does not compile is not cached.
+#[tokio::test]
+async fn eval_does_not_cache_invalid_scripts() {
+    let addr = start_server(ServerConfig::new()).await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    write_command(&mut stream, &["EVAL", "return (", "0"]).await;
+    let expected = "-ERR Error compiling script: user_script:1: unexpected symbol near '<eof>'\r\n";
+    assert_reply(&mut stream, expected.as_bytes()).await;
+
+    // SHA1 digest of `return (`.
+    let sha1 = "728acb63e2aaef0ee859ece5db586bff5d800d1e";
+    write_command(&mut stream, &["SCRIPT", "EXISTS", sha1]).await;
+    assert_reply(&mut stream, b"*1\r\n:0\r\n").await;
+}
+
+/// A script running for longer than the time limit is aborted, after which other clients are
+/// served again.
+#[tokio::test]
+async fn eval_aborts_scripts_exceeding_the_time_limit() {
+    let config = ServerConfig::new().script_time_limit(Duration::from_millis(100));
+    let addr = start_server(config).await;
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let mut other = TcpStream::connect(addr).await.unwrap();
+
+    write_command(&mut stream, &["EVAL", "while true do end", "0"]).await;
+    let expected = "-ERR Error running script: runtime error: script exceeded the time limit\r\n";
+    assert_reply(&mut stream, expected.as_bytes()).await;
+
+    write_command(&mut other, &["SET", "key", "value"]).await;
+    assert_reply(&mut other, b"+OK\r\n").await;
+}
+
+/// A script which modified the keyspace runs to completion, even past the time limit.
+#[tokio::test]
+async fn eval_completes_scripts_which_modified_the_keyspace() {
+    let config = ServerConfig::new().script_time_limit(Duration::from_millis(1));
+    let addr = start_server(config).await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    let script = "redis.call('SET', 'key', 'value') for i = 1, 3000000 do end return 1";
+    write_command(&mut stream, &["EVAL", script, "0"]).await;
+    assert_reply(&mut stream, b":1\r\n").await;
+
+    write_command(&mut stream, &["GET", "key"]).await;
+    assert_reply(&mut stream, b"$5\r\nvalue\r\n").await;
+}
+
+/// A script past the time limit may not start modifying the keyspace.
+#[tokio::test]
+async fn eval_rejects_commands_past_the_time_limit() {
+    let config = ServerConfig::new().script_time_limit(Duration::from_millis(1));
+    let addr = start_server(config).await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    let script = "for i = 1, 3000000 do end redis.pcall('SET', 'key', 'value') return 1";
+    write_command(&mut stream, &["EVAL", script, "0"]).await;
+    let expected = "-ERR Error running script: runtime error: script exceeded the time limit\r\n";
+    assert_reply(&mut stream, expected.as_bytes()).await;
+
+    write_command(&mut stream, &["GET", "key"]).await;
+    assert_reply(&mut stream, b"$-1\r\n").await;
+}
+
+/// Scripts may neither set globals nor modify the libraries, and do not see the changes other
+/// scripts made to their own `redis` library.
+#[tokio::test]
+async fn eval_cannot_modify_globals() {
+    let addr = start_server(ServerConfig::new()).await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    let expected = "-ERR Error running script: runtime error: user_script:1: Attempt to modify a readonly table\r\n";
+    for script in &["x = 1", "string.len = nil", "_G.redis = nil"] {
+        write_command(&mut stream, &["EVAL", script, "0"]).await;
+        assert_reply(&mut stream, expected.as_bytes()).await;
+    }
+
+    write_command(&mut stream, &["EVAL", "redis.x = 'a' return redis.x", "0"]).await;
+    assert_reply(&mut stream, b"$1\r\na\r\n").await;
+
+    write_command(&mut stream, &["EVAL", "return redis.x", "0"]).await;
+    assert_reply(&mut stream, b"$-1\r\n").await;
+
+    write_command(&mut stream, &["EVAL", "return string.len('abc')", "0"]).await;
+    assert_reply(&mut stream, b":3\r\n").await;
+}
+
+/// The number of keys must be an integer within the number of arguments.
+#[tokio::test]
+async fn eval_rejects_invalid_numkeys() {
+    let addr = start_server(ServerConfig::new()).await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    write_command(&mut stream, &["EVAL", "return 1", "2", "key"]).await;
+    let expected = "-ERR Number of keys can't be greater than number of args\r\n";
+    assert_reply(&mut stream, expected.as_bytes()).await;
+
+    write_command(&mut stream, &["EVAL", "return 1", "-1"]).await;
+    assert_reply(&mut stream, b"-ERR Number of keys can't be negative\r\n").await;
+
+    write_command(&mut stream, &["EVAL", "return 1", "one"]).await;
+    assert_reply(
+        &mut stream,
+        b"-ERR value is not an integer or out of range\r\n",
+    )
+    .await;
+}
+
+/// Custom command replying `PONG`.
+#[derive(Debug)]
+struct CustomPing;
+
+#[async_trait]
+impl CommandHandler for CustomPing {
+    fn parse(_parse: &mut Parse) -> indb::Result<CustomPing> {
+        Ok(CustomPing)
+    }
+
+    async fn apply(self: Box<Self>, _db: &Db, dst: &mut Connection) -> indb::Result<()> {
+        dst.write_frame(&Frame::Simple("PONG".to_string())).await?;
+        Ok(())
+    }
+}
+
+/// Custom command replying with the string stored at `key`.
+#[derive(Debug)]
+struct CustomGet;
+
+#[async_trait]
+impl CommandHandler for CustomGet {
+    fn parse(_parse: &mut Parse) -> indb::Result<CustomGet> {
+        Ok(CustomGet)
+    }
+
+    async fn apply(self: Box<Self>, db: &Db, dst: &mut Connection) -> indb::Result<()> {
+        let value = {
+            let _section = db.enter_section().await;
+            db.get("key")
+        };
+
+        dst.write_frame(&value.map_or(Frame::Null, Frame::Bulk))
+            .await?;
+        Ok(())
+    }
+}
+
+/// Starts a server with `CUSTOMPING` registered as a custom command.
+async fn start_server_with_custom_ping() -> SocketAddr {
+    let mut commands = CommandRegistry::new();
+    commands.register::<CustomPing>("customping");
+
+    start_server(ServerConfig::new().commands(commands)).await
+}
+
+/// Custom commands are applied outside of transactions.
+#[tokio::test]
+async fn custom_command_is_applied() {
+    let addr = start_server_with_custom_ping().await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    write_command(&mut stream, &["CUSTOMPING"]).await;
+    assert_reply(&mut stream, b"+PONG\r\n").await;
+}
+
+/// Custom commands are rejected within a transaction, which is then aborted.
+#[tokio::test]
+async fn custom_command_aborts_transaction() {
+    let addr = start_server_with_custom_ping().await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    write_command(&mut stream, &["MULTI"]).await;
+    assert_reply(&mut stream, b"+OK\r\n").await;
+
+    write_command(&mut stream, &["CUSTOMPING"]).await;
+    assert_reply(
+        &mut stream,
+        b"-ERR Command not allowed inside a transaction\r\n",
+    )
+    .await;
+
+    write_command(&mut stream, &["EXEC"]).await;
+    assert_reply(
+        &mut stream,
+        b"-EXECABORT Transaction discarded because of previous errors.\r\n",
+    )
+    .await;
+}
+
+/// Scripts only see the commands indb implements.
+#[tokio::test]
+async fn custom_command_is_unknown_to_scripts() {
+    let addr = start_server_with_custom_ping().await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    write_command(
+        &mut stream,
+        &["EVAL", "return redis.call('customping')", "0"],
+    )
+    .await;
+    assert_reply(&mut stream, b"-ERR unknown command 'customping'\r\n").await;
+}
+
+/// A command which cannot be parsed inside a transaction discards it as well.
+#[tokio::test]
+async fn exec_aborts_after_parse_error() {
+    let addr = start_server(ServerConfig::new()).await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    write_command(&mut stream, &["MULTI"]).await;
+    assert_reply(&mut stream, b"+OK\r\n").await;
+
+    write_command(&mut stream, &["SET", "key"]).await;
+    assert_reply(
+        &mut stream,
+        b"-ERR protocol error: unexpected end of stream\r\n",
+    )
+    .await;
+
+    write_command(&mut stream, &["EXEC"]).await;
+    assert_reply(
+        &mut stream,
+        b"-EXECABORT Transaction discarded because of previous errors.\r\n",
+    )
+    .await;
+}
+
+/// Clients waiting for a script to end do not stall the worker serving the other clients.
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn clients_waiting_for_a_script_do_not_block_the_worker() {
+    let config = ServerConfig::new();
+    assert_waiting_for_a_script_does_not_block_the_worker(config, &["GET", "key"]).await;
+}
+
+/// Custom commands wait for scripts to end the same way.
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn custom_command_waiting_for_a_script_does_not_block_the_worker() {
+    let mut commands = CommandRegistry::new();
+    commands.register::<CustomGet>("customget");
+
+    let config = ServerConfig::new().commands(commands);
+    assert_waiting_for_a_script_does_not_block_the_worker(config, &["CUSTOMGET"]).await;
+}
+
+/// Runs a script until the time limit while a client sends `waiting`, and asserts that another
+/// client is served meanwhile. `waiting` must reply with the missing string stored at `key`.
+async fn assert_waiting_for_a_script_does_not_block_the_worker(
+    config: ServerConfig,
+    waiting_args: &[&str],
+) {
+    let config = config.script_time_limit(Duration::from_millis(500));
+    let addr = start_server(config).await;
+
+    let mut script = TcpStream::connect(addr).await.unwrap();
+    let mut waiting = TcpStream::connect(addr).await.unwrap();
+    let mut other = TcpStream::connect(addr).await.unwrap();
+
+    write_command(&mut script, &["EVAL", "while true do end", "0"]).await;
+    time::sleep(Duration::from_millis(50)).await;
+
+    // Waits for the script to end.
+    write_command(&mut waiting, waiting_args).await;
+
+    // A blocked worker would not drive the timers either, so the test thread sleeps and the
+    // delay is measured rather than timed out.
+    std::thread::sleep(Duration::from_millis(50));
+
+    // Replied without accessing the `Db`, on the only worker.
+    let start = std::time::Instant::now();
+    write_command(&mut other, &["nosuchcommand"]).await;
+    assert_reply(&mut other, b"-ERR unknown command 'nosuchcommand'\r\n").await;
+    assert!(
+        start.elapsed() < Duration::from_millis(200),
+        "the worker is blocked"
+    );
+
+    let expected = "-ERR Error running script: runtime error: script exceeded the time limit\r\n";
+    assert_reply(&mut script, expected.as_bytes()).await;
+    assert_reply(&mut waiting, b"$-1\r\n").await;
+}
+
+/// Starts a server with the given configuration on a random port and returns its address.
+async fn start_server(config: ServerConfig) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move { server::run(listener, config, future::pending::<()>()).await });
+
+    addr
+}
+
+/// Reads a reply from `stream` and asserts that it is `expected`.
+async fn assert_reply(stream: &mut TcpStream, expected: &[u8]) {
+    let mut response = vec![0; expected.len()];
+
+    // A shorter reply would otherwise wait forever for the missing bytes.
+    let read = time::timeout(Duration::from_secs(1), stream.read_exact(&mut response)).await;
+    read.expect("timed out waiting for the reply").unwrap();
+
+    assert_eq!(expected, &response[..]);
+}
+
+/// Writes the command made of `args` to `stream`.
+async fn write_command(stream: &mut TcpStream, args: &[&str]) {
+    let mut frame = format!("*{}\r\n", args.len());
+    for arg in args {
+        frame.push_str(&format!("${}\r\n{}\r\n", arg.len(), arg));
+    }
+
+    stream.write_all(frame.as_bytes()).await.unwrap();
+}